@@ -0,0 +1,26 @@
+//! Account existence checks.
+//!
+//! Deployments that can see a company's account registry (a core banking
+//! lookup, a verification service, ...) plug it in here so evaluations can
+//! flag payloads referencing accounts that don't exist.
+
+use axum::async_trait;
+use uuid::Uuid;
+
+/// Looks up whether an account exists for a company.
+#[async_trait]
+pub trait AccountRegistry: Send + Sync {
+    /// Whether `account_id` exists for the company, or `None` if the
+    /// registry can't tell (not covered, lookup failed).
+    async fn account_exists(&self, company_id: Uuid, account_id: &str) -> Option<bool>;
+}
+
+/// Registry that knows nothing; every lookup is inconclusive.
+pub struct NoopAccountRegistry;
+
+#[async_trait]
+impl AccountRegistry for NoopAccountRegistry {
+    async fn account_exists(&self, _company_id: Uuid, _account_id: &str) -> Option<bool> {
+        None
+    }
+}
@@ -2,7 +2,7 @@
 
 use axum::{
     middleware,
-    routing::{get, post, put},
+    routing::{delete, get, post, put},
     Router,
 };
 use tower_http::cors::{Any, CorsLayer};
@@ -12,7 +12,10 @@ use utoipa::{Modify, OpenApi};
 use utoipa_swagger_ui::SwaggerUi;
 
 use crate::api::handlers;
-use crate::auth::{optional_jwt, require_api_key, require_jwt, ApiKeyValidator, JwtManager};
+use crate::auth::{
+    optional_jwt, require_api_key, require_jwt, require_scope, ApiKeyValidator, JwtManager,
+};
+use crate::domain::ApiScope;
 use crate::AppState;
 
 /// Security scheme modifier for OpenAPI.
@@ -45,21 +48,34 @@ impl Modify for SecurityAddon {
 #[openapi(
     paths(
         handlers::evaluate_action,
+        handlers::evaluate_action_batch,
+        handlers::get_action_decision,
         handlers::simple_evaluate,
         handlers::list_hitl_tasks,
         handlers::get_hitl_task,
         handlers::submit_hitl_decision,
+        handlers::submit_bulk_hitl_decision,
+        handlers::stream_hitl_tasks,
+        handlers::get_reviewer_stats,
         handlers::health_check,
+        handlers::liveness_check,
+        handlers::get_operational_metrics,
         handlers::login,
         handlers::oauth_sync,
         handlers::refresh_token,
+        handlers::logout,
         handlers::get_current_user,
+        handlers::change_password,
         // Company endpoints
         handlers::create_company,
+        handlers::check_company_slug,
         handlers::list_companies,
         handlers::get_company,
         handlers::update_company,
         handlers::delete_company,
+        handlers::restore_company,
+        handlers::hard_delete_company,
+        handlers::backfill_risk_scores,
         handlers::list_company_members,
         handlers::add_company_member,
         handlers::update_member_role,
@@ -70,34 +86,73 @@ impl Modify for SecurityAddon {
         handlers::get_app,
         handlers::update_app,
         handlers::delete_app,
+        handlers::bulk_update_app_status,
+        handlers::rotate_app_webhook_secret,
         // Metrics endpoints
         handlers::get_metrics_overview,
+        handlers::compare_metrics,
         handlers::get_time_series,
         handlers::get_risk_distribution,
+        handlers::get_metrics_by_model,
+        handlers::get_metrics_by_reviewer,
+        handlers::get_metrics_prometheus,
         // Actions list
         handlers::list_company_actions,
+        handlers::export_company_actions_csv,
+        handlers::reevaluate_action,
         // Attacks
         handlers::list_attacks,
+        handlers::list_company_audit,
         // Settings
         handlers::get_company_settings,
         handlers::update_company_settings,
+        handlers::rotate_webhook_secret,
+        handlers::purge_company_actions,
+        handlers::get_effective_config,
+        handlers::replay_webhook,
+        handlers::list_webhook_deliveries,
+        handlers::replay_webhook_delivery,
+        handlers::test_webhook,
+        handlers::list_policy_profiles,
+        handlers::create_policy_profile,
+        handlers::get_policy_profile,
+        handlers::update_policy_profile,
+        handlers::delete_policy_profile,
+        handlers::list_beneficiaries,
+        handlers::add_beneficiary,
+        handlers::delete_beneficiary,
+        // Audit
+        handlers::verify_audit_log,
+        handlers::run_selftest,
     ),
     components(schemas(
         crate::api::types::EvaluateActionRequest,
         crate::api::types::EvaluateActionResponse,
+        crate::api::types::BatchEvaluateRequest,
+        crate::api::types::BatchEvaluateResult,
+        crate::api::types::BatchEvaluateResponse,
+        crate::api::types::ActionDecisionResponse,
         crate::api::types::ListHitlTasksQuery,
-        crate::api::types::ListHitlTasksResponse,
         crate::api::types::GetHitlTaskResponse,
         crate::api::types::HitlDecisionRequest,
         crate::api::types::HitlDecisionResponse,
+        crate::api::types::BulkHitlDecisionRequest,
+        crate::api::types::BulkDecisionOutcome,
+        crate::api::types::BulkHitlDecisionResult,
+        crate::api::types::BulkHitlDecisionResponse,
+        crate::api::types::ReviewerStatsResponse,
         crate::api::types::HealthResponse,
+        crate::api::types::LivenessResponse,
         crate::api::types::LoginRequest,
+        crate::api::types::ChangePasswordRequest,
         crate::api::types::LoginResponse,
         crate::api::types::UserInfo,
         crate::api::types::UserInfoResponse,
         crate::api::types::OAuthSyncRequest,
         crate::api::types::OAuthSyncResponse,
+        crate::api::types::TokenRefreshRequest,
         crate::api::types::TokenRefreshResponse,
+        crate::api::types::LogoutRequest,
         crate::api::types::CurrentUserResponse,
         crate::domain::User,
         crate::domain::UserRole,
@@ -105,62 +160,134 @@ impl Modify for SecurityAddon {
         crate::domain::UserCompanyMembership,
         // Company types
         crate::api::types::CreateCompanyRequest,
+        crate::api::types::SlugCheckQuery,
+        crate::api::types::SlugCheckResponse,
         crate::api::types::UpdateCompanyRequest,
+        crate::api::types::DeleteCompanyQuery,
         crate::api::types::CompanyResponse,
+        crate::api::types::BackfillRiskScoresResponse,
         crate::api::types::ListCompaniesResponse,
         crate::api::types::AddMemberRequest,
         crate::api::types::UpdateMemberRoleRequest,
         crate::api::types::MemberResponse,
-        crate::api::types::ListMembersResponse,
+        crate::api::types::ListMembersQuery,
         crate::api::types::CreateAppRequest,
         crate::api::types::UpdateAppRequest,
         crate::api::types::CreateAppResponse,
         crate::api::types::AppResponse,
         crate::api::types::ListAppsResponse,
+        crate::api::types::BulkAppStatusRequest,
+        crate::api::types::BulkAppStatusResult,
+        crate::api::types::BulkAppStatusResponse,
         // Metrics types
         crate::api::types::MetricsQuery,
+        crate::api::types::MetricsCompareQuery,
         crate::api::types::MetricsOverviewResponse,
+        crate::api::types::MetricsComparisonResponse,
         crate::api::types::TimeSeriesResponse,
         crate::api::types::RiskDistributionResponse,
+        crate::api::types::ModelBreakdownResponse,
+        crate::api::types::ReviewerBreakdownResponse,
         // Actions list types
         crate::api::types::ListActionsQuery,
         crate::api::types::ActionListItem,
-        crate::api::types::ListActionsResponse,
+        crate::api::types::ReevaluateQuery,
+        crate::api::types::ReevaluateResponse,
         // Attacks types
         crate::api::types::ListAttacksQuery,
-        crate::api::types::ListAttacksResponse,
+        crate::api::types::ListAuditQuery,
         // Settings types
         crate::api::types::SettingsResponse,
+        crate::api::types::WebhookSecretResponse,
+        crate::api::types::RotateWebhookSecretQuery,
+        crate::api::types::PurgeActionsQuery,
+        crate::api::types::PurgeActionsResponse,
+        crate::domain::PurgeSummary,
+        crate::api::types::EffectiveConfigResponse,
+        crate::api::types::EffectiveGuardConfig,
+        crate::api::types::EffectiveLayers,
         crate::api::types::UpdateSettingsRequest,
+        crate::api::types::ReplayWebhookRequest,
+        crate::api::types::ReplayWebhookResponse,
+        crate::api::types::ListWebhookDeliveriesQuery,
+        crate::api::types::ReplayDeliveryResponse,
+        crate::domain::WebhookDeliveryRecord,
+        crate::api::types::WebhookTestResponse,
+        // Audit types
+        crate::audit::AuditVerification,
+        // Self-test types
+        crate::api::types::SelfTestRequest,
+        crate::selftest::SelfTestReport,
+        crate::selftest::LatencyStats,
+        crate::selftest::DecisionCounts,
+        crate::api::types::CreatePolicyProfileRequest,
+        crate::api::types::UpdatePolicyProfileRequest,
+        crate::api::types::PolicyProfileResponse,
+        crate::api::types::ListPolicyProfilesResponse,
+        crate::api::types::AddBeneficiaryRequest,
+        crate::api::types::BeneficiaryResponse,
+        crate::api::types::ListBeneficiariesResponse,
         // Domain types
         crate::domain::AgentAction,
         crate::domain::ActionType,
         crate::domain::EvaluationResult,
+        crate::domain::ReasonEntry,
+        crate::domain::ReasonLayer,
+        crate::domain::DecisionLayers,
+        crate::domain::FirewallLayer,
+        crate::domain::FirewallVerdict,
+        crate::domain::AlignmentLayer,
+        crate::domain::AlignmentVerdict,
+        crate::domain::PolicyRuleHit,
         crate::domain::DecisionStatus,
         crate::domain::RiskTier,
+        crate::domain::EvaluationDiff,
+        crate::domain::DecisionChange,
+        crate::domain::RiskTierChange,
         crate::domain::HitlTask,
         crate::domain::HitlStatus,
         crate::domain::HitlTaskDetails,
         crate::domain::HitlTaskSummary,
+        crate::domain::ReviewerStats,
+        crate::domain::ReviewerDecision,
         crate::domain::TransferFundsPayload,
         crate::domain::GetBalancePayload,
         crate::domain::PayBillPayload,
+        crate::domain::RefundTransactionPayload,
+        crate::domain::CryptoTransferPayload,
         crate::domain::Company,
         crate::domain::CompanyMember,
         crate::domain::CompanyRole,
         crate::domain::App,
         crate::domain::AppStatus,
+        crate::domain::ApiScope,
         crate::domain::AttackEvent,
+        crate::domain::AuditEntry,
         crate::domain::AttackType,
         crate::domain::AttackOutcome,
         crate::domain::MetricsOverview,
         crate::domain::Trends,
+        crate::domain::MetricsComparison,
+        crate::domain::PeriodStats,
+        crate::domain::PeriodDeltas,
+        crate::domain::MetricDelta,
         crate::domain::TimeSeriesData,
         crate::domain::TimeSeriesPoint,
         crate::domain::RiskDistribution,
         crate::domain::RiskDistributionPoint,
+        crate::domain::ModelBreakdown,
+        crate::domain::ModelMetrics,
+        crate::domain::ReviewerBreakdown,
+        crate::domain::ReviewerMetrics,
         crate::domain::CompanySettings,
+        crate::domain::PolicyProfile,
+        crate::domain::Beneficiary,
         crate::domain::PolicyThresholds,
+        crate::domain::IntentAllowlistEntry,
+        crate::domain::OffHoursSchedule,
+        crate::domain::DrainDetection,
+        crate::domain::NotificationDigest,
+        crate::domain::PipelineLayers,
     )),
     modifiers(&SecurityAddon),
     tags(
@@ -172,6 +299,8 @@ impl Modify for SecurityAddon {
         (name = "auth", description = "Authentication endpoints"),
         (name = "companies", description = "Company management"),
         (name = "apps", description = "App/API key management"),
+        (name = "audit", description = "Tamper-evident decision log"),
+        (name = "admin", description = "Operator tooling"),
         (name = "health", description = "Health and status endpoints")
     ),
     info(
@@ -210,8 +339,27 @@ fn build_authenticated_router(
     cors: CorsLayer,
 ) -> Router {
     // Routes requiring API key (for agents)
-    let agent_routes = Router::new()
+    let evaluate_routes = Router::new()
         .route("/v1/actions/evaluate", post(handlers::evaluate_action))
+        .route(
+            "/v1/actions/evaluate/batch",
+            post(handlers::evaluate_action_batch),
+        )
+        .route_layer(middleware::from_fn_with_state(
+            ApiScope::ActionsEvaluate,
+            require_scope,
+        ));
+    let read_routes = Router::new()
+        .route(
+            "/v1/actions/:id/decision",
+            get(handlers::get_action_decision),
+        )
+        .route_layer(middleware::from_fn_with_state(
+            ApiScope::ActionsRead,
+            require_scope,
+        ));
+    let agent_routes = evaluate_routes
+        .merge(read_routes)
         .layer(middleware::from_fn_with_state(
             api_key_validator.clone(),
             require_api_key,
@@ -227,18 +375,28 @@ fn build_authenticated_router(
     let admin_routes = Router::new()
         // HITL routes
         .route("/v1/hitl/tasks", get(handlers::list_hitl_tasks))
+        .route("/v1/hitl/tasks/stream", get(handlers::stream_hitl_tasks))
         .route("/v1/hitl/tasks/:id", get(handlers::get_hitl_task))
         .route(
             "/v1/hitl/tasks/:id/decision",
             post(handlers::submit_hitl_decision),
         )
+        .route(
+            "/v1/hitl/tasks/bulk-decision",
+            post(handlers::submit_bulk_hitl_decision),
+        )
         // Auth routes
         .route("/v1/auth/me", get(handlers::get_current_user))
+        .route("/v1/auth/password", put(handlers::change_password))
         // Company routes
         .route(
             "/v1/companies",
             get(handlers::list_companies).post(handlers::create_company),
         )
+        .route(
+            "/v1/companies/slug-check",
+            get(handlers::check_company_slug),
+        )
         .route(
             "/v1/companies/:id",
             get(handlers::get_company)
@@ -264,11 +422,23 @@ fn build_authenticated_router(
                 .put(handlers::update_app)
                 .delete(handlers::delete_app),
         )
+        .route(
+            "/v1/companies/:id/apps/bulk-status",
+            post(handlers::bulk_update_app_status),
+        )
+        .route(
+            "/v1/companies/:company_id/apps/:app_id/webhook-secret",
+            post(handlers::rotate_app_webhook_secret),
+        )
         // Metrics routes
         .route(
             "/v1/companies/:id/metrics/overview",
             get(handlers::get_metrics_overview),
         )
+        .route(
+            "/v1/companies/:id/metrics/compare",
+            get(handlers::compare_metrics),
+        )
         .route(
             "/v1/companies/:id/metrics/time-series",
             get(handlers::get_time_series),
@@ -277,27 +447,105 @@ fn build_authenticated_router(
             "/v1/companies/:id/metrics/risk-distribution",
             get(handlers::get_risk_distribution),
         )
+        .route(
+            "/v1/companies/:id/metrics/by-model",
+            get(handlers::get_metrics_by_model),
+        )
+        .route(
+            "/v1/companies/:id/metrics/reviewers",
+            get(handlers::get_metrics_by_reviewer),
+        )
+        .route(
+            "/v1/companies/:id/metrics/prometheus",
+            get(handlers::get_metrics_prometheus),
+        )
         // Actions list
         .route(
             "/v1/companies/:id/actions",
             get(handlers::list_company_actions),
         )
+        .route(
+            "/v1/companies/:id/actions.csv",
+            get(handlers::export_company_actions_csv),
+        )
+        .route(
+            "/v1/companies/:company_id/actions/:action_id/reevaluate",
+            post(handlers::reevaluate_action),
+        )
         // Attacks
         .route("/v1/companies/:id/attacks", get(handlers::list_attacks))
+        .route("/v1/companies/:id/audit", get(handlers::list_company_audit))
+        // Reviewers
+        .route(
+            "/v1/companies/:company_id/reviewers/:reviewer_id/stats",
+            get(handlers::get_reviewer_stats),
+        )
         // Settings
         .route(
             "/v1/companies/:id/settings",
             get(handlers::get_company_settings).put(handlers::update_company_settings),
         )
-        .layer(middleware::from_fn_with_state(
-            jwt_manager.clone(),
-            require_jwt,
-        ))
-        .with_state(state.clone());
-
-    // Token refresh route (requires JWT)
-    let token_refresh_routes = Router::new()
-        .route("/v1/auth/token/refresh", post(handlers::refresh_token))
+        .route(
+            "/v1/companies/:id/settings/webhook-secret",
+            post(handlers::rotate_webhook_secret),
+        )
+        .route(
+            "/v1/companies/:id/settings/webhook/test",
+            post(handlers::test_webhook),
+        )
+        .route(
+            "/v1/companies/:id/maintenance/purge",
+            post(handlers::purge_company_actions),
+        )
+        .route(
+            "/v1/companies/:id/effective-config",
+            get(handlers::get_effective_config),
+        )
+        .route(
+            "/v1/companies/:id/webhooks/replay",
+            post(handlers::replay_webhook),
+        )
+        .route(
+            "/v1/companies/:id/webhooks/deliveries",
+            get(handlers::list_webhook_deliveries),
+        )
+        .route(
+            "/v1/companies/:id/webhooks/deliveries/:delivery_id/replay",
+            post(handlers::replay_webhook_delivery),
+        )
+        .route(
+            "/v1/companies/:id/policy-profiles",
+            get(handlers::list_policy_profiles).post(handlers::create_policy_profile),
+        )
+        .route(
+            "/v1/companies/:company_id/policy-profiles/:profile_id",
+            get(handlers::get_policy_profile)
+                .put(handlers::update_policy_profile)
+                .delete(handlers::delete_policy_profile),
+        )
+        .route(
+            "/v1/companies/:id/beneficiaries",
+            get(handlers::list_beneficiaries).post(handlers::add_beneficiary),
+        )
+        .route(
+            "/v1/companies/:company_id/beneficiaries/:beneficiary_id",
+            delete(handlers::delete_beneficiary),
+        )
+        // Audit routes
+        .route("/v1/audit/verify", get(handlers::verify_audit_log))
+        .route("/v1/admin/selftest", post(handlers::run_selftest))
+        .route(
+            "/v1/admin/companies/:id",
+            delete(handlers::hard_delete_company),
+        )
+        .route(
+            "/v1/admin/companies/:id/restore",
+            post(handlers::restore_company),
+        )
+        .route(
+            "/v1/admin/companies/:id/risk-scores/backfill",
+            post(handlers::backfill_risk_scores),
+        )
         .layer(middleware::from_fn_with_state(
             jwt_manager.clone(),
             require_jwt,
@@ -307,17 +555,21 @@ fn build_authenticated_router(
     // Public routes (no auth required)
     let public_routes = Router::new()
         .route("/v1/health", get(handlers::health_check))
+        .route("/v1/health/live", get(handlers::liveness_check))
+        .route("/metrics", get(handlers::get_operational_metrics))
         .route("/v1/auth/login", post(handlers::login))
         .route("/v1/auth/oauth/sync", post(handlers::oauth_sync))
+        .route("/v1/auth/token/refresh", post(handlers::refresh_token))
+        .route("/v1/auth/logout", post(handlers::logout))
         .with_state(state.clone());
 
     Router::new()
         .merge(agent_routes)
         .merge(simple_evaluate_route)
         .merge(admin_routes)
-        .merge(token_refresh_routes)
         .merge(public_routes)
         .merge(SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", ApiDoc::openapi()))
+        .layer(middleware::from_fn(crate::telemetry::track_http_requests))
         .layer(TraceLayer::new_for_http())
         .layer(cors)
 }
@@ -329,20 +581,37 @@ fn build_unauthenticated_router(state: AppState, cors: CorsLayer) -> Router {
     Router::new()
         // Action evaluation
         .route("/v1/actions/evaluate", post(handlers::evaluate_action))
+        .route(
+            "/v1/actions/evaluate/batch",
+            post(handlers::evaluate_action_batch),
+        )
+        .route(
+            "/v1/actions/:id/decision",
+            get(handlers::get_action_decision),
+        )
         // Simple evaluate (API key validated in handler)
         .route("/v1/evaluate", post(handlers::simple_evaluate))
         // HITL management
         .route("/v1/hitl/tasks", get(handlers::list_hitl_tasks))
+        .route("/v1/hitl/tasks/stream", get(handlers::stream_hitl_tasks))
         .route("/v1/hitl/tasks/:id", get(handlers::get_hitl_task))
         .route(
             "/v1/hitl/tasks/:id/decision",
             post(handlers::submit_hitl_decision),
         )
+        .route(
+            "/v1/hitl/tasks/bulk-decision",
+            post(handlers::submit_bulk_hitl_decision),
+        )
         // Company routes
         .route(
             "/v1/companies",
             get(handlers::list_companies).post(handlers::create_company),
         )
+        .route(
+            "/v1/companies/slug-check",
+            get(handlers::check_company_slug),
+        )
         .route(
             "/v1/companies/:id",
             get(handlers::get_company)
@@ -368,11 +637,23 @@ fn build_unauthenticated_router(state: AppState, cors: CorsLayer) -> Router {
                 .put(handlers::update_app)
                 .delete(handlers::delete_app),
         )
+        .route(
+            "/v1/companies/:id/apps/bulk-status",
+            post(handlers::bulk_update_app_status),
+        )
+        .route(
+            "/v1/companies/:company_id/apps/:app_id/webhook-secret",
+            post(handlers::rotate_app_webhook_secret),
+        )
         // Metrics routes
         .route(
             "/v1/companies/:id/metrics/overview",
             get(handlers::get_metrics_overview),
         )
+        .route(
+            "/v1/companies/:id/metrics/compare",
+            get(handlers::compare_metrics),
+        )
         .route(
             "/v1/companies/:id/metrics/time-series",
             get(handlers::get_time_series),
@@ -381,23 +662,114 @@ fn build_unauthenticated_router(state: AppState, cors: CorsLayer) -> Router {
             "/v1/companies/:id/metrics/risk-distribution",
             get(handlers::get_risk_distribution),
         )
+        .route(
+            "/v1/companies/:id/metrics/by-model",
+            get(handlers::get_metrics_by_model),
+        )
+        .route(
+            "/v1/companies/:id/metrics/reviewers",
+            get(handlers::get_metrics_by_reviewer),
+        )
+        .route(
+            "/v1/companies/:id/metrics/prometheus",
+            get(handlers::get_metrics_prometheus),
+        )
         // Actions list
         .route(
             "/v1/companies/:id/actions",
             get(handlers::list_company_actions),
         )
+        .route(
+            "/v1/companies/:id/actions.csv",
+            get(handlers::export_company_actions_csv),
+        )
+        .route(
+            "/v1/companies/:company_id/actions/:action_id/reevaluate",
+            post(handlers::reevaluate_action),
+        )
         // Attacks
         .route("/v1/companies/:id/attacks", get(handlers::list_attacks))
+        .route("/v1/companies/:id/audit", get(handlers::list_company_audit))
+        // Reviewers
+        .route(
+            "/v1/companies/:company_id/reviewers/:reviewer_id/stats",
+            get(handlers::get_reviewer_stats),
+        )
         // Settings
         .route(
             "/v1/companies/:id/settings",
             get(handlers::get_company_settings).put(handlers::update_company_settings),
         )
+        .route(
+            "/v1/companies/:id/settings/webhook-secret",
+            post(handlers::rotate_webhook_secret),
+        )
+        .route(
+            "/v1/companies/:id/settings/webhook/test",
+            post(handlers::test_webhook),
+        )
+        .route(
+            "/v1/companies/:id/maintenance/purge",
+            post(handlers::purge_company_actions),
+        )
+        .route(
+            "/v1/companies/:id/effective-config",
+            get(handlers::get_effective_config),
+        )
+        .route(
+            "/v1/companies/:id/webhooks/replay",
+            post(handlers::replay_webhook),
+        )
+        .route(
+            "/v1/companies/:id/webhooks/deliveries",
+            get(handlers::list_webhook_deliveries),
+        )
+        .route(
+            "/v1/companies/:id/webhooks/deliveries/:delivery_id/replay",
+            post(handlers::replay_webhook_delivery),
+        )
+        .route(
+            "/v1/companies/:id/policy-profiles",
+            get(handlers::list_policy_profiles).post(handlers::create_policy_profile),
+        )
+        .route(
+            "/v1/companies/:company_id/policy-profiles/:profile_id",
+            get(handlers::get_policy_profile)
+                .put(handlers::update_policy_profile)
+                .delete(handlers::delete_policy_profile),
+        )
+        .route(
+            "/v1/companies/:id/beneficiaries",
+            get(handlers::list_beneficiaries).post(handlers::add_beneficiary),
+        )
+        .route(
+            "/v1/companies/:company_id/beneficiaries/:beneficiary_id",
+            delete(handlers::delete_beneficiary),
+        )
+        // Audit routes
+        .route("/v1/audit/verify", get(handlers::verify_audit_log))
+        .route("/v1/admin/selftest", post(handlers::run_selftest))
+        .route(
+            "/v1/admin/companies/:id",
+            delete(handlers::hard_delete_company),
+        )
+        .route(
+            "/v1/admin/companies/:id/restore",
+            post(handlers::restore_company),
+        )
+        .route(
+            "/v1/admin/companies/:id/risk-scores/backfill",
+            post(handlers::backfill_risk_scores),
+        )
         // Health
         .route("/v1/health", get(handlers::health_check))
+        .route("/v1/health/live", get(handlers::liveness_check))
+        .route("/metrics", get(handlers::get_operational_metrics))
         // Auth endpoints
         .route("/v1/auth/me", get(handlers::get_current_user))
+        .route("/v1/auth/password", put(handlers::change_password))
         .route("/v1/auth/token/refresh", post(handlers::refresh_token))
+        .route("/v1/auth/logout", post(handlers::logout))
         .route("/v1/auth/login", post(handlers::login))
         .route("/v1/auth/oauth/sync", post(handlers::oauth_sync))
         // Apply optional JWT middleware (validates token if present, doesn't fail if missing)
@@ -406,6 +778,117 @@ fn build_unauthenticated_router(state: AppState, cors: CorsLayer) -> Router {
         // OpenAPI docs
         .merge(SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", ApiDoc::openapi()))
         // Middleware
+        .layer(middleware::from_fn(crate::telemetry::track_http_requests))
         .layer(TraceLayer::new_for_http())
         .layer(cors)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_routers_build_without_conflicts() {
+        let state = AppState::for_tests().await;
+        let jwt_manager = state.jwt_manager.clone();
+
+        // Route registration panics on conflicting paths, so building is the assertion
+        let _ = build_router(
+            state.clone(),
+            true,
+            ApiKeyValidator::new(vec![]),
+            jwt_manager.clone(),
+        );
+        let _ = build_router(state, false, ApiKeyValidator::new(vec![]), jwt_manager);
+    }
+
+    #[tokio::test]
+    async fn test_read_only_key_is_rejected_from_evaluate() {
+        use axum::body::Body;
+        use axum::http::{Request, StatusCode};
+        use tower::ServiceExt;
+
+        let state = AppState::for_tests().await;
+        let company = crate::domain::Company::new("Acme".to_string(), "acme".to_string(), None);
+        state.repository.create_company(&company).await.unwrap();
+        let mut app = crate::domain::App::new(company.id, "Reader".to_string(), None, 100);
+        assert_eq!(app.scopes, vec![ApiScope::ActionsEvaluate]);
+        app.scopes = vec![ApiScope::ActionsRead];
+        let api_key = app.api_key.clone().unwrap();
+        state
+            .repository
+            .create_app(&app, &crate::domain::App::hash_api_key(&api_key))
+            .await
+            .unwrap();
+
+        let router = build_router(
+            state.clone(),
+            true,
+            ApiKeyValidator::new(vec![]).with_app_keys(state.repository.clone()),
+            state.jwt_manager.clone(),
+        );
+        let request = |method: &str, uri: &str, body: &str| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("X-API-Key", &api_key)
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+
+        let response = router
+            .clone()
+            .oneshot(request("POST", "/v1/actions/evaluate", "{}"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        // Reading is within the key's scope, so the lookup itself runs
+        let response = router
+            .oneshot(request(
+                "GET",
+                &format!("/v1/actions/{}/decision", uuid::Uuid::new_v4()),
+                "",
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_default_key_can_read_decisions() {
+        use axum::body::Body;
+        use axum::http::{Request, StatusCode};
+        use tower::ServiceExt;
+
+        let state = AppState::for_tests().await;
+        let company = crate::domain::Company::new("Acme".to_string(), "acme".to_string(), None);
+        state.repository.create_company(&company).await.unwrap();
+        let app = crate::domain::App::new(company.id, "Agent".to_string(), None, 100);
+        let api_key = app.api_key.clone().unwrap();
+        state
+            .repository
+            .create_app(&app, &crate::domain::App::hash_api_key(&api_key))
+            .await
+            .unwrap();
+
+        let router = build_router(
+            state.clone(),
+            true,
+            ApiKeyValidator::new(vec![]).with_app_keys(state.repository.clone()),
+            state.jwt_manager.clone(),
+        );
+        let response = router
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/v1/actions/{}/decision", uuid::Uuid::new_v4()))
+                    .header("X-API-Key", &api_key)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -2,14 +2,82 @@
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use utoipa::ToSchema;
 use uuid::Uuid;
 
 use crate::domain::{
-    AgentAction, App, AppStatus, Company, CompanyMember, CompanyRole, EvaluationResult, HitlStatus,
-    HitlTaskDetails, HitlTaskSummary, User, UserCompanyMembership, UserRole,
+    AgentAction, ApiScope, App, AppStatus, Company, CompanyMember, CompanyRole, ConversationTurn,
+    DecisionStatus, EvaluationResult, HitlStatus, HitlTaskDetails, HitlTaskSummary, PurgeSummary,
+    ReasonEntry, ReviewerDecision, ReviewerStats, User, UserCompanyMembership, UserRole,
+    WebhookDeliveryRecord,
 };
 
+// ==================== Pagination ====================
+
+/// A page of results, shared by every paginated list endpoint.
+///
+/// Lists that predate the envelope also return the results under their old
+/// field name (`tasks`, `actions`, `attacks`, `members`) until clients have
+/// moved to `items`.
+#[derive(Debug, ToSchema)]
+pub struct Page<T> {
+    /// Results on this page.
+    pub items: Vec<T>,
+    /// Total number of results matching the filters.
+    pub total: i64,
+    /// Limit used.
+    pub limit: i64,
+    /// Offset of the first item.
+    pub offset: i64,
+    /// Cursor for the next page, if more results remain.
+    pub next_cursor: Option<String>,
+    /// Deprecated field name the results are also served under.
+    #[schema(ignore)]
+    pub legacy_key: Option<&'static str>,
+}
+
+impl<T> Page<T> {
+    /// Wrap one page of `items` starting at `offset`.
+    pub fn new(items: Vec<T>, total: i64, limit: i64, offset: i64) -> Self {
+        let next_offset = offset + items.len() as i64;
+        let next_cursor = (next_offset < total).then(|| next_offset.to_string());
+        Self {
+            items,
+            total,
+            limit,
+            offset,
+            next_cursor,
+            legacy_key: None,
+        }
+    }
+
+    /// Also serve the results under `key`, the list's pre-envelope name.
+    pub fn with_legacy_key(mut self, key: &'static str) -> Self {
+        self.legacy_key = Some(key);
+        self
+    }
+}
+
+impl<T: Serialize> Serialize for Page<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("items", &self.items)?;
+        if let Some(key) = self.legacy_key {
+            map.serialize_entry(key, &self.items)?;
+        }
+        map.serialize_entry("total", &self.total)?;
+        map.serialize_entry("limit", &self.limit)?;
+        map.serialize_entry("offset", &self.offset)?;
+        if let Some(cursor) = &self.next_cursor {
+            map.serialize_entry("next_cursor", cursor)?;
+        }
+        map.end()
+    }
+}
+
 // ==================== Evaluate Action ====================
 
 /// Request to evaluate an agent action.
@@ -21,13 +89,64 @@ pub struct EvaluateActionRequest {
 }
 
 /// Response from action evaluation.
-#[derive(Debug, Serialize, ToSchema)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct EvaluateActionResponse {
     /// The evaluation result.
     pub evaluation: EvaluationResult,
     /// ID of the HITL task if one was created.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub hitl_task_id: Option<Uuid>,
+    /// Console page for reviewing the HITL task, when a console URL is configured.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub review_url: Option<String>,
+}
+
+/// Request to evaluate several agent actions at once.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct BatchEvaluateRequest {
+    /// Actions to evaluate (at most 50). Each is parsed on its own, so one
+    /// malformed action doesn't reject the batch.
+    #[schema(value_type = Vec<AgentAction>)]
+    pub actions: Vec<serde_json::Value>,
+}
+
+/// Result for one action of a batch evaluation.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BatchEvaluateResult {
+    /// Whether the action was evaluated.
+    pub ok: bool,
+    /// The evaluation, when `ok`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<EvaluateActionResponse>,
+    /// Why the action couldn't be evaluated, when not `ok`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Response from a batch evaluation.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BatchEvaluateResponse {
+    /// Per-action results, in request order.
+    pub results: Vec<BatchEvaluateResult>,
+}
+
+/// Final decision for an evaluated action, for agents polling the outcome
+/// of a review.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ActionDecisionResponse {
+    /// Action ID.
+    pub action_id: Uuid,
+    /// Decision returned when the action was evaluated.
+    pub original_decision: DecisionStatus,
+    /// Effective decision: the original one, or the HITL outcome once the
+    /// review is decided (`require_hitl` while it is pending).
+    pub decision: DecisionStatus,
+    /// Review task, when the evaluation created one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hitl_task_id: Option<Uuid>,
+    /// Review status, when the evaluation created a task.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hitl_status: Option<HitlStatus>,
 }
 
 // ==================== Simple Evaluate (API Key identified) ====================
@@ -58,6 +177,14 @@ pub struct SimpleEvaluateRequest {
     /// Chain of thought or reasoning (optional).
     #[serde(default)]
     pub cot_trace: Option<String>,
+
+    /// Earlier turns of the conversation, oldest first (optional).
+    #[serde(default)]
+    pub conversation: Vec<ConversationTurn>,
+
+    /// Trace ID for distributed tracing (optional, generated if omitted).
+    #[serde(default)]
+    pub trace_id: Option<String>,
 }
 
 /// Response from simple evaluation.
@@ -71,13 +198,25 @@ pub struct SimpleEvaluateResponse {
     pub risk_tier: String,
     /// Human-readable reasons for the decision.
     pub reasons: Vec<String>,
+    /// The same reasons with their codes, layer and severity.
+    pub reason_entries: Vec<ReasonEntry>,
+    /// Firewall keywords/patterns found in the input.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub matched_patterns: Vec<String>,
     /// ID of the HITL task if human review is required.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub hitl_task_id: Option<Uuid>,
+    /// Console page for reviewing the HITL task, when a console URL is configured.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub review_url: Option<String>,
     /// The full evaluation ID for reference.
     pub evaluation_id: Uuid,
     /// The action ID for reference.
     pub action_id: Uuid,
+    /// Evaluated for a sandbox app: stored apart from production data and
+    /// never escalated to a HITL task.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub sandbox: bool,
 }
 
 // ==================== HITL Tasks ====================
@@ -94,6 +233,9 @@ pub struct ListHitlTasksQuery {
     /// Offset for pagination.
     #[serde(default)]
     pub offset: i64,
+    /// Continuation cursor from a previous page (takes precedence over offset).
+    #[serde(default)]
+    pub cursor: Option<String>,
 }
 
 fn default_limit() -> i64 {
@@ -101,16 +243,30 @@ fn default_limit() -> i64 {
 }
 
 /// Response for listing HITL tasks.
-#[derive(Debug, Serialize, ToSchema)]
-pub struct ListHitlTasksResponse {
-    /// List of task summaries.
-    pub tasks: Vec<HitlTaskSummary>,
-    /// Total count (for pagination).
-    pub total: usize,
-    /// Limit used.
+pub type ListHitlTasksResponse = Page<HitlTaskSummary>;
+
+/// Query parameters for streaming new HITL tasks.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct StreamHitlTasksQuery {
+    /// Company whose tasks to stream.
+    pub company_id: Uuid,
+}
+
+/// Query parameters for a reviewer's stats.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ReviewerStatsQuery {
+    /// Maximum number of recent decisions returned.
+    #[serde(default = "default_limit")]
     pub limit: i64,
-    /// Offset used.
-    pub offset: i64,
+}
+
+/// Response for a reviewer's stats and decision history.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReviewerStatsResponse {
+    /// Totals over all of the reviewer's decisions.
+    pub stats: ReviewerStats,
+    /// Most recent decisions, newest first.
+    pub recent_decisions: Vec<ReviewerDecision>,
 }
 
 /// Response for getting HITL task details.
@@ -125,11 +281,13 @@ pub struct GetHitlTaskResponse {
 pub struct HitlDecisionRequest {
     /// Decision: "approve" or "reject".
     pub decision: String,
-    /// ID of the reviewer.
-    pub reviewer_id: String,
     /// Optional notes.
     #[serde(default)]
     pub notes: Option<String>,
+    /// On final approval, also approve the action's destination as a
+    /// beneficiary of its user.
+    #[serde(default)]
+    pub add_beneficiary: bool,
 }
 
 /// Response after HITL decision.
@@ -139,25 +297,89 @@ pub struct HitlDecisionResponse {
     pub task_id: Uuid,
     /// New status.
     pub status: HitlStatus,
+    /// Effective decision of the reviewed action: `allow` once approved,
+    /// `block` once rejected, `require_hitl` while still pending.
+    pub resolved_decision: DecisionStatus,
+    /// Whether the approval was recorded but waits for a second reviewer
+    /// (the task stays pending until then).
+    pub awaiting_confirmation: bool,
     /// Message.
     pub message: String,
 }
 
+/// Request to decide several HITL tasks at once.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct BulkHitlDecisionRequest {
+    /// Tasks to decide (at most 100).
+    pub task_ids: Vec<Uuid>,
+    /// Decision: "approve" or "reject".
+    pub decision: String,
+    /// Optional notes, recorded on every task.
+    #[serde(default)]
+    pub notes: Option<String>,
+}
+
+/// What a bulk decision did to one task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkDecisionOutcome {
+    /// The decision was recorded.
+    Updated,
+    /// The task was already decided.
+    SkippedNotPending,
+    /// The approval needs a second reviewer; decide the task on its own.
+    SkippedNeedsConfirmation,
+    /// No task has this ID.
+    NotFound,
+    /// The reviewer isn't an owner or admin of the task's company.
+    Forbidden,
+}
+
+/// Result for one task of a bulk decision.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BulkHitlDecisionResult {
+    /// Task ID.
+    pub task_id: Uuid,
+    /// What happened to the task.
+    pub outcome: BulkDecisionOutcome,
+}
+
+/// Response after a bulk HITL decision.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BulkHitlDecisionResponse {
+    /// One result per requested task, in request order.
+    pub results: Vec<BulkHitlDecisionResult>,
+    /// Number of tasks updated.
+    pub updated: usize,
+}
+
 // ==================== Health ====================
 
 /// Health check response.
 #[derive(Debug, Serialize, ToSchema)]
 pub struct HealthResponse {
-    /// Service status.
+    /// `healthy`, `degraded` (an external dependency is unreachable) or
+    /// `unhealthy` (the database is unreachable).
     pub status: String,
     /// Service version.
     pub version: String,
     /// Database connectivity.
     pub database: String,
+    /// External dependencies (e.g. `llama_guard`): `ok` or the error.
+    pub dependencies: BTreeMap<String, String>,
     /// Timestamp.
     pub timestamp: String,
 }
 
+/// Liveness response.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LivenessResponse {
+    /// Always `alive`.
+    pub status: String,
+    /// Service version.
+    pub version: String,
+}
+
 // ==================== Authentication ====================
 
 /// Login request.
@@ -169,6 +391,16 @@ pub struct LoginRequest {
     pub password: String,
 }
 
+/// Request to set or change the current user's password.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ChangePasswordRequest {
+    /// Current password (omit only if the account has none yet).
+    #[serde(default)]
+    pub current_password: Option<String>,
+    /// New password; must meet the configured strength policy.
+    pub new_password: String,
+}
+
 /// Login response.
 #[derive(Debug, Serialize, ToSchema)]
 pub struct LoginResponse {
@@ -178,6 +410,10 @@ pub struct LoginResponse {
     pub token: String,
     /// Token expiration in seconds.
     pub expires_in: i64,
+    /// Token for `POST /v1/auth/token/refresh`.
+    pub refresh_token: String,
+    /// Refresh token expiration in seconds.
+    pub refresh_expires_in: i64,
     /// Companies the user belongs to.
     pub companies: Vec<UserCompanyMembership>,
 }
@@ -259,12 +495,30 @@ pub struct OAuthSyncResponse {
     pub token: String,
     /// Token expiration in seconds.
     pub expires_in: i64,
+    /// Token for `POST /v1/auth/token/refresh`.
+    pub refresh_token: String,
+    /// Refresh token expiration in seconds.
+    pub refresh_expires_in: i64,
     /// Whether this is a newly created user.
     pub is_new_user: bool,
     /// Companies the user belongs to.
     pub companies: Vec<UserCompanyMembership>,
 }
 
+/// Request to exchange a refresh token for a new access token.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct TokenRefreshRequest {
+    /// Refresh token returned at login.
+    pub refresh_token: String,
+}
+
+/// Request to end a session.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct LogoutRequest {
+    /// Refresh token to revoke.
+    pub refresh_token: String,
+}
+
 /// Token refresh response.
 #[derive(Debug, Serialize, ToSchema)]
 pub struct TokenRefreshResponse {
@@ -295,6 +549,21 @@ pub struct CreateCompanyRequest {
     pub description: Option<String>,
 }
 
+/// Query parameters for previewing a company slug.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SlugCheckQuery {
+    /// Company name as it would be submitted.
+    pub name: String,
+}
+
+/// Slug a company name would get, and whether it is free.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SlugCheckResponse {
+    pub slug: String,
+    /// False when another company already has this slug.
+    pub available: bool,
+}
+
 /// Request to update a company.
 #[derive(Debug, Deserialize, ToSchema)]
 pub struct UpdateCompanyRequest {
@@ -304,6 +573,26 @@ pub struct UpdateCompanyRequest {
     /// New description.
     #[serde(default)]
     pub description: Option<String>,
+    /// New plan (system admins only).
+    #[serde(default)]
+    pub plan: Option<String>,
+}
+
+/// Query parameters for deleting a company.
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct DeleteCompanyQuery {
+    /// Delete even with active apps or pending HITL tasks (default false).
+    #[serde(default)]
+    pub force: bool,
+}
+
+/// Outcome of recomputing a company's risk scores.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BackfillRiskScoresResponse {
+    /// Evaluations whose score was recomputed.
+    pub evaluations: usize,
+    /// Evaluations whose stored score was missing or differed.
+    pub updated: usize,
 }
 
 /// Response for company operations.
@@ -347,13 +636,26 @@ pub struct MemberResponse {
     pub member: CompanyMember,
 }
 
-/// Response for listing members.
-#[derive(Debug, Serialize, ToSchema)]
-pub struct ListMembersResponse {
-    /// List of members.
-    pub members: Vec<CompanyMember>,
+/// Query parameters for listing members.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ListMembersQuery {
+    /// Filter by role: owner, admin, member or viewer.
+    #[serde(default)]
+    pub role: Option<String>,
+    /// Maximum results.
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+    /// Pagination offset.
+    #[serde(default)]
+    pub offset: i64,
+    /// Continuation cursor from a previous page (takes precedence over offset).
+    #[serde(default)]
+    pub cursor: Option<String>,
 }
 
+/// Response for listing members.
+pub type ListMembersResponse = Page<CompanyMember>;
+
 // ==================== Apps ====================
 
 /// Request to create an app.
@@ -367,6 +669,9 @@ pub struct CreateAppRequest {
     /// Rate limit (requests per minute).
     #[serde(default = "default_rate_limit")]
     pub rate_limit: u32,
+    /// What the app's API key may do (defaults to `actions:evaluate`).
+    #[serde(default)]
+    pub scopes: Option<Vec<ApiScope>>,
 }
 
 fn default_rate_limit() -> u32 {
@@ -388,6 +693,38 @@ pub struct UpdateAppRequest {
     /// New rate limit.
     #[serde(default)]
     pub rate_limit: Option<u32>,
+    /// Mark the app as trusted/internal (allows per-request guard overrides).
+    /// System admins only.
+    #[serde(default)]
+    pub trusted: Option<bool>,
+    /// Mark the app as a sandbox (non-production) app.
+    #[serde(default)]
+    pub sandbox: Option<bool>,
+    /// New set of scopes for the app's API key.
+    #[serde(default)]
+    pub scopes: Option<Vec<ApiScope>>,
+    /// Policy profile to evaluate against; `null` reverts to company thresholds.
+    #[serde(default, deserialize_with = "deserialize_some")]
+    #[schema(value_type = Option<Uuid>)]
+    pub policy_profile_id: Option<Option<Uuid>>,
+    /// Other companies the app may evaluate for via `X-Company-Id`
+    /// (the caller must be an owner or admin of each).
+    #[serde(default)]
+    pub permitted_company_ids: Option<Vec<Uuid>>,
+    /// Webhook for the app's notifications; `null` reverts to the
+    /// company's.
+    #[serde(default, deserialize_with = "deserialize_some")]
+    #[schema(value_type = Option<String>)]
+    pub webhook_url: Option<Option<String>>,
+}
+
+/// Distinguish an explicit `null` (`Some(None)`) from an absent field (`None`).
+fn deserialize_some<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
 }
 
 /// Response for app creation (includes API key).
@@ -415,11 +752,49 @@ pub struct ListAppsResponse {
     pub apps: Vec<App>,
 }
 
+/// Request to change the status of several apps at once.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct BulkAppStatusRequest {
+    /// App IDs to update (ignored when `all` is set).
+    #[serde(default)]
+    pub app_ids: Vec<Uuid>,
+    /// Apply the status to every app in the company.
+    #[serde(default)]
+    pub all: bool,
+    /// Target status.
+    pub status: AppStatus,
+}
+
+/// Per-app result of a bulk status change.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BulkAppStatusResult {
+    /// App ID.
+    pub app_id: Uuid,
+    /// Outcome: "updated", "unchanged", or "not_found".
+    pub result: String,
+    /// Status before the change (absent if the app was not found).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous_status: Option<AppStatus>,
+    /// Status after the change (absent if the app was not found).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<AppStatus>,
+}
+
+/// Response for a bulk app status change.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BulkAppStatusResponse {
+    /// Per-app results, in request order.
+    pub results: Vec<BulkAppStatusResult>,
+    /// Number of apps whose status changed.
+    pub updated: usize,
+}
+
 // ==================== Metrics ====================
 
 use crate::domain::{
-    AttackEvent, CompanySettings, MetricsOverview, PolicyThresholds, RiskDistribution,
-    TimeSeriesData,
+    AttackEvent, AuditEntry, Beneficiary, CompanySettings, EvaluationDiff, MetricsComparison,
+    MetricsOverview, ModelBreakdown, NotificationDigest, PipelineLayers, PolicyProfile,
+    PolicyThresholds, ReviewerBreakdown, RiskDistribution, TimeSeriesData,
 };
 
 /// Query parameters for metrics.
@@ -431,6 +806,10 @@ pub struct MetricsQuery {
     /// Optional app filter.
     #[serde(default)]
     pub app_id: Option<Uuid>,
+    /// Time-series granularity: hour or day. Defaults to hour for 24h and
+    /// day otherwise.
+    #[serde(default)]
+    pub granularity: Option<String>,
 }
 
 fn default_time_range() -> String {
@@ -444,6 +823,29 @@ pub struct MetricsOverviewResponse {
     pub metrics: MetricsOverview,
 }
 
+/// Query parameters for comparing two periods.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct MetricsCompareQuery {
+    /// Start of period A (inclusive).
+    pub a_start: DateTime<Utc>,
+    /// End of period A (exclusive).
+    pub a_end: DateTime<Utc>,
+    /// Start of baseline period B (inclusive).
+    pub b_start: DateTime<Utc>,
+    /// End of baseline period B (exclusive).
+    pub b_end: DateTime<Utc>,
+    /// Optional app filter.
+    #[serde(default)]
+    pub app_id: Option<Uuid>,
+}
+
+/// Response for period comparison.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MetricsComparisonResponse {
+    #[serde(flatten)]
+    pub comparison: MetricsComparison,
+}
+
 /// Response for time series data.
 #[derive(Debug, Serialize, ToSchema)]
 pub struct TimeSeriesResponse {
@@ -458,6 +860,20 @@ pub struct RiskDistributionResponse {
     pub data: RiskDistribution,
 }
 
+/// Response for the per-model breakdown.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ModelBreakdownResponse {
+    #[serde(flatten)]
+    pub data: ModelBreakdown,
+}
+
+/// Response for the per-reviewer breakdown.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReviewerBreakdownResponse {
+    #[serde(flatten)]
+    pub data: ReviewerBreakdown,
+}
+
 // ==================== Actions List ====================
 
 /// Query parameters for listing actions.
@@ -481,12 +897,20 @@ pub struct ListActionsQuery {
     /// Time range filter.
     #[serde(default)]
     pub time_range: Option<String>,
+    /// List sandbox app actions instead of production ones.
+    #[serde(default)]
+    pub sandbox: bool,
     /// Maximum results.
     #[serde(default = "default_limit")]
     pub limit: i64,
-    /// Pagination offset.
+    /// Pagination offset (kept for older clients; prefer `cursor`).
     #[serde(default)]
     pub offset: i64,
+    /// Continuation cursor from a previous page (takes precedence over
+    /// offset). Pages continue after the last action seen, so actions
+    /// arriving in between don't shift them.
+    #[serde(default)]
+    pub cursor: Option<String>,
 }
 
 /// Action item in list response.
@@ -512,12 +936,35 @@ pub struct ActionListItem {
 }
 
 /// Response for listing actions.
+pub type ListActionsResponse = Page<ActionListItem>;
+
+/// Query parameters for re-evaluating an action.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ReevaluateQuery {
+    /// Include a diff against the original evaluation (default true).
+    #[serde(default = "default_include_diff")]
+    pub diff: bool,
+    /// Save the new evaluation as the action's latest (default false).
+    #[serde(default)]
+    pub persist: bool,
+}
+
+fn default_include_diff() -> bool {
+    true
+}
+
+/// Response for re-evaluating an action.
 #[derive(Debug, Serialize, ToSchema)]
-pub struct ListActionsResponse {
-    pub actions: Vec<ActionListItem>,
-    pub total: i64,
-    pub limit: i64,
-    pub offset: i64,
+pub struct ReevaluateResponse {
+    /// The evaluation stored when the action was first seen.
+    pub original: EvaluationResult,
+    /// The evaluation under the current policy.
+    pub evaluation: EvaluationResult,
+    /// Whether `evaluation` was saved.
+    pub persisted: bool,
+    /// What changed between the two, unless disabled with `diff=false`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub diff: Option<EvaluationDiff>,
 }
 
 // ==================== Attacks ====================
@@ -543,13 +990,35 @@ pub struct ListAttacksQuery {
     /// Pagination offset.
     #[serde(default)]
     pub offset: i64,
+    /// Continuation cursor from a previous page (takes precedence over offset).
+    #[serde(default)]
+    pub cursor: Option<String>,
 }
 
 /// Response for listing attacks.
-#[derive(Debug, Serialize, ToSchema)]
-pub struct ListAttacksResponse {
-    pub attacks: Vec<AttackEvent>,
-    pub total: i64,
+pub type ListAttacksResponse = Page<AttackEvent>;
+
+/// Response for listing audit trail entries.
+pub type ListAuditResponse = Page<AuditEntry>;
+
+/// Query parameters for listing audit trail entries.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ListAuditQuery {
+    /// Filter by the user who made the change.
+    #[serde(default)]
+    pub actor_id: Option<String>,
+    /// Time range filter: 24h, 7d, 30d, or 90d.
+    #[serde(default)]
+    pub time_range: Option<String>,
+    /// Maximum results.
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+    /// Pagination offset.
+    #[serde(default)]
+    pub offset: i64,
+    /// Continuation cursor from a previous page (takes precedence over offset).
+    #[serde(default)]
+    pub cursor: Option<String>,
 }
 
 // ==================== Settings ====================
@@ -561,19 +1030,303 @@ pub struct SettingsResponse {
     pub settings: CompanySettings,
 }
 
+/// Newly generated webhook signing secret, shown only once.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct WebhookSecretResponse {
+    /// HMAC-SHA256 key for verifying the `X-Shield-Signature` header.
+    pub secret: String,
+    /// Until when payloads are also signed with the replaced secret.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous_valid_until: Option<DateTime<Utc>>,
+}
+
+/// Query parameters for rotating a webhook secret.
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct RotateWebhookSecretQuery {
+    /// Minutes the replaced secret keeps signing payloads alongside the new
+    /// one, so receivers can switch without failed verifications (default
+    /// 0, at most 10080).
+    #[serde(default)]
+    pub overlap_minutes: u32,
+}
+
+/// Query parameters for purging a company's old actions.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct PurgeActionsQuery {
+    /// Age past which actions are deleted, e.g. `90d` or `12h`.
+    pub older_than: String,
+}
+
+/// Result of purging a company's old actions.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PurgeActionsResponse {
+    pub company_id: Uuid,
+    /// Actions created before this were deleted.
+    pub cutoff: DateTime<Utc>,
+    /// Rows deleted.
+    pub deleted: PurgeSummary,
+}
+
+/// Query parameters for the effective configuration.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct EffectiveConfigQuery {
+    /// Resolve for this app, applying its policy profile if it has one.
+    #[serde(default)]
+    pub app_id: Option<Uuid>,
+}
+
+/// Configuration actually applied when evaluating a company's actions,
+/// after global defaults, plan, company settings and policy profile are
+/// merged.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct EffectiveConfigResponse {
+    /// Company the configuration applies to.
+    pub company_id: Uuid,
+    /// Company plan.
+    pub plan: String,
+    /// App the configuration was resolved for, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub app_id: Option<Uuid>,
+    /// Policy profile in use (the app's), if any.
+    pub policy_profile_id: Option<Uuid>,
+    /// Thresholds and lists in use: the profile's, else the company's.
+    pub thresholds: PolicyThresholds,
+    /// Auto-approval limit during business hours.
+    pub max_auto_amount: f64,
+    /// HITL threshold during business hours.
+    pub hitl_threshold: f64,
+    /// Neural guard state.
+    pub guard: EffectiveGuardConfig,
+    /// Which optional safety layers run.
+    pub layers: EffectiveLayers,
+}
+
+/// Neural guard (Llama Guard) state for a company.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct EffectiveGuardConfig {
+    /// Whether the company's plan allows the guard.
+    pub available: bool,
+    /// Whether the guard runs unless a trusted app overrides it per request.
+    pub enabled: bool,
+    /// Guard model, if one is configured.
+    pub model: Option<String>,
+}
+
+/// Safety layers and whether they run.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct EffectiveLayers {
+    pub firewall: bool,
+    pub alignment: bool,
+    pub policy: bool,
+    pub tool_call_injection: bool,
+    pub obfuscation: bool,
+    pub cot_divergence: bool,
+    pub automation_loop: bool,
+    pub conversation: bool,
+    pub amount_mismatch: bool,
+    pub currency_mismatch: bool,
+    pub block_cooldown: bool,
+    pub user_rate: bool,
+    pub trace_reuse: bool,
+    pub daily_transfer_limit: bool,
+    pub off_hours: bool,
+    pub drain_detection: bool,
+    pub absolute_block_amount: bool,
+}
+
 /// Request to update settings.
 #[derive(Debug, Deserialize, ToSchema)]
 pub struct UpdateSettingsRequest {
     /// New logo URL.
     #[serde(default)]
     pub logo: Option<String>,
-    /// New webhook URL.
+    /// New webhook URL. Must be https and point at a public host.
     #[serde(default)]
     pub webhook_url: Option<String>,
     /// New notification email.
     #[serde(default)]
     pub notification_email: Option<String>,
+    /// Console base URL for HITL review links (empty string clears it).
+    #[serde(default)]
+    pub console_url: Option<String>,
+    /// New IANA timezone (e.g. "Europe/Madrid").
+    #[serde(default)]
+    pub timezone: Option<String>,
+    /// New HITL notification digest mode.
+    #[serde(default)]
+    pub notification_digest: Option<NotificationDigest>,
+    /// Evaluation pipeline layers to run.
+    #[serde(default)]
+    pub pipeline: Option<PipelineLayers>,
+    /// Days the company's actions are kept, overriding the server default
+    /// (0 clears it).
+    #[serde(default)]
+    pub retention_days: Option<u32>,
     /// New policy thresholds.
     #[serde(default)]
     pub policy_thresholds: Option<PolicyThresholds>,
 }
+
+// ==================== Webhooks ====================
+
+/// Request to re-send a past webhook event. Exactly one reference is required.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ReplayWebhookRequest {
+    /// HITL task whose creation event is re-sent.
+    #[serde(default)]
+    pub task_id: Option<Uuid>,
+    /// Evaluation whose HITL task creation event is re-sent.
+    #[serde(default)]
+    pub evaluation_id: Option<Uuid>,
+}
+
+/// Response for a webhook replay.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReplayWebhookResponse {
+    /// Type of the re-sent event.
+    pub event: String,
+    /// Task the event is about.
+    pub task_id: Uuid,
+    /// Delivery the re-sent event is logged under.
+    pub delivery_id: Uuid,
+    /// Webhook the event was sent to.
+    pub webhook_url: String,
+}
+
+/// Query parameters for listing webhook deliveries.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ListWebhookDeliveriesQuery {
+    /// Maximum results.
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+    /// Pagination offset.
+    #[serde(default)]
+    pub offset: i64,
+    /// Continuation cursor from a previous page (takes precedence over offset).
+    #[serde(default)]
+    pub cursor: Option<String>,
+}
+
+/// Response for listing webhook deliveries.
+pub type ListWebhookDeliveriesResponse = Page<WebhookDeliveryRecord>;
+
+/// Response for replaying a logged webhook delivery.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReplayDeliveryResponse {
+    /// Delivery whose payload was re-sent.
+    pub delivery_id: Uuid,
+    /// Type of the re-sent event.
+    pub event: String,
+    /// Webhook the payload was sent to.
+    pub webhook_url: String,
+}
+
+/// Outcome of a webhook test delivery.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct WebhookTestResponse {
+    /// Webhook the sample event was sent to.
+    pub webhook_url: String,
+    /// Whether the receiver answered with a 2xx status.
+    pub delivered: bool,
+    /// HTTP status the receiver answered with, if it answered.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status_code: Option<u16>,
+    /// Milliseconds until the answer or the failure.
+    pub latency_ms: u64,
+    /// Why the delivery failed, if it did.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+// ==================== Policy Profiles ====================
+
+/// Request to create a policy profile.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreatePolicyProfileRequest {
+    /// Profile name, unique within the company.
+    pub name: String,
+    /// Thresholds and lists for the profile.
+    #[serde(default)]
+    pub thresholds: PolicyThresholds,
+}
+
+/// Request to update a policy profile.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdatePolicyProfileRequest {
+    /// New name.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// New thresholds (replaces the existing ones).
+    #[serde(default)]
+    pub thresholds: Option<PolicyThresholds>,
+}
+
+/// Response for policy profile operations.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PolicyProfileResponse {
+    pub profile: PolicyProfile,
+}
+
+/// Response for listing policy profiles.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ListPolicyProfilesResponse {
+    pub profiles: Vec<PolicyProfile>,
+}
+
+// ==================== Beneficiaries ====================
+
+/// Query parameters for listing beneficiaries.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ListBeneficiariesQuery {
+    /// Only list this end user's beneficiaries.
+    #[serde(default)]
+    pub user_id: Option<String>,
+}
+
+/// Request to approve a beneficiary for an end user.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AddBeneficiaryRequest {
+    /// End user who may send to the account.
+    pub user_id: String,
+    /// Destination account (account ID, IBAN, biller ID, ...).
+    pub account_identifier: String,
+}
+
+/// Response for beneficiary operations.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BeneficiaryResponse {
+    pub beneficiary: Beneficiary,
+}
+
+/// Response for listing beneficiaries.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ListBeneficiariesResponse {
+    pub beneficiaries: Vec<Beneficiary>,
+}
+
+// ==================== Self-test ====================
+
+/// Request to run synthetic evaluations for capacity planning.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SelfTestRequest {
+    /// Evaluations to run (1-10000).
+    #[serde(default = "default_selftest_evaluations")]
+    pub evaluations: usize,
+    /// Evaluations in flight at once (1-64).
+    #[serde(default = "default_selftest_concurrency")]
+    pub concurrency: usize,
+    /// Call the configured neural guard instead of skipping it.
+    #[serde(default)]
+    pub real_guard: bool,
+    /// Milliseconds to wait in place of the guard when it is skipped.
+    #[serde(default)]
+    pub simulated_guard_ms: Option<u64>,
+}
+
+fn default_selftest_evaluations() -> usize {
+    100
+}
+
+fn default_selftest_concurrency() -> usize {
+    4
+}
@@ -0,0 +1,298 @@
+//! Append-only audit log of decisions.
+//!
+//! Each decision is appended to a JSONL file as an [`AuditRecord`] carrying
+//! the SHA-256 of the record before it, so editing, dropping or reordering
+//! any line breaks the chain from that point on. The operational database
+//! stays the mutable source the API reads from; this file is only ever
+//! appended to and can be shipped to WORM storage as is.
+
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+use utoipa::ToSchema;
+
+use crate::events::DecisionEvent;
+
+/// `prev_hash` of the first record in a log.
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// One line of the audit log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditRecord {
+    /// Position in the log, starting at 1.
+    pub seq: u64,
+    /// `hash` of the previous record, or [`GENESIS_HASH`].
+    pub prev_hash: String,
+    /// The decision, as published in decision events.
+    pub event: serde_json::Value,
+    /// SHA-256 over `seq`, `prev_hash` and `event`.
+    pub hash: String,
+}
+
+impl AuditRecord {
+    fn new(seq: u64, prev_hash: String, event: serde_json::Value) -> Self {
+        let hash = Self::compute_hash(seq, &prev_hash, &event);
+        Self {
+            seq,
+            prev_hash,
+            event,
+            hash,
+        }
+    }
+
+    /// Hash of a record's contents. `event` is hashed in serde_json's
+    /// canonical (sorted key) form, so re-parsing a line reproduces it.
+    fn compute_hash(seq: u64, prev_hash: &str, event: &serde_json::Value) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(format!("{}:{}:", seq, prev_hash));
+        hasher.update(event.to_string());
+        hex::encode(hasher.finalize())
+    }
+}
+
+/// Result of checking an audit log's hash chain.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct AuditVerification {
+    /// Whether every record links to the one before it.
+    pub valid: bool,
+    /// Records verified (up to the first broken one).
+    pub records: u64,
+    /// Hash of the last verified record.
+    pub last_hash: String,
+    /// Line of the first record that fails verification.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub broken_at: Option<u64>,
+    /// Why that record failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Check the hash chain of an audit log read line by line.
+pub fn verify(reader: impl BufRead) -> std::io::Result<AuditVerification> {
+    let mut verification = AuditVerification {
+        valid: true,
+        records: 0,
+        last_hash: GENESIS_HASH.to_string(),
+        broken_at: None,
+        error: None,
+    };
+
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        let expected_seq = verification.records + 1;
+        let problem = match serde_json::from_str::<AuditRecord>(&line) {
+            Err(e) => Some(format!("unreadable record: {}", e)),
+            Ok(record) if record.seq != expected_seq => Some(format!(
+                "sequence {} where {} was expected",
+                record.seq, expected_seq
+            )),
+            Ok(record) if record.prev_hash != verification.last_hash => {
+                Some("previous hash does not match the preceding record".to_string())
+            }
+            Ok(record)
+                if record.hash
+                    != AuditRecord::compute_hash(record.seq, &record.prev_hash, &record.event) =>
+            {
+                Some("record hash does not match its contents".to_string())
+            }
+            Ok(record) => {
+                verification.records = record.seq;
+                verification.last_hash = record.hash;
+                None
+            }
+        };
+
+        if let Some(problem) = problem {
+            verification.valid = false;
+            verification.broken_at = Some(index as u64 + 1);
+            verification.error = Some(problem);
+            break;
+        }
+    }
+
+    Ok(verification)
+}
+
+/// Check the hash chain of the audit log at `path`.
+pub fn verify_file(path: &Path) -> std::io::Result<AuditVerification> {
+    let file = std::fs::File::open(path)?;
+    verify(std::io::BufReader::new(file))
+}
+
+struct AuditWriter {
+    path: PathBuf,
+    file: tokio::fs::File,
+    next_seq: u64,
+    last_hash: String,
+}
+
+/// Writer for the audit log; a no-op when auditing is off.
+pub struct AuditLog {
+    writer: Option<Mutex<AuditWriter>>,
+}
+
+impl AuditLog {
+    /// A log that records nothing.
+    pub fn disabled() -> Self {
+        Self { writer: None }
+    }
+
+    /// Open (or create) the log at `path`, continuing its chain. Fails if
+    /// the existing records don't verify, rather than extending a broken
+    /// chain.
+    pub async fn open(path: impl Into<PathBuf>) -> anyhow::Result<Self> {
+        let path = path.into();
+        let verification = if path.exists() {
+            verify_file(&path)?
+        } else {
+            verify(std::io::empty())?
+        };
+        if !verification.valid {
+            anyhow::bail!(
+                "audit log {} fails verification at line {}: {}",
+                path.display(),
+                verification.broken_at.unwrap_or_default(),
+                verification.error.unwrap_or_default()
+            );
+        }
+
+        let file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .await?;
+
+        Ok(Self {
+            writer: Some(Mutex::new(AuditWriter {
+                path,
+                file,
+                next_seq: verification.records + 1,
+                last_hash: verification.last_hash,
+            })),
+        })
+    }
+
+    /// Append a decision, waiting until it is on disk.
+    pub async fn record(&self, event: &DecisionEvent) -> anyhow::Result<()> {
+        let Some(writer) = &self.writer else {
+            return Ok(());
+        };
+
+        let mut writer = writer.lock().await;
+        let record = AuditRecord::new(
+            writer.next_seq,
+            writer.last_hash.clone(),
+            serde_json::to_value(event)?,
+        );
+        let mut line = serde_json::to_string(&record)?;
+        line.push('\n');
+        writer.file.write_all(line.as_bytes()).await?;
+        writer.file.sync_data().await?;
+
+        writer.next_seq += 1;
+        writer.last_hash = record.hash;
+        Ok(())
+    }
+
+    /// Check the chain written so far; `None` when auditing is off.
+    pub async fn verify(&self) -> Option<std::io::Result<AuditVerification>> {
+        let writer = self.writer.as_ref()?.lock().await;
+        Some(verify_file(&writer.path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{ActionType, AgentAction, DecisionStatus, EvaluationResult, RiskTier};
+
+    fn temp_log() -> PathBuf {
+        std::env::temp_dir().join(format!("shield-audit-{}.jsonl", uuid::Uuid::new_v4()))
+    }
+
+    fn event(decision: DecisionStatus) -> DecisionEvent {
+        let action = AgentAction::new(
+            "user-1",
+            "api",
+            "gpt-4",
+            "transfer $50 to savings",
+            ActionType::TransferFunds,
+            serde_json::json!({"amount": 50.0}),
+        );
+        let evaluation = EvaluationResult::new(
+            action.id,
+            decision,
+            RiskTier::Low,
+            vec!["Amount within limits".to_string()],
+            vec![],
+        );
+        DecisionEvent::new(None, &action, &evaluation, None)
+    }
+
+    #[tokio::test]
+    async fn test_decisions_form_verifiable_chain() {
+        let path = temp_log();
+        let log = AuditLog::open(&path).await.unwrap();
+        for decision in [
+            DecisionStatus::Allow,
+            DecisionStatus::RequireHitl,
+            DecisionStatus::Block,
+        ] {
+            log.record(&event(decision)).await.unwrap();
+        }
+
+        let verification = log.verify().await.unwrap().unwrap();
+        assert!(verification.valid, "{:?}", verification);
+        assert_eq!(verification.records, 3);
+
+        // Reopening continues the chain instead of restarting it
+        drop(log);
+        let log = AuditLog::open(&path).await.unwrap();
+        log.record(&event(DecisionStatus::Allow)).await.unwrap();
+        let verification = log.verify().await.unwrap().unwrap();
+        assert!(verification.valid, "{:?}", verification);
+        assert_eq!(verification.records, 4);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_tampering_breaks_chain() {
+        let path = temp_log();
+        let log = AuditLog::open(&path).await.unwrap();
+        for decision in [
+            DecisionStatus::Block,
+            DecisionStatus::Block,
+            DecisionStatus::Allow,
+        ] {
+            log.record(&event(decision)).await.unwrap();
+        }
+        drop(log);
+        let original = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = original.lines().collect();
+
+        // Rewriting a decision in place
+        let edited = original.replacen("\"decision\":\"block\"", "\"decision\":\"allow\"", 1);
+        std::fs::write(&path, &edited).unwrap();
+        let verification = verify_file(&path).unwrap();
+        assert!(!verification.valid);
+        assert_eq!(verification.records, 0);
+        assert_eq!(verification.broken_at, Some(1));
+        assert!(verification.error.unwrap().contains("hash does not match"));
+
+        // Dropping a record
+        std::fs::write(&path, format!("{}\n{}\n", lines[0], lines[2])).unwrap();
+        let verification = verify_file(&path).unwrap();
+        assert_eq!(verification.records, 1);
+        assert_eq!(verification.broken_at, Some(2));
+
+        // A broken log isn't extended
+        assert!(AuditLog::open(&path).await.is_err());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
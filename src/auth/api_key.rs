@@ -4,6 +4,10 @@ use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use crate::domain::{ApiScope, AppStatus};
+use crate::storage::ShieldRepository;
 
 /// Represents an API key with its metadata.
 #[derive(Debug, Clone)]
@@ -23,11 +27,26 @@ pub struct ApiKeyInfo {
     pub rate_limit: Option<u32>,
 }
 
+/// The app behind a request authenticated with an app's API key.
+#[derive(Debug, Clone)]
+pub struct AppKeyContext {
+    pub app_id: Uuid,
+    pub company_id: Uuid,
+    /// Whether the app is a sandbox app.
+    pub sandbox: bool,
+    /// What the app's key may do.
+    pub scopes: Vec<ApiScope>,
+    /// Requests allowed per minute (0 means unlimited).
+    pub rate_limit: u32,
+}
+
 /// API Key validator and store.
 #[derive(Clone)]
 pub struct ApiKeyValidator {
     /// Map of hashed keys to their info.
     keys: Arc<RwLock<HashMap<String, ApiKeyInfo>>>,
+    /// Repository to look app keys up in, when app keys are accepted.
+    apps: Option<ShieldRepository>,
 }
 
 impl ApiKeyValidator {
@@ -51,9 +70,37 @@ impl ApiKeyValidator {
 
         Self {
             keys: Arc::new(RwLock::new(keys)),
+            apps: None,
         }
     }
 
+    /// Also accept the API keys of active apps stored in `repository`.
+    pub fn with_app_keys(mut self, repository: ShieldRepository) -> Self {
+        self.apps = Some(repository);
+        self
+    }
+
+    /// Validate an app's API key and return the app it belongs to, if the
+    /// app is active. Records the app as just used.
+    pub async fn validate_app(&self, key: &str) -> Option<AppKeyContext> {
+        let repository = self.apps.as_ref()?;
+        let app = repository
+            .get_app_by_api_key_hash(&Self::hash_key(key))
+            .await
+            .ok()
+            .filter(|app| app.status == AppStatus::Active)?;
+
+        let _ = repository.update_app_last_used(app.id).await;
+
+        Some(AppKeyContext {
+            app_id: app.id,
+            company_id: app.company_id,
+            sandbox: app.sandbox,
+            scopes: app.scopes,
+            rate_limit: app.rate_limit,
+        })
+    }
+
     /// Hash an API key for secure storage/comparison.
     pub fn hash_key(key: &str) -> String {
         let mut hasher = Sha256::new();
@@ -8,7 +8,9 @@ use axum::{
     Json,
 };
 use chrono::{Duration, Utc};
-use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, TokenData, Validation};
+use jsonwebtoken::{
+    decode, decode_header, encode, DecodingKey, EncodingKey, Header, TokenData, Validation,
+};
 use serde::{Deserialize, Serialize};
 
 use crate::error::{ShieldError, ShieldResult};
@@ -79,6 +81,29 @@ pub enum UserRole {
     Admin,
 }
 
+impl std::fmt::Display for UserRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UserRole::Viewer => write!(f, "viewer"),
+            UserRole::Reviewer => write!(f, "reviewer"),
+            UserRole::Admin => write!(f, "admin"),
+        }
+    }
+}
+
+impl std::str::FromStr for UserRole {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "viewer" => Ok(UserRole::Viewer),
+            "reviewer" => Ok(UserRole::Reviewer),
+            "admin" => Ok(UserRole::Admin),
+            _ => Err(format!("Unknown role: {}", s)),
+        }
+    }
+}
+
 impl UserRole {
     /// Check if this role can review HITL tasks.
     #[allow(dead_code)]
@@ -93,27 +118,68 @@ impl UserRole {
     }
 }
 
+/// Key ID of the signing key unless configured otherwise.
+pub const DEFAULT_JWT_KEY_ID: &str = "primary";
+
+/// Additional key accepted when verifying tokens, e.g. the previous signing
+/// key during a rotation.
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct JwtVerificationKey {
+    /// Key ID, matched against the `kid` token header.
+    pub kid: String,
+    /// HMAC secret.
+    pub secret: String,
+}
+
 /// JWT token manager.
+///
+/// Tokens are signed with the primary key and carry its `kid`. Verification
+/// accepts the primary key and any additional verification keys, so a key
+/// can be rotated without logging everyone out: add the new key for
+/// verification, make it primary, then drop the old one once its tokens
+/// have expired.
 #[derive(Clone)]
 pub struct JwtManager {
+    signing_kid: String,
     encoding_key: EncodingKey,
-    decoding_key: DecodingKey,
+    /// Verification keys by `kid`, primary first.
+    decoding_keys: Vec<(String, DecodingKey)>,
     issuer: String,
     /// Token validity duration in hours.
     token_duration_hours: i64,
 }
 
 impl JwtManager {
-    /// Create a new JWT manager with the given secret.
+    /// Create a new JWT manager signing with the given secret.
     pub fn new(secret: &str, issuer: String, token_duration_hours: i64) -> Self {
         Self {
+            signing_kid: DEFAULT_JWT_KEY_ID.to_string(),
             encoding_key: EncodingKey::from_secret(secret.as_bytes()),
-            decoding_key: DecodingKey::from_secret(secret.as_bytes()),
+            decoding_keys: vec![(
+                DEFAULT_JWT_KEY_ID.to_string(),
+                DecodingKey::from_secret(secret.as_bytes()),
+            )],
             issuer,
             token_duration_hours,
         }
     }
 
+    /// Set the `kid` of the signing key.
+    pub fn with_key_id(mut self, kid: impl Into<String>) -> Self {
+        self.signing_kid = kid.into();
+        self.decoding_keys[0].0 = self.signing_kid.clone();
+        self
+    }
+
+    /// Also accept tokens signed with `key`.
+    pub fn with_verification_key(mut self, key: &JwtVerificationKey) -> Self {
+        self.decoding_keys.push((
+            key.kid.clone(),
+            DecodingKey::from_secret(key.secret.as_bytes()),
+        ));
+        self
+    }
+
     /// Get token duration in hours.
     pub fn token_duration_hours(&self) -> i64 {
         self.token_duration_hours
@@ -138,22 +204,46 @@ impl JwtManager {
             iss: self.issuer.clone(),
         };
 
-        encode(&Header::default(), &claims, &self.encoding_key)
+        let header = Header {
+            kid: Some(self.signing_kid.clone()),
+            ..Header::default()
+        };
+        encode(&header, &claims, &self.encoding_key)
             .map_err(|e| ShieldError::Internal(format!("Failed to generate token: {}", e)))
     }
 
     /// Validate and decode a JWT token.
+    ///
+    /// A token naming a known `kid` is checked against that key only;
+    /// tokens without one (or with an unknown one) are tried against every
+    /// key, primary first.
     pub fn validate_token(&self, token: &str) -> ShieldResult<Claims> {
         let mut validation = Validation::default();
         validation.set_issuer(&[&self.issuer]);
 
-        let token_data: TokenData<Claims> =
-            decode(token, &self.decoding_key, &validation).map_err(|e| {
-                tracing::debug!(error = %e, "JWT validation failed");
-                ShieldError::BadRequest(format!("Invalid token: {}", e))
-            })?;
+        let kid = decode_header(token).ok().and_then(|header| header.kid);
+        let keys: Vec<_> = match self
+            .decoding_keys
+            .iter()
+            .find(|(id, _)| Some(id) == kid.as_ref())
+        {
+            Some(key) => vec![key],
+            None => self.decoding_keys.iter().collect(),
+        };
 
-        Ok(token_data.claims)
+        let mut first_error = None;
+        for (_, key) in keys {
+            match decode::<Claims>(token, key, &validation) {
+                Ok(TokenData { claims, .. }) => return Ok(claims),
+                Err(e) => {
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+
+        let e = first_error.expect("there is always a primary key");
+        tracing::debug!(error = %e, "JWT validation failed");
+        Err(ShieldError::BadRequest(format!("Invalid token: {}", e)))
     }
 }
 
@@ -164,7 +254,7 @@ pub struct ConfiguredUser {
     pub id: String,
     /// User email.
     pub email: String,
-    /// Password hash (SHA256 hex).
+    /// Password hash (Argon2 PHC string, or legacy SHA256 hex).
     pub password_hash: String,
     /// User role.
     pub role: UserRole,
@@ -173,11 +263,7 @@ pub struct ConfiguredUser {
 impl ConfiguredUser {
     /// Verify a password against the stored hash.
     pub fn verify_password(&self, password: &str) -> bool {
-        use sha2::{Digest, Sha256};
-        let mut hasher = Sha256::new();
-        hasher.update(password.as_bytes());
-        let hash = hex::encode(hasher.finalize());
-        hash == self.password_hash
+        super::verify_password(password, &self.password_hash)
     }
 }
 
@@ -202,6 +288,11 @@ impl UserStore {
         self.users.get(email)
     }
 
+    /// Find a user by ID.
+    pub fn find_by_id(&self, id: &str) -> Option<&ConfiguredUser> {
+        self.users.values().find(|user| user.id == id)
+    }
+
     /// Authenticate a user with email and password.
     pub fn authenticate(&self, email: &str, password: &str) -> Option<&ConfiguredUser> {
         self.find_by_email(email)
@@ -227,6 +318,42 @@ mod tests {
         assert_eq!(claims.role, UserRole::Admin);
     }
 
+    #[test]
+    fn test_token_signed_with_old_key_verifies_after_rotation() {
+        let old = JwtManager::new("old-secret-key-12345", "shield-core".to_string(), 24)
+            .with_key_id("2024-01");
+        let token = old
+            .generate_token("user-1", "admin@example.com", UserRole::Admin)
+            .unwrap();
+        assert_eq!(
+            decode_header(&token).unwrap().kid.as_deref(),
+            Some("2024-01")
+        );
+
+        // The new key becomes primary; the old one is kept for verification
+        let rotated = JwtManager::new("new-secret-key-67890", "shield-core".to_string(), 24)
+            .with_key_id("2024-06")
+            .with_verification_key(&JwtVerificationKey {
+                kid: "2024-01".to_string(),
+                secret: "old-secret-key-12345".to_string(),
+            });
+        assert_eq!(rotated.validate_token(&token).unwrap().sub, "user-1");
+        let new_token = rotated
+            .generate_token("user-2", "reviewer@example.com", UserRole::Reviewer)
+            .unwrap();
+        assert_eq!(
+            decode_header(&new_token).unwrap().kid.as_deref(),
+            Some("2024-06")
+        );
+        assert_eq!(rotated.validate_token(&new_token).unwrap().sub, "user-2");
+
+        // Once the old key is dropped, its tokens are rejected
+        let dropped = JwtManager::new("new-secret-key-67890", "shield-core".to_string(), 24)
+            .with_key_id("2024-06");
+        assert!(dropped.validate_token(&token).is_err());
+        assert!(dropped.validate_token(&new_token).is_ok());
+    }
+
     #[test]
     fn test_user_authentication() {
         use sha2::{Digest, Sha256};
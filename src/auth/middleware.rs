@@ -10,7 +10,8 @@ use axum::{
 };
 use serde::Serialize;
 
-use crate::auth::{ApiKeyValidator, Claims, JwtManager};
+use crate::auth::{ApiKeyValidator, AppKeyContext, Claims, JwtManager};
+use crate::domain::ApiScope;
 
 /// Error response for authentication failures.
 #[derive(Debug, Serialize)]
@@ -28,6 +29,8 @@ impl IntoResponse for AuthError {
 /// Extract and validate API key from request.
 ///
 /// Looks for `X-API-Key` header or `Authorization: Bearer <key>` header.
+/// Configured keys attach their [`crate::auth::ApiKeyInfo`]; app keys attach
+/// the app's [`crate::auth::AppKeyContext`].
 pub async fn require_api_key(
     State(validator): State<ApiKeyValidator>,
     mut request: Request<Body>,
@@ -55,20 +58,46 @@ pub async fn require_api_key(
         code: "MISSING_API_KEY".to_string(),
     })?;
 
-    let key_info = validator.validate(&api_key).await.ok_or_else(|| {
+    // Add key info (configured keys) or the app (app keys) to request
+    // extensions for handlers to access
+    if let Some(key_info) = validator.validate(&api_key).await {
+        request.extensions_mut().insert(key_info);
+    } else if let Some(app) = validator.validate_app(&api_key).await {
+        request.extensions_mut().insert(app);
+    } else {
         tracing::warn!(key_prefix = %&api_key[..8.min(api_key.len())], "Invalid API key attempted");
-        AuthError {
+        return Err(AuthError {
             error: "Invalid API key".to_string(),
             code: "INVALID_API_KEY".to_string(),
-        }
-    })?;
-
-    // Add key info to request extensions for handlers to access
-    request.extensions_mut().insert(key_info);
+        });
+    }
 
     Ok(next.run(request).await)
 }
 
+/// Reject app keys lacking the route's scope with 403.
+///
+/// Runs after [`require_api_key`]; configured keys carry no scopes and keep
+/// full access.
+pub async fn require_scope(
+    State(scope): State<ApiScope>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    if let Some(app) = request.extensions().get::<AppKeyContext>() {
+        if !app.scopes.iter().any(|held| held.grants(scope)) {
+            tracing::warn!(app_id = %app.app_id, scope = %scope, "API key lacks required scope");
+            let error = AuthError {
+                error: format!("API key lacks the {} scope", scope),
+                code: "INSUFFICIENT_SCOPE".to_string(),
+            };
+            return (StatusCode::FORBIDDEN, Json(error)).into_response();
+        }
+    }
+
+    next.run(request).await
+}
+
 /// Extract and validate JWT token from request.
 ///
 /// Expects `Authorization: Bearer <token>` header.
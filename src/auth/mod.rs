@@ -3,11 +3,16 @@
 //! Provides two authentication mechanisms:
 //! - API Key: For agent/LLM clients calling action evaluation
 //! - JWT: For admin console accessing HITL endpoints
+//!
+//! Console user passwords are checked against a configurable strength
+//! policy and hashed with Argon2.
 
 mod api_key;
 mod jwt;
 mod middleware;
+mod password;
 
 pub use api_key::*;
 pub use jwt::*;
 pub use middleware::*;
+pub use password::*;
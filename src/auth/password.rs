@@ -0,0 +1,167 @@
+//! Password policy and hashing.
+//!
+//! New passwords must pass the configured strength policy and are stored as
+//! Argon2id PHC strings. Legacy unsalted SHA256 hex hashes (as found in
+//! older configs) still verify.
+
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::{Algorithm, Argon2, Params, Version};
+use sha2::{Digest, Sha256};
+
+use crate::config::PasswordConfig;
+use crate::error::{ShieldError, ShieldResult};
+
+/// Check a password against the strength policy.
+///
+/// The error lists every requirement the password misses.
+pub fn validate_password(config: &PasswordConfig, password: &str) -> ShieldResult<()> {
+    let mut missing = Vec::new();
+    if password.chars().count() < config.min_length {
+        missing.push(format!("be at least {} characters long", config.min_length));
+    }
+    if config.require_uppercase && !password.chars().any(|c| c.is_uppercase()) {
+        missing.push("contain an uppercase letter".to_string());
+    }
+    if config.require_lowercase && !password.chars().any(|c| c.is_lowercase()) {
+        missing.push("contain a lowercase letter".to_string());
+    }
+    if config.require_digit && !password.chars().any(|c| c.is_ascii_digit()) {
+        missing.push("contain a digit".to_string());
+    }
+    if config.require_symbol && password.chars().all(|c| c.is_alphanumeric()) {
+        missing.push("contain a symbol".to_string());
+    }
+
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(ShieldError::BadRequest(format!(
+            "Password is too weak: it must {}",
+            missing.join(", ")
+        )))
+    }
+}
+
+/// Validate a new password and hash it with the configured Argon2 cost.
+pub fn hash_password(config: &PasswordConfig, password: &str) -> ShieldResult<String> {
+    validate_password(config, password)?;
+
+    let params = Params::new(
+        config.argon2_memory_kib,
+        config.argon2_iterations,
+        config.argon2_parallelism,
+        None,
+    )
+    .map_err(|e| ShieldError::Internal(format!("Invalid Argon2 parameters: {}", e)))?;
+    let salt = SaltString::encode_b64(uuid::Uuid::new_v4().as_bytes())
+        .map_err(|e| ShieldError::Internal(e.to_string()))?;
+
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| ShieldError::Internal(format!("Failed to hash password: {}", e)))
+}
+
+/// Verify a password against an Argon2 PHC string or a legacy SHA256 hex hash.
+pub fn verify_password(password: &str, hash: &str) -> bool {
+    if hash.starts_with('$') {
+        // Cost parameters are read from the hash itself
+        return PasswordHash::new(hash)
+            .map(|parsed| {
+                Argon2::default()
+                    .verify_password(password.as_bytes(), &parsed)
+                    .is_ok()
+            })
+            .unwrap_or(false);
+    }
+
+    let mut hasher = Sha256::new();
+    hasher.update(password.as_bytes());
+    hex::encode(hasher.finalize()) == hash
+}
+
+/// [`hash_password`] on the blocking thread pool. Argon2 is deliberately
+/// slow, so request handlers must not run it on the async runtime.
+pub async fn hash_password_blocking(
+    config: &PasswordConfig,
+    password: &str,
+) -> ShieldResult<String> {
+    let config = config.clone();
+    let password = password.to_string();
+    tokio::task::spawn_blocking(move || hash_password(&config, &password))
+        .await
+        .map_err(|e| ShieldError::Internal(format!("Password hashing task failed: {}", e)))?
+}
+
+/// [`verify_password`] on the blocking thread pool.
+pub async fn verify_password_blocking(password: &str, hash: &str) -> bool {
+    let password = password.to_string();
+    let hash = hash.to_string();
+    tokio::task::spawn_blocking(move || verify_password(&password, &hash))
+        .await
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Cheap hashing so tests stay fast.
+    fn config() -> PasswordConfig {
+        PasswordConfig {
+            argon2_memory_kib: 1024,
+            argon2_iterations: 1,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_weak_password_is_rejected() {
+        let result = hash_password(&config(), "password");
+        match result {
+            Err(ShieldError::BadRequest(msg)) => {
+                assert!(msg.contains("at least 12 characters"));
+                assert!(msg.contains("uppercase letter"));
+                assert!(msg.contains("digit"));
+                assert!(!msg.contains("lowercase letter"));
+            }
+            other => panic!("expected weak password error, got {:?}", other),
+        }
+
+        let strict = PasswordConfig {
+            require_symbol: true,
+            ..config()
+        };
+        assert!(validate_password(&strict, "Correct-Horse-42").is_ok());
+        assert!(validate_password(&strict, "CorrectHorse42x").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_blocking_helpers_hash_and_verify() {
+        let hash = hash_password_blocking(&config(), "CorrectHorse42x")
+            .await
+            .unwrap();
+        assert!(verify_password_blocking("CorrectHorse42x", &hash).await);
+        assert!(!verify_password_blocking("WrongHorse42x", &hash).await);
+        assert!(hash_password_blocking(&config(), "weak").await.is_err());
+    }
+
+    #[test]
+    fn test_strong_password_is_hashed_and_verifies() {
+        let hash = hash_password(&config(), "CorrectHorse42x").unwrap();
+        assert!(hash.starts_with("$argon2id$v=19$m=1024,t=1,p=1$"));
+        assert!(verify_password("CorrectHorse42x", &hash));
+        assert!(!verify_password("CorrectHorse42y", &hash));
+
+        // Salted: the same password hashes differently each time
+        assert_ne!(hash, hash_password(&config(), "CorrectHorse42x").unwrap());
+    }
+
+    #[test]
+    fn test_legacy_sha256_hashes_still_verify() {
+        // echo -n "shield2024" | shasum -a 256
+        let legacy = "2ae2107409cfc29923bd8429b398786c5bf41ea4bb0eccd3c5b11af42ca3fba3";
+        assert!(verify_password("shield2024", legacy));
+        assert!(!verify_password("shield2025", legacy));
+    }
+}
@@ -29,6 +29,8 @@ pub enum ActionType {
     CloseAccount,
     /// Refund a transaction.
     RefundTransaction,
+    /// Withdraw crypto assets to an external wallet.
+    CryptoTransfer,
     /// Unknown or unclassified action.
     Unknown,
 }
@@ -45,6 +47,7 @@ impl std::fmt::Display for ActionType {
             ActionType::UpdateProfile => write!(f, "update_profile"),
             ActionType::CloseAccount => write!(f, "close_account"),
             ActionType::RefundTransaction => write!(f, "refund_transaction"),
+            ActionType::CryptoTransfer => write!(f, "crypto_transfer"),
             ActionType::Unknown => write!(f, "unknown"),
         }
     }
@@ -63,6 +66,9 @@ impl ActionType {
             "update_profile" | "updateprofile" | "profile" => ActionType::UpdateProfile,
             "close_account" | "closeaccount" => ActionType::CloseAccount,
             "refund_transaction" | "refundtransaction" | "refund" => ActionType::RefundTransaction,
+            "crypto_transfer" | "cryptotransfer" | "crypto_withdrawal" => {
+                ActionType::CryptoTransfer
+            }
             _ => ActionType::Unknown,
         }
     }
@@ -96,6 +102,51 @@ pub struct PayBillPayload {
     pub reference: Option<String>,
 }
 
+/// Payload for RefundTransaction action.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RefundTransactionPayload {
+    /// Action ID of the original transfer or payment, as returned by Shield.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub original_transaction_id: Option<String>,
+    pub amount: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// Payload for CryptoTransfer action.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CryptoTransferPayload {
+    /// Asset ticker, e.g. "BTC" or "USDC".
+    pub asset: String,
+    /// Network the transfer settles on, e.g. "bitcoin" or "ethereum".
+    pub network: String,
+    /// Wallet address receiving the funds.
+    pub destination_address: String,
+    /// Amount in units of `asset`.
+    pub amount: f64,
+}
+
+/// One turn of the conversation that led to an action.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct ConversationTurn {
+    /// Who spoke: "user", "assistant", "tool", ...
+    pub role: String,
+    pub content: String,
+}
+
+/// Earlier actions sharing an action's trace ID.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceHistory {
+    /// When the trace ID was first seen.
+    pub first_seen: DateTime<Utc>,
+    /// Whether any of those actions was blocked.
+    pub blocked: bool,
+    /// Whether any of them belonged to a different user.
+    pub other_user: bool,
+}
+
 /// An action proposed by an LLM/agent.
 ///
 /// This is the primary input to the Shield evaluation pipeline.
@@ -136,6 +187,12 @@ pub struct AgentAction {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub cot_trace: Option<String>,
 
+    /// Earlier turns of the conversation, oldest first (optional).
+    /// Scanned together with the intent so injections split across turns
+    /// are caught.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub conversation: Vec<ConversationTurn>,
+
     /// Additional metadata for future extensions.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub metadata: Option<serde_json::Value>,
@@ -149,6 +206,44 @@ fn default_trace_id() -> String {
     Uuid::new_v4().to_string()
 }
 
+/// Nesting depth below which payload strings are no longer collected.
+pub const MAX_PAYLOAD_DEPTH: usize = 32;
+
+/// Add the strings in `value` to `out`, paths prefixed with `path`.
+/// Returns whether anything was left out for being nested too deep.
+fn collect_payload_strings<'a>(
+    value: &'a serde_json::Value,
+    path: String,
+    depth: usize,
+    out: &mut Vec<(String, &'a str)>,
+) -> bool {
+    if depth > MAX_PAYLOAD_DEPTH {
+        return true;
+    }
+    let mut truncated = false;
+    match value {
+        serde_json::Value::String(s) => out.push((path, s)),
+        serde_json::Value::Array(items) => {
+            for (i, item) in items.iter().enumerate() {
+                truncated |=
+                    collect_payload_strings(item, format!("{}[{}]", path, i), depth + 1, out);
+            }
+        }
+        serde_json::Value::Object(fields) => {
+            for (key, field) in fields {
+                let path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                truncated |= collect_payload_strings(field, path, depth + 1, out);
+            }
+        }
+        _ => {}
+    }
+    truncated
+}
+
 impl AgentAction {
     /// Create a new AgentAction with required fields.
     pub fn new(
@@ -170,6 +265,7 @@ impl AgentAction {
             action_type,
             payload,
             cot_trace: None,
+            conversation: Vec::new(),
             metadata: None,
             created_at: Utc::now(),
         }
@@ -181,13 +277,128 @@ impl AgentAction {
         self
     }
 
+    /// Every string in the payload with its path (`memo.lines[0]`), however
+    /// deeply nested it is, up to [`MAX_PAYLOAD_DEPTH`] levels down, and
+    /// whether the payload went deeper than that.
+    pub fn payload_strings(&self) -> (Vec<(String, &str)>, bool) {
+        let mut strings = Vec::new();
+        let truncated = collect_payload_strings(&self.payload, String::new(), 0, &mut strings);
+        (strings, truncated)
+    }
+
     /// Try to extract the amount from the payload (for monetary actions).
     pub fn extract_amount(&self) -> Option<f64> {
         match self.action_type {
             ActionType::TransferFunds
             | ActionType::PayBill
             | ActionType::RequestLoan
-            | ActionType::RefundTransaction => self.payload.get("amount").and_then(|v| v.as_f64()),
+            | ActionType::RefundTransaction
+            | ActionType::CryptoTransfer => self.payload.get("amount").and_then(|v| v.as_f64()),
+            _ => None,
+        }
+    }
+
+    /// Try to extract the referenced original transaction (for refunds).
+    pub fn extract_original_transaction_id(&self) -> Option<&str> {
+        match self.action_type {
+            ActionType::RefundTransaction => self
+                .payload
+                .get("original_transaction_id")
+                .and_then(|v| v.as_str()),
+            _ => None,
+        }
+    }
+
+    /// Try to extract the source account's available balance, reported in
+    /// the payload or, failing that, the metadata.
+    pub fn extract_available_balance(&self) -> Option<f64> {
+        self.payload
+            .get("available_balance")
+            .or_else(|| {
+                self.metadata
+                    .as_ref()
+                    .and_then(|m| m.get("available_balance"))
+            })
+            .and_then(|v| v.as_f64())
+    }
+
+    /// Whether the action moves money out to a third party.
+    ///
+    /// Transfers flagged `"internal": true` (between the user's own
+    /// accounts) are not outflows.
+    pub fn is_external_outflow(&self) -> bool {
+        match self.action_type {
+            ActionType::PayBill => true,
+            ActionType::TransferFunds => !self
+                .payload
+                .get("internal")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false),
+            _ => false,
+        }
+    }
+
+    /// Whether the action only reads data (balances, transaction history).
+    pub fn is_read_only(&self) -> bool {
+        matches!(
+            self.action_type,
+            ActionType::GetBalance | ActionType::GetTransactions
+        )
+    }
+
+    /// Whether the action carries an amount (transfers, bills, loans,
+    /// refunds, crypto).
+    pub fn is_monetary(&self) -> bool {
+        matches!(
+            self.action_type,
+            ActionType::TransferFunds
+                | ActionType::PayBill
+                | ActionType::RequestLoan
+                | ActionType::RefundTransaction
+                | ActionType::CryptoTransfer
+        )
+    }
+
+    /// Whether the action type always needs review (new beneficiaries,
+    /// account closure).
+    pub fn is_high_risk(&self) -> bool {
+        matches!(
+            self.action_type,
+            ActionType::AddBeneficiary | ActionType::CloseAccount
+        )
+    }
+
+    /// Try to extract the account money leaves from.
+    pub fn extract_source_account(&self) -> Option<&str> {
+        self.payload.get("from_account_id").and_then(|v| v.as_str())
+    }
+
+    /// Try to extract the account money goes to.
+    pub fn extract_destination_account(&self) -> Option<&str> {
+        self.payload.get("to_account_id").and_then(|v| v.as_str())
+    }
+
+    /// Try to extract the account a transfer, bill payment or new
+    /// beneficiary points at.
+    pub fn extract_beneficiary_account(&self) -> Option<&str> {
+        let field = |name| self.payload.get(name).and_then(|v| v.as_str());
+        match self.action_type {
+            ActionType::TransferFunds => field("to_account_id"),
+            ActionType::PayBill => field("biller_id"),
+            ActionType::AddBeneficiary => field("account_identifier")
+                .or_else(|| field("account_id"))
+                .or_else(|| field("to_account_id")),
+            _ => None,
+        }
+    }
+
+    /// Try to extract the wallet address a crypto transfer goes to.
+    pub fn extract_crypto_destination(&self) -> Option<&str> {
+        match self.action_type {
+            ActionType::CryptoTransfer => self
+                .payload
+                .get("destination_address")
+                .and_then(|v| v.as_str()),
             _ => None,
         }
     }
@@ -245,4 +456,41 @@ mod tests {
 
         assert_eq!(action.extract_amount(), None);
     }
+
+    #[test]
+    fn test_payload_strings_walks_nested_values() {
+        let mut action = AgentAction::new(
+            "user123",
+            "chatbot",
+            "gpt-4",
+            "Pay my bill",
+            ActionType::PayBill,
+            serde_json::json!({
+                "biller_id": "ELEC-01",
+                "amount": 80.0,
+                "memo": {"lines": ["March", {"note": "thanks"}]}
+            }),
+        );
+        assert_eq!(
+            action.payload_strings(),
+            (
+                vec![
+                    ("biller_id".to_string(), "ELEC-01"),
+                    ("memo.lines[0]".to_string(), "March"),
+                    ("memo.lines[1].note".to_string(), "thanks"),
+                ],
+                false
+            )
+        );
+
+        // Nothing is collected past the depth limit
+        let mut deep = serde_json::json!("too deep");
+        for _ in 0..=MAX_PAYLOAD_DEPTH {
+            deep = serde_json::json!([deep]);
+        }
+        action.payload = serde_json::json!({"nested": deep});
+        let (strings, truncated) = action.payload_strings();
+        assert!(strings.is_empty());
+        assert!(truncated);
+    }
 }
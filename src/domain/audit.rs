@@ -0,0 +1,59 @@
+//! Audit trail of administrative changes.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// One recorded change: who did what to which object, with its state
+/// before and after. Entries are only ever appended.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AuditEntry {
+    /// Unique identifier.
+    pub id: Uuid,
+    /// Company the change belongs to (none for actions evaluated outside
+    /// a company).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub company_id: Option<Uuid>,
+    /// User who made the change.
+    pub actor_id: String,
+    /// What was done, e.g. `hitl_task.approved`.
+    pub action: String,
+    /// Kind of object changed, e.g. `hitl_task`.
+    pub target_type: String,
+    /// ID of the object changed.
+    pub target_id: String,
+    /// State before the change.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub before: Option<serde_json::Value>,
+    /// State after the change.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after: Option<serde_json::Value>,
+    /// When the change was made.
+    pub created_at: DateTime<Utc>,
+}
+
+impl AuditEntry {
+    /// Record a change made now.
+    pub fn new(
+        company_id: Option<Uuid>,
+        actor_id: impl Into<String>,
+        action: impl Into<String>,
+        target_type: impl Into<String>,
+        target_id: impl Into<String>,
+        before: Option<serde_json::Value>,
+        after: Option<serde_json::Value>,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            company_id,
+            actor_id: actor_id.into(),
+            action: action.into(),
+            target_type: target_type.into(),
+            target_id: target_id.into(),
+            before,
+            after,
+            created_at: Utc::now(),
+        }
+    }
+}
@@ -0,0 +1,39 @@
+//! Approved beneficiaries.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// An account a company has approved as a destination for one of its
+/// users' transfers and payments.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Beneficiary {
+    /// Unique identifier.
+    pub id: Uuid,
+    /// Company the approval belongs to.
+    pub company_id: Uuid,
+    /// End user who may send to the account.
+    pub user_id: String,
+    /// Destination account (account ID, IBAN, biller ID, ...).
+    pub account_identifier: String,
+    /// When the account was approved.
+    pub approved_at: DateTime<Utc>,
+}
+
+impl Beneficiary {
+    /// Approve `account_identifier` for `user_id` now.
+    pub fn new(
+        company_id: Uuid,
+        user_id: impl Into<String>,
+        account_identifier: impl Into<String>,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            company_id,
+            user_id: user_id.into(),
+            account_identifier: account_identifier.into(),
+            approved_at: Utc::now(),
+        }
+    }
+}
@@ -8,6 +8,15 @@ use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 use uuid::Uuid;
 
+use crate::domain::ExpiringSecret;
+
+/// Plan assigned to companies that haven't been put on a specific plan.
+pub const UNLIMITED_PLAN: &str = "unlimited";
+
+fn default_plan() -> String {
+    UNLIMITED_PLAN.to_string()
+}
+
 /// A company/organization that owns apps.
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct Company {
@@ -20,6 +29,9 @@ pub struct Company {
     /// Optional description.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+    /// Billing plan, which determines member/app limits.
+    #[serde(default = "default_plan")]
+    pub plan: String,
     /// When the company was created.
     pub created_at: DateTime<Utc>,
     /// When the company was last updated.
@@ -35,6 +47,7 @@ impl Company {
             name,
             slug,
             description,
+            plan: default_plan(),
             created_at: now,
             updated_at: now,
         }
@@ -67,6 +80,13 @@ pub enum CompanyRole {
     Viewer,
 }
 
+impl CompanyRole {
+    /// Whether the role counts towards a plan's owner/admin limit.
+    pub fn is_owner_or_admin(self) -> bool {
+        matches!(self, CompanyRole::Owner | CompanyRole::Admin)
+    }
+}
+
 impl std::fmt::Display for CompanyRole {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -158,6 +178,41 @@ impl std::str::FromStr for AppStatus {
     }
 }
 
+/// Agent-facing operation an app's API key may perform.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub enum ApiScope {
+    /// Evaluate actions (`/v1/actions/evaluate`, `/v1/evaluate`), and read
+    /// the decisions on the app's own actions.
+    #[serde(rename = "actions:evaluate")]
+    ActionsEvaluate,
+    /// Read evaluated actions and their decisions.
+    #[serde(rename = "actions:read")]
+    ActionsRead,
+}
+
+impl ApiScope {
+    /// Whether holding this scope allows what `required` guards. Evaluating
+    /// includes reading back decisions, so agents can poll a held action.
+    pub fn grants(self, required: ApiScope) -> bool {
+        self == required || (self == ApiScope::ActionsEvaluate && required == ApiScope::ActionsRead)
+    }
+}
+
+impl std::fmt::Display for ApiScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ApiScope::ActionsEvaluate => write!(f, "actions:evaluate"),
+            ApiScope::ActionsRead => write!(f, "actions:read"),
+        }
+    }
+}
+
+/// Scopes of apps that don't name any: evaluation only, which is all app
+/// keys could do before scopes existed.
+pub fn default_app_scopes() -> Vec<ApiScope> {
+    vec![ApiScope::ActionsEvaluate]
+}
+
 /// An app/agent that belongs to a company.
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct App {
@@ -177,8 +232,34 @@ pub struct App {
     pub api_key_prefix: String,
     /// Current status.
     pub status: AppStatus,
-    /// Rate limit (requests per minute).
+    /// Rate limit on `/v1/evaluate` (requests per minute, 0 for no limit).
     pub rate_limit: u32,
+    /// What the app's API key may do.
+    #[serde(default = "default_app_scopes")]
+    pub scopes: Vec<ApiScope>,
+    /// Trusted internal app, allowed to override guard settings per request.
+    #[serde(default)]
+    pub trusted: bool,
+    /// Non-production app: evaluations are returned but kept out of metrics
+    /// and never create HITL tasks or notifications.
+    #[serde(default)]
+    pub sandbox: bool,
+    /// Policy profile the app is evaluated against (company thresholds if unset).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy_profile_id: Option<Uuid>,
+    /// Other companies the app may evaluate for via `X-Company-Id`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub permitted_company_ids: Vec<Uuid>,
+    /// Webhook receiving this app's notifications instead of the company's.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook_url: Option<String>,
+    /// Key the app's webhook payloads are signed with. Only returned when
+    /// rotated.
+    #[serde(skip)]
+    pub webhook_secret: Option<String>,
+    /// Key replaced by the last rotation, still signed with until it expires.
+    #[serde(skip)]
+    pub previous_webhook_secret: Option<ExpiringSecret>,
     /// When the app was created.
     pub created_at: DateTime<Utc>,
     /// When the app was last updated.
@@ -209,12 +290,25 @@ impl App {
             api_key_prefix,
             status: AppStatus::Active,
             rate_limit,
+            scopes: default_app_scopes(),
+            trusted: false,
+            sandbox: false,
+            policy_profile_id: None,
+            permitted_company_ids: Vec::new(),
+            webhook_url: None,
+            webhook_secret: None,
+            previous_webhook_secret: None,
             created_at: now,
             updated_at: now,
             last_used_at: None,
         }
     }
 
+    /// Whether the app's key may perform `scope`.
+    pub fn has_scope(&self, scope: ApiScope) -> bool {
+        self.scopes.iter().any(|held| held.grants(scope))
+    }
+
     /// Generate a secure API key.
     fn generate_api_key() -> String {
         use sha2::{Digest, Sha256};
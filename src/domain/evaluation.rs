@@ -7,8 +7,8 @@ use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 use uuid::Uuid;
 
-/// Risk tier classification.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+/// Risk tier classification, ordered from lowest to highest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum RiskTier {
     /// Low risk - safe to auto-approve.
@@ -68,6 +68,105 @@ impl std::fmt::Display for DecisionStatus {
     }
 }
 
+/// Pipeline layer a reason came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReasonLayer {
+    /// Input firewall, including the neural guard.
+    Firewall,
+    /// Alignment checker.
+    Alignment,
+    /// Policy engine.
+    Policy,
+}
+
+/// Machine-readable counterpart of an entry in `reasons`, for clients that
+/// localize or filter reasons.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct ReasonEntry {
+    /// Stable code of the rule or detector (e.g. `FIREWALL_BLOCK`).
+    pub code: String,
+    /// Human-readable message, as in `reasons`.
+    pub message: String,
+    /// Layer that produced the reason.
+    pub layer: ReasonLayer,
+    /// How serious the reason is on its own.
+    pub severity: RiskTier,
+}
+
+impl ReasonEntry {
+    pub fn new(
+        code: impl Into<String>,
+        message: impl Into<String>,
+        layer: ReasonLayer,
+        severity: RiskTier,
+    ) -> Self {
+        Self {
+            code: code.into(),
+            message: message.into(),
+            layer,
+            severity,
+        }
+    }
+}
+
+/// How each pipeline layer saw an action. Layers that didn't run (turned
+/// off, or skipped after a firewall block) are left out.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct DecisionLayers {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub firewall: Option<FirewallLayer>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alignment: Option<AlignmentLayer>,
+    /// Policy rules that triggered (empty when none did).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy: Option<Vec<PolicyRuleHit>>,
+}
+
+/// Firewall verdict, including the neural guard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FirewallVerdict {
+    Clean,
+    Suspicious,
+    Blocked,
+}
+
+/// Firewall layer outcome.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct FirewallLayer {
+    pub outcome: FirewallVerdict,
+    pub reasons: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub matched_patterns: Vec<String>,
+}
+
+/// Alignment checker verdict.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AlignmentVerdict {
+    Aligned,
+    Misaligned,
+    /// The checker couldn't tell.
+    Unknown,
+}
+
+/// Alignment layer outcome.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct AlignmentLayer {
+    pub outcome: AlignmentVerdict,
+    pub reasons: Vec<String>,
+}
+
+/// A policy rule that triggered.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct PolicyRuleHit {
+    pub rule_id: String,
+    pub description: String,
+    pub suggests_block: bool,
+    pub requires_hitl: bool,
+}
+
 /// Result of evaluating an agent action.
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct EvaluationResult {
@@ -83,15 +182,37 @@ pub struct EvaluationResult {
     /// Assessed risk tier.
     pub risk_tier: RiskTier,
 
+    /// Numeric risk from 0 to 100 (absent on evaluations stored before it
+    /// was recorded, until they are backfilled).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub risk_score: Option<f64>,
+
     /// Human-readable reasons for the decision.
     pub reasons: Vec<String>,
 
+    /// The same reasons with their codes, layer and severity.
+    #[serde(default)]
+    pub reason_entries: Vec<ReasonEntry>,
+
     /// IDs/keys of rules that triggered.
     pub rule_hits: Vec<String>,
 
     /// Names of neural detectors that fired (stub for MVP).
     pub neural_signals: Vec<String>,
 
+    /// Firewall keywords/patterns found in the input, also quoted in `reasons`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub matched_patterns: Vec<String>,
+
+    /// Neural guard model that took part in the evaluation, if any ran.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub guard_model: Option<String>,
+
+    /// Per-layer breakdown of the decision (absent on evaluations stored
+    /// before it was recorded).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layers: Option<DecisionLayers>,
+
     /// When this evaluation was created.
     pub created_at: DateTime<Utc>,
 }
@@ -110,9 +231,14 @@ impl EvaluationResult {
             agent_action_id,
             decision,
             risk_tier,
+            risk_score: None,
             reasons,
+            reason_entries: Vec::new(),
             rule_hits,
             neural_signals: Vec::new(),
+            matched_patterns: Vec::new(),
+            guard_model: None,
+            layers: None,
             created_at: Utc::now(),
         }
     }
@@ -155,10 +281,107 @@ impl EvaluationResult {
     }
 }
 
+/// A decision change between two evaluations.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct DecisionChange {
+    pub from: DecisionStatus,
+    pub to: DecisionStatus,
+}
+
+/// A risk tier change between two evaluations.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct RiskTierChange {
+    pub from: RiskTier,
+    pub to: RiskTier,
+}
+
+/// What changed between an original evaluation and a re-evaluation.
+///
+/// Rule hits are compared as sets, so reordering alone is not a change.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct EvaluationDiff {
+    /// Whether anything below differs.
+    pub changed: bool,
+    /// Decision change, if the decision differs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decision: Option<DecisionChange>,
+    /// Risk tier change, if the tier differs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub risk_tier: Option<RiskTierChange>,
+    /// Rules that fire now but didn't originally.
+    pub rule_hits_added: Vec<String>,
+    /// Rules that fired originally but no longer do.
+    pub rule_hits_removed: Vec<String>,
+}
+
+impl EvaluationDiff {
+    /// Diff an original evaluation against a newer one.
+    pub fn between(original: &EvaluationResult, updated: &EvaluationResult) -> Self {
+        let decision = (original.decision != updated.decision).then_some(DecisionChange {
+            from: original.decision,
+            to: updated.decision,
+        });
+        let risk_tier = (original.risk_tier != updated.risk_tier).then_some(RiskTierChange {
+            from: original.risk_tier,
+            to: updated.risk_tier,
+        });
+        let rule_hits_added = set_difference(&updated.rule_hits, &original.rule_hits);
+        let rule_hits_removed = set_difference(&original.rule_hits, &updated.rule_hits);
+
+        Self {
+            changed: decision.is_some()
+                || risk_tier.is_some()
+                || !rule_hits_added.is_empty()
+                || !rule_hits_removed.is_empty(),
+            decision,
+            risk_tier,
+            rule_hits_added,
+            rule_hits_removed,
+        }
+    }
+}
+
+/// Distinct items of `a` not present in `b`, in `a`'s order.
+fn set_difference(a: &[String], b: &[String]) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for item in a {
+        if !b.contains(item) && !out.contains(item) {
+            out.push(item.clone());
+        }
+    }
+    out
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_evaluation_diff_reports_changes() {
+        let action_id = Uuid::new_v4();
+        let original = EvaluationResult::allow(action_id);
+        let updated = EvaluationResult::require_hitl(
+            action_id,
+            vec!["Over limit".to_string()],
+            vec!["AMOUNT_REQUIRES_HITL".to_string()],
+        );
+
+        let diff = EvaluationDiff::between(&original, &updated);
+        assert!(diff.changed);
+        assert_eq!(
+            diff.decision,
+            Some(DecisionChange {
+                from: DecisionStatus::Allow,
+                to: DecisionStatus::RequireHitl,
+            })
+        );
+        assert_eq!(diff.risk_tier.unwrap().to, RiskTier::High);
+        assert_eq!(diff.rule_hits_added, vec!["AMOUNT_REQUIRES_HITL"]);
+        assert!(diff.rule_hits_removed.is_empty());
+
+        assert!(!EvaluationDiff::between(&updated, &updated).changed);
+    }
+
     #[test]
     fn test_decision_status_serialization() {
         let status = DecisionStatus::RequireHitl;
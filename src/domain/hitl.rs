@@ -5,7 +5,7 @@ use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 use uuid::Uuid;
 
-use super::{AgentAction, EvaluationResult};
+use super::{AgentAction, DecisionStatus, EvaluationResult};
 
 /// Status of a HITL task.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
@@ -69,6 +69,15 @@ pub struct HitlTask {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub review_notes: Option<String>,
 
+    /// Reviewer whose approval awaits (or received) a second reviewer's
+    /// confirmation.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_approver_id: Option<String>,
+
+    /// When the first approval was given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_approved_at: Option<DateTime<Utc>>,
+
     /// When this task was created.
     pub created_at: DateTime<Utc>,
 }
@@ -84,6 +93,8 @@ impl HitlTask {
             reviewer_id: None,
             reviewed_at: None,
             review_notes: None,
+            first_approver_id: None,
+            first_approved_at: None,
             created_at: Utc::now(),
         }
     }
@@ -96,6 +107,21 @@ impl HitlTask {
         self.review_notes = notes;
     }
 
+    /// Whether a first approval is waiting for a second reviewer.
+    pub fn awaiting_confirmation(&self) -> bool {
+        self.status == HitlStatus::Pending && self.first_approver_id.is_some()
+    }
+
+    /// The decision the action ends up with: `allow` once approved, `block`
+    /// once rejected, and still `require_hitl` while pending.
+    pub fn resolved_decision(&self) -> DecisionStatus {
+        match self.status {
+            HitlStatus::Pending => DecisionStatus::RequireHitl,
+            HitlStatus::Approved => DecisionStatus::Allow,
+            HitlStatus::Rejected => DecisionStatus::Block,
+        }
+    }
+
     /// Reject the task.
     pub fn reject(&mut self, reviewer_id: String, notes: Option<String>) {
         self.status = HitlStatus::Rejected;
@@ -111,6 +137,8 @@ pub struct HitlTaskDetails {
     pub task: HitlTask,
     pub agent_action: AgentAction,
     pub evaluation: EvaluationResult,
+    /// Effective decision after review; see [`HitlTask::resolved_decision`].
+    pub resolved_decision: DecisionStatus,
 }
 
 /// Summary of a HITL task for list views.
@@ -126,6 +154,52 @@ pub struct HitlTaskSummary {
     pub created_at: DateTime<Utc>,
 }
 
+impl HitlTaskSummary {
+    /// Summarize a task just created for `action`.
+    pub fn new(task: &HitlTask, action: &AgentAction, evaluation: &EvaluationResult) -> Self {
+        Self {
+            id: task.id,
+            user_id: action.user_id.clone(),
+            action_type: action.action_type.to_string(),
+            amount: action.payload.get("amount").and_then(|v| v.as_f64()),
+            risk_tier: evaluation.risk_tier.to_string(),
+            status: task.status,
+            created_at: task.created_at,
+        }
+    }
+}
+
+/// A reviewer's decision totals within one company.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ReviewerStats {
+    pub reviewer_id: String,
+    /// Tasks the reviewer approved or rejected.
+    pub total_reviewed: i64,
+    pub approved: i64,
+    pub rejected: i64,
+    /// Mean seconds from task creation to the reviewer's decision.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avg_time_to_decision_secs: Option<f64>,
+    /// Approvals of tasks the engine rated high or critical risk.
+    pub overrides: i64,
+    /// Share of reviewed tasks that were overrides (0-1).
+    pub override_rate: f64,
+}
+
+/// One decision in a reviewer's history.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ReviewerDecision {
+    pub task_id: Uuid,
+    pub agent_action_id: Uuid,
+    pub action_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount: Option<f64>,
+    pub risk_tier: String,
+    pub status: HitlStatus,
+    pub created_at: DateTime<Utc>,
+    pub reviewed_at: DateTime<Utc>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,43 @@
+//! Stored responses for idempotent evaluate requests.
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// The first response produced under an `Idempotency-Key`, replayed to
+/// retries of the same request.
+#[derive(Debug, Clone)]
+pub struct IdempotencyRecord {
+    /// App whose key sent the request; `None` for service API keys. Keys
+    /// are only unique within one app.
+    pub app_id: Option<Uuid>,
+    /// Key sent by the client.
+    pub key: String,
+    /// Fingerprint of the request body that produced the response.
+    pub request_hash: String,
+    /// Evaluation the request produced.
+    pub evaluation_id: Uuid,
+    /// Response body returned the first time.
+    pub response: serde_json::Value,
+    /// When the response was stored.
+    pub created_at: DateTime<Utc>,
+}
+
+impl IdempotencyRecord {
+    /// Store a response produced now.
+    pub fn new(
+        app_id: Option<Uuid>,
+        key: impl Into<String>,
+        request_hash: impl Into<String>,
+        evaluation_id: Uuid,
+        response: serde_json::Value,
+    ) -> Self {
+        Self {
+            app_id,
+            key: key.into(),
+            request_hash: request_hash.into(),
+            evaluation_id,
+            response,
+            created_at: Utc::now(),
+        }
+    }
+}
@@ -36,6 +36,25 @@ impl TimeRange {
     pub fn start_time(&self) -> DateTime<Utc> {
         Utc::now() - chrono::Duration::hours(self.hours())
     }
+
+    /// Granularity used when the caller doesn't ask for one: hourly for the
+    /// last 24 hours, daily otherwise.
+    pub fn default_granularity(&self) -> Granularity {
+        match self {
+            TimeRange::Last24h => Granularity::Hour,
+            _ => Granularity::Day,
+        }
+    }
+
+    /// Short form accepted in queries (`24h`, `7d`, ...).
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TimeRange::Last24h => "24h",
+            TimeRange::Last7d => "7d",
+            TimeRange::Last30d => "30d",
+            TimeRange::Last90d => "90d",
+        }
+    }
 }
 
 impl std::str::FromStr for TimeRange {
@@ -47,7 +66,10 @@ impl std::str::FromStr for TimeRange {
             "7d" | "last7d" => Ok(TimeRange::Last7d),
             "30d" | "last30d" => Ok(TimeRange::Last30d),
             "90d" | "last90d" => Ok(TimeRange::Last90d),
-            _ => Err(format!("Invalid time range: {}. Use 24h, 7d, 30d, or 90d", s)),
+            _ => Err(format!(
+                "Invalid time range: {}. Use 24h, 7d, 30d, or 90d",
+                s
+            )),
         }
     }
 }
@@ -68,6 +90,23 @@ pub enum Granularity {
     Day,
 }
 
+impl Granularity {
+    /// Length of one bucket.
+    pub fn step(&self) -> chrono::Duration {
+        match self {
+            Granularity::Hour => chrono::Duration::hours(1),
+            Granularity::Day => chrono::Duration::days(1),
+        }
+    }
+
+    /// Start of the bucket containing `ts`.
+    pub fn bucket_start(&self, ts: DateTime<Utc>) -> DateTime<Utc> {
+        let step = self.step().num_seconds();
+        let secs = ts.timestamp();
+        DateTime::from_timestamp(secs - secs.rem_euclid(step), 0).unwrap_or(ts)
+    }
+}
+
 impl Default for Granularity {
     fn default() -> Self {
         Granularity::Day
@@ -137,6 +176,115 @@ impl Default for MetricsOverview {
     }
 }
 
+/// Evaluation statistics for a bounded period.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PeriodStats {
+    /// Period start (inclusive).
+    pub start: DateTime<Utc>,
+    /// Period end (exclusive).
+    pub end: DateTime<Utc>,
+    /// Total number of actions evaluated.
+    pub total_actions: i64,
+    /// Number of allowed actions.
+    pub allowed_actions: i64,
+    /// Number of blocked actions.
+    pub blocked_actions: i64,
+    /// Number of actions escalated to HITL.
+    pub escalated_actions: i64,
+    /// Number of attack attempts detected.
+    pub attack_attempts: i64,
+    /// Number of unique users.
+    pub users_impacted: i64,
+}
+
+/// Change of a single metric between two periods.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MetricDelta {
+    /// Absolute change (period A minus period B).
+    pub change: i64,
+    /// Percentage change relative to period B.
+    pub percent_change: f64,
+}
+
+impl MetricDelta {
+    /// Compute the change from `previous` to `current`.
+    pub fn between(current: i64, previous: i64) -> Self {
+        Self {
+            change: current - previous,
+            percent_change: percent_change(current, previous),
+        }
+    }
+}
+
+/// Deltas between two periods, per metric.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PeriodDeltas {
+    pub total_actions: MetricDelta,
+    pub allowed_actions: MetricDelta,
+    pub blocked_actions: MetricDelta,
+    pub escalated_actions: MetricDelta,
+    pub attack_attempts: MetricDelta,
+    pub users_impacted: MetricDelta,
+}
+
+/// Comparison of two arbitrary periods.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MetricsComparison {
+    /// Period being analyzed.
+    pub period_a: PeriodStats,
+    /// Baseline period.
+    pub period_b: PeriodStats,
+    /// Change from period B to period A.
+    pub deltas: PeriodDeltas,
+}
+
+impl MetricsComparison {
+    /// Compare period A against baseline period B.
+    pub fn new(period_a: PeriodStats, period_b: PeriodStats) -> Self {
+        let deltas = PeriodDeltas {
+            total_actions: MetricDelta::between(period_a.total_actions, period_b.total_actions),
+            allowed_actions: MetricDelta::between(
+                period_a.allowed_actions,
+                period_b.allowed_actions,
+            ),
+            blocked_actions: MetricDelta::between(
+                period_a.blocked_actions,
+                period_b.blocked_actions,
+            ),
+            escalated_actions: MetricDelta::between(
+                period_a.escalated_actions,
+                period_b.escalated_actions,
+            ),
+            attack_attempts: MetricDelta::between(
+                period_a.attack_attempts,
+                period_b.attack_attempts,
+            ),
+            users_impacted: MetricDelta::between(period_a.users_impacted, period_b.users_impacted),
+        };
+
+        Self {
+            period_a,
+            period_b,
+            deltas,
+        }
+    }
+}
+
+/// Percentage change from `previous` to `current`.
+///
+/// Growth from zero is reported as 100%.
+pub fn percent_change(current: i64, previous: i64) -> f64 {
+    if previous == 0 {
+        if current > 0 {
+            100.0
+        } else {
+            0.0
+        }
+    } else {
+        ((current - previous) as f64 / previous as f64) * 100.0
+    }
+}
+
 /// A single data point in a time series.
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct TimeSeriesPoint {
@@ -175,6 +323,51 @@ pub struct RiskDistribution {
     pub data: Vec<RiskDistributionPoint>,
 }
 
+/// Decision rates for actions generated by one model.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ModelMetrics {
+    /// `model_name` reported with the actions.
+    pub model_name: String,
+    /// Evaluated actions.
+    pub total_actions: i64,
+    /// Actions blocked.
+    pub blocked_actions: i64,
+    /// Actions escalated to HITL.
+    pub escalated_actions: i64,
+    /// Percentage of actions blocked.
+    pub block_rate: f64,
+    /// Percentage of actions escalated to HITL.
+    pub escalation_rate: f64,
+}
+
+/// Decision rates broken down by model.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ModelBreakdown {
+    /// One entry per model, most actions first.
+    pub data: Vec<ModelMetrics>,
+}
+
+/// HITL decisions made by one reviewer.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ReviewerMetrics {
+    /// Reviewer who decided the tasks.
+    pub reviewer_id: String,
+    /// Tasks approved.
+    pub approved: i64,
+    /// Tasks rejected.
+    pub rejected: i64,
+    /// Median seconds from task creation to the reviewer's decision.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub median_time_to_decision_secs: Option<f64>,
+}
+
+/// HITL decisions broken down by reviewer.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ReviewerBreakdown {
+    /// One entry per reviewer, most decisions first.
+    pub data: Vec<ReviewerMetrics>,
+}
+
 /// Attack statistics per app.
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct AppAttackStats {
@@ -228,3 +421,106 @@ impl Default for AppMetrics {
     }
 }
 
+/// Render a company's metrics in the OpenMetrics text format.
+///
+/// Values cover `time_range`, so everything is exposed as a gauge labelled
+/// with the company and window rather than as a monotonic counter.
+pub fn render_openmetrics(
+    company_id: Uuid,
+    time_range: TimeRange,
+    overview: &MetricsOverview,
+    risk: &RiskDistribution,
+    models: &ModelBreakdown,
+) -> String {
+    let base = format!(
+        "company_id=\"{}\",time_range=\"{}\"",
+        company_id,
+        time_range.as_str()
+    );
+    let mut out = String::new();
+    let mut gauge = |name: &str, help: &str, samples: Vec<(String, String)>| {
+        out.push_str(&format!(
+            "# TYPE {} gauge\n# HELP {} {}\n",
+            name, name, help
+        ));
+        for (labels, value) in samples {
+            out.push_str(&format!("{}{{{}{}}} {}\n", name, base, labels, value));
+        }
+    };
+
+    gauge(
+        "shield_actions",
+        "Actions evaluated.",
+        vec![(String::new(), overview.total_actions.to_string())],
+    );
+    gauge(
+        "shield_blocked_actions",
+        "Actions blocked.",
+        vec![(String::new(), overview.blocked_actions.to_string())],
+    );
+    gauge(
+        "shield_escalated_actions",
+        "Actions escalated to HITL.",
+        vec![(String::new(), overview.escalated_actions.to_string())],
+    );
+    gauge(
+        "shield_attack_attempts",
+        "Attack attempts detected.",
+        vec![(String::new(), overview.attack_attempts.to_string())],
+    );
+    gauge(
+        "shield_attack_success_ratio",
+        "Share of attack attempts that were allowed.",
+        vec![(
+            String::new(),
+            (overview.attack_success_rate / 100.0).to_string(),
+        )],
+    );
+    gauge(
+        "shield_users_impacted",
+        "Distinct users targeted by attacks.",
+        vec![(String::new(), overview.users_impacted.to_string())],
+    );
+    gauge(
+        "shield_risk_tier_actions",
+        "Actions by risk tier.",
+        risk.data
+            .iter()
+            .map(|p| (label("tier", &p.tier), p.count.to_string()))
+            .collect(),
+    );
+    let per_model = |value: fn(&ModelMetrics) -> i64| {
+        models
+            .data
+            .iter()
+            .map(|m| (label("model", &m.model_name), value(m).to_string()))
+            .collect()
+    };
+    gauge(
+        "shield_model_actions",
+        "Actions evaluated by model.",
+        per_model(|m| m.total_actions),
+    );
+    gauge(
+        "shield_model_blocked_actions",
+        "Actions blocked by model.",
+        per_model(|m| m.blocked_actions),
+    );
+    gauge(
+        "shield_model_escalated_actions",
+        "Actions escalated to HITL by model.",
+        per_model(|m| m.escalated_actions),
+    );
+
+    out.push_str("# EOF\n");
+    out
+}
+
+/// Format an extra label, escaped per the OpenMetrics spec.
+fn label(name: &str, value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n");
+    format!(",{}=\"{}\"", name, escaped)
+}
@@ -4,19 +4,31 @@
 
 mod action;
 mod attack;
+mod audit;
+mod beneficiary;
 mod company;
 mod evaluation;
 mod hitl;
+mod idempotency;
 mod metrics;
+mod refresh_token;
+mod retention;
 mod settings;
 mod user;
+mod webhook;
 
 pub use action::*;
 pub use attack::*;
+pub use audit::*;
+pub use beneficiary::*;
 pub use company::*;
 pub use evaluation::*;
 pub use hitl::*;
+pub use idempotency::*;
 pub use metrics::*;
+pub use refresh_token::*;
+pub use retention::*;
 pub use settings::*;
 pub use user::*;
+pub use webhook::*;
 
@@ -0,0 +1,64 @@
+//! Refresh tokens for console sessions.
+
+use chrono::{DateTime, Duration, Utc};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::auth::UserRole;
+
+/// A long-lived token exchanged for new access tokens until it expires or
+/// is revoked. Only its hash is stored.
+#[derive(Debug, Clone)]
+pub struct RefreshToken {
+    /// Unique identifier.
+    pub id: Uuid,
+    /// SHA-256 of the token, hex encoded.
+    pub token_hash: String,
+    /// User the token was issued to.
+    pub user_id: String,
+    /// User email, copied into refreshed access tokens.
+    pub email: String,
+    /// User role, copied into refreshed access tokens.
+    pub role: UserRole,
+    /// When the token stops being accepted.
+    pub expires_at: DateTime<Utc>,
+    /// When the token was revoked (logout).
+    pub revoked_at: Option<DateTime<Utc>>,
+    /// When the token was issued.
+    pub created_at: DateTime<Utc>,
+}
+
+impl RefreshToken {
+    /// Issue a token for a user, valid for `lifetime`. Returns the stored
+    /// record and the token to hand to the client.
+    pub fn issue(
+        user_id: impl Into<String>,
+        email: impl Into<String>,
+        role: UserRole,
+        lifetime: Duration,
+    ) -> (Self, String) {
+        let token = format!("rt_{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+        let now = Utc::now();
+        let record = Self {
+            id: Uuid::new_v4(),
+            token_hash: Self::hash(&token),
+            user_id: user_id.into(),
+            email: email.into(),
+            role,
+            expires_at: now + lifetime,
+            revoked_at: None,
+            created_at: now,
+        };
+        (record, token)
+    }
+
+    /// Hash a token for storage and lookup.
+    pub fn hash(token: &str) -> String {
+        hex::encode(Sha256::digest(token.as_bytes()))
+    }
+
+    /// Whether the token may still be exchanged at `now`.
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.revoked_at.is_none() && self.expires_at > now
+    }
+}
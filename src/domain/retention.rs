@@ -0,0 +1,71 @@
+//! Data retention types.
+
+use chrono::Duration;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// Rows deleted by a purge of old actions.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, ToSchema)]
+pub struct PurgeSummary {
+    pub actions: u64,
+    pub evaluations: u64,
+    pub hitl_tasks: u64,
+    pub attack_events: u64,
+    /// Audit trail entries of reviewers' decisions on the deleted HITL tasks.
+    pub reviewer_decisions: u64,
+    /// Cached evaluate responses for the deleted evaluations.
+    pub idempotency_keys: u64,
+    /// Webhook deliveries logged before the cutoff.
+    pub webhook_deliveries: u64,
+}
+
+impl PurgeSummary {
+    /// Add the rows deleted by another purge.
+    pub fn add(&mut self, other: PurgeSummary) {
+        self.actions += other.actions;
+        self.evaluations += other.evaluations;
+        self.hitl_tasks += other.hitl_tasks;
+        self.attack_events += other.attack_events;
+        self.reviewer_decisions += other.reviewer_decisions;
+        self.idempotency_keys += other.idempotency_keys;
+        self.webhook_deliveries += other.webhook_deliveries;
+    }
+}
+
+/// Parse an age like `90d` or `12h`.
+pub fn parse_retention_age(age: &str) -> Result<Duration, String> {
+    let trimmed = age.trim();
+    let amount = |amount: &str| amount.parse::<i64>().ok().filter(|amount| *amount > 0);
+    let duration = if let Some(days) = trimmed.strip_suffix('d') {
+        amount(days).and_then(Duration::try_days)
+    } else if let Some(hours) = trimmed.strip_suffix('h') {
+        amount(hours).and_then(Duration::try_hours)
+    } else {
+        None
+    };
+    duration.ok_or_else(|| format!("Invalid age '{}': use e.g. 90d or 12h", age))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_retention_age() {
+        assert_eq!(parse_retention_age("90d"), Ok(Duration::days(90)));
+        assert_eq!(parse_retention_age("12h"), Ok(Duration::hours(12)));
+        for age in [
+            "",
+            "d",
+            "90",
+            "0d",
+            "-5d",
+            "3w",
+            "1.5d",
+            "5é",
+            "99999999999999d",
+        ] {
+            assert!(parse_retention_age(age).is_err(), "{age}");
+        }
+    }
+}
@@ -2,10 +2,16 @@
 //!
 //! Provides company configuration and policy thresholds.
 
+use std::net::{IpAddr, Ipv4Addr};
+
+use chrono::{DateTime, Datelike, Duration, TimeZone, Timelike, Utc, Weekday};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 use uuid::Uuid;
 
+use crate::domain::{ActionType, RiskTier};
+
 /// Policy thresholds for safety rules.
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct PolicyThresholds {
@@ -15,12 +21,299 @@ pub struct PolicyThresholds {
     pub hitl_threshold_amount: f64,
     /// Maximum actions per hour per user.
     pub velocity_limit_per_hour: i32,
-    /// Maximum actions per day per user.
+    /// Maximum actions per day per user, counted over the company's local
+    /// calendar day.
     pub velocity_limit_per_day: i32,
     /// Whether to block high-risk actions automatically.
     pub block_high_risk_actions: bool,
-    /// Whether to require HITL for new beneficiaries.
+    /// Whether to require HITL for transfers and payments to accounts that
+    /// aren't on the user's beneficiary allowlist. Off unless enabled.
+    #[serde(default)]
     pub require_hitl_for_new_beneficiaries: bool,
+    /// Maximum cumulative transfer and payment amount per user per day
+    /// (the company's local day). Actions that would push the daily total
+    /// past this require HITL.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub daily_transfer_limit: Option<f64>,
+    /// Intent phrasings trusted to match an action type.
+    /// Matching intents skip alignment flagging; firewall and policy still apply.
+    #[serde(default)]
+    pub alignment_allowlist: Vec<IntentAllowlistEntry>,
+    /// Stricter amount thresholds applied outside business hours.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub off_hours: Option<OffHoursSchedule>,
+    /// Escalation of transfer sequences that rapidly empty an account.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub drain_detection: Option<DrainDetection>,
+    /// Lowest risk tier recorded for allowed actions that move money, e.g.
+    /// `medium` to weight every allowed transfer in analytics.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowed_risk_floor: Option<RiskTier>,
+    /// Single-transfer ceiling: larger transfers and payments are blocked
+    /// outright instead of going to HITL.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub absolute_block_amount: Option<f64>,
+    /// Wallet addresses crypto transfers may go to. Transfers anywhere
+    /// else are blocked.
+    #[serde(default)]
+    pub crypto_address_allowlist: Vec<String>,
+}
+
+/// A named policy ruleset owned by a company.
+///
+/// Apps assigned to a profile are evaluated against its thresholds
+/// instead of the company-wide ones, e.g. separate consumer and
+/// business product lines.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PolicyProfile {
+    /// Unique identifier.
+    pub id: Uuid,
+    /// Company that owns the profile.
+    pub company_id: Uuid,
+    /// Profile name, unique within the company.
+    pub name: String,
+    /// Thresholds and lists applied to apps using this profile.
+    pub thresholds: PolicyThresholds,
+    /// When the profile was created.
+    pub created_at: DateTime<Utc>,
+    /// When the profile was last updated.
+    pub updated_at: DateTime<Utc>,
+}
+
+impl PolicyProfile {
+    /// Create a new profile.
+    pub fn new(company_id: Uuid, name: String, thresholds: PolicyThresholds) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4(),
+            company_id,
+            name,
+            thresholds,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+}
+
+/// Business hours schedule with stricter thresholds outside of it.
+///
+/// Hours are in the company's local timezone. Off-hours, each threshold is
+/// the lower of the base one and the schedule's.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct OffHoursSchedule {
+    /// Hour business hours start (0-23, inclusive).
+    pub business_hours_start: u32,
+    /// Hour business hours end (1-24, exclusive).
+    pub business_hours_end: u32,
+    /// Whether Saturdays and Sundays are off-hours all day.
+    #[serde(default = "default_weekends_off")]
+    pub weekends_off: bool,
+    /// Auto-approval limit applied off-hours.
+    pub max_auto_approve_amount: f64,
+    /// HITL threshold applied off-hours.
+    pub hitl_threshold_amount: f64,
+}
+
+fn default_weekends_off() -> bool {
+    true
+}
+
+impl OffHoursSchedule {
+    /// Check whether the given instant falls outside business hours in `tz`.
+    pub fn is_off_hours(&self, at: DateTime<Utc>, tz: Tz) -> bool {
+        let local = at.with_timezone(&tz);
+        if self.weekends_off && matches!(local.weekday(), Weekday::Sat | Weekday::Sun) {
+            return true;
+        }
+        let hour = local.hour();
+        hour < self.business_hours_start || hour >= self.business_hours_end
+    }
+}
+
+/// Check that a console base URL is an absolute http(s) URL that review
+/// paths can be appended to.
+pub fn validate_console_url(url: &str) -> Result<(), String> {
+    let parsed = reqwest::Url::parse(url).map_err(|e| format!("Invalid console URL {url}: {e}"))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(format!("Console URL must use http or https: {url}"));
+    }
+    if parsed.query().is_some() || parsed.fragment().is_some() {
+        return Err(format!(
+            "Console URL must not have a query or fragment: {url}"
+        ));
+    }
+    Ok(())
+}
+
+/// Hosts webhooks may never point at, whatever the configured blocklist says.
+const ALWAYS_BLOCKED_HOSTS: [&str; 2] = ["localhost", "*.localhost"];
+
+/// Check that a webhook URL is safe for the server to call on an admin's
+/// behalf: https, not an IP literal outside public ranges, and not a host
+/// matching `blocked_hosts` (exact names, or `*.suffix` for any subdomain)
+/// or `localhost`. Hostnames still have to resolve to public addresses; see
+/// [`is_public_ip`].
+pub fn validate_webhook_url(url: &str, blocked_hosts: &[String]) -> Result<reqwest::Url, String> {
+    let parsed = reqwest::Url::parse(url).map_err(|e| format!("Invalid webhook URL {url}: {e}"))?;
+    if parsed.scheme() != "https" {
+        return Err(format!("Webhook URL must use https: {url}"));
+    }
+    let host = parsed.host_str().unwrap_or_default();
+    let allowed = match host.trim_matches(['[', ']']).parse::<IpAddr>() {
+        Ok(ip) => is_public_ip(ip),
+        Err(_) => {
+            let host = host.trim_end_matches('.').to_ascii_lowercase();
+            !host.is_empty()
+                && !ALWAYS_BLOCKED_HOSTS
+                    .into_iter()
+                    .chain(blocked_hosts.iter().map(String::as_str))
+                    .any(|pattern| {
+                        let pattern = pattern.to_ascii_lowercase();
+                        match pattern.strip_prefix("*.") {
+                            Some(suffix) => host.ends_with(&format!(".{}", suffix)),
+                            None => host == pattern,
+                        }
+                    })
+        }
+    };
+    if !allowed {
+        return Err(format!(
+            "Webhook URL must not point at a local or private address: {url}"
+        ));
+    }
+    Ok(parsed)
+}
+
+/// Whether an address is routable on the public internet, i.e. not
+/// loopback, private, link-local, shared (CGNAT), benchmarking,
+/// documentation or otherwise reserved. IPv6 addresses embedding an IPv4
+/// one (mapped, compatible or NAT64) are judged by the IPv4 address.
+pub fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || ip.is_multicast()
+                || a == 0
+                || a >= 240
+                || (a == 100 && (64..128).contains(&b))
+                || (a == 198 && (b & 0xfe) == 18))
+        }
+        IpAddr::V6(ip) => {
+            if let Some(v4) = ip.to_ipv4_mapped() {
+                return is_public_ip(v4.into());
+            }
+            let segments = ip.segments();
+            // IPv4-compatible (::/96) and NAT64 (64:ff9b::/96) addresses
+            if matches!(
+                segments[..6],
+                [0, 0, 0, 0, 0, 0] | [0x64, 0xff9b, 0, 0, 0, 0]
+            ) {
+                let [.., a, b, c, d] = ip.octets();
+                return is_public_ip(Ipv4Addr::new(a, b, c, d).into());
+            }
+            let first = segments[0];
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                || (first & 0xfe00) == 0xfc00
+                || (first & 0xffc0) == 0xfe80
+                || (first == 0x2001 && segments[1] == 0x0db8))
+        }
+    }
+}
+
+/// Console page where a HITL task is reviewed.
+pub fn review_url(console_url: &str, task_id: Uuid) -> String {
+    format!("{}/hitl/{}", console_url.trim_end_matches('/'), task_id)
+}
+
+/// Start of the local calendar day containing `at` in `tz`.
+///
+/// Daily velocity and transfer limits count from this boundary, so a
+/// company's "per day" follows its own calendar rather than UTC's. When a DST
+/// change skips local midnight, the day starts at the first local time that exists.
+pub fn local_day_start(at: DateTime<Utc>, tz: Tz) -> DateTime<Utc> {
+    let local = at.with_timezone(&tz);
+    let midnight = local
+        .date_naive()
+        .and_hms_opt(0, 0, 0)
+        .expect("midnight is a valid time");
+    (0..=local.hour())
+        .find_map(|h| {
+            tz.from_local_datetime(&(midnight + Duration::hours(i64::from(h))))
+                .earliest()
+        })
+        .map_or(at, |start| start.with_timezone(&Utc))
+}
+
+/// Detection of transfers that rapidly move most of an account's funds.
+///
+/// Needs the account's available balance in the action payload or
+/// metadata; actions without it are not checked.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DrainDetection {
+    /// Minutes of transfer history considered.
+    #[serde(default = "default_drain_window")]
+    pub window_minutes: u32,
+    /// Fraction of available funds (0-1) moved within the window that
+    /// requires HITL.
+    #[serde(default = "default_drain_fraction")]
+    pub max_fraction: f64,
+    /// Transfers within the window, including the current one, before
+    /// the rule applies. Single large transfers are left to amount rules.
+    #[serde(default = "default_drain_min_transfers")]
+    pub min_transfers: u32,
+}
+
+fn default_drain_window() -> u32 {
+    60
+}
+
+fn default_drain_fraction() -> f64 {
+    0.8
+}
+
+fn default_drain_min_transfers() -> u32 {
+    2
+}
+
+impl Default for DrainDetection {
+    fn default() -> Self {
+        Self {
+            window_minutes: default_drain_window(),
+            max_fraction: default_drain_fraction(),
+            min_transfers: default_drain_min_transfers(),
+        }
+    }
+}
+
+/// Intent phrases a company trusts for one action type.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct IntentAllowlistEntry {
+    /// Action type the phrases apply to.
+    pub action_type: ActionType,
+    /// Phrases matched case-insensitively anywhere in the original intent.
+    pub phrases: Vec<String>,
+}
+
+impl IntentAllowlistEntry {
+    /// Check whether this entry covers the given intent and action type.
+    pub fn matches(&self, action_type: &ActionType, intent: &str) -> bool {
+        if &self.action_type != action_type {
+            return false;
+        }
+        let intent_lower = intent.to_lowercase();
+        self.phrases
+            .iter()
+            .filter(|p| !p.trim().is_empty())
+            .any(|p| intent_lower.contains(&p.to_lowercase()))
+    }
 }
 
 impl Default for PolicyThresholds {
@@ -31,11 +324,101 @@ impl Default for PolicyThresholds {
             velocity_limit_per_hour: 10,
             velocity_limit_per_day: 50,
             block_high_risk_actions: true,
-            require_hitl_for_new_beneficiaries: true,
+            require_hitl_for_new_beneficiaries: false,
+            daily_transfer_limit: None,
+            alignment_allowlist: Vec::new(),
+            off_hours: None,
+            drain_detection: None,
+            allowed_risk_floor: None,
+            absolute_block_amount: None,
+            crypto_address_allowlist: Vec::new(),
+        }
+    }
+}
+
+/// Batching of HITL notifications into periodic digests.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct NotificationDigest {
+    /// Batch task notifications instead of sending one per task.
+    #[serde(default)]
+    pub enabled: bool,
+    /// Minutes to collect tasks before sending a summary.
+    #[serde(default = "default_digest_interval")]
+    pub interval_minutes: u32,
+    /// Still notify immediately for critical-risk tasks.
+    #[serde(default = "default_critical_immediate")]
+    pub critical_immediate: bool,
+}
+
+fn default_digest_interval() -> u32 {
+    15
+}
+
+fn default_critical_immediate() -> bool {
+    true
+}
+
+impl Default for NotificationDigest {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_minutes: default_digest_interval(),
+            critical_immediate: default_critical_immediate(),
+        }
+    }
+}
+
+/// Evaluation pipeline layers a company runs.
+///
+/// Lets tenants skip layers they don't need, e.g. the neural guard for
+/// cost. Disabling the guard here overrides the global setting and
+/// per-request overrides; enabling it only allows it to run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct PipelineLayers {
+    /// Keyword and pattern input firewall.
+    #[serde(default = "default_layer_enabled")]
+    pub firewall: bool,
+    /// Intent/action alignment check.
+    #[serde(default = "default_layer_enabled")]
+    pub alignment: bool,
+    /// Neural guard (Llama Guard).
+    #[serde(default = "default_layer_enabled")]
+    pub guard: bool,
+    /// Symbolic policy rules.
+    #[serde(default = "default_layer_enabled")]
+    pub policy: bool,
+}
+
+fn default_layer_enabled() -> bool {
+    true
+}
+
+impl Default for PipelineLayers {
+    fn default() -> Self {
+        Self {
+            firewall: true,
+            alignment: true,
+            guard: true,
+            policy: true,
         }
     }
 }
 
+/// A replaced webhook signing key, kept in use until `expires_at` so
+/// receivers can switch keys without failing verification.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpiringSecret {
+    pub secret: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl ExpiringSecret {
+    /// The secret, if it's still in use at `now`.
+    pub fn active_at(&self, now: DateTime<Utc>) -> Option<&str> {
+        (now < self.expires_at).then_some(self.secret.as_str())
+    }
+}
+
 /// Company settings.
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct CompanySettings {
@@ -49,11 +432,35 @@ pub struct CompanySettings {
     /// Webhook URL for notifications.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub webhook_url: Option<String>,
+    /// Key webhook payloads are signed with. Only returned when rotated.
+    #[serde(skip)]
+    pub webhook_secret: Option<String>,
+    /// Key replaced by the last rotation, still signed with until it expires.
+    #[serde(skip)]
+    pub previous_webhook_secret: Option<ExpiringSecret>,
     /// Notification email.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub notification_email: Option<String>,
+    /// Console base URL for HITL review links, overriding `server.console_url`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub console_url: Option<String>,
+    /// Digest mode for HITL notifications.
+    #[serde(default)]
+    pub notification_digest: NotificationDigest,
+    /// Evaluation pipeline layers.
+    #[serde(default)]
+    pub pipeline: PipelineLayers,
+    /// Days the company's actions are kept, overriding `retention.days`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retention_days: Option<u32>,
+    /// IANA timezone used for schedules (e.g. "America/New_York").
+    pub timezone: String,
     /// Policy thresholds.
     pub policy_thresholds: PolicyThresholds,
+    /// When the thresholds were last set. Until then the configured default
+    /// amount limits apply rather than the ones above.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thresholds_updated_at: Option<DateTime<Utc>>,
 }
 
 impl CompanySettings {
@@ -64,8 +471,16 @@ impl CompanySettings {
             name,
             logo: None,
             webhook_url: None,
+            webhook_secret: None,
+            previous_webhook_secret: None,
             notification_email: None,
+            console_url: None,
+            notification_digest: NotificationDigest::default(),
+            pipeline: PipelineLayers::default(),
+            retention_days: None,
+            timezone: "UTC".to_string(),
             policy_thresholds: PolicyThresholds::default(),
+            thresholds_updated_at: None,
         }
     }
 }
@@ -87,3 +502,97 @@ pub struct UpdateSettingsRequest {
     pub policy_thresholds: Option<PolicyThresholds>,
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_webhook_url_validation() {
+        let blocked = vec!["localhost".to_string(), "*.internal".to_string()];
+        for url in [
+            "https://169.254.169.254/latest/meta-data",
+            "https://127.0.0.1/hook",
+            "https://192.168.1.10/hook",
+            "https://[fd00::1]/hook",
+            "https://[::ffff:10.0.0.1]/hook",
+            "https://[::10.0.0.1]/hook",
+            "https://[64:ff9b::10.0.0.1]/hook",
+            "https://[2001:db8::1]/hook",
+            "https://198.18.0.1/hook",
+            "https://198.19.255.1/hook",
+            "https://240.0.0.1/hook",
+            "http://hooks.example.com/shield",
+            "https://localhost/hook",
+            "https://metadata.google.internal/hook",
+            "not a url",
+        ] {
+            assert!(validate_webhook_url(url, &blocked).is_err(), "{url}");
+        }
+        assert!(validate_webhook_url("https://hooks.example.com/shield", &blocked).is_ok());
+        assert!(validate_webhook_url("https://93.184.216.34/shield", &blocked).is_ok());
+        assert!(validate_webhook_url("https://198.20.0.1/shield", &blocked).is_ok());
+        assert!(validate_webhook_url("https://[64:ff9b::93.184.216.34]/shield", &blocked).is_ok());
+
+        // The blocklist is configurable, but localhost stays blocked
+        let blocked = vec!["hooks.example.com".to_string()];
+        assert!(validate_webhook_url("https://hooks.example.com/shield", &blocked).is_err());
+        assert!(validate_webhook_url("https://metadata.google.internal/hook", &blocked).is_ok());
+        assert!(validate_webhook_url("https://localhost/hook", &[]).is_err());
+        assert!(validate_webhook_url("https://api.localhost/hook", &[]).is_err());
+    }
+
+    #[test]
+    fn test_off_hours_uses_company_timezone() {
+        let schedule = OffHoursSchedule {
+            business_hours_start: 9,
+            business_hours_end: 17,
+            weekends_off: true,
+            max_auto_approve_amount: 20.0,
+            hitl_threshold_amount: 200.0,
+        };
+        let tz: Tz = "America/New_York".parse().unwrap();
+
+        // Wednesday 15:00 UTC is 11:00 in New York
+        let midday = Utc.with_ymd_and_hms(2026, 3, 18, 15, 0, 0).unwrap();
+        assert!(!schedule.is_off_hours(midday, tz));
+        assert!(schedule.is_off_hours(midday, "Asia/Tokyo".parse().unwrap()));
+
+        // Wednesday 23:00 UTC is 19:00 in New York
+        let evening = Utc.with_ymd_and_hms(2026, 3, 18, 23, 0, 0).unwrap();
+        assert!(schedule.is_off_hours(evening, tz));
+
+        // Saturday midday
+        let saturday = Utc.with_ymd_and_hms(2026, 3, 21, 15, 0, 0).unwrap();
+        assert!(schedule.is_off_hours(saturday, tz));
+    }
+
+    #[test]
+    fn test_local_day_follows_company_calendar() {
+        let tz: Tz = "America/Mexico_City".parse().unwrap();
+
+        // 02:30 UTC on the 19th is still 20:30 on the 18th in Mexico City
+        let at = Utc.with_ymd_and_hms(2026, 3, 19, 2, 30, 15).unwrap();
+        assert_eq!(
+            local_day_start(at, tz),
+            Utc.with_ymd_and_hms(2026, 3, 18, 6, 0, 0).unwrap()
+        );
+
+        // Half-hour offsets shift the day boundary too (08:00:15 in Kolkata)
+        assert_eq!(
+            local_day_start(at, "Asia/Kolkata".parse().unwrap()),
+            Utc.with_ymd_and_hms(2026, 3, 18, 18, 30, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_local_day_starts_after_skipped_midnight() {
+        // Chile springs forward at midnight, so 2026-09-06 starts at 01:00 local
+        let tz: Tz = "America/Santiago".parse().unwrap();
+        let at = Utc.with_ymd_and_hms(2026, 9, 6, 12, 0, 0).unwrap();
+        assert_eq!(
+            local_day_start(at, tz),
+            Utc.with_ymd_and_hms(2026, 9, 6, 4, 0, 0).unwrap()
+        );
+    }
+}
@@ -110,15 +110,8 @@ impl User {
 
     /// Verify password (returns false for OAuth-only users).
     pub fn verify_password(&self, password: &str) -> bool {
-        use sha2::{Digest, Sha256};
-
         match &self.password_hash {
-            Some(hash) => {
-                let mut hasher = Sha256::new();
-                hasher.update(password.as_bytes());
-                let computed = hex::encode(hasher.finalize());
-                computed == *hash
-            }
+            Some(hash) => crate::auth::verify_password(password, hash),
             None => false,
         }
     }
@@ -0,0 +1,58 @@
+//! Log of webhook deliveries.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// One webhook event as delivered to a receiver, including its retries.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct WebhookDeliveryRecord {
+    pub id: Uuid,
+    pub company_id: Uuid,
+    /// Notification type, e.g. `hitl_task_created`.
+    pub event_type: String,
+    pub url: String,
+    /// Status of the last answer, if the receiver answered.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status_code: Option<u16>,
+    /// Attempts made, retries included.
+    pub attempts: u32,
+    /// Why the last attempt failed; unset once delivered.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    /// JSON body exactly as sent; replays send it again unchanged.
+    pub payload: String,
+    /// Delivery this one replayed, if it is a replay.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replay_of: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl WebhookDeliveryRecord {
+    /// A delivery of `payload` about to be attempted.
+    pub fn new(
+        company_id: Uuid,
+        event_type: impl Into<String>,
+        url: impl Into<String>,
+        payload: String,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            company_id,
+            event_type: event_type.into(),
+            url: url.into(),
+            status_code: None,
+            attempts: 0,
+            last_error: None,
+            payload,
+            replay_of: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Whether the receiver accepted the event.
+    pub fn delivered(&self) -> bool {
+        self.attempts > 0 && self.last_error.is_none()
+    }
+}
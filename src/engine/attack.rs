@@ -0,0 +1,97 @@
+//! Attack classification.
+//!
+//! Evaluations stopped by the firewall, the neural guard or the alignment
+//! checker are recorded as attack events for the attacks dashboard. This
+//! infers what kind of attack they were from the evaluation's rule hits and
+//! reasons.
+
+use crate::domain::{AttackOutcome, AttackType, DecisionStatus, EvaluationResult};
+use crate::engine::SafetyCategory;
+
+/// Classify an evaluation as an attack, if a detection layer fired and the
+/// action was blocked or escalated.
+pub fn classify_attack(evaluation: &EvaluationResult) -> Option<(AttackType, AttackOutcome)> {
+    let outcome = match evaluation.decision {
+        DecisionStatus::Block => AttackOutcome::Blocked,
+        DecisionStatus::RequireHitl => AttackOutcome::Escalated,
+        DecisionStatus::Allow => return None,
+    };
+    let hit = |rule: &str| evaluation.rule_hits.iter().any(|h| h == rule);
+
+    let attack_type = if hit("FIREWALL_BLOCK") || hit("FIREWALL_SUSPICIOUS") {
+        // The guard reports through the firewall; its category is the more
+        // specific signal
+        evaluation
+            .reasons
+            .iter()
+            .find_map(|reason| SafetyCategory::from_reason(reason))
+            .map(|category| category.attack_type())
+            .unwrap_or(AttackType::PromptInjection)
+    } else if hit("ALIGNMENT_MISALIGNED") {
+        AttackType::Misalignment
+    } else {
+        return None;
+    };
+
+    Some((attack_type, outcome))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::RiskTier;
+    use uuid::Uuid;
+
+    fn evaluation(
+        decision: DecisionStatus,
+        rule_hits: &[&str],
+        reasons: &[&str],
+    ) -> EvaluationResult {
+        EvaluationResult::new(
+            Uuid::new_v4(),
+            decision,
+            RiskTier::High,
+            reasons.iter().map(|r| r.to_string()).collect(),
+            rule_hits.iter().map(|r| r.to_string()).collect(),
+        )
+    }
+
+    #[test]
+    fn test_classifies_detection_layer_hits() {
+        let keyword = evaluation(
+            DecisionStatus::Block,
+            &["FIREWALL_BLOCK"],
+            &["Blocked keyword detected: 'ignore all previous instructions'"],
+        );
+        assert_eq!(
+            classify_attack(&keyword),
+            Some((AttackType::PromptInjection, AttackOutcome::Blocked))
+        );
+
+        let guard = evaluation(
+            DecisionStatus::RequireHitl,
+            &["FIREWALL_SUSPICIOUS"],
+            &["Llama Guard: Privacy violation"],
+        );
+        assert_eq!(
+            classify_attack(&guard),
+            Some((AttackType::DataExfiltration, AttackOutcome::Escalated))
+        );
+
+        let misaligned = evaluation(DecisionStatus::RequireHitl, &["ALIGNMENT_MISALIGNED"], &[]);
+        assert_eq!(
+            classify_attack(&misaligned),
+            Some((AttackType::Misalignment, AttackOutcome::Escalated))
+        );
+
+        // Policy escalations and allowed suspicions aren't attacks
+        let policy = evaluation(
+            DecisionStatus::RequireHitl,
+            &["AMOUNT_EXCEEDS_AUTO_LIMIT"],
+            &[],
+        );
+        assert_eq!(classify_attack(&policy), None);
+        let allowed = evaluation(DecisionStatus::Allow, &["FIREWALL_SUSPICIOUS"], &[]);
+        assert_eq!(classify_attack(&allowed), None);
+    }
+}
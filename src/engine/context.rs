@@ -0,0 +1,96 @@
+//! Evaluation context - per-request state supplied by the caller.
+//!
+//! The engine layers are synchronous and have no database access, so
+//! anything that depends on company settings or action history is
+//! resolved up front and passed in through this struct.
+
+use chrono::{DateTime, Utc};
+
+use crate::domain::{
+    AgentAction, DrainDetection, IntentAllowlistEntry, PipelineLayers, RiskTier, TraceHistory,
+};
+
+/// Company policy and pre-fetched history for a single evaluation.
+#[derive(Debug, Clone, Default)]
+pub struct EvaluationContext {
+    /// Cap on a user's cumulative transfers and payments per day.
+    pub daily_transfer_limit: Option<f64>,
+    /// Amount the user has already moved since the start of the company's
+    /// local day, excluding this action, in the base currency.
+    pub daily_transfer_total: f64,
+    /// A currency of those transfers with no exchange rate, which leaves
+    /// the total unknown.
+    pub daily_transfer_unconvertible: Option<String>,
+    /// Amount still refundable on the original transaction a refund
+    /// references, if that transaction is known and recent.
+    pub refundable_amount: Option<f64>,
+    /// Payload currency of that original transaction (`None` when it has
+    /// none, i.e. the base currency).
+    pub refundable_currency: Option<String>,
+    /// Intent phrasings the company trusts to bypass alignment flagging.
+    pub alignment_allowlist: Vec<IntentAllowlistEntry>,
+    /// Whether the action falls outside the company's business hours.
+    pub off_hours: bool,
+    /// Auto-approval limit overriding the configured default.
+    pub max_auto_amount: Option<f64>,
+    /// HITL threshold overriding the configured default.
+    pub hitl_threshold: Option<f64>,
+    /// Force the neural guard on or off for this evaluation (trusted callers only).
+    pub guard_override: Option<bool>,
+    /// Account-drain detection settings, if the company enabled them.
+    pub drain_detection: Option<DrainDetection>,
+    /// The user's outgoing transfers within the drain window, excluding this action.
+    pub recent_outflow_count: u32,
+    /// Amount of those outgoing transfers, in the base currency.
+    pub recent_outflow_total: f64,
+    /// A currency of those transfers with no exchange rate, which leaves
+    /// the total unknown.
+    pub recent_outflow_unconvertible: Option<String>,
+    /// Whether the account registry knows the payload's source account
+    /// (`None` when there is none or the registry can't tell).
+    pub source_account_exists: Option<bool>,
+    /// Same for the destination account.
+    pub destination_account_exists: Option<bool>,
+    /// When the user's most recent block within the cool-down window happened.
+    pub recent_block_at: Option<DateTime<Utc>>,
+    /// Single-transfer amount above which the action is blocked outright.
+    pub absolute_block_amount: Option<f64>,
+    /// Whether the destination is an approved beneficiary of the user
+    /// (`None` when the company doesn't check).
+    pub beneficiary_approved: Option<bool>,
+    /// Wallet addresses the company allows crypto transfers to.
+    pub crypto_address_allowlist: Vec<String>,
+    /// Lowest risk tier for allowed actions that move money.
+    pub allowed_risk_floor: Option<RiskTier>,
+    /// The user's evaluations within the rate window, excluding this action.
+    pub recent_user_evaluations: u32,
+    /// Earlier actions with the same trace ID, if any.
+    pub trace_history: Option<TraceHistory>,
+    /// Transfers and payments allowed per user per hour, overriding the
+    /// configured default (0 means no limit).
+    pub velocity_limit_per_hour: Option<u32>,
+    /// Transfers and payments allowed per user per day (0 means no limit).
+    pub velocity_limit_per_day: Option<u32>,
+    /// The user's transfers and payments within the last hour, excluding
+    /// this action.
+    pub hourly_transfer_count: u32,
+    /// Same since the start of the company's local day.
+    pub daily_transfer_count: u32,
+    /// The transfer counts couldn't be looked up.
+    pub transfer_counts_unavailable: bool,
+    /// Pipeline layers the company runs (the guard is governed by
+    /// `guard_override`).
+    pub layers: PipelineLayers,
+    /// The evaluation is a re-run or probe, not a new submission: checks
+    /// that keep their own history read it without adding to it.
+    pub dry_run: bool,
+}
+
+impl EvaluationContext {
+    /// Check whether the action's intent is allowlisted for its action type.
+    pub fn is_intent_allowlisted(&self, action: &AgentAction) -> bool {
+        self.alignment_allowlist
+            .iter()
+            .any(|entry| entry.matches(&action.action_type, &action.original_intent))
+    }
+}
@@ -3,12 +3,16 @@
 //! This module provides neural detection capabilities using Llama Guard 4
 //! for prompt injection, jailbreak, and other content safety classifications.
 
+use axum::async_trait;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
-use std::time::Duration;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
-use crate::domain::AgentAction;
-use crate::engine::firewall::{FirewallOutcome, InputFirewall};
+use crate::domain::{AgentAction, AttackType};
+use crate::engine::firewall::{AsyncInputFirewall, FirewallOutcome};
 
 /// OpenRouter API configuration.
 #[derive(Debug, Clone)]
@@ -21,6 +25,44 @@ pub struct OpenRouterConfig {
     pub timeout_secs: u64,
     /// Whether the guard is enabled.
     pub enabled: bool,
+    /// Verdict format to request from the model.
+    pub output_format: GuardOutputFormat,
+    /// Base URL of the OpenRouter API.
+    pub base_url: String,
+    /// Classifications kept for identical content (0 disables caching).
+    pub cache_capacity: usize,
+    /// How long a cached classification is reused, in seconds.
+    pub cache_ttl_secs: u64,
+    /// Stop actions when the guard can't classify them (API error,
+    /// timeout) instead of letting them through.
+    pub fail_closed: bool,
+    /// What failing closed does to the action.
+    pub fail_closed_outcome: FailClosedOutcome,
+}
+
+/// Firewall outcome for actions the guard failed to classify when failing
+/// closed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailClosedOutcome {
+    /// Flag the action as suspicious, sending it to human review.
+    #[default]
+    Escalate,
+    /// Block the action outright.
+    Block,
+}
+
+/// Verdict format requested from the guard model.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GuardOutputFormat {
+    /// Llama Guard's native "safe" / "unsafe\nS1,S2" text.
+    #[default]
+    Text,
+    /// A JSON verdict (`{"safe": false, "categories": ["S2"]}`), for models
+    /// and providers supporting structured output. Falls back to text
+    /// parsing when the reply isn't valid JSON.
+    Json,
 }
 
 impl Default for OpenRouterConfig {
@@ -30,6 +72,12 @@ impl Default for OpenRouterConfig {
             model: "meta-llama/llama-guard-4-12b".to_string(),
             timeout_secs: 10,
             enabled: false,
+            output_format: GuardOutputFormat::default(),
+            base_url: "https://openrouter.ai/api/v1".to_string(),
+            cache_capacity: 1000,
+            cache_ttl_secs: 300,
+            fail_closed: false,
+            fail_closed_outcome: FailClosedOutcome::default(),
         }
     }
 }
@@ -41,6 +89,8 @@ struct ChatRequest {
     messages: Vec<ChatMessage>,
     #[serde(skip_serializing_if = "Option::is_none")]
     max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Serialize)]
@@ -65,6 +115,54 @@ struct ResponseMessage {
     content: String,
 }
 
+/// Send a single-message chat completion to OpenRouter and return the
+/// reply text (empty when the model gave none).
+pub(super) async fn chat_completion(
+    client: &Client,
+    config: &OpenRouterConfig,
+    prompt: String,
+    max_tokens: u32,
+    response_format: Option<serde_json::Value>,
+) -> Result<String, String> {
+    let request = ChatRequest {
+        model: config.model.clone(),
+        messages: vec![ChatMessage {
+            role: "user".to_string(),
+            content: prompt,
+        }],
+        max_tokens: Some(max_tokens),
+        response_format,
+    };
+
+    let response = client
+        .post(format!("{}/chat/completions", config.base_url))
+        .header("Authorization", format!("Bearer {}", config.api_key))
+        .header("Content-Type", "application/json")
+        .header("HTTP-Referer", "https://shield.lat")
+        .header("X-Title", "Shield Core")
+        .json(&request)
+        .send()
+        .await
+        .map_err(|e| format!("Request failed: {}", e))?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!("API error {}: {}", status, body));
+    }
+
+    let chat_response: ChatResponse = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+    Ok(chat_response
+        .choices
+        .first()
+        .map(|c| c.message.content.clone())
+        .unwrap_or_default())
+}
+
 /// Llama Guard safety categories (MLCommons hazard taxonomy).
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum SafetyCategory {
@@ -140,8 +238,43 @@ impl SafetyCategory {
             SafetyCategory::Unknown(s) => s.as_str(),
         }
     }
+
+    /// Recover the category from an evaluation reason written by the guard,
+    /// or `None` for reasons from other layers.
+    pub fn from_reason(reason: &str) -> Option<Self> {
+        let description = reason.strip_prefix(GUARD_REASON_PREFIX)?;
+        Some(
+            (1..=14)
+                .map(|n| Self::from_code(&format!("S{}", n)))
+                .find(|category| category.description() == description)
+                .unwrap_or_else(|| SafetyCategory::Unknown(description.to_string())),
+        )
+    }
+
+    /// The kind of attack a request in this category amounts to.
+    pub fn attack_type(&self) -> AttackType {
+        match self {
+            SafetyCategory::NonViolentCrimes | SafetyCategory::Defamation => {
+                AttackType::SocialEngineering
+            }
+            SafetyCategory::Privacy | SafetyCategory::IntellectualProperty => {
+                AttackType::DataExfiltration
+            }
+            SafetyCategory::CodeInterpreterAbuse => AttackType::PrivilegeEscalation,
+            SafetyCategory::Unknown(_) => AttackType::Unknown,
+            // Requests for harmful content the agent should never produce
+            _ => AttackType::JailbreakAttempt,
+        }
+    }
 }
 
+/// Prefix of the evaluation reasons the guard adds, one per category.
+const GUARD_REASON_PREFIX: &str = "Llama Guard: ";
+
+/// Reason given when a fail-closed guard couldn't classify an action. The
+/// API error itself is only logged.
+const GUARD_UNAVAILABLE_REASON: &str = "Llama Guard unavailable; failing closed";
+
 /// Result of Llama Guard classification.
 #[derive(Debug, Clone)]
 pub struct GuardResult {
@@ -153,7 +286,50 @@ pub struct GuardResult {
     pub raw_response: String,
 }
 
+/// JSON verdict requested in [`GuardOutputFormat::Json`] mode.
+#[derive(Debug, Deserialize)]
+struct JsonVerdict {
+    safe: bool,
+    #[serde(default)]
+    categories: Vec<String>,
+}
+
 impl GuardResult {
+    /// Parse a response in the requested format.
+    fn parse_as(format: GuardOutputFormat, response: &str) -> Self {
+        match format {
+            GuardOutputFormat::Json => Self::parse_json(response).unwrap_or_else(|| {
+                tracing::debug!("Guard reply is not a JSON verdict, parsing as text");
+                Self::parse(response)
+            }),
+            GuardOutputFormat::Text => Self::parse(response),
+        }
+    }
+
+    /// Parse a JSON verdict, tolerating a surrounding Markdown code fence.
+    fn parse_json(response: &str) -> Option<Self> {
+        let body = response
+            .trim()
+            .trim_start_matches("```json")
+            .trim_start_matches("```")
+            .trim_end_matches("```")
+            .trim();
+        let verdict: JsonVerdict = serde_json::from_str(body).ok()?;
+        Some(GuardResult {
+            is_safe: verdict.safe,
+            violated_categories: if verdict.safe {
+                Vec::new()
+            } else {
+                verdict
+                    .categories
+                    .iter()
+                    .map(|code| SafetyCategory::from_code(code))
+                    .collect()
+            },
+            raw_response: response.trim().to_string(),
+        })
+    }
+
     /// Parse Llama Guard response format.
     ///
     /// Llama Guard outputs:
@@ -203,10 +379,85 @@ impl GuardResult {
     }
 }
 
+/// Bounded cache of classifications, keyed by a hash of the content.
+///
+/// Entries expire after the TTL; when full, the least recently used
+/// entry is evicted.
+struct GuardCache {
+    capacity: usize,
+    ttl: Duration,
+    entries: Mutex<HashMap<[u8; 32], CachedResult>>,
+}
+
+struct CachedResult {
+    result: GuardResult,
+    stored_at: Instant,
+    used_at: Instant,
+}
+
+impl GuardCache {
+    fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            capacity,
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn key(content: &str) -> [u8; 32] {
+        Sha256::digest(content.as_bytes()).into()
+    }
+
+    fn get(&self, content: &str) -> Option<GuardResult> {
+        if self.capacity == 0 {
+            return None;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        let key = Self::key(content);
+        let entry = entries.get_mut(&key)?;
+        if entry.stored_at.elapsed() >= self.ttl {
+            entries.remove(&key);
+            return None;
+        }
+        entry.used_at = Instant::now();
+        Some(entry.result.clone())
+    }
+
+    fn insert(&self, content: &str, result: GuardResult) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        let key = Self::key(content);
+        if entries.len() >= self.capacity && !entries.contains_key(&key) {
+            entries.retain(|_, entry| entry.stored_at.elapsed() < self.ttl);
+            if entries.len() >= self.capacity {
+                let oldest = entries
+                    .iter()
+                    .min_by_key(|(_, entry)| entry.used_at)
+                    .map(|(key, _)| *key);
+                if let Some(oldest) = oldest {
+                    entries.remove(&oldest);
+                }
+            }
+        }
+        let now = Instant::now();
+        entries.insert(
+            key,
+            CachedResult {
+                result,
+                stored_at: now,
+                used_at: now,
+            },
+        );
+    }
+}
+
 /// Neural firewall using Llama Guard via OpenRouter.
 pub struct LlamaGuardFirewall {
     config: OpenRouterConfig,
     client: Client,
+    cache: GuardCache,
 }
 
 impl LlamaGuardFirewall {
@@ -216,11 +467,22 @@ impl LlamaGuardFirewall {
             .timeout(Duration::from_secs(config.timeout_secs))
             .build()
             .expect("Failed to create HTTP client");
+        let cache = GuardCache::new(
+            config.cache_capacity,
+            Duration::from_secs(config.cache_ttl_secs),
+        );
 
-        Self { config, client }
+        Self {
+            config,
+            client,
+            cache,
+        }
     }
 
     /// Classify content using Llama Guard.
+    ///
+    /// Identical content within the cache TTL reuses the earlier
+    /// classification without calling the API.
     pub async fn classify(&self, content: &str) -> Result<GuardResult, String> {
         if !self.config.enabled || self.config.api_key.is_empty() {
             return Ok(GuardResult {
@@ -230,6 +492,26 @@ impl LlamaGuardFirewall {
             });
         }
 
+        if let Some(result) = self.cache.get(content) {
+            tracing::debug!("Llama Guard classification served from cache");
+            return Ok(result);
+        }
+        let result = self.request_classification(content).await?;
+        self.cache.insert(content, result.clone());
+        Ok(result)
+    }
+
+    /// Ask the API to classify content.
+    async fn request_classification(&self, content: &str) -> Result<GuardResult, String> {
+        let instructions = match self.config.output_format {
+            GuardOutputFormat::Text => {
+                "- First line must read 'safe' or 'unsafe'.\n- If unsafe, a second line must include a comma-separated list of violated categories."
+            }
+            GuardOutputFormat::Json => {
+                r#"- Respond only with a JSON object: {"safe": true} or {"safe": false, "categories": ["S1", "S2"]}."#
+            }
+        };
+
         // Format prompt for Llama Guard
         // The model expects a specific format for classification
         let prompt = format!(
@@ -244,56 +526,33 @@ User: {}
 <END CONVERSATION>
 
 Provide your safety assessment for the User message.
-- First line must read 'safe' or 'unsafe'.
-- If unsafe, a second line must include a comma-separated list of violated categories.<|eot_id|><|start_header_id|>assistant<|end_header_id|>"#,
-            content
+{}<|eot_id|><|start_header_id|>assistant<|end_header_id|>"#,
+            content, instructions
         );
 
-        let request = ChatRequest {
-            model: self.config.model.clone(),
-            messages: vec![ChatMessage {
-                role: "user".to_string(),
-                content: prompt,
-            }],
-            max_tokens: Some(100),
-        };
-
-        let response = self
-            .client
-            .post("https://openrouter.ai/api/v1/chat/completions")
-            .header("Authorization", format!("Bearer {}", self.config.api_key))
-            .header("Content-Type", "application/json")
-            .header("HTTP-Referer", "https://shield.lat")
-            .header("X-Title", "Shield Core")
-            .json(&request)
-            .send()
-            .await
-            .map_err(|e| format!("Request failed: {}", e))?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let body = response.text().await.unwrap_or_default();
-            return Err(format!("API error {}: {}", status, body));
-        }
-
-        let chat_response: ChatResponse = response
-            .json()
-            .await
-            .map_err(|e| format!("Failed to parse response: {}", e))?;
+        let response_format = (self.config.output_format == GuardOutputFormat::Json)
+            .then(|| serde_json::json!({"type": "json_object"}));
+        let started = Instant::now();
+        let content =
+            chat_completion(&self.client, &self.config, prompt, 100, response_format).await;
+        crate::telemetry::record_guard_latency(started.elapsed());
+        let content = content?;
 
-        let content = chat_response
-            .choices
-            .first()
-            .map(|c| c.message.content.clone())
-            .unwrap_or_default();
-
-        Ok(GuardResult::parse(&content))
+        Ok(GuardResult::parse_as(self.config.output_format, &content))
     }
 
     /// Build text content from an action for classification.
     fn build_content(&self, action: &AgentAction) -> String {
         let mut content = String::new();
 
+        if !action.conversation.is_empty() {
+            content.push_str("Conversation so far:\n");
+            for turn in &action.conversation {
+                content.push_str(&format!("{}: {}\n", turn.role, turn.content));
+            }
+            content.push('\n');
+        }
+
         content.push_str("User intent: ");
         content.push_str(&action.original_intent);
         content.push('\n');
@@ -308,55 +567,33 @@ Provide your safety assessment for the User message.
             content.push('\n');
         }
 
-        // Include relevant payload fields
-        if let Some(obj) = action.payload.as_object() {
-            for (key, value) in obj {
-                if let Some(s) = value.as_str() {
-                    content.push_str(&format!("{}: {}\n", key, s));
-                }
-            }
+        // Include the payload's strings, nested ones under their path
+        for (path, s) in action.payload_strings().0 {
+            content.push_str(&format!("{}: {}\n", path, s));
         }
 
         content
     }
 }
 
-/// Synchronous wrapper for the async firewall.
-/// Uses a tokio runtime handle for blocking operations.
-pub struct SyncLlamaGuardFirewall {
-    inner: LlamaGuardFirewall,
-}
-
-impl SyncLlamaGuardFirewall {
-    pub fn new(config: OpenRouterConfig) -> Self {
-        Self {
-            inner: LlamaGuardFirewall::new(config),
-        }
-    }
-}
-
-impl InputFirewall for SyncLlamaGuardFirewall {
-    fn evaluate(&self, action: &AgentAction) -> FirewallOutcome {
+#[async_trait]
+impl AsyncInputFirewall for LlamaGuardFirewall {
+    async fn evaluate(&self, action: &AgentAction) -> FirewallOutcome {
         tracing::debug!(
             trace_id = %action.trace_id,
-            enabled = self.inner.config.enabled,
+            enabled = self.config.enabled,
             "Llama Guard firewall evaluating action"
         );
 
-        if !self.inner.config.enabled {
+        if !self.config.enabled {
             tracing::debug!("Llama Guard is disabled, skipping");
             return FirewallOutcome::Clean;
         }
 
-        let content = self.inner.build_content(action);
+        let content = self.build_content(action);
         tracing::debug!(content_len = content.len(), "Sending to Llama Guard API");
 
-        // Use tokio's current runtime to block on the async operation
-        let result = tokio::task::block_in_place(|| {
-            tokio::runtime::Handle::current().block_on(self.inner.classify(&content))
-        });
-
-        match result {
+        match self.classify(&content).await {
             Ok(guard_result) => {
                 tracing::debug!(
                     is_safe = guard_result.is_safe,
@@ -369,7 +606,7 @@ impl InputFirewall for SyncLlamaGuardFirewall {
                     let reasons: Vec<String> = guard_result
                         .violated_categories
                         .iter()
-                        .map(|c| format!("Llama Guard: {}", c.description()))
+                        .map(|c| format!("{}{}", GUARD_REASON_PREFIX, c.description()))
                         .collect();
 
                     // Critical categories should block
@@ -383,24 +620,65 @@ impl InputFirewall for SyncLlamaGuardFirewall {
                     });
 
                     if critical {
-                        FirewallOutcome::Blocked { reasons }
+                        FirewallOutcome::Blocked {
+                            reasons,
+                            matched_patterns: Vec::new(),
+                        }
                     } else {
-                        FirewallOutcome::Suspicious { reasons }
+                        FirewallOutcome::Suspicious {
+                            reasons,
+                            matched_patterns: Vec::new(),
+                        }
+                    }
+                }
+            }
+            Err(e) if self.config.fail_closed => {
+                let reasons = vec![GUARD_UNAVAILABLE_REASON.to_string()];
+                match self.config.fail_closed_outcome {
+                    FailClosedOutcome::Escalate => {
+                        tracing::warn!(
+                            error = %e,
+                            "Llama Guard classification failed, failing closed: escalating action"
+                        );
+                        FirewallOutcome::Suspicious {
+                            reasons,
+                            matched_patterns: Vec::new(),
+                        }
+                    }
+                    FailClosedOutcome::Block => {
+                        tracing::warn!(
+                            error = %e,
+                            "Llama Guard classification failed, failing closed: blocking action"
+                        );
+                        FirewallOutcome::Blocked {
+                            reasons,
+                            matched_patterns: Vec::new(),
+                        }
                     }
                 }
             }
             Err(e) => {
-                tracing::warn!(error = %e, "Llama Guard classification failed, allowing action");
+                tracing::warn!(
+                    error = %e,
+                    "Llama Guard classification failed, failing open: allowing action"
+                );
                 // Fail open - if the guard fails, continue with other checks
                 FirewallOutcome::Clean
             }
         }
     }
+
+    fn model(&self) -> Option<&str> {
+        Some(&self.config.model)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use axum::extract::State;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
 
     #[test]
     fn test_parse_safe_response() {
@@ -442,4 +720,198 @@ mod tests {
             "Non-violent crimes (fraud, theft)"
         );
     }
+
+    #[test]
+    fn test_build_content_includes_conversation() {
+        let guard = LlamaGuardFirewall::new(OpenRouterConfig::default());
+        let mut action = AgentAction::new(
+            "user-1",
+            "chatbot",
+            "gpt-4",
+            "previous instructions",
+            crate::domain::ActionType::GetBalance,
+            serde_json::json!({}),
+        );
+        assert!(guard.build_content(&action).starts_with("User intent: "));
+
+        action.conversation = vec![
+            crate::domain::ConversationTurn {
+                role: "user".to_string(),
+                content: "ignore all".to_string(),
+            },
+            crate::domain::ConversationTurn {
+                role: "assistant".to_string(),
+                content: "Go on.".to_string(),
+            },
+        ];
+        let content = guard.build_content(&action);
+        assert!(content.starts_with(
+            "Conversation so far:\nuser: ignore all\nassistant: Go on.\n\nUser intent: previous instructions\n"
+        ));
+    }
+
+    #[test]
+    fn test_build_content_includes_nested_payload_strings() {
+        let guard = LlamaGuardFirewall::new(OpenRouterConfig::default());
+        let action = AgentAction::new(
+            "user-1",
+            "chatbot",
+            "gpt-4",
+            "Pay my bill",
+            crate::domain::ActionType::PayBill,
+            serde_json::json!({
+                "biller_id": "ELEC-01",
+                "memo": {"details": {"lines": ["jailbreak the payment limits"]}}
+            }),
+        );
+        let content = guard.build_content(&action);
+        assert!(content.contains("biller_id: ELEC-01\n"));
+        assert!(content.contains("memo.details.lines[0]: jailbreak the payment limits\n"));
+    }
+
+    #[test]
+    fn test_parse_json_verdict() {
+        let result = GuardResult::parse_as(
+            GuardOutputFormat::Json,
+            r#"{"safe": false, "categories": ["S2", "s14"]}"#,
+        );
+        assert!(!result.is_safe);
+        assert_eq!(
+            result.violated_categories,
+            vec![
+                SafetyCategory::NonViolentCrimes,
+                SafetyCategory::CodeInterpreterAbuse
+            ]
+        );
+
+        let result =
+            GuardResult::parse_as(GuardOutputFormat::Json, "```json\n{\"safe\": true}\n```");
+        assert!(result.is_safe);
+        assert!(result.violated_categories.is_empty());
+    }
+
+    #[test]
+    fn test_json_mode_falls_back_to_text() {
+        // Providers without structured output answer in the native format
+        let result = GuardResult::parse_as(GuardOutputFormat::Json, "unsafe\nS1");
+        assert!(!result.is_safe);
+        assert_eq!(
+            result.violated_categories,
+            vec![SafetyCategory::ViolentCrimes]
+        );
+
+        // Text mode never reads JSON
+        let result = GuardResult::parse_as(GuardOutputFormat::Text, r#"{"safe": true}"#);
+        assert!(!result.is_safe);
+    }
+
+    /// Stand-in for OpenRouter that answers "safe" and counts requests.
+    async fn mock_openrouter() -> (String, Arc<AtomicUsize>) {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let app = axum::Router::new()
+            .route(
+                "/chat/completions",
+                axum::routing::post(|State(requests): State<Arc<AtomicUsize>>| async move {
+                    requests.fetch_add(1, Ordering::SeqCst);
+                    axum::Json(serde_json::json!({
+                        "choices": [{ "message": { "content": "safe" } }]
+                    }))
+                }),
+            )
+            .with_state(requests.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (format!("http://{}", addr), requests)
+    }
+
+    /// Guard pointed at a port nothing listens on, so every call errors.
+    async fn unreachable_guard(
+        fail_closed: bool,
+        outcome: FailClosedOutcome,
+    ) -> LlamaGuardFirewall {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+        LlamaGuardFirewall::new(OpenRouterConfig {
+            api_key: "sk-test".to_string(),
+            enabled: true,
+            base_url: format!("http://{}", addr),
+            fail_closed,
+            fail_closed_outcome: outcome,
+            ..Default::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn test_guard_failure_honors_fail_closed() {
+        let action = AgentAction::new(
+            "user123",
+            "chatbot",
+            "gpt-4",
+            "What is my balance?",
+            crate::domain::ActionType::GetBalance,
+            serde_json::json!({}),
+        );
+
+        let guard = unreachable_guard(false, FailClosedOutcome::Block).await;
+        assert!(matches!(
+            guard.evaluate(&action).await,
+            FirewallOutcome::Clean
+        ));
+
+        let guard = unreachable_guard(true, FailClosedOutcome::Escalate).await;
+        match guard.evaluate(&action).await {
+            FirewallOutcome::Suspicious { reasons, .. } => {
+                assert_eq!(reasons, vec![GUARD_UNAVAILABLE_REASON.to_string()]);
+            }
+            other => panic!("expected Suspicious, got {:?}", other),
+        }
+
+        let guard = unreachable_guard(true, FailClosedOutcome::Block).await;
+        assert!(guard.evaluate(&action).await.is_blocked());
+    }
+
+    #[tokio::test]
+    async fn test_identical_content_classified_once() {
+        let (base_url, requests) = mock_openrouter().await;
+        let guard = LlamaGuardFirewall::new(OpenRouterConfig {
+            api_key: "sk-test".to_string(),
+            enabled: true,
+            base_url: base_url.clone(),
+            cache_capacity: 1,
+            ..Default::default()
+        });
+
+        assert!(guard.classify("What is my balance?").await.unwrap().is_safe);
+        assert!(guard.classify("What is my balance?").await.unwrap().is_safe);
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+        // Bounded: a second content evicts the first
+        guard.classify("Send $50 to savings").await.unwrap();
+        guard.classify("What is my balance?").await.unwrap();
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+
+        // Expired entries are classified again
+        let guard = LlamaGuardFirewall::new(OpenRouterConfig {
+            api_key: "sk-test".to_string(),
+            enabled: true,
+            base_url: base_url.clone(),
+            cache_ttl_secs: 0,
+            ..Default::default()
+        });
+        guard.classify("What is my balance?").await.unwrap();
+        guard.classify("What is my balance?").await.unwrap();
+        assert_eq!(requests.load(Ordering::SeqCst), 5);
+
+        // A disabled guard neither calls the API nor reads the cache
+        let guard = LlamaGuardFirewall::new(OpenRouterConfig {
+            api_key: "sk-test".to_string(),
+            base_url,
+            ..Default::default()
+        });
+        let result = guard.classify("What is my balance?").await.unwrap();
+        assert_eq!(result.raw_response, "Guard disabled");
+        assert_eq!(requests.load(Ordering::SeqCst), 5);
+    }
 }
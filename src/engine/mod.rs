@@ -6,16 +6,24 @@
 //! - Alignment Checker: Verifies intent matches action
 //! - Policy Engine: Applies symbolic rules (thresholds, limits)
 //! - Evaluation Coordinator: Orchestrates all layers
+//! - Attack classification: Turns detections into attack events
+//! - Risk tiers: Maps the final decision to a risk tier
 
 mod alignment;
+mod attack;
+mod context;
 mod coordinator;
 mod firewall;
 mod llm_guard;
 mod policy;
+mod risk;
 
 pub use alignment::*;
+pub use attack::*;
+pub use context::*;
 pub use coordinator::*;
 pub use firewall::*;
 pub use llm_guard::*;
 pub use policy::*;
+pub use risk::*;
 
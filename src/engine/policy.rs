@@ -4,7 +4,10 @@
 //! action properties like amount, frequency, and type.
 
 use crate::config::SafetyConfig;
-use crate::domain::{ActionType, AgentAction, DecisionStatus};
+use crate::domain::{
+    ActionType, AgentAction, DecisionStatus, PolicyRuleHit, ReasonEntry, ReasonLayer, RiskTier,
+};
+use crate::engine::EvaluationContext;
 
 /// Outcome of policy evaluation.
 #[derive(Debug, Clone)]
@@ -69,6 +72,19 @@ impl PolicyOutcome {
             .collect()
     }
 
+    /// Triggered rules as reported in an evaluation's layer breakdown.
+    pub fn rule_hits(&self) -> Vec<PolicyRuleHit> {
+        self.triggered_rules
+            .iter()
+            .map(|r| PolicyRuleHit {
+                rule_id: r.rule_id.clone(),
+                description: r.description.clone(),
+                suggests_block: r.suggests_block,
+                requires_hitl: r.requires_hitl,
+            })
+            .collect()
+    }
+
     /// Get human-readable descriptions of triggered rules.
     pub fn descriptions(&self) -> Vec<String> {
         self.triggered_rules
@@ -76,14 +92,89 @@ impl PolicyOutcome {
             .map(|r| r.description.clone())
             .collect()
     }
+
+    /// Get triggered rules as structured reasons.
+    pub fn reason_entries(&self) -> Vec<ReasonEntry> {
+        self.triggered_rules
+            .iter()
+            .map(|r| {
+                let severity = if r.suggests_block {
+                    RiskTier::Critical
+                } else if r.requires_hitl {
+                    RiskTier::High
+                } else {
+                    RiskTier::Medium
+                };
+                ReasonEntry::new(&r.rule_id, &r.description, ReasonLayer::Policy, severity)
+            })
+            .collect()
+    }
 }
 
 /// Trait for policy engine implementations.
 pub trait PolicyEngine: Send + Sync {
     /// Evaluate policies against an action.
     fn evaluate_policies(&self, action: &AgentAction) -> PolicyOutcome;
+
+    /// Evaluate policies with per-company context.
+    ///
+    /// Engines that don't use context fall back to `evaluate_policies`.
+    fn evaluate_policies_with_context(
+        &self,
+        action: &AgentAction,
+        _context: &EvaluationContext,
+    ) -> PolicyOutcome {
+        self.evaluate_policies(action)
+    }
+}
+
+/// An amount with its currency code, e.g. `500.00 EUR`.
+fn money(amount: f64, currency: &str) -> String {
+    format!("{:.2} {}", amount, currency.trim().to_uppercase())
+}
+
+/// Qualifier for threshold descriptions when off-hours limits apply.
+fn off_hours_label(context: &EvaluationContext) -> &'static str {
+    if context.off_hours {
+        "off-hours "
+    } else {
+        ""
+    }
 }
 
+/// Currency symbols recognised in intents. Ambiguous ones are left out
+/// (see [`AMBIGUOUS_CURRENCY_SYMBOLS`]).
+const INTENT_CURRENCY_SYMBOLS: &[(&str, &str)] =
+    &[("us$", "USD"), ("r$", "BRL"), ("€", "EUR"), ("£", "GBP")];
+
+/// Symbols that mark an amount without saying which currency it's in: `$`
+/// is also the peso sign across Latin America, and `¥` is both yen and
+/// yuan. Their amounts only get a currency from a code or name elsewhere
+/// in the text ("¥5000 JPY").
+const AMBIGUOUS_CURRENCY_SYMBOLS: &[&str] = &["$", "¥"];
+
+/// Currency codes and names recognised as words in intents.
+const INTENT_CURRENCY_WORDS: &[(&str, &str)] = &[
+    ("usd", "USD"),
+    ("dollar", "USD"),
+    ("dollars", "USD"),
+    ("eur", "EUR"),
+    ("euro", "EUR"),
+    ("euros", "EUR"),
+    ("gbp", "GBP"),
+    ("brl", "BRL"),
+    ("reais", "BRL"),
+    ("mxn", "MXN"),
+    ("ars", "ARS"),
+    ("clp", "CLP"),
+    ("cop", "COP"),
+    ("jpy", "JPY"),
+    ("yen", "JPY"),
+    ("cny", "CNY"),
+    ("rmb", "CNY"),
+    ("yuan", "CNY"),
+];
+
 /// Configuration-driven policy engine.
 ///
 /// Applies rules based on thresholds and limits from config.
@@ -96,40 +187,74 @@ impl ConfigPolicyEngine {
         Self { config }
     }
 
-    /// Extract amount from natural language text.
-    /// Looks for patterns like "$1000", "1000 dollars", "1,000", etc.
-    fn extract_amount_from_text(text: &str) -> Option<f64> {
+    /// Extract the currency named in natural language text, e.g. "€500",
+    /// "R$ 200" or "300 euros". `None` when no currency, or more than
+    /// one, is named.
+    fn extract_currency_from_text(text: &str) -> Option<&'static str> {
+        let text_lower = text.to_lowercase();
+        let mut found: Vec<&'static str> = Vec::new();
+
+        let symbols = INTENT_CURRENCY_SYMBOLS
+            .iter()
+            .filter(|(symbol, _)| text_lower.contains(symbol));
+        let words = text_lower
+            .split(|c: char| !c.is_alphanumeric())
+            .filter_map(|word| INTENT_CURRENCY_WORDS.iter().find(|(w, _)| *w == word));
+        for (_, code) in symbols.chain(words) {
+            if !found.contains(code) {
+                found.push(code);
+            }
+        }
+
+        match found.as_slice() {
+            [code] => Some(code),
+            _ => None,
+        }
+    }
+
+    /// Extract the amount, and the currency it's in when one is named,
+    /// from natural language text: "$1,000", "€2,000", "1.2m dollars",
+    /// "EUR 500", or a bare number after a verb ("transfer 5k"). A bare `$`
+    /// or `¥` names no currency unless a code or name does elsewhere.
+    pub(crate) fn extract_amount_from_text(text: &str) -> Option<(f64, Option<&'static str>)> {
         let text_lower = text.to_lowercase();
 
-        // Pattern 1: $1,000 or $1000 or $1000.00
-        if let Some(idx) = text.find('$') {
-            let after_dollar = &text[idx + 1..];
-            let amount_str: String = after_dollar
-                .chars()
-                .take_while(|c| c.is_ascii_digit() || *c == ',' || *c == '.')
-                .filter(|c| *c != ',')
-                .collect();
-            if let Ok(amount) = amount_str.parse::<f64>() {
-                if amount > 0.0 {
-                    return Some(amount);
+        // Pattern 1: a currency symbol before the number ("$1,000", "R$ 200")
+        for (symbol, code) in INTENT_CURRENCY_SYMBOLS
+            .iter()
+            .map(|(symbol, code)| (*symbol, Some(*code)))
+            .chain(
+                AMBIGUOUS_CURRENCY_SYMBOLS
+                    .iter()
+                    .map(|symbol| (*symbol, None)),
+            )
+        {
+            if let Some(idx) = text_lower.find(symbol) {
+                let after_symbol = text_lower[idx + symbol.len()..].trim_start();
+                if let Some(amount) = Self::parse_amount_prefix(after_symbol) {
+                    let code = code.or_else(|| Self::extract_currency_from_text(text));
+                    return Some((amount, code));
                 }
             }
         }
 
-        // Pattern 2: "1000 dollars" or "1,000 dollars"
-        let words: Vec<&str> = text_lower.split_whitespace().collect();
+        // Pattern 2: a currency word or code next to the number
+        // ("1,000 dollars", "5k euros", "EUR 500")
+        let words: Vec<&str> = text_lower
+            .split_whitespace()
+            .map(|w| w.trim_matches(|c: char| !c.is_alphanumeric() && c != '.'))
+            .collect();
         for (i, word) in words.iter().enumerate() {
-            if *word == "dollars" || *word == "dollar" || *word == "usd" {
-                if i > 0 {
-                    let amount_str: String = words[i - 1]
-                        .chars()
-                        .filter(|c| c.is_ascii_digit() || *c == '.')
-                        .collect();
-                    if let Ok(amount) = amount_str.parse::<f64>() {
-                        if amount > 0.0 {
-                            return Some(amount);
-                        }
-                    }
+            let word = word.trim_end_matches('.');
+            let Some((_, code)) = INTENT_CURRENCY_WORDS.iter().find(|(w, _)| *w == word) else {
+                continue;
+            };
+            let before = i.checked_sub(1).and_then(|j| words.get(j));
+            let after = words.get(i + 1).filter(|_| word.len() == 3);
+            for neighbour in before.into_iter().chain(after) {
+                let digits = neighbour.trim_start_matches(|c: char| !c.is_ascii_digit());
+                if let Some(amount) = Self::parse_amount_prefix(digits) {
+                    return Some((amount, Some(code)));
                 }
             }
         }
@@ -139,38 +264,14 @@ impl ConfigPolicyEngine {
         for verb in financial_verbs {
             if let Some(verb_idx) = text_lower.find(verb) {
                 let after_verb = &text_lower[verb_idx + verb.len()..];
-                // Find the first number sequence using char_indices for proper byte positions
-                let mut num_start_byte = None;
-                for (byte_idx, c) in after_verb.char_indices() {
-                    if c.is_ascii_digit() {
-                        if num_start_byte.is_none() {
-                            num_start_byte = Some(byte_idx);
-                        }
-                    } else if num_start_byte.is_some() && c != ',' && c != '.' {
-                        let start = num_start_byte.unwrap();
-                        let amount_str: String = after_verb[start..byte_idx]
-                            .chars()
-                            .filter(|c| c.is_ascii_digit() || *c == '.')
-                            .collect();
-                        if let Ok(amount) = amount_str.parse::<f64>() {
-                            if amount > 0.0 {
-                                return Some(amount);
-                            }
-                        }
-                        break;
-                    }
-                }
-                // Handle case where number is at end of string
+                // Find the first digit using char_indices for proper byte positions
+                let num_start_byte = after_verb
+                    .char_indices()
+                    .find(|(_, c)| c.is_ascii_digit())
+                    .map(|(byte_idx, _)| byte_idx);
                 if let Some(start) = num_start_byte {
-                    let amount_str: String = after_verb[start..]
-                        .chars()
-                        .take_while(|c| c.is_ascii_digit() || *c == ',' || *c == '.')
-                        .filter(|c| *c != ',')
-                        .collect();
-                    if let Ok(amount) = amount_str.parse::<f64>() {
-                        if amount > 0.0 {
-                            return Some(amount);
-                        }
+                    if let Some(amount) = Self::parse_amount_prefix(&after_verb[start..]) {
+                        return Some((amount, None));
                     }
                 }
             }
@@ -179,8 +280,125 @@ impl ConfigPolicyEngine {
         None
     }
 
+    /// Parse the positive number `text` starts with, allowing thousands
+    /// separators and a `k` or `m` multiplier ("2,000", "5k", "1.2m").
+    fn parse_amount_prefix(text: &str) -> Option<f64> {
+        let number_end = text
+            .char_indices()
+            .find(|(_, c)| !(c.is_ascii_digit() || *c == ',' || *c == '.'))
+            .map_or(text.len(), |(byte_idx, _)| byte_idx);
+        let amount = text[..number_end]
+            .chars()
+            .filter(|c| *c != ',')
+            .collect::<String>()
+            .trim_end_matches('.')
+            .parse::<f64>()
+            .ok()
+            .filter(|amount| *amount > 0.0)?;
+
+        // A multiplier only counts as a whole suffix: "5k" but not "5kg"
+        let mut rest = text[number_end..].chars();
+        let multiplier = match rest.next() {
+            Some('k') => 1_000.0,
+            Some('m') => 1_000_000.0,
+            _ => 1.0,
+        };
+        if multiplier > 1.0 && !rest.next().is_some_and(char::is_alphanumeric) {
+            Some(amount * multiplier)
+        } else {
+            Some(amount)
+        }
+    }
+
+    /// `amount` in the base currency the thresholds use, from `currency`
+    /// (the base currency when unset), with a label for rule descriptions
+    /// naming the original currency. `None` when there's no rate for it.
+    fn base_amount(&self, amount: f64, currency: Option<&str>) -> Option<(f64, String)> {
+        let rates = &self.config.exchange_rates;
+        match currency.filter(|c| !rates.is_base(c)) {
+            None => Some((amount, self.base_money(amount))),
+            Some(currency) => rates.to_base(amount, currency).map(|base| {
+                (
+                    base,
+                    format!("{} ({})", money(amount, currency), self.base_money(base)),
+                )
+            }),
+        }
+    }
+
+    /// `amount` in the base currency, for rule descriptions.
+    fn base_money(&self, amount: f64) -> String {
+        money(amount, &self.config.exchange_rates.base_currency)
+    }
+
+    /// `amount` in the action's payload currency (the base currency when
+    /// unset), for rule descriptions.
+    fn action_money(&self, action: &AgentAction, amount: f64) -> String {
+        match action.extract_currency() {
+            Some(currency) => money(amount, currency),
+            None => self.base_money(amount),
+        }
+    }
+
+    /// Rule for an amount in a currency the rate table can't convert.
+    fn unconvertible_currency_rule(amount: f64, currency: Option<&str>) -> TriggeredRule {
+        TriggeredRule {
+            rule_id: "AMOUNT_CURRENCY_UNCONVERTIBLE".to_string(),
+            description: format!(
+                "No exchange rate for currency {}; amount {:.2} can't be checked against thresholds",
+                currency.unwrap_or_default().trim(),
+                amount
+            ),
+            suggests_block: false,
+            requires_hitl: true,
+        }
+    }
+
+    /// Rule for earlier transfers in a currency the rate table can't
+    /// convert, which leave the total `check` needs unknown.
+    fn unconvertible_history_rule(rule_id: &str, currency: &str, check: &str) -> TriggeredRule {
+        TriggeredRule {
+            rule_id: rule_id.to_string(),
+            description: format!(
+                "No exchange rate for currency {} of earlier transfers; {} can't be checked",
+                currency.trim(),
+                check
+            ),
+            suggests_block: false,
+            requires_hitl: true,
+        }
+    }
+
+    /// Whether two payload currencies are the same, `None` and any spelling
+    /// of the base currency included.
+    fn same_currency(&self, a: Option<&str>, b: Option<&str>) -> bool {
+        let code = |currency: Option<&str>| match currency {
+            Some(currency) if !self.config.exchange_rates.is_base(currency) => {
+                currency.trim().to_uppercase()
+            }
+            _ => self.config.exchange_rates.base_currency.to_uppercase(),
+        };
+        code(a) == code(b)
+    }
+
+    /// Auto-approval limit, with any schedule override from the context.
+    fn max_auto_amount(&self, context: &EvaluationContext) -> f64 {
+        context
+            .max_auto_amount
+            .unwrap_or(self.config.max_auto_amount)
+    }
+
+    /// HITL threshold, with any schedule override from the context.
+    fn hitl_threshold(&self, context: &EvaluationContext) -> f64 {
+        context.hitl_threshold.unwrap_or(self.config.hitl_threshold)
+    }
+
     /// Check amount-based rules for monetary actions.
-    fn check_amount_rules(&self, action: &AgentAction) -> Vec<TriggeredRule> {
+    fn check_amount_rules(
+        &self,
+        action: &AgentAction,
+        context: &EvaluationContext,
+    ) -> Vec<TriggeredRule> {
         let mut rules = Vec::new();
 
         // Only applies to monetary actions
@@ -205,34 +423,62 @@ impl ConfigPolicyEngine {
             }
         };
 
-        // Check against thresholds
-        if amount > self.config.hitl_threshold {
-            rules.push(TriggeredRule {
-                rule_id: "AMOUNT_EXCEEDS_HITL_THRESHOLD".to_string(),
-                description: format!(
-                    "Amount ${:.2} exceeds HITL threshold ${:.2}",
-                    amount, self.config.hitl_threshold
-                ),
-                suggests_block: false,
-                requires_hitl: true,
-            });
-        } else if amount > self.config.max_auto_amount {
-            rules.push(TriggeredRule {
-                rule_id: "AMOUNT_EXCEEDS_AUTO_LIMIT".to_string(),
-                description: format!(
-                    "Amount ${:.2} exceeds auto-approval limit ${:.2}",
-                    amount, self.config.max_auto_amount
-                ),
-                suggests_block: false,
-                requires_hitl: true,
-            });
+        // Thresholds are in the base currency; convert foreign amounts
+        // first, and send ones we can't convert to a human
+        let currency = action.extract_currency();
+        match self.base_amount(amount, currency) {
+            None => rules.push(Self::unconvertible_currency_rule(amount, currency)),
+            Some((base_amount, shown)) => {
+                // Past the company's absolute ceiling there's nothing to review
+                if let Some(cap) = context.absolute_block_amount {
+                    if base_amount > cap {
+                        rules.push(TriggeredRule {
+                            rule_id: "AMOUNT_EXCEEDS_ABSOLUTE_CAP".to_string(),
+                            description: format!(
+                                "Amount {} exceeds absolute cap {}",
+                                shown,
+                                self.base_money(cap)
+                            ),
+                            suggests_block: true,
+                            requires_hitl: false,
+                        });
+                    }
+                }
+
+                // Check against thresholds
+                if base_amount > self.hitl_threshold(context) {
+                    rules.push(TriggeredRule {
+                        rule_id: "AMOUNT_EXCEEDS_HITL_THRESHOLD".to_string(),
+                        description: format!(
+                            "Amount {} exceeds {}HITL threshold {}",
+                            shown,
+                            off_hours_label(context),
+                            self.base_money(self.hitl_threshold(context))
+                        ),
+                        suggests_block: false,
+                        requires_hitl: true,
+                    });
+                } else if base_amount > self.max_auto_amount(context) {
+                    rules.push(TriggeredRule {
+                        rule_id: "AMOUNT_EXCEEDS_AUTO_LIMIT".to_string(),
+                        description: format!(
+                            "Amount {} exceeds {}auto-approval limit {}",
+                            shown,
+                            off_hours_label(context),
+                            self.base_money(self.max_auto_amount(context))
+                        ),
+                        suggests_block: false,
+                        requires_hitl: true,
+                    });
+                }
+            }
         }
 
         // Check for negative or zero amounts
         if amount <= 0.0 {
             rules.push(TriggeredRule {
                 rule_id: "AMOUNT_INVALID".to_string(),
-                description: format!("Invalid amount: ${:.2}", amount),
+                description: format!("Invalid amount: {}", self.action_money(action, amount)),
                 suggests_block: true,
                 requires_hitl: false,
             });
@@ -243,8 +489,8 @@ impl ConfigPolicyEngine {
             rules.push(TriggeredRule {
                 rule_id: "AMOUNT_SUSPICIOUS_ROUND".to_string(),
                 description: format!(
-                    "Suspiciously round amount ${:.2} may indicate automation",
-                    amount
+                    "Suspiciously round amount {} may indicate automation",
+                    self.action_money(action, amount)
                 ),
                 suggests_block: false,
                 requires_hitl: true,
@@ -254,305 +500,1744 @@ impl ConfigPolicyEngine {
         rules
     }
 
-    /// Check action-type-specific rules.
-    fn check_action_type_rules(&self, action: &AgentAction) -> Vec<TriggeredRule> {
+    /// Check crypto withdrawals: unlisted destinations are blocked, and
+    /// anything above the crypto threshold goes to HITL.
+    fn check_crypto_rules(
+        &self,
+        action: &AgentAction,
+        context: &EvaluationContext,
+    ) -> Vec<TriggeredRule> {
         let mut rules = Vec::new();
 
-        match action.action_type {
-            ActionType::TransferFunds => {
-                // Check for same source and destination
-                if let Some(payload) = action.payload.as_object() {
-                    let from = payload.get("from_account_id").and_then(|v| v.as_str());
-                    let to = payload.get("to_account_id").and_then(|v| v.as_str());
+        if action.action_type != ActionType::CryptoTransfer {
+            return rules;
+        }
 
-                    if from.is_some() && from == to {
-                        rules.push(TriggeredRule {
-                            rule_id: "TRANSFER_SAME_ACCOUNT".to_string(),
-                            description: "Transfer source and destination are the same".to_string(),
-                            suggests_block: true,
-                            requires_hitl: false,
-                        });
-                    }
-                }
-            }
-            // High-risk action types always require HITL
-            ActionType::AddBeneficiary => {
-                rules.push(TriggeredRule {
-                    rule_id: "ACTION_ADD_BENEFICIARY".to_string(),
-                    description: "Adding new beneficiary requires human approval".to_string(),
-                    suggests_block: false,
-                    requires_hitl: true,
-                });
-            }
-            ActionType::CloseAccount => {
-                rules.push(TriggeredRule {
-                    rule_id: "ACTION_CLOSE_ACCOUNT".to_string(),
-                    description: "Account closure is a critical action requiring review"
-                        .to_string(),
-                    suggests_block: false,
-                    requires_hitl: true,
-                });
-            }
-            ActionType::UpdateProfile => {
-                rules.push(TriggeredRule {
-                    rule_id: "ACTION_UPDATE_PROFILE".to_string(),
-                    description: "Profile updates should be reviewed".to_string(),
-                    suggests_block: false,
-                    requires_hitl: true,
-                });
-            }
-            ActionType::RequestLoan => {
-                rules.push(TriggeredRule {
-                    rule_id: "ACTION_REQUEST_LOAN".to_string(),
-                    description: "Loan requests require human verification".to_string(),
-                    suggests_block: false,
-                    requires_hitl: true,
-                });
-            }
-            ActionType::RefundTransaction => {
+        let destination = action
+            .extract_crypto_destination()
+            .map(str::trim)
+            .filter(|address| !address.is_empty());
+        match destination {
+            None => rules.push(TriggeredRule {
+                rule_id: "CRYPTO_DESTINATION_MISSING".to_string(),
+                description: "Crypto transfer missing destination address".to_string(),
+                suggests_block: true,
+                requires_hitl: false,
+            }),
+            Some(address)
+                if !context
+                    .crypto_address_allowlist
+                    .iter()
+                    .any(|allowed| allowed.trim() == address) =>
+            {
                 rules.push(TriggeredRule {
-                    rule_id: "ACTION_REFUND".to_string(),
-                    description: "Refunds require human approval".to_string(),
-                    suggests_block: false,
-                    requires_hitl: true,
+                    rule_id: "CRYPTO_DESTINATION_NOT_ALLOWLISTED".to_string(),
+                    description: format!(
+                        "Crypto destination {} is not on the company's allowlist",
+                        address
+                    ),
+                    suggests_block: true,
+                    requires_hitl: false,
                 });
             }
-            ActionType::Unknown => {
-                // Analyze the input text for financial intent
-                let intent_lower = action.original_intent.to_lowercase();
-
-                // Check for financial keywords
-                let financial_keywords = [
-                    "transfer",
-                    "send",
-                    "pay",
-                    "wire",
-                    "withdraw",
-                    "deposit",
-                    "move money",
-                    "move funds",
-                    "payment",
-                    "transaction",
-                ];
-                let has_financial_keyword = financial_keywords
-                    .iter()
-                    .any(|kw| intent_lower.contains(kw));
-
-                // Extract amount from text using regex-like pattern matching
-                let amount_from_text = Self::extract_amount_from_text(&action.original_intent);
+            Some(_) => {}
+        }
 
-                // Check payload amount as fallback
-                let amount_from_payload = action.extract_amount();
-                let detected_amount = amount_from_text.or(amount_from_payload);
+        let Some(amount) = action.extract_amount() else {
+            rules.push(TriggeredRule {
+                rule_id: "AMOUNT_MISSING".to_string(),
+                description: "Monetary action missing amount field".to_string(),
+                suggests_block: false,
+                requires_hitl: true,
+            });
+            return rules;
+        };
+        let asset = action.payload.get("asset").and_then(|v| v.as_str());
 
-                if has_financial_keyword {
-                    if let Some(amount) = detected_amount {
-                        // Financial keyword + amount = definitely needs review
-                        if amount > self.config.hitl_threshold {
-                            rules.push(TriggeredRule {
-                                rule_id: "UNCLASSIFIED_HIGH_VALUE_TRANSFER".to_string(),
-                                description: format!(
-                                    "Unclassified financial action with ${:.2} exceeds threshold - BLOCKED",
-                                    amount
-                                ),
-                                suggests_block: true,
-                                requires_hitl: false,
-                            });
-                        } else if amount > self.config.max_auto_amount {
-                            rules.push(TriggeredRule {
-                                rule_id: "UNCLASSIFIED_TRANSFER_NEEDS_REVIEW".to_string(),
-                                description: format!(
-                                    "Unclassified financial action with ${:.2} requires human review",
-                                    amount
-                                ),
-                                suggests_block: false,
-                                requires_hitl: true,
-                            });
-                        } else {
-                            rules.push(TriggeredRule {
-                                rule_id: "UNCLASSIFIED_SMALL_TRANSFER".to_string(),
-                                description: format!(
-                                    "Unclassified financial action with ${:.2} - flagged for monitoring",
-                                    amount
-                                ),
-                                suggests_block: false,
-                                requires_hitl: false,
-                            });
-                        }
-                    } else {
-                        // Financial keyword but no amount - suspicious
-                        rules.push(TriggeredRule {
-                            rule_id: "UNCLASSIFIED_FINANCIAL_INTENT".to_string(),
-                            description:
-                                "Unclassified action with financial keywords requires review"
-                                    .to_string(),
-                            suggests_block: false,
-                            requires_hitl: true,
-                        });
-                    }
-                } else if let Some(amount) = detected_amount {
-                    // No financial keyword but has amount - flag for review if significant
-                    if amount > self.config.max_auto_amount {
-                        rules.push(TriggeredRule {
-                            rule_id: "UNCLASSIFIED_AMOUNT_DETECTED".to_string(),
-                            description: format!(
-                                "Unclassified action mentions ${:.2} - requires review",
-                                amount
-                            ),
-                            suggests_block: false,
-                            requires_hitl: true,
-                        });
-                    }
+        match self.base_amount(amount, asset) {
+            None => rules.push(Self::unconvertible_currency_rule(amount, asset)),
+            Some((base_amount, shown)) => {
+                let threshold = self.config.crypto.hitl_threshold;
+                if base_amount > threshold {
+                    rules.push(TriggeredRule {
+                        rule_id: "CRYPTO_TRANSFER_EXCEEDS_THRESHOLD".to_string(),
+                        description: format!(
+                            "Crypto transfer of {} exceeds crypto HITL threshold {}",
+                            shown,
+                            self.base_money(threshold)
+                        ),
+                        suggests_block: false,
+                        requires_hitl: true,
+                    });
                 }
-                // No financial keywords and no amounts = allow (conversational)
             }
-            // Read-only actions are generally safe
-            ActionType::GetBalance | ActionType::GetTransactions => {}
-            // Payment actions are handled by amount rules
-            ActionType::PayBill => {}
         }
 
         rules
     }
-}
-
-impl PolicyEngine for ConfigPolicyEngine {
-    fn evaluate_policies(&self, action: &AgentAction) -> PolicyOutcome {
-        let mut all_rules = Vec::new();
 
-        // Run all rule checks
-        all_rules.extend(self.check_amount_rules(action));
-        all_rules.extend(self.check_action_type_rules(action));
+    /// Send transfers, payments and beneficiary additions to an account the
+    /// user hasn't been approved to use to a human.
+    fn check_beneficiary_rules(
+        &self,
+        action: &AgentAction,
+        context: &EvaluationContext,
+    ) -> Vec<TriggeredRule> {
+        let mut rules = Vec::new();
 
-        // Determine outcome based on triggered rules
-        if all_rules.is_empty() {
-            PolicyOutcome::allow()
-        } else if all_rules.iter().any(|r| r.suggests_block) {
-            PolicyOutcome::block(all_rules)
-        } else if all_rules.iter().any(|r| r.requires_hitl) {
-            PolicyOutcome::require_hitl(all_rules)
-        } else {
-            PolicyOutcome {
-                decision_hint: Some(DecisionStatus::Allow),
-                triggered_rules: all_rules,
-            }
+        if context.beneficiary_approved == Some(false) {
+            rules.push(TriggeredRule {
+                rule_id: "NEW_BENEFICIARY".to_string(),
+                description: format!(
+                    "{} is not an approved beneficiary for this user",
+                    action
+                        .extract_beneficiary_account()
+                        .unwrap_or("Destination")
+                ),
+                suggests_block: false,
+                requires_hitl: true,
+            });
         }
+
+        rules
     }
-}
 
-#[cfg(test)]
+    /// Check the cumulative daily transfer cap from the evaluation context.
+    fn check_daily_limit_rules(
+        &self,
+        action: &AgentAction,
+        context: &EvaluationContext,
+    ) -> Vec<TriggeredRule> {
+        let mut rules = Vec::new();
+
+        if !matches!(
+            action.action_type,
+            ActionType::TransferFunds | ActionType::PayBill
+        ) {
+            return rules;
+        }
+
+        let (Some(limit), Some(amount)) = (context.daily_transfer_limit, action.extract_amount())
+        else {
+            return rules;
+        };
+        // The amount rules already flag a payload currency we can't convert
+        let Some((amount, _)) = self.base_amount(amount, action.extract_currency()) else {
+            return rules;
+        };
+        if let Some(currency) = &context.daily_transfer_unconvertible {
+            rules.push(Self::unconvertible_history_rule(
+                "DAILY_TRANSFER_TOTAL_UNCONVERTIBLE",
+                currency,
+                "daily transfer limit",
+            ));
+            return rules;
+        }
+
+        let projected = context.daily_transfer_total + amount;
+        if projected > limit {
+            rules.push(TriggeredRule {
+                rule_id: "DAILY_TRANSFER_LIMIT_EXCEEDED".to_string(),
+                description: format!(
+                    "Daily total {} would exceed daily transfer limit {}",
+                    self.base_money(projected),
+                    self.base_money(limit)
+                ),
+                suggests_block: false,
+                requires_hitl: true,
+            });
+        }
+
+        rules
+    }
+
+    /// Check the user's transfer and payment velocity from the evaluation context.
+    ///
+    /// Counts exclude the current action, so the limit is exceeded once the
+    /// user already made `limit` transfers in the window.
+    fn check_velocity_rules(
+        &self,
+        action: &AgentAction,
+        context: &EvaluationContext,
+    ) -> Vec<TriggeredRule> {
+        let mut rules = Vec::new();
+
+        if !matches!(
+            action.action_type,
+            ActionType::TransferFunds | ActionType::PayBill
+        ) {
+            return rules;
+        }
+
+        if context.transfer_counts_unavailable {
+            rules.push(TriggeredRule {
+                rule_id: "VELOCITY_CHECK_UNAVAILABLE".to_string(),
+                description: "Recent transfers could not be counted - velocity limits unchecked"
+                    .to_string(),
+                suggests_block: false,
+                requires_hitl: true,
+            });
+            return rules;
+        }
+
+        let hourly_limit = context
+            .velocity_limit_per_hour
+            .unwrap_or(self.config.max_transfers_per_hour);
+        let daily_limit = context.velocity_limit_per_day.unwrap_or(0);
+        for (rule_id, window, count, limit) in [
+            (
+                "VELOCITY_LIMIT_HOUR",
+                "within the last hour",
+                context.hourly_transfer_count,
+                hourly_limit,
+            ),
+            (
+                "VELOCITY_LIMIT_DAY",
+                "today",
+                context.daily_transfer_count,
+                daily_limit,
+            ),
+        ] {
+            if limit > 0 && count >= limit {
+                rules.push(TriggeredRule {
+                    rule_id: rule_id.to_string(),
+                    description: format!(
+                        "{} transfers {} exceed the limit of {}",
+                        count + 1,
+                        window,
+                        limit
+                    ),
+                    suggests_block: false,
+                    requires_hitl: true,
+                });
+            }
+        }
+
+        rules
+    }
+
+    /// Check for a sequence of outflows that empties the account.
+    ///
+    /// The reported balance is what remains after earlier transfers in the
+    /// window, so funds at the start of the window are balance plus those.
+    /// The balance is taken to be in the payload currency and compared in
+    /// the base currency.
+    fn check_drain_rules(
+        &self,
+        action: &AgentAction,
+        context: &EvaluationContext,
+    ) -> Vec<TriggeredRule> {
+        let mut rules = Vec::new();
+
+        let Some(drain) = &context.drain_detection else {
+            return rules;
+        };
+        if !action.is_external_outflow() || context.recent_outflow_count + 1 < drain.min_transfers {
+            return rules;
+        }
+        let (Some(amount), Some(balance)) =
+            (action.extract_amount(), action.extract_available_balance())
+        else {
+            return rules;
+        };
+        let currency = action.extract_currency();
+        let (Some((amount, _)), Some((balance, _))) = (
+            self.base_amount(amount, currency),
+            self.base_amount(balance, currency),
+        ) else {
+            return rules;
+        };
+        if let Some(currency) = &context.recent_outflow_unconvertible {
+            rules.push(Self::unconvertible_history_rule(
+                "DRAIN_TOTAL_UNCONVERTIBLE",
+                currency,
+                "account drain detection",
+            ));
+            return rules;
+        }
+
+        let moved = context.recent_outflow_total + amount;
+        let funds = balance + context.recent_outflow_total;
+        if moved >= funds * drain.max_fraction {
+            rules.push(TriggeredRule {
+                rule_id: "ACCOUNT_DRAIN_PATTERN".to_string(),
+                description: format!(
+                    "{} transfers would move {} of {} available within {} minutes",
+                    context.recent_outflow_count + 1,
+                    self.base_money(moved),
+                    self.base_money(funds),
+                    drain.window_minutes
+                ),
+                suggests_block: false,
+                requires_hitl: true,
+            });
+        }
+
+        rules
+    }
+
+    /// Check that the payload amount matches the amount stated in the intent.
+    ///
+    /// A prompt-injected or tampered tool call often keeps the user's wording
+    /// but changes the amount.
+    fn check_amount_mismatch_rules(&self, action: &AgentAction) -> Vec<TriggeredRule> {
+        let mut rules = Vec::new();
+        let config = &self.config.amount_mismatch;
+
+        if !config.enabled {
+            return rules;
+        }
+        if self.currency_conflict(action).is_some() {
+            // Amounts in different currencies aren't comparable
+            return rules;
+        }
+        let (Some(stated), Some(amount)) = (
+            Self::extract_amount_from_text(&action.original_intent).map(|(amount, _)| amount),
+            action.extract_amount(),
+        ) else {
+            return rules;
+        };
+        if amount <= 0.0 {
+            // Left to AMOUNT_INVALID
+            return rules;
+        }
+
+        let ratio = stated.max(amount) / stated.min(amount);
+        if ratio >= config.block_ratio {
+            rules.push(TriggeredRule {
+                rule_id: "AMOUNT_INTENT_MISMATCH_SEVERE".to_string(),
+                description: format!(
+                    "Payload amount {} is far from the {} stated in the request",
+                    self.action_money(action, amount),
+                    self.action_money(action, stated)
+                ),
+                suggests_block: true,
+                requires_hitl: false,
+            });
+        } else if ratio - 1.0 > config.tolerance {
+            rules.push(TriggeredRule {
+                rule_id: "AMOUNT_INTENT_MISMATCH".to_string(),
+                description: format!(
+                    "Payload amount {} differs from the {} stated in the request",
+                    self.action_money(action, amount),
+                    self.action_money(action, stated)
+                ),
+                suggests_block: false,
+                requires_hitl: true,
+            });
+        }
+
+        rules
+    }
+
+    /// Currencies of the intent and payload, when both are known and differ
+    /// and the currency check is on.
+    fn currency_conflict(&self, action: &AgentAction) -> Option<(&'static str, String)> {
+        if !self.config.currency_mismatch.enabled {
+            return None;
+        }
+        let stated = Self::extract_currency_from_text(&action.original_intent)?;
+        let payload = action.extract_currency()?.trim().to_uppercase();
+        (payload != stated).then_some((stated, payload))
+    }
+
+    /// Check that the payload currency matches the currency named in the
+    /// intent. Thresholds are compared in the payload currency, so paying
+    /// out in another one changes what the user agreed to.
+    fn check_currency_mismatch_rules(&self, action: &AgentAction) -> Vec<TriggeredRule> {
+        let mut rules = Vec::new();
+        let decision = self.config.currency_mismatch.decision;
+
+        if let Some((stated, payload)) = self.currency_conflict(action) {
+            rules.push(TriggeredRule {
+                rule_id: "CURRENCY_INTENT_MISMATCH".to_string(),
+                description: format!(
+                    "Payload currency {} differs from the {} stated in the request",
+                    payload, stated
+                ),
+                suggests_block: decision == DecisionStatus::Block,
+                requires_hitl: decision == DecisionStatus::RequireHitl,
+            });
+        }
+
+        rules
+    }
+
+    /// Escalate a user's actions during the cool-down after a block.
+    fn check_cooldown_rules(
+        &self,
+        action: &AgentAction,
+        context: &EvaluationContext,
+    ) -> Vec<TriggeredRule> {
+        let mut rules = Vec::new();
+
+        if let Some(blocked_at) = context.recent_block_at {
+            if action.is_monetary() || action.is_high_risk() {
+                rules.push(TriggeredRule {
+                    rule_id: "RECENT_BLOCK_COOLDOWN".to_string(),
+                    description: format!(
+                        "User had an action blocked at {} (within the {} minute cool-down)",
+                        blocked_at.to_rfc3339(),
+                        self.config.block_cooldown.window_minutes
+                    ),
+                    suggests_block: false,
+                    requires_hitl: true,
+                });
+            }
+        }
+
+        rules
+    }
+
+    /// Escalate users evaluating more often than the configured rate.
+    fn check_user_rate_rules(&self, context: &EvaluationContext) -> Vec<TriggeredRule> {
+        let mut rules = Vec::new();
+        let rate = &self.config.user_rate;
+
+        if rate.max_evaluations > 0 && context.recent_user_evaluations >= rate.max_evaluations {
+            rules.push(TriggeredRule {
+                rule_id: "USER_RATE_EXCEEDED".to_string(),
+                description: format!(
+                    "User made {} evaluations within {} seconds (limit {}) - possible account takeover",
+                    context.recent_user_evaluations + 1,
+                    rate.window_seconds,
+                    rate.max_evaluations
+                ),
+                suggests_block: false,
+                requires_hitl: true,
+            });
+        }
+
+        rules
+    }
+
+    /// Flag trace IDs reused from blocked actions or other users, or too old.
+    fn check_trace_rules(
+        &self,
+        action: &AgentAction,
+        context: &EvaluationContext,
+    ) -> Vec<TriggeredRule> {
+        let mut rules = Vec::new();
+        let config = &self.config.trace_reuse;
+        let (Some(decision), Some(history)) = (config.decision, &context.trace_history) else {
+            return rules;
+        };
+
+        let mut reuses = Vec::new();
+        if history.blocked {
+            reuses.push("a blocked action");
+        }
+        if history.other_user {
+            reuses.push("another user");
+        }
+        if !reuses.is_empty() {
+            rules.push(TriggeredRule {
+                rule_id: "TRACE_ID_REUSED".to_string(),
+                description: format!(
+                    "Trace ID '{}' was previously used by {} - possible replay",
+                    action.trace_id,
+                    reuses.join(" and ")
+                ),
+                suggests_block: decision == DecisionStatus::Block,
+                requires_hitl: decision == DecisionStatus::RequireHitl,
+            });
+        }
+
+        if let Some(max_age) = config.max_age_minutes {
+            if action.created_at - history.first_seen
+                > chrono::Duration::minutes(i64::from(max_age))
+            {
+                rules.push(TriggeredRule {
+                    rule_id: "TRACE_ID_STALE".to_string(),
+                    description: format!(
+                        "Trace ID '{}' was first seen at {} (more than {} minutes ago)",
+                        action.trace_id,
+                        history.first_seen.to_rfc3339(),
+                        max_age
+                    ),
+                    suggests_block: decision == DecisionStatus::Block,
+                    requires_hitl: decision == DecisionStatus::RequireHitl,
+                });
+            }
+        }
+
+        rules
+    }
+
+    /// Check the payload's accounts against the account registry's answers.
+    fn check_account_rules(
+        &self,
+        action: &AgentAction,
+        context: &EvaluationContext,
+    ) -> Vec<TriggeredRule> {
+        let mut rules = Vec::new();
+        let config = &self.config.unknown_accounts;
+
+        let unknown = |account: Option<&str>, exists: Option<bool>| match (account, exists) {
+            (Some(account), Some(false)) => Some(account.to_string()),
+            _ => None,
+        };
+
+        if let Some(account) = unknown(
+            action.extract_source_account(),
+            context.source_account_exists,
+        ) {
+            rules.push(TriggeredRule {
+                rule_id: "UNKNOWN_SOURCE_ACCOUNT".to_string(),
+                description: format!("Source account '{}' does not exist", account),
+                suggests_block: config.source == DecisionStatus::Block,
+                requires_hitl: config.source == DecisionStatus::RequireHitl,
+            });
+        }
+
+        if let (Some(decision), Some(account)) = (
+            config.destination,
+            unknown(
+                action.extract_destination_account(),
+                context.destination_account_exists,
+            ),
+        ) {
+            rules.push(TriggeredRule {
+                rule_id: "UNKNOWN_DESTINATION_ACCOUNT".to_string(),
+                description: format!("Destination account '{}' does not exist", account),
+                suggests_block: decision == DecisionStatus::Block,
+                requires_hitl: decision == DecisionStatus::RequireHitl,
+            });
+        }
+
+        rules
+    }
+
+    /// Check refunds against the original transaction they reference.
+    ///
+    /// Small refunds within the remaining amount of a known, recent
+    /// original transaction are allowed; everything else requires HITL.
+    fn check_refund_rules(
+        &self,
+        action: &AgentAction,
+        context: &EvaluationContext,
+    ) -> Vec<TriggeredRule> {
+        let mut rules = Vec::new();
+
+        if action.action_type != ActionType::RefundTransaction {
+            return rules;
+        }
+
+        if action.extract_original_transaction_id().is_none() {
+            rules.push(TriggeredRule {
+                rule_id: "ACTION_REFUND".to_string(),
+                description: "Refunds without an original transaction require human approval"
+                    .to_string(),
+                suggests_block: false,
+                requires_hitl: true,
+            });
+            return rules;
+        }
+
+        let Some(refundable) = context.refundable_amount else {
+            rules.push(TriggeredRule {
+                rule_id: "REFUND_ORIGINAL_UNKNOWN".to_string(),
+                description: "Refund references an unknown or expired original transaction"
+                    .to_string(),
+                suggests_block: false,
+                requires_hitl: true,
+            });
+            return rules;
+        };
+
+        // The refundable amount is in the original's currency
+        let currency = action.extract_currency();
+        let original_currency = context.refundable_currency.as_deref();
+        if !self.same_currency(currency, original_currency) {
+            let code = |currency: Option<&str>| {
+                currency.map_or_else(
+                    || self.config.exchange_rates.base_currency.clone(),
+                    |c| c.trim().to_uppercase(),
+                )
+            };
+            rules.push(TriggeredRule {
+                rule_id: "REFUND_CURRENCY_MISMATCH".to_string(),
+                description: format!(
+                    "Refund in {} doesn't match original transaction currency {}",
+                    code(currency),
+                    code(original_currency)
+                ),
+                suggests_block: false,
+                requires_hitl: true,
+            });
+            return rules;
+        }
+
+        match action.extract_amount() {
+            Some(amount) if amount <= 0.0 => {
+                rules.push(TriggeredRule {
+                    rule_id: "AMOUNT_INVALID".to_string(),
+                    description: format!("Invalid amount: {}", self.action_money(action, amount)),
+                    suggests_block: true,
+                    requires_hitl: false,
+                });
+            }
+            Some(amount) if amount > refundable => {
+                rules.push(TriggeredRule {
+                    rule_id: "REFUND_EXCEEDS_ORIGINAL".to_string(),
+                    description: format!(
+                        "Refund {} exceeds refundable amount {} of original transaction",
+                        self.action_money(action, amount),
+                        self.action_money(action, refundable)
+                    ),
+                    suggests_block: false,
+                    requires_hitl: true,
+                });
+            }
+            Some(amount) => {
+                // The auto-approval limit is in the base currency
+                match self.base_amount(amount, currency) {
+                    None => rules.push(Self::unconvertible_currency_rule(amount, currency)),
+                    Some((base_amount, shown)) if base_amount > self.max_auto_amount(context) => {
+                        rules.push(TriggeredRule {
+                            rule_id: "AMOUNT_EXCEEDS_AUTO_LIMIT".to_string(),
+                            description: format!(
+                                "Amount {} exceeds auto-approval limit {}",
+                                shown,
+                                self.base_money(self.max_auto_amount(context))
+                            ),
+                            suggests_block: false,
+                            requires_hitl: true,
+                        });
+                    }
+                    Some(_) => {}
+                }
+            }
+            None => {
+                rules.push(TriggeredRule {
+                    rule_id: "AMOUNT_MISSING".to_string(),
+                    description: "Monetary action missing amount field".to_string(),
+                    suggests_block: false,
+                    requires_hitl: true,
+                });
+            }
+        }
+
+        rules
+    }
+
+    /// Check action-type-specific rules.
+    fn check_action_type_rules(
+        &self,
+        action: &AgentAction,
+        context: &EvaluationContext,
+    ) -> Vec<TriggeredRule> {
+        let mut rules = Vec::new();
+
+        match action.action_type {
+            ActionType::TransferFunds => {
+                // Check for same source and destination
+                if let Some(payload) = action.payload.as_object() {
+                    let from = payload.get("from_account_id").and_then(|v| v.as_str());
+                    let to = payload.get("to_account_id").and_then(|v| v.as_str());
+
+                    if from.is_some() && from == to {
+                        rules.push(TriggeredRule {
+                            rule_id: "TRANSFER_SAME_ACCOUNT".to_string(),
+                            description: "Transfer source and destination are the same".to_string(),
+                            suggests_block: true,
+                            requires_hitl: false,
+                        });
+                    }
+                }
+            }
+            // High-risk action types always require HITL
+            ActionType::AddBeneficiary => {
+                rules.push(TriggeredRule {
+                    rule_id: "ACTION_ADD_BENEFICIARY".to_string(),
+                    description: "Adding new beneficiary requires human approval".to_string(),
+                    suggests_block: false,
+                    requires_hitl: true,
+                });
+            }
+            ActionType::CloseAccount => {
+                rules.push(TriggeredRule {
+                    rule_id: "ACTION_CLOSE_ACCOUNT".to_string(),
+                    description: "Account closure is a critical action requiring review"
+                        .to_string(),
+                    suggests_block: false,
+                    requires_hitl: true,
+                });
+            }
+            ActionType::UpdateProfile => {
+                rules.push(TriggeredRule {
+                    rule_id: "ACTION_UPDATE_PROFILE".to_string(),
+                    description: "Profile updates should be reviewed".to_string(),
+                    suggests_block: false,
+                    requires_hitl: true,
+                });
+            }
+            ActionType::RequestLoan => {
+                rules.push(TriggeredRule {
+                    rule_id: "ACTION_REQUEST_LOAN".to_string(),
+                    description: "Loan requests require human verification".to_string(),
+                    suggests_block: false,
+                    requires_hitl: true,
+                });
+            }
+            ActionType::Unknown => {
+                // Analyze the input text for financial intent
+                let intent_lower = action.original_intent.to_lowercase();
+
+                // Check for financial keywords
+                let financial_keywords = [
+                    "transfer",
+                    "send",
+                    "pay",
+                    "wire",
+                    "withdraw",
+                    "deposit",
+                    "move money",
+                    "move funds",
+                    "payment",
+                    "transaction",
+                ];
+                let has_financial_keyword = financial_keywords
+                    .iter()
+                    .any(|kw| intent_lower.contains(kw));
+
+                // Extract amount from text using regex-like pattern matching,
+                // falling back to the payload amount
+                let detected = Self::extract_amount_from_text(&action.original_intent)
+                    .map(|(amount, currency)| (amount, currency.or(action.extract_currency())))
+                    .or_else(|| {
+                        action
+                            .extract_amount()
+                            .map(|amount| (amount, action.extract_currency()))
+                    });
+
+                // Compare in the base currency; an amount we can't convert
+                // goes to a human
+                let detected_amount = match detected {
+                    Some((amount, currency)) => match self.base_amount(amount, currency) {
+                        Some(converted) => Some(converted),
+                        None => {
+                            rules.push(Self::unconvertible_currency_rule(amount, currency));
+                            return rules;
+                        }
+                    },
+                    None => None,
+                };
+
+                if has_financial_keyword {
+                    if let Some((amount, shown)) = detected_amount {
+                        // Financial keyword + amount = definitely needs review
+                        if amount > self.hitl_threshold(context) {
+                            rules.push(TriggeredRule {
+                                rule_id: "UNCLASSIFIED_HIGH_VALUE_TRANSFER".to_string(),
+                                description: format!(
+                                    "Unclassified financial action with {} exceeds threshold - BLOCKED",
+                                    shown
+                                ),
+                                suggests_block: true,
+                                requires_hitl: false,
+                            });
+                        } else if amount > self.max_auto_amount(context) {
+                            rules.push(TriggeredRule {
+                                rule_id: "UNCLASSIFIED_TRANSFER_NEEDS_REVIEW".to_string(),
+                                description: format!(
+                                    "Unclassified financial action with {} requires human review",
+                                    shown
+                                ),
+                                suggests_block: false,
+                                requires_hitl: true,
+                            });
+                        } else {
+                            rules.push(TriggeredRule {
+                                rule_id: "UNCLASSIFIED_SMALL_TRANSFER".to_string(),
+                                description: format!(
+                                    "Unclassified financial action with {} - flagged for monitoring",
+                                    shown
+                                ),
+                                suggests_block: false,
+                                requires_hitl: false,
+                            });
+                        }
+                    } else {
+                        // Financial keyword but no amount - suspicious
+                        rules.push(TriggeredRule {
+                            rule_id: "UNCLASSIFIED_FINANCIAL_INTENT".to_string(),
+                            description:
+                                "Unclassified action with financial keywords requires review"
+                                    .to_string(),
+                            suggests_block: false,
+                            requires_hitl: true,
+                        });
+                    }
+                } else if let Some((amount, shown)) = detected_amount {
+                    // No financial keyword but has amount - flag for review if significant
+                    if amount > self.max_auto_amount(context) {
+                        rules.push(TriggeredRule {
+                            rule_id: "UNCLASSIFIED_AMOUNT_DETECTED".to_string(),
+                            description: format!(
+                                "Unclassified action mentions {} - requires review",
+                                shown
+                            ),
+                            suggests_block: false,
+                            requires_hitl: true,
+                        });
+                    }
+                }
+                // No financial keywords and no amounts = allow (conversational)
+            }
+            // Read-only actions are generally safe
+            ActionType::GetBalance | ActionType::GetTransactions => {}
+            // Payment actions are handled by amount rules
+            ActionType::PayBill => {}
+            // Refunds are handled by refund rules
+            ActionType::RefundTransaction => {}
+            // Crypto withdrawals are handled by crypto rules
+            ActionType::CryptoTransfer => {}
+        }
+
+        rules
+    }
+}
+
+impl PolicyEngine for ConfigPolicyEngine {
+    fn evaluate_policies(&self, action: &AgentAction) -> PolicyOutcome {
+        self.evaluate_policies_with_context(action, &EvaluationContext::default())
+    }
+
+    fn evaluate_policies_with_context(
+        &self,
+        action: &AgentAction,
+        context: &EvaluationContext,
+    ) -> PolicyOutcome {
+        let mut all_rules = Vec::new();
+
+        // Run all rule checks
+        all_rules.extend(self.check_amount_rules(action, context));
+        all_rules.extend(self.check_action_type_rules(action, context));
+        all_rules.extend(self.check_crypto_rules(action, context));
+        all_rules.extend(self.check_beneficiary_rules(action, context));
+        all_rules.extend(self.check_daily_limit_rules(action, context));
+        all_rules.extend(self.check_velocity_rules(action, context));
+        all_rules.extend(self.check_drain_rules(action, context));
+        all_rules.extend(self.check_amount_mismatch_rules(action));
+        all_rules.extend(self.check_currency_mismatch_rules(action));
+        all_rules.extend(self.check_account_rules(action, context));
+        all_rules.extend(self.check_cooldown_rules(action, context));
+        all_rules.extend(self.check_user_rate_rules(context));
+        all_rules.extend(self.check_trace_rules(action, context));
+        all_rules.extend(self.check_refund_rules(action, context));
+
+        // Determine outcome based on triggered rules
+        if all_rules.is_empty() {
+            PolicyOutcome::allow()
+        } else if all_rules.iter().any(|r| r.suggests_block) {
+            PolicyOutcome::block(all_rules)
+        } else if all_rules.iter().any(|r| r.requires_hitl) {
+            PolicyOutcome::require_hitl(all_rules)
+        } else {
+            PolicyOutcome {
+                decision_hint: Some(DecisionStatus::Allow),
+                triggered_rules: all_rules,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
 mod tests {
     use super::*;
+    use crate::domain::TraceHistory;
+
+    fn make_config() -> SafetyConfig {
+        SafetyConfig {
+            max_auto_amount: 100.0,
+            hitl_threshold: 1000.0,
+            max_transfers_per_hour: 3,
+            suspicious_keywords: vec![],
+            block_keywords: vec![],
+            block_keyword_languages: vec![],
+            suspicious_block_score: None,
+            regex_rules: vec![],
+            amount_mismatch: Default::default(),
+            currency_mismatch: Default::default(),
+            exchange_rates: Default::default(),
+            crypto: Default::default(),
+            unknown_accounts: Default::default(),
+            block_cooldown: Default::default(),
+            user_rate: Default::default(),
+            tool_call_injection: Default::default(),
+            obfuscation: Default::default(),
+            cot_divergence: Default::default(),
+            automation_loop: Default::default(),
+            conversation: Default::default(),
+            trace_reuse: Default::default(),
+            risk_tiers: vec![],
+        }
+    }
+
+    fn make_transfer(amount: f64) -> AgentAction {
+        AgentAction::new(
+            "user123",
+            "chatbot",
+            "gpt-4",
+            "transfer money",
+            ActionType::TransferFunds,
+            serde_json::json!({
+                "from_account_id": "checking",
+                "to_account_id": "savings",
+                "amount": amount,
+                "currency": "USD"
+            }),
+        )
+    }
+
+    #[test]
+    fn test_small_amount_allowed() {
+        let engine = ConfigPolicyEngine::new(make_config());
+        let action = make_transfer(50.0);
+
+        let result = engine.evaluate_policies(&action);
+        assert_eq!(result.strictest_decision(), Some(DecisionStatus::Allow));
+        assert!(result.triggered_rules.is_empty());
+    }
+
+    #[test]
+    fn test_medium_amount_hitl() {
+        let engine = ConfigPolicyEngine::new(make_config());
+        let action = make_transfer(500.0);
+
+        let result = engine.evaluate_policies(&action);
+        assert_eq!(
+            result.strictest_decision(),
+            Some(DecisionStatus::RequireHitl)
+        );
+        assert!(result
+            .rule_ids()
+            .contains(&"AMOUNT_EXCEEDS_AUTO_LIMIT".to_string()));
+    }
+
+    #[test]
+    fn test_large_amount_hitl() {
+        let engine = ConfigPolicyEngine::new(make_config());
+        let action = make_transfer(5000.0);
+
+        let result = engine.evaluate_policies(&action);
+        assert_eq!(
+            result.strictest_decision(),
+            Some(DecisionStatus::RequireHitl)
+        );
+        assert!(result
+            .rule_ids()
+            .contains(&"AMOUNT_EXCEEDS_HITL_THRESHOLD".to_string()));
+    }
+
+    #[test]
+    fn test_amount_above_absolute_cap_blocked() {
+        let engine = ConfigPolicyEngine::new(make_config());
+        let context = EvaluationContext {
+            absolute_block_amount: Some(1_000_000.0),
+            ..Default::default()
+        };
+
+        // Below the cap a large transfer still goes to review
+        let result = engine.evaluate_policies_with_context(&make_transfer(999_999.0), &context);
+        assert_eq!(
+            result.strictest_decision(),
+            Some(DecisionStatus::RequireHitl)
+        );
+
+        let result = engine.evaluate_policies_with_context(&make_transfer(1_000_001.0), &context);
+        assert_eq!(result.strictest_decision(), Some(DecisionStatus::Block));
+        assert!(result
+            .rule_ids()
+            .contains(&"AMOUNT_EXCEEDS_ABSOLUTE_CAP".to_string()));
+    }
+
+    #[test]
+    fn test_negative_amount_blocked() {
+        let engine = ConfigPolicyEngine::new(make_config());
+        let action = make_transfer(-100.0);
+
+        let result = engine.evaluate_policies(&action);
+        assert_eq!(result.strictest_decision(), Some(DecisionStatus::Block));
+        assert!(result.rule_ids().contains(&"AMOUNT_INVALID".to_string()));
+    }
+
+    #[test]
+    fn test_same_account_transfer_blocked() {
+        let engine = ConfigPolicyEngine::new(make_config());
+        let action = AgentAction::new(
+            "user123",
+            "chatbot",
+            "gpt-4",
+            "transfer money",
+            ActionType::TransferFunds,
+            serde_json::json!({
+                "from_account_id": "checking",
+                "to_account_id": "checking",
+                "amount": 50.0,
+                "currency": "USD"
+            }),
+        );
+
+        let result = engine.evaluate_policies(&action);
+        assert_eq!(result.strictest_decision(), Some(DecisionStatus::Block));
+    }
+
+    #[test]
+    fn test_get_balance_allowed() {
+        let engine = ConfigPolicyEngine::new(make_config());
+        let action = AgentAction::new(
+            "user123",
+            "chatbot",
+            "gpt-4",
+            "check balance",
+            ActionType::GetBalance,
+            serde_json::json!({"account_id": "checking"}),
+        );
+
+        let result = engine.evaluate_policies(&action);
+        assert_eq!(result.strictest_decision(), Some(DecisionStatus::Allow));
+    }
+
+    #[test]
+    fn test_daily_limit_escalates_when_cumulative_total_exceeds_cap() {
+        let engine = ConfigPolicyEngine::new(make_config());
+        let action = make_transfer(80.0);
+
+        let under = EvaluationContext {
+            daily_transfer_limit: Some(250.0),
+            daily_transfer_total: 160.0,
+            ..Default::default()
+        };
+        let result = engine.evaluate_policies_with_context(&action, &under);
+        assert_eq!(result.strictest_decision(), Some(DecisionStatus::Allow));
+
+        let over = EvaluationContext {
+            daily_transfer_limit: Some(250.0),
+            daily_transfer_total: 240.0,
+            ..Default::default()
+        };
+        let result = engine.evaluate_policies_with_context(&action, &over);
+        assert_eq!(
+            result.strictest_decision(),
+            Some(DecisionStatus::RequireHitl)
+        );
+        assert!(result
+            .rule_ids()
+            .contains(&"DAILY_TRANSFER_LIMIT_EXCEEDED".to_string()));
+    }
+
+    #[test]
+    fn test_drain_pattern_escalates() {
+        let engine = ConfigPolicyEngine::new(make_config());
+        let mut action = make_transfer(90.0);
+        action.payload["to_account_id"] = serde_json::json!("external-1");
+        action.payload["available_balance"] = serde_json::json!(120.0);
+
+        // Two earlier transfers took 180 of the 300 the account started with
+        let draining = EvaluationContext {
+            drain_detection: Some(Default::default()),
+            recent_outflow_count: 2,
+            recent_outflow_total: 180.0,
+            ..Default::default()
+        };
+        let result = engine.evaluate_policies_with_context(&action, &draining);
+        assert_eq!(
+            result.strictest_decision(),
+            Some(DecisionStatus::RequireHitl)
+        );
+        assert!(result
+            .rule_ids()
+            .contains(&"ACCOUNT_DRAIN_PATTERN".to_string()));
+
+        // A first transfer is left to the amount rules
+        let first = EvaluationContext {
+            drain_detection: Some(Default::default()),
+            ..Default::default()
+        };
+        let result = engine.evaluate_policies_with_context(&action, &first);
+        assert_eq!(result.strictest_decision(), Some(DecisionStatus::Allow));
+
+        // Without a reported balance there's nothing to compare against
+        action.payload["available_balance"].take();
+        let result = engine.evaluate_policies_with_context(&action, &draining);
+        assert_eq!(result.strictest_decision(), Some(DecisionStatus::Allow));
+    }
+
+    #[test]
+    fn test_amount_mismatch_between_intent_and_payload() {
+        let engine = ConfigPolicyEngine::new(make_config());
+        let transfer = |intent: &str, amount: f64| {
+            let mut action = make_transfer(amount);
+            action.original_intent = intent.to_string();
+            engine.evaluate_policies(&action)
+        };
+
+        // Matching
+        let result = transfer("transfer $50 to savings", 50.0);
+        assert_eq!(result.strictest_decision(), Some(DecisionStatus::Allow));
+        assert!(result.triggered_rules.is_empty());
+
+        // Within tolerance, e.g. a fee
+        let result = transfer("transfer $50 to savings", 51.5);
+        assert!(result.triggered_rules.is_empty());
+
+        // Mildly different
+        let result = transfer("transfer $50 to savings", 80.0);
+        assert_eq!(
+            result.strictest_decision(),
+            Some(DecisionStatus::RequireHitl)
+        );
+        assert!(result
+            .rule_ids()
+            .contains(&"AMOUNT_INTENT_MISMATCH".to_string()));
+
+        // Grossly different
+        let result = transfer("transfer $50 to savings", 5000.0);
+        assert_eq!(result.strictest_decision(), Some(DecisionStatus::Block));
+        assert!(result
+            .rule_ids()
+            .contains(&"AMOUNT_INTENT_MISMATCH_SEVERE".to_string()));
+
+        // Disabled
+        let mut config = make_config();
+        config.amount_mismatch.enabled = false;
+        let engine = ConfigPolicyEngine::new(config);
+        let mut action = make_transfer(80.0);
+        action.original_intent = "transfer $50 to savings".to_string();
+        assert!(engine.evaluate_policies(&action).triggered_rules.is_empty());
+    }
+
+    #[test]
+    fn test_currency_mismatch_between_intent_and_payload() {
+        let engine = ConfigPolicyEngine::new(make_config());
+        let transfer = |intent: &str, currency: &str| {
+            let mut action = make_transfer(50.0);
+            action.original_intent = intent.to_string();
+            action.payload["currency"] = serde_json::json!(currency);
+            engine.evaluate_policies(&action)
+        };
+
+        // Matching, or nothing to compare against
+        for (intent, currency) in [
+            ("send €50 to savings", "EUR"),
+            ("send 50 euros to savings", "eur"),
+            ("send US$50 to savings", "USD"),
+            ("send $50 to savings", "MXN"),
+            ("convert €50 to usd", "USD"),
+        ] {
+            let result = transfer(intent, currency);
+            assert!(
+                result.triggered_rules.is_empty(),
+                "{} / {}: {:?}",
+                intent,
+                currency,
+                result.rule_ids()
+            );
+        }
+
+        // Conflicting
+        let result = transfer("send €50 to savings", "USD");
+        assert_eq!(
+            result.strictest_decision(),
+            Some(DecisionStatus::RequireHitl)
+        );
+        assert_eq!(
+            result.rule_ids(),
+            vec!["CURRENCY_INTENT_MISMATCH".to_string()]
+        );
+        let result = transfer("pagar R$ 50 para a conta", "USD");
+        assert_eq!(
+            result.rule_ids(),
+            vec!["CURRENCY_INTENT_MISMATCH".to_string()]
+        );
 
-    fn make_config() -> SafetyConfig {
-        SafetyConfig {
-            max_auto_amount: 100.0,
-            hitl_threshold: 1000.0,
-            max_transfers_per_hour: 3,
-            suspicious_keywords: vec![],
-        }
+        // Configurable decision
+        let mut config = make_config();
+        config.currency_mismatch.decision = DecisionStatus::Block;
+        let engine = ConfigPolicyEngine::new(config);
+        let mut action = make_transfer(50.0);
+        action.original_intent = "send €50 to savings".to_string();
+        assert_eq!(
+            engine.evaluate_policies(&action).strictest_decision(),
+            Some(DecisionStatus::Block)
+        );
     }
 
-    fn make_transfer(amount: f64) -> AgentAction {
-        AgentAction::new(
+    #[test]
+    fn test_recent_block_escalates_until_read_only() {
+        let engine = ConfigPolicyEngine::new(make_config());
+        let context = EvaluationContext {
+            recent_block_at: Some(chrono::Utc::now()),
+            ..Default::default()
+        };
+
+        let result = engine.evaluate_policies_with_context(&make_transfer(50.0), &context);
+        assert_eq!(
+            result.strictest_decision(),
+            Some(DecisionStatus::RequireHitl)
+        );
+        assert!(result
+            .rule_ids()
+            .contains(&"RECENT_BLOCK_COOLDOWN".to_string()));
+
+        let balance = AgentAction::new(
             "user123",
             "chatbot",
             "gpt-4",
-            "transfer money",
-            ActionType::TransferFunds,
-            serde_json::json!({
-                "from_account_id": "checking",
-                "to_account_id": "savings",
-                "amount": amount,
-                "currency": "USD"
-            }),
-        )
+            "check balance",
+            ActionType::GetBalance,
+            serde_json::json!({"account_id": "checking"}),
+        );
+        let result = engine.evaluate_policies_with_context(&balance, &context);
+        assert_eq!(result.strictest_decision(), Some(DecisionStatus::Allow));
     }
 
     #[test]
-    fn test_small_amount_allowed() {
+    fn test_user_rate_escalates_once_over_limit() {
+        let mut config = make_config();
+        config.user_rate.max_evaluations = 5;
+        let engine = ConfigPolicyEngine::new(config);
+        let action = make_transfer(50.0);
+
+        let at_limit = EvaluationContext {
+            recent_user_evaluations: 4,
+            ..Default::default()
+        };
+        let result = engine.evaluate_policies_with_context(&action, &at_limit);
+        assert_eq!(result.strictest_decision(), Some(DecisionStatus::Allow));
+
+        let over_limit = EvaluationContext {
+            recent_user_evaluations: 5,
+            ..Default::default()
+        };
+        let result = engine.evaluate_policies_with_context(&action, &over_limit);
+        assert_eq!(
+            result.strictest_decision(),
+            Some(DecisionStatus::RequireHitl)
+        );
+        assert_eq!(result.rule_ids(), vec!["USER_RATE_EXCEEDED".to_string()]);
+    }
+
+    #[test]
+    fn test_trace_reuse() {
+        let action = make_transfer(50.0);
+        let reused = EvaluationContext {
+            trace_history: Some(TraceHistory {
+                first_seen: action.created_at - chrono::Duration::minutes(5),
+                blocked: true,
+                other_user: false,
+            }),
+            ..Default::default()
+        };
+
+        // Off by default
         let engine = ConfigPolicyEngine::new(make_config());
+        let result = engine.evaluate_policies_with_context(&action, &reused);
+        assert_eq!(result.strictest_decision(), Some(DecisionStatus::Allow));
+
+        let mut config = make_config();
+        config.trace_reuse.decision = Some(DecisionStatus::RequireHitl);
+        config.trace_reuse.max_age_minutes = Some(60);
+        let engine = ConfigPolicyEngine::new(config);
+        let result = engine.evaluate_policies_with_context(&action, &reused);
+        assert_eq!(
+            result.strictest_decision(),
+            Some(DecisionStatus::RequireHitl)
+        );
+        assert_eq!(result.rule_ids(), vec!["TRACE_ID_REUSED".to_string()]);
+
+        // The same user continuing a clean, recent trace is fine
+        let continued = EvaluationContext {
+            trace_history: Some(TraceHistory {
+                first_seen: action.created_at - chrono::Duration::minutes(5),
+                blocked: false,
+                other_user: false,
+            }),
+            ..Default::default()
+        };
+        let result = engine.evaluate_policies_with_context(&action, &continued);
+        assert_eq!(result.strictest_decision(), Some(DecisionStatus::Allow));
+
+        // ...unless it's older than the configured age
+        let stale = EvaluationContext {
+            trace_history: Some(TraceHistory {
+                first_seen: action.created_at - chrono::Duration::hours(2),
+                blocked: false,
+                other_user: false,
+            }),
+            ..Default::default()
+        };
+        let result = engine.evaluate_policies_with_context(&action, &stale);
+        assert_eq!(result.rule_ids(), vec!["TRACE_ID_STALE".to_string()]);
+    }
+
+    #[test]
+    fn test_unknown_accounts() {
         let action = make_transfer(50.0);
+        let unknown_source = EvaluationContext {
+            source_account_exists: Some(false),
+            destination_account_exists: Some(false),
+            ..Default::default()
+        };
 
-        let result = engine.evaluate_policies(&action);
+        // Unknown source escalates by default; destinations are ignored
+        let engine = ConfigPolicyEngine::new(make_config());
+        let result = engine.evaluate_policies_with_context(&action, &unknown_source);
+        assert_eq!(
+            result.strictest_decision(),
+            Some(DecisionStatus::RequireHitl)
+        );
+        assert_eq!(
+            result.rule_ids(),
+            vec!["UNKNOWN_SOURCE_ACCOUNT".to_string()]
+        );
+
+        let mut config = make_config();
+        config.unknown_accounts.source = DecisionStatus::Block;
+        config.unknown_accounts.destination = Some(DecisionStatus::RequireHitl);
+        let engine = ConfigPolicyEngine::new(config);
+        let result = engine.evaluate_policies_with_context(&action, &unknown_source);
+        assert_eq!(result.strictest_decision(), Some(DecisionStatus::Block));
+        assert!(result
+            .rule_ids()
+            .contains(&"UNKNOWN_DESTINATION_ACCOUNT".to_string()));
+
+        // Inconclusive lookups don't count against the action
+        let result = engine.evaluate_policies_with_context(&action, &EvaluationContext::default());
         assert_eq!(result.strictest_decision(), Some(DecisionStatus::Allow));
-        assert!(result.triggered_rules.is_empty());
+    }
+
+    fn make_refund(amount: f64, original: Option<&str>) -> AgentAction {
+        let mut payload = serde_json::json!({ "amount": amount, "currency": "USD" });
+        if let Some(original) = original {
+            payload["original_transaction_id"] = serde_json::json!(original);
+        }
+        AgentAction::new(
+            "user123",
+            "chatbot",
+            "gpt-4",
+            "refund my last payment",
+            ActionType::RefundTransaction,
+            payload,
+        )
     }
 
     #[test]
-    fn test_medium_amount_hitl() {
+    fn test_refund_rules() {
         let engine = ConfigPolicyEngine::new(make_config());
-        let action = make_transfer(500.0);
+        let known = EvaluationContext {
+            refundable_amount: Some(80.0),
+            ..Default::default()
+        };
 
-        let result = engine.evaluate_policies(&action);
+        // Conforming partial refund of a known original
+        let result =
+            engine.evaluate_policies_with_context(&make_refund(30.0, Some("txn-1")), &known);
+        assert_eq!(result.strictest_decision(), Some(DecisionStatus::Allow));
+        assert!(result.triggered_rules.is_empty());
+
+        // Over the original amount
+        let result =
+            engine.evaluate_policies_with_context(&make_refund(95.0, Some("txn-1")), &known);
         assert_eq!(
             result.strictest_decision(),
             Some(DecisionStatus::RequireHitl)
         );
         assert!(result
             .rule_ids()
-            .contains(&"AMOUNT_EXCEEDS_AUTO_LIMIT".to_string()));
+            .contains(&"REFUND_EXCEEDS_ORIGINAL".to_string()));
+
+        // Refunds in another currency than the original's
+        let in_euros = EvaluationContext {
+            refundable_currency: Some("EUR".to_string()),
+            ..known.clone()
+        };
+        let result =
+            engine.evaluate_policies_with_context(&make_refund(30.0, Some("txn-1")), &in_euros);
+        assert_eq!(
+            result.rule_ids(),
+            vec!["REFUND_CURRENCY_MISMATCH".to_string()]
+        );
+        assert_eq!(
+            result.strictest_decision(),
+            Some(DecisionStatus::RequireHitl)
+        );
+        // An original without a currency is in the base currency
+        let mut refund = make_refund(30.0, Some("txn-1"));
+        refund.payload["currency"] = serde_json::json!("usd");
+        let result = engine.evaluate_policies_with_context(&refund, &known);
+        assert!(result.triggered_rules.is_empty());
+
+        // Unreferenced, and referencing an unknown original
+        let result = engine.evaluate_policies_with_context(&make_refund(30.0, None), &known);
+        assert!(result.rule_ids().contains(&"ACTION_REFUND".to_string()));
+        let result = engine.evaluate_policies(&make_refund(30.0, Some("txn-1")));
+        assert!(result
+            .rule_ids()
+            .contains(&"REFUND_ORIGINAL_UNKNOWN".to_string()));
     }
 
     #[test]
-    fn test_large_amount_hitl() {
+    fn test_velocity_limit_crossed_at_boundary() {
         let engine = ConfigPolicyEngine::new(make_config());
-        let action = make_transfer(5000.0);
+        let action = make_transfer(50.0);
 
-        let result = engine.evaluate_policies(&action);
+        // Two earlier transfers: this is the third, still within a limit of 3
+        let at_limit = EvaluationContext {
+            velocity_limit_per_hour: Some(3),
+            hourly_transfer_count: 2,
+            ..Default::default()
+        };
+        let result = engine.evaluate_policies_with_context(&action, &at_limit);
+        assert_eq!(result.strictest_decision(), Some(DecisionStatus::Allow));
+
+        let over_limit = EvaluationContext {
+            hourly_transfer_count: 3,
+            ..at_limit.clone()
+        };
+        let result = engine.evaluate_policies_with_context(&action, &over_limit);
+        assert_eq!(
+            result.strictest_decision(),
+            Some(DecisionStatus::RequireHitl)
+        );
+        assert_eq!(result.rule_ids(), vec!["VELOCITY_LIMIT_HOUR".to_string()]);
+
+        // Without a company limit the configured default (3) applies
+        let result = engine.evaluate_policies_with_context(
+            &action,
+            &EvaluationContext {
+                hourly_transfer_count: 3,
+                daily_transfer_count: 3,
+                velocity_limit_per_day: Some(3),
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            result.rule_ids(),
+            vec![
+                "VELOCITY_LIMIT_HOUR".to_string(),
+                "VELOCITY_LIMIT_DAY".to_string()
+            ]
+        );
+
+        // A limit of 0 disables the check
+        let result = engine.evaluate_policies_with_context(
+            &action,
+            &EvaluationContext {
+                velocity_limit_per_hour: Some(0),
+                ..over_limit
+            },
+        );
+        assert_eq!(result.strictest_decision(), Some(DecisionStatus::Allow));
+    }
+
+    #[test]
+    fn test_velocity_lookup_failure_requires_hitl() {
+        let engine = ConfigPolicyEngine::new(make_config());
+        let context = EvaluationContext {
+            transfer_counts_unavailable: true,
+            ..Default::default()
+        };
+
+        let result = engine.evaluate_policies_with_context(&make_transfer(50.0), &context);
+        assert_eq!(
+            result.strictest_decision(),
+            Some(DecisionStatus::RequireHitl)
+        );
+        assert_eq!(
+            result.rule_ids(),
+            vec!["VELOCITY_CHECK_UNAVAILABLE".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_amount_thresholds_convert_payload_currency() {
+        let engine = ConfigPolicyEngine::new(make_config());
+        let transfer = |amount: f64, currency: &str| {
+            let mut action = make_transfer(amount);
+            action.payload["currency"] = serde_json::json!(currency);
+            engine.evaluate_policies(&action)
+        };
+
+        // 500 JPY is a few dollars, well under the auto-approval limit
+        assert!(transfer(500.0, "JPY").triggered_rules.is_empty());
+        assert!(transfer(500.0, "jpy").triggered_rules.is_empty());
+
+        // 500 USD isn't
+        let result = transfer(500.0, "USD");
+        assert_eq!(
+            result.rule_ids(),
+            vec!["AMOUNT_EXCEEDS_AUTO_LIMIT".to_string()]
+        );
+
+        // Converted amounts name the original currency
+        let result = transfer(500.0, "EUR");
+        assert_eq!(
+            result.rule_ids(),
+            vec!["AMOUNT_EXCEEDS_AUTO_LIMIT".to_string()]
+        );
+        assert!(result.triggered_rules[0].description.contains("500.00 EUR"));
+        assert!(result.triggered_rules[0]
+            .description
+            .ends_with("auto-approval limit 100.00 USD"));
+
+        // Amounts from the payload keep its currency
+        let result = transfer(2000.0, "EUR");
+        let round = result
+            .triggered_rules
+            .iter()
+            .find(|rule| rule.rule_id == "AMOUNT_SUSPICIOUS_ROUND")
+            .unwrap();
+        assert!(
+            round.description.contains("2000.00 EUR"),
+            "{}",
+            round.description
+        );
+        assert!(!round.description.contains('$'));
+
+        // Currencies without a rate go to a human
+        let result = transfer(5.0, "XYZ");
+        assert_eq!(
+            result.rule_ids(),
+            vec!["AMOUNT_CURRENCY_UNCONVERTIBLE".to_string()]
+        );
         assert_eq!(
             result.strictest_decision(),
             Some(DecisionStatus::RequireHitl)
         );
+    }
+
+    #[test]
+    fn test_cumulative_and_refund_rules_convert_currencies() {
+        let engine = ConfigPolicyEngine::new(make_config());
+        let in_currency = |mut action: AgentAction, currency: &str| {
+            action.payload["currency"] = serde_json::json!(currency);
+            action
+        };
+
+        // 80 EUR on top of 170 USD passes a 250 USD daily cap once converted
+        let daily = EvaluationContext {
+            daily_transfer_limit: Some(250.0),
+            daily_transfer_total: 170.0,
+            ..Default::default()
+        };
+        let result =
+            engine.evaluate_policies_with_context(&in_currency(make_transfer(80.0), "EUR"), &daily);
         assert!(result
             .rule_ids()
-            .contains(&"AMOUNT_EXCEEDS_HITL_THRESHOLD".to_string()));
+            .contains(&"DAILY_TRANSFER_LIMIT_EXCEEDED".to_string()));
+
+        // Earlier transfers we can't convert leave the total unknown
+        let unknown_total = EvaluationContext {
+            daily_transfer_unconvertible: Some("XYZ".to_string()),
+            ..daily
+        };
+        let result = engine.evaluate_policies_with_context(&make_transfer(10.0), &unknown_total);
+        assert_eq!(
+            result.rule_ids(),
+            vec!["DAILY_TRANSFER_TOTAL_UNCONVERTIBLE".to_string()]
+        );
+        assert_eq!(
+            result.strictest_decision(),
+            Some(DecisionStatus::RequireHitl)
+        );
+
+        // 90 of a 1000 JPY balance is little, but the 180 USD moved earlier
+        // was nearly everything
+        let mut action = in_currency(make_transfer(90.0), "JPY");
+        action.payload["to_account_id"] = serde_json::json!("external-1");
+        action.payload["available_balance"] = serde_json::json!(1000.0);
+        let draining = EvaluationContext {
+            drain_detection: Some(Default::default()),
+            recent_outflow_count: 2,
+            recent_outflow_total: 180.0,
+            ..Default::default()
+        };
+        let result = engine.evaluate_policies_with_context(&action, &draining);
+        assert_eq!(result.rule_ids(), vec!["ACCOUNT_DRAIN_PATTERN".to_string()]);
+
+        let unknown_outflows = EvaluationContext {
+            recent_outflow_unconvertible: Some("XYZ".to_string()),
+            ..draining
+        };
+        let result = engine.evaluate_policies_with_context(&action, &unknown_outflows);
+        assert_eq!(
+            result.rule_ids(),
+            vec!["DRAIN_TOTAL_UNCONVERTIBLE".to_string()]
+        );
+
+        // 95 EUR is over the 100 USD auto-approval limit
+        let known = EvaluationContext {
+            refundable_amount: Some(200.0),
+            refundable_currency: Some("EUR".to_string()),
+            ..Default::default()
+        };
+        let result = engine.evaluate_policies_with_context(
+            &in_currency(make_refund(95.0, Some("txn-1")), "EUR"),
+            &known,
+        );
+        assert_eq!(
+            result.rule_ids(),
+            vec!["AMOUNT_EXCEEDS_AUTO_LIMIT".to_string()]
+        );
+        let result = engine.evaluate_policies_with_context(
+            &in_currency(make_refund(30.0, Some("txn-1")), "XYZ"),
+            &EvaluationContext {
+                refundable_currency: Some("xyz".to_string()),
+                ..known
+            },
+        );
+        assert_eq!(
+            result.rule_ids(),
+            vec!["AMOUNT_CURRENCY_UNCONVERTIBLE".to_string()]
+        );
     }
 
     #[test]
-    fn test_negative_amount_blocked() {
+    fn test_extract_amount_from_text_suffixes_and_symbols() {
+        let extract = ConfigPolicyEngine::extract_amount_from_text;
+        assert_eq!(extract("transfer 5k to savings"), Some((5_000.0, None)));
+        assert_eq!(extract("send €2,000 to Ana"), Some((2_000.0, Some("EUR"))));
+        assert_eq!(
+            extract("wire 1.2m dollars today"),
+            Some((1_200_000.0, Some("USD")))
+        );
+        assert_eq!(extract("pay £50.25 rent"), Some((50.25, Some("GBP"))));
+        // ¥ is both yen and yuan, so it takes a code to tell them apart
+        assert_eq!(extract("send ¥5000 to Ken"), Some((5_000.0, None)));
+        assert_eq!(
+            extract("send ¥5000 JPY to Ken"),
+            Some((5_000.0, Some("JPY")))
+        );
+        assert_eq!(
+            extract("send ¥5000 yuan to Li"),
+            Some((5_000.0, Some("CNY")))
+        );
+        assert_eq!(extract("send EUR 500 to Ana"), Some((500.0, Some("EUR"))));
+        assert_eq!(extract("pay R$ 200 to João"), Some((200.0, Some("BRL"))));
+        assert_eq!(extract("send $1,000"), Some((1_000.0, None)));
+        // A unit isn't a multiplier
+        assert_eq!(extract("send 5kg of coffee"), Some((5.0, None)));
+        assert_eq!(extract("what's my balance?"), None);
+    }
+
+    #[test]
+    fn test_unclassified_transfer_uses_intent_currency() {
         let engine = ConfigPolicyEngine::new(make_config());
-        let action = make_transfer(-100.0);
+        let unknown = |intent: &str| {
+            let action = AgentAction::new(
+                "user123",
+                "chatbot",
+                "gpt-4",
+                intent,
+                ActionType::Unknown,
+                serde_json::json!({}),
+            );
+            engine.evaluate_policies(&action)
+        };
 
-        let result = engine.evaluate_policies(&action);
+        // ¥5000 JPY is a few dollars; without the code it's taken at face value
+        assert_eq!(
+            unknown("send ¥5000 JPY to Ken").rule_ids(),
+            vec!["UNCLASSIFIED_SMALL_TRANSFER".to_string()]
+        );
+        assert_eq!(
+            unknown("send ¥5000 to Ken").rule_ids(),
+            vec!["UNCLASSIFIED_HIGH_VALUE_TRANSFER".to_string()]
+        );
+        assert_eq!(
+            unknown("send $5000 to Ken").rule_ids(),
+            vec!["UNCLASSIFIED_HIGH_VALUE_TRANSFER".to_string()]
+        );
+        assert_eq!(
+            unknown("transfer 5k to Ken").rule_ids(),
+            vec!["UNCLASSIFIED_HIGH_VALUE_TRANSFER".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_crypto_transfer_requires_allowlisted_destination() {
+        let engine = ConfigPolicyEngine::new(make_config());
+        let context = EvaluationContext {
+            crypto_address_allowlist: vec!["bc1qallowed".to_string()],
+            ..Default::default()
+        };
+        let crypto = |asset: &str, address: &str, amount: f64| {
+            let action = AgentAction::new(
+                "user123",
+                "chatbot",
+                "gpt-4",
+                "withdraw to my wallet",
+                ActionType::CryptoTransfer,
+                serde_json::json!({
+                    "asset": asset,
+                    "network": "ethereum",
+                    "destination_address": address,
+                    "amount": amount,
+                }),
+            );
+            engine.evaluate_policies_with_context(&action, &context)
+        };
+
+        // Small transfer to an allowlisted address
+        assert!(crypto("USDC", "bc1qallowed", 20.0)
+            .triggered_rules
+            .is_empty());
+
+        // Anywhere else is blocked, whatever the amount
+        let result = crypto("USDC", "0xattacker", 20.0);
         assert_eq!(result.strictest_decision(), Some(DecisionStatus::Block));
-        assert!(result.rule_ids().contains(&"AMOUNT_INVALID".to_string()));
+        assert_eq!(
+            result.rule_ids(),
+            vec!["CRYPTO_DESTINATION_NOT_ALLOWLISTED".to_string()]
+        );
+
+        // Above the crypto threshold, or in an asset without a rate, needs review
+        assert_eq!(
+            crypto("USDC", "bc1qallowed", 500.0).rule_ids(),
+            vec!["CRYPTO_TRANSFER_EXCEEDS_THRESHOLD".to_string()]
+        );
+        assert_eq!(
+            crypto("BTC", "bc1qallowed", 0.001).strictest_decision(),
+            Some(DecisionStatus::RequireHitl)
+        );
     }
 
     #[test]
-    fn test_same_account_transfer_blocked() {
+    fn test_unapproved_beneficiary_requires_hitl() {
         let engine = ConfigPolicyEngine::new(make_config());
-        let action = AgentAction::new(
+        let transfer = AgentAction::new(
             "user123",
             "chatbot",
             "gpt-4",
-            "transfer money",
+            "Send $50 to Bob",
             ActionType::TransferFunds,
             serde_json::json!({
                 "from_account_id": "checking",
-                "to_account_id": "checking",
+                "to_account_id": "bob",
                 "amount": 50.0,
-                "currency": "USD"
             }),
         );
+        let with_approval = |approved| EvaluationContext {
+            beneficiary_approved: approved,
+            ..Default::default()
+        };
 
-        let result = engine.evaluate_policies(&action);
-        assert_eq!(result.strictest_decision(), Some(DecisionStatus::Block));
-    }
-
-    #[test]
-    fn test_get_balance_allowed() {
-        let engine = ConfigPolicyEngine::new(make_config());
-        let action = AgentAction::new(
-            "user123",
-            "chatbot",
-            "gpt-4",
-            "check balance",
-            ActionType::GetBalance,
-            serde_json::json!({"account_id": "checking"}),
+        let result = engine.evaluate_policies_with_context(&transfer, &with_approval(Some(false)));
+        assert_eq!(
+            result.strictest_decision(),
+            Some(DecisionStatus::RequireHitl)
         );
+        assert_eq!(result.rule_ids(), vec!["NEW_BENEFICIARY".to_string()]);
 
-        let result = engine.evaluate_policies(&action);
-        assert_eq!(result.strictest_decision(), Some(DecisionStatus::Allow));
+        // Approved destinations, and companies that don't check, pass
+        for approved in [Some(true), None] {
+            assert!(engine
+                .evaluate_policies_with_context(&transfer, &with_approval(approved))
+                .triggered_rules
+                .is_empty());
+        }
     }
 }
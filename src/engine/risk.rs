@@ -0,0 +1,158 @@
+//! Risk tier mapping.
+//!
+//! The coordinator settles the decision first (the strictest layer wins),
+//! then picks the risk tier recorded with it from an ordered rule set.
+//! Configured rules are checked before the built-in ones, which reproduce
+//! the default mapping, so every decision always gets a tier.
+//!
+//! Alongside the tier, each evaluation gets a numeric risk score computed
+//! from its stored signals alone, so it can be recomputed for evaluations
+//! stored before the score existed.
+
+use serde::Deserialize;
+
+use crate::domain::{AgentAction, DecisionStatus, EvaluationResult, RiskTier};
+
+/// Kind of action a risk tier rule applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActionCategory {
+    /// Balance and transaction history reads.
+    ReadOnly,
+    /// Actions carrying an amount (transfers, bills, loans, refunds, crypto).
+    Monetary,
+    /// Everything else (beneficiaries, profile changes, account closure).
+    Other,
+}
+
+impl ActionCategory {
+    pub fn of(action: &AgentAction) -> Self {
+        if action.is_read_only() {
+            ActionCategory::ReadOnly
+        } else if action.is_monetary() {
+            ActionCategory::Monetary
+        } else {
+            ActionCategory::Other
+        }
+    }
+}
+
+/// Risk tier for decisions matching every condition set on the rule.
+///
+/// Signals are firewall suspicions, misalignment reasons and triggered
+/// policy rules.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct RiskTierRule {
+    pub decision: DecisionStatus,
+    /// Only actions of this category (unset matches any).
+    #[serde(default)]
+    pub category: Option<ActionCategory>,
+    /// At least this many signals.
+    #[serde(default)]
+    pub min_signals: usize,
+    /// At most this many signals (unset means no limit).
+    #[serde(default)]
+    pub max_signals: Option<usize>,
+    pub tier: RiskTier,
+}
+
+impl RiskTierRule {
+    fn matches(&self, decision: DecisionStatus, category: ActionCategory, signals: usize) -> bool {
+        self.decision == decision
+            && self.category.is_none_or(|c| c == category)
+            && signals >= self.min_signals
+            && self.max_signals.is_none_or(|max| signals <= max)
+    }
+}
+
+/// Ordered risk tier rules; the first matching rule wins.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RiskTierRules {
+    rules: Vec<RiskTierRule>,
+}
+
+impl RiskTierRules {
+    /// `rules` checked ahead of the built-in mapping.
+    pub fn new(rules: Vec<RiskTierRule>) -> Self {
+        Self {
+            rules: rules.into_iter().chain(Self::builtin()).collect(),
+        }
+    }
+
+    /// Block is critical, HITL is high, and allowed actions are medium
+    /// with any signal and low otherwise.
+    fn builtin() -> Vec<RiskTierRule> {
+        let rule = |decision, min_signals, tier| RiskTierRule {
+            decision,
+            category: None,
+            min_signals,
+            max_signals: None,
+            tier,
+        };
+        vec![
+            rule(DecisionStatus::Block, 0, RiskTier::Critical),
+            rule(DecisionStatus::RequireHitl, 0, RiskTier::High),
+            rule(DecisionStatus::Allow, 1, RiskTier::Medium),
+            rule(DecisionStatus::Allow, 0, RiskTier::Low),
+        ]
+    }
+
+    /// Tier for `decision` on `action` given how many signals fired.
+    pub fn tier_for(
+        &self,
+        decision: DecisionStatus,
+        action: &AgentAction,
+        signals: usize,
+    ) -> RiskTier {
+        let category = ActionCategory::of(action);
+        self.rules
+            .iter()
+            .find(|rule| rule.matches(decision, category, signals))
+            .map(|rule| rule.tier)
+            // The built-in rules cover every decision
+            .unwrap_or(RiskTier::Critical)
+    }
+}
+
+impl Default for RiskTierRules {
+    fn default() -> Self {
+        Self::new(Vec::new())
+    }
+}
+
+/// How much a signal of each severity adds to the risk score.
+fn severity_weight(severity: RiskTier) -> f64 {
+    match severity {
+        RiskTier::Low => 0.1,
+        RiskTier::Medium => 0.25,
+        RiskTier::High => 0.5,
+        RiskTier::Critical => 0.9,
+    }
+}
+
+/// Risk score from 0 (nothing fired) to 100, rounded to one decimal.
+///
+/// The signals are the evaluation's `reason_entries`, each weighted by its
+/// `severity` (low 0.1, medium 0.25, high 0.5, critical 0.9). Each signal
+/// independently adds its weight of the remaining headroom, so scores rise
+/// with every signal but never pass 100: one medium entry scores 25, a
+/// medium and a high entry 62.5. Evaluations stored before reason entries
+/// were recorded have none, so each of their rule hits counts as a medium
+/// entry instead.
+pub fn risk_score(evaluation: &EvaluationResult) -> f64 {
+    let weights: Vec<f64> = if evaluation.reason_entries.is_empty() {
+        evaluation
+            .rule_hits
+            .iter()
+            .map(|_| severity_weight(RiskTier::Medium))
+            .collect()
+    } else {
+        evaluation
+            .reason_entries
+            .iter()
+            .map(|entry| severity_weight(entry.severity))
+            .collect()
+    };
+    let headroom: f64 = weights.iter().map(|weight| 1.0 - weight).product();
+    ((1.0 - headroom) * 1000.0).round() / 10.0
+}
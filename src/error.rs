@@ -3,7 +3,7 @@
 //! Defines a unified error type that maps cleanly to HTTP responses.
 
 use axum::{
-    http::StatusCode,
+    http::{header::RETRY_AFTER, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
@@ -19,12 +19,21 @@ pub enum ShieldError {
     #[error("Invalid request: {0}")]
     BadRequest(String),
 
+    #[error("Validation failed: {}", field_names(.0))]
+    Validation(Vec<FieldError>),
+
     #[error("Unauthorized: {0}")]
     Unauthorized(String),
 
     #[error("Forbidden: {0}")]
     Forbidden(String),
 
+    #[error("Conflict: {0}")]
+    Conflict(String),
+
+    #[error("Rate limit exceeded, retry after {retry_after_secs}s")]
+    RateLimited { retry_after_secs: u64 },
+
     #[error("Database error: {0}")]
     Database(#[from] sqlx::Error),
 
@@ -38,6 +47,41 @@ pub enum ShieldError {
     Internal(String),
 }
 
+/// A problem with one field of a request, for clients to show next to it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldError {
+    /// Request field at fault.
+    pub field: String,
+    /// Machine-readable reason, e.g. `required` or `invalid`.
+    pub code: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: &str, code: &str, message: impl Into<String>) -> Self {
+        Self {
+            field: field.to_string(),
+            code: code.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+impl ShieldError {
+    /// A validation failure of a single field.
+    pub fn invalid_field(field: &str, code: &str, message: impl Into<String>) -> Self {
+        ShieldError::Validation(vec![FieldError::new(field, code, message)])
+    }
+}
+
+fn field_names(errors: &[FieldError]) -> String {
+    errors
+        .iter()
+        .map(|e| e.field.as_str())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 /// Error response body for API clients.
 #[derive(Debug, Serialize)]
 pub struct ErrorResponse {
@@ -45,6 +89,9 @@ pub struct ErrorResponse {
     pub code: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub details: Option<String>,
+    /// Field-level problems of a `VALIDATION_ERROR`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldError>,
 }
 
 impl IntoResponse for ShieldError {
@@ -54,12 +101,36 @@ impl IntoResponse for ShieldError {
             ShieldError::BadRequest(msg) => {
                 (StatusCode::BAD_REQUEST, "BAD_REQUEST", msg.clone(), None)
             }
+            ShieldError::Validation(fields) => {
+                let body = ErrorResponse {
+                    error: "Request validation failed".to_string(),
+                    code: "VALIDATION_ERROR".to_string(),
+                    details: None,
+                    fields: fields.clone(),
+                };
+                return (StatusCode::UNPROCESSABLE_ENTITY, Json(body)).into_response();
+            }
             ShieldError::Unauthorized(msg) => {
                 (StatusCode::UNAUTHORIZED, "UNAUTHORIZED", msg.clone(), None)
             }
             ShieldError::Forbidden(msg) => {
                 (StatusCode::FORBIDDEN, "FORBIDDEN", msg.clone(), None)
             }
+            ShieldError::Conflict(msg) => (StatusCode::CONFLICT, "CONFLICT", msg.clone(), None),
+            ShieldError::RateLimited { retry_after_secs } => {
+                let body = ErrorResponse {
+                    error: "Rate limit exceeded".to_string(),
+                    code: "RATE_LIMITED".to_string(),
+                    details: None,
+                    fields: Vec::new(),
+                };
+                return (
+                    StatusCode::TOO_MANY_REQUESTS,
+                    [(RETRY_AFTER, retry_after_secs.to_string())],
+                    Json(body),
+                )
+                    .into_response();
+            }
             ShieldError::Database(e) => {
                 // Log the actual error but don't expose internals
                 tracing::error!(error = %e, "Database error");
@@ -97,6 +168,7 @@ impl IntoResponse for ShieldError {
             error: message,
             code: code.to_string(),
             details,
+            fields: Vec::new(),
         };
 
         (status, Json(body)).into_response()
@@ -0,0 +1,318 @@
+//! Decision event publishing.
+//!
+//! Every persisted evaluation is published as a [`DecisionEvent`] for
+//! event-driven consumers. Publishing never blocks an evaluation: events go
+//! through a bounded buffer drained by a background task, and are dropped
+//! (with a warning) when the publisher can't keep up.
+//!
+//! New HITL tasks are also broadcast in process on a [`HitlTaskFeed`] for
+//! the review console's live stream.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use axum::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::sync::{broadcast, mpsc};
+use uuid::Uuid;
+
+use crate::domain::{
+    ActionType, AgentAction, DecisionStatus, EvaluationResult, HitlTaskSummary, RiskTier,
+};
+
+/// A structured record of one evaluation.
+#[derive(Debug, Clone, Serialize)]
+pub struct DecisionEvent {
+    pub event_id: Uuid,
+    pub company_id: Option<Uuid>,
+    pub app_id: Option<Uuid>,
+    pub action_id: Uuid,
+    pub evaluation_id: Uuid,
+    pub trace_id: String,
+    pub user_id: String,
+    pub action_type: ActionType,
+    pub decision: DecisionStatus,
+    pub risk_tier: RiskTier,
+    pub reasons: Vec<String>,
+    pub rule_hits: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hitl_task_id: Option<Uuid>,
+    pub evaluated_at: DateTime<Utc>,
+}
+
+impl DecisionEvent {
+    /// Build the event for an evaluated action.
+    pub fn new(
+        company_id: Option<Uuid>,
+        action: &AgentAction,
+        evaluation: &EvaluationResult,
+        hitl_task_id: Option<Uuid>,
+    ) -> Self {
+        Self {
+            event_id: Uuid::new_v4(),
+            company_id,
+            app_id: action.app_id,
+            action_id: action.id,
+            evaluation_id: evaluation.id,
+            trace_id: action.trace_id.clone(),
+            user_id: action.user_id.clone(),
+            action_type: action.action_type.clone(),
+            decision: evaluation.decision,
+            risk_tier: evaluation.risk_tier,
+            reasons: evaluation.reasons.clone(),
+            rule_hits: evaluation.rule_hits.clone(),
+            hitl_task_id,
+            evaluated_at: evaluation.created_at,
+        }
+    }
+}
+
+/// Destination for decision events (message queue, stream, ...).
+#[async_trait]
+pub trait EventPublisher: Send + Sync {
+    /// Publish one event.
+    async fn publish(&self, event: &DecisionEvent) -> anyhow::Result<()>;
+}
+
+/// Non-blocking front for an [`EventPublisher`].
+pub struct EventBus {
+    tx: Option<mpsc::Sender<DecisionEvent>>,
+    dropped: AtomicU64,
+}
+
+impl EventBus {
+    /// A bus that discards every event.
+    pub fn disabled() -> Self {
+        Self {
+            tx: None,
+            dropped: AtomicU64::new(0),
+        }
+    }
+
+    /// Start a background task delivering events to `publisher`, buffering
+    /// at most `capacity` of them.
+    pub fn start(publisher: Arc<dyn EventPublisher>, capacity: usize) -> Self {
+        let (tx, mut rx) = mpsc::channel::<DecisionEvent>(capacity.max(1));
+        tokio::spawn(async move {
+            while let Some(event) = rx.recv().await {
+                if let Err(e) = publisher.publish(&event).await {
+                    tracing::warn!(
+                        event_id = %event.event_id,
+                        evaluation_id = %event.evaluation_id,
+                        error = %e,
+                        "Failed to publish decision event"
+                    );
+                }
+            }
+        });
+        Self {
+            tx: Some(tx),
+            dropped: AtomicU64::new(0),
+        }
+    }
+
+    /// Queue an event for publishing without waiting.
+    pub fn publish(&self, event: DecisionEvent) {
+        let Some(tx) = &self.tx else {
+            return;
+        };
+        if let Err(e) = tx.try_send(event) {
+            let dropped = self.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+            let reason = match e {
+                mpsc::error::TrySendError::Full(_) => "buffer full",
+                mpsc::error::TrySendError::Closed(_) => "publisher stopped",
+            };
+            tracing::warn!(reason, dropped, "Dropped decision event");
+        }
+    }
+
+    /// Number of events dropped since the bus started.
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+/// A HITL task that was just created.
+#[derive(Debug, Clone)]
+pub struct NewHitlTask {
+    /// Company the task belongs to (none for actions evaluated outside a
+    /// company).
+    pub company_id: Option<Uuid>,
+    pub task: HitlTaskSummary,
+}
+
+/// In-process broadcast of new HITL tasks to live subscribers.
+///
+/// Subscribers that fall more than the channel capacity behind miss the
+/// oldest tasks; the task list remains the source of truth.
+pub struct HitlTaskFeed {
+    tx: broadcast::Sender<NewHitlTask>,
+}
+
+impl Default for HitlTaskFeed {
+    fn default() -> Self {
+        Self::new(256)
+    }
+}
+
+impl HitlTaskFeed {
+    pub fn new(capacity: usize) -> Self {
+        let (tx, _) = broadcast::channel(capacity.max(1));
+        Self { tx }
+    }
+
+    /// Announce a new task; a no-op when nobody is subscribed.
+    pub fn publish(&self, task: NewHitlTask) {
+        let _ = self.tx.send(task);
+    }
+
+    /// Receive tasks created from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<NewHitlTask> {
+        self.tx.subscribe()
+    }
+}
+
+/// Appends decision events to a Redis stream as a single `event` JSON field.
+#[cfg(feature = "redis-events")]
+pub struct RedisStreamPublisher {
+    connection: redis::aio::MultiplexedConnection,
+    stream: String,
+}
+
+#[cfg(feature = "redis-events")]
+impl RedisStreamPublisher {
+    /// Connect to the Redis server at `url`.
+    pub async fn connect(url: &str, stream: String) -> anyhow::Result<Self> {
+        let client = redis::Client::open(url)?;
+        let connection = client.get_multiplexed_async_connection().await?;
+        Ok(Self { connection, stream })
+    }
+}
+
+#[cfg(feature = "redis-events")]
+#[async_trait]
+impl EventPublisher for RedisStreamPublisher {
+    async fn publish(&self, event: &DecisionEvent) -> anyhow::Result<()> {
+        let payload = serde_json::to_string(event)?;
+        let mut connection = self.connection.clone();
+        redis::cmd("XADD")
+            .arg(&self.stream)
+            .arg("*")
+            .arg("event")
+            .arg(payload)
+            .query_async::<String>(&mut connection)
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::Semaphore;
+
+    /// Records published events, optionally waiting for a permit first.
+    #[derive(Default)]
+    struct CapturingPublisher {
+        events: std::sync::Mutex<Vec<DecisionEvent>>,
+        gate: Option<Semaphore>,
+    }
+
+    #[async_trait]
+    impl EventPublisher for CapturingPublisher {
+        async fn publish(&self, event: &DecisionEvent) -> anyhow::Result<()> {
+            if let Some(gate) = &self.gate {
+                gate.acquire().await?.forget();
+            }
+            self.events.lock().unwrap().push(event.clone());
+            Ok(())
+        }
+    }
+
+    fn event() -> DecisionEvent {
+        let action = AgentAction::new(
+            "user-1",
+            "api",
+            "gpt-4",
+            "check my balance",
+            ActionType::GetBalance,
+            serde_json::json!({"account_id": "checking"}),
+        );
+        let evaluation = EvaluationResult::new(
+            action.id,
+            DecisionStatus::Allow,
+            RiskTier::Low,
+            vec![],
+            vec![],
+        );
+        DecisionEvent::new(Some(Uuid::new_v4()), &action, &evaluation, None)
+    }
+
+    async fn wait_for(publisher: &CapturingPublisher, count: usize) {
+        for _ in 0..100 {
+            if publisher.events.lock().unwrap().len() >= count {
+                return;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        panic!("expected {} published events", count);
+    }
+
+    #[tokio::test]
+    async fn test_events_are_published_in_order() {
+        let publisher = Arc::new(CapturingPublisher::default());
+        let bus = EventBus::start(publisher.clone(), 8);
+
+        let events: Vec<DecisionEvent> = (0..3).map(|_| event()).collect();
+        for e in &events {
+            bus.publish(e.clone());
+        }
+
+        wait_for(&publisher, 3).await;
+        let published: Vec<Uuid> = publisher
+            .events
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|e| e.event_id)
+            .collect();
+        let expected: Vec<Uuid> = events.iter().map(|e| e.event_id).collect();
+        assert_eq!(published, expected);
+        assert_eq!(bus.dropped(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_full_buffer_drops_instead_of_blocking() {
+        let publisher = Arc::new(CapturingPublisher {
+            gate: Some(Semaphore::new(0)),
+            ..Default::default()
+        });
+        let bus = EventBus::start(publisher.clone(), 2);
+
+        // The publisher is stalled, so only the buffer's worth (plus the one
+        // it's holding) can be accepted
+        for _ in 0..10 {
+            bus.publish(event());
+        }
+        assert!(bus.dropped() >= 7);
+
+        publisher.gate.as_ref().unwrap().add_permits(10);
+        wait_for(&publisher, 10 - bus.dropped() as usize).await;
+    }
+
+    #[test]
+    fn test_disabled_bus_discards_events() {
+        let bus = EventBus::disabled();
+        bus.publish(event());
+        assert_eq!(bus.dropped(), 0);
+    }
+
+    #[test]
+    fn test_event_serialization() {
+        let json = serde_json::to_value(event()).unwrap();
+        assert_eq!(json["decision"], "allow");
+        assert_eq!(json["action_type"], "get_balance");
+        assert!(json.get("hitl_task_id").is_none());
+    }
+}
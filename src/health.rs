@@ -0,0 +1,223 @@
+//! Readiness checks of the services Shield Core depends on.
+//!
+//! The database is checked on every request; external dependencies (the
+//! Llama Guard API) are checked at most every [`DEPENDENCY_CHECK_TTL`] so
+//! frequent probes don't turn into API traffic.
+
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use axum::async_trait;
+use reqwest::Client;
+use tokio::sync::Mutex;
+
+use crate::storage::ShieldRepository;
+
+/// How long an external dependency's last check result is reused.
+pub const DEPENDENCY_CHECK_TTL: Duration = Duration::from_secs(30);
+
+/// Checks whether a dependency is reachable.
+#[async_trait]
+pub trait DependencyCheck: Send + Sync {
+    /// `Err` describes why the dependency can't be used.
+    async fn check(&self) -> Result<(), String>;
+}
+
+/// Database connectivity (`SELECT 1`).
+pub struct DatabaseCheck(pub ShieldRepository);
+
+#[async_trait]
+impl DependencyCheck for DatabaseCheck {
+    async fn check(&self) -> Result<(), String> {
+        sqlx::query("SELECT 1")
+            .fetch_one(self.0.pool())
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// OpenRouter reachability, by listing models with the configured key.
+pub struct OpenRouterCheck {
+    client: Client,
+    base_url: String,
+    api_key: String,
+}
+
+impl OpenRouterCheck {
+    pub fn new(base_url: String, api_key: String, timeout: Duration) -> Self {
+        let client = Client::builder()
+            .timeout(timeout)
+            .build()
+            .expect("Failed to create HTTP client");
+        Self {
+            client,
+            base_url,
+            api_key,
+        }
+    }
+}
+
+#[async_trait]
+impl DependencyCheck for OpenRouterCheck {
+    async fn check(&self) -> Result<(), String> {
+        let response = self
+            .client
+            .get(format!("{}/models", self.base_url))
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e))?;
+        if !response.status().is_success() {
+            return Err(format!("API error {}", response.status()));
+        }
+        Ok(())
+    }
+}
+
+/// A check whose result is reused for a while.
+struct CachedCheck {
+    check: Box<dyn DependencyCheck>,
+    ttl: Duration,
+    last: Mutex<Option<(Instant, Result<(), String>)>>,
+}
+
+impl CachedCheck {
+    async fn check(&self) -> Result<(), String> {
+        // Held across the check so concurrent probes share one request
+        let mut last = self.last.lock().await;
+        if let Some((at, result)) = last.as_ref() {
+            if at.elapsed() < self.ttl {
+                return result.clone();
+            }
+        }
+        let result = self.check.check().await;
+        *last = Some((Instant::now(), result.clone()));
+        result
+    }
+}
+
+/// Overall health derived from the checks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HealthStatus {
+    Healthy,
+    /// The database is up but an external dependency isn't.
+    Degraded,
+    /// The database is unreachable.
+    Unhealthy,
+}
+
+impl std::fmt::Display for HealthStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HealthStatus::Healthy => write!(f, "healthy"),
+            HealthStatus::Degraded => write!(f, "degraded"),
+            HealthStatus::Unhealthy => write!(f, "unhealthy"),
+        }
+    }
+}
+
+/// Result of running every check.
+#[derive(Debug, Clone)]
+pub struct HealthReport {
+    pub status: HealthStatus,
+    pub database: Result<(), String>,
+    /// External dependencies by name.
+    pub dependencies: BTreeMap<String, Result<(), String>>,
+}
+
+/// The database check plus the external dependencies to check.
+pub struct HealthChecks {
+    database: Box<dyn DependencyCheck>,
+    dependencies: Vec<(String, CachedCheck)>,
+}
+
+impl HealthChecks {
+    pub fn new(database: Box<dyn DependencyCheck>) -> Self {
+        Self {
+            database,
+            dependencies: Vec::new(),
+        }
+    }
+
+    /// Also check `name`, reusing each result for `ttl`.
+    pub fn with_dependency(
+        mut self,
+        name: &str,
+        check: Box<dyn DependencyCheck>,
+        ttl: Duration,
+    ) -> Self {
+        self.dependencies.push((
+            name.to_string(),
+            CachedCheck {
+                check,
+                ttl,
+                last: Mutex::new(None),
+            },
+        ));
+        self
+    }
+
+    /// Run the checks (external ones concurrently).
+    pub async fn report(&self) -> HealthReport {
+        let (database, dependencies) = tokio::join!(
+            self.database.check(),
+            futures_util::future::join_all(
+                self.dependencies
+                    .iter()
+                    .map(|(name, check)| async move { (name.clone(), check.check().await) }),
+            )
+        );
+        let dependencies: BTreeMap<_, _> = dependencies.into_iter().collect();
+
+        let status = if database.is_err() {
+            HealthStatus::Unhealthy
+        } else if dependencies.values().any(Result::is_err) {
+            HealthStatus::Degraded
+        } else {
+            HealthStatus::Healthy
+        };
+
+        HealthReport {
+            status,
+            database,
+            dependencies,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// Check that fails, counting how often it ran.
+    struct Down(Arc<AtomicUsize>);
+
+    #[async_trait]
+    impl DependencyCheck for Down {
+        async fn check(&self) -> Result<(), String> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Err("connection refused".to_string())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dependency_results_are_cached() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let checks = HealthChecks::new(Box::new(Down(Arc::new(AtomicUsize::new(0)))))
+            .with_dependency(
+                "llama_guard",
+                Box::new(Down(calls.clone())),
+                Duration::from_secs(60),
+            );
+
+        for _ in 0..3 {
+            let report = checks.report().await;
+            assert_eq!(report.status, HealthStatus::Unhealthy);
+            assert!(report.dependencies["llama_guard"].is_err());
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,67 @@
+//! Idempotency keys for the evaluate endpoint.
+//!
+//! Agents retrying a timed-out request resend it with the same
+//! `Idempotency-Key` header. Requests sharing a key are serialized here so
+//! only one of them runs the pipeline; the rest replay its stored response.
+//! Locks live in memory, so serialization applies per instance.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use sha2::{Digest, Sha256};
+use tokio::sync::{Mutex as AsyncMutex, OwnedMutexGuard};
+
+/// Per-key locks held while a keyed request is evaluated.
+#[derive(Default)]
+pub struct IdempotencyLocks {
+    locks: Mutex<HashMap<String, Arc<AsyncMutex<()>>>>,
+}
+
+impl IdempotencyLocks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Wait until no other request holds `key`, then hold it until the
+    /// guard is dropped.
+    pub async fn lock(&self, key: &str) -> OwnedMutexGuard<()> {
+        let lock = {
+            let mut locks = self.locks.lock().unwrap();
+            // Forget keys nobody holds or waits on
+            locks.retain(|_, lock| Arc::strong_count(lock) > 1);
+            locks.entry(key.to_string()).or_default().clone()
+        };
+        lock.lock_owned().await
+    }
+}
+
+/// Fingerprint of a request body, used to tell a retry from a different
+/// request reusing its key.
+pub fn request_fingerprint(body: &serde_json::Value) -> String {
+    hex::encode(Sha256::digest(body.to_string().as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_same_key_waits_for_holder() {
+        let locks = Arc::new(IdempotencyLocks::new());
+        let guard = locks.lock("retry-1").await;
+
+        // Other keys are free
+        drop(locks.lock("retry-2").await);
+
+        let waiter = tokio::spawn({
+            let locks = locks.clone();
+            async move { drop(locks.lock("retry-1").await) }
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!waiter.is_finished());
+
+        drop(guard);
+        waiter.await.unwrap();
+    }
+}
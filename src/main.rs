@@ -3,27 +3,45 @@
 //! This service evaluates LLM agent actions before execution,
 //! applying layered safety checks to protect financial operations.
 
+use std::net::SocketAddr;
 use std::sync::Arc;
 
-use sqlx::sqlite::SqlitePool;
 use tokio::net::TcpListener;
 
+mod accounts;
 mod api;
+mod audit;
 mod auth;
 mod config;
 mod domain;
 mod engine;
 mod error;
+mod events;
+mod health;
+mod idempotency;
 mod logging;
+mod notifications;
+mod rate_limit;
+mod retention;
+mod selftest;
 mod storage;
+mod telemetry;
 
+use crate::accounts::{AccountRegistry, NoopAccountRegistry};
 use crate::api::build_router;
 use crate::auth::{ApiKeyValidator, JwtManager, UserStore};
 use crate::config::Config;
 use crate::engine::{
-    CompositeFirewall, ConfigPolicyEngine, EvaluationCoordinator, HeuristicAlignmentChecker,
-    KeywordFirewall,
+    AutomationLoopFirewall, CompositeFirewall, ConfigPolicyEngine, CotDivergenceFirewall,
+    EvaluationCoordinator, HeuristicAlignmentChecker, InMemoryRecentActions, KeywordFirewall,
+    ObfuscationFirewall, RegexFirewall, RiskTierRules, ToolCallFirewall,
 };
+use crate::audit::AuditLog;
+use crate::events::{EventBus, HitlTaskFeed};
+use crate::health::{DatabaseCheck, HealthChecks, OpenRouterCheck, DEPENDENCY_CHECK_TTL};
+use crate::idempotency::IdempotencyLocks;
+use crate::notifications::{NotificationDispatcher, WebhookSender};
+use crate::rate_limit::{AppRateLimiter, LoginThrottle};
 use crate::storage::ShieldRepository;
 
 /// Application state shared across handlers.
@@ -37,6 +55,92 @@ pub struct AppState {
     pub jwt_manager: JwtManager,
     /// User store for config-based users (legacy).
     pub user_store: UserStore,
+    /// Loaded service configuration.
+    pub config: Arc<Config>,
+    /// HITL notification dispatcher (handles digest batching).
+    pub notifier: Arc<NotificationDispatcher>,
+    /// Decision event publisher.
+    pub events: Arc<EventBus>,
+    /// Live feed of new HITL tasks.
+    pub hitl_feed: Arc<HitlTaskFeed>,
+    /// Tamper-evident decision log.
+    pub audit: Arc<AuditLog>,
+    /// Account existence lookups for payload accounts.
+    pub accounts: Arc<dyn AccountRegistry>,
+    /// Per-app request budgets for the evaluate endpoint.
+    pub rate_limiter: Arc<AppRateLimiter>,
+    /// Failed console logins and their lockouts.
+    pub login_throttle: Arc<LoginThrottle>,
+    /// Locks serializing evaluate requests that share an idempotency key.
+    pub idempotency_locks: Arc<IdempotencyLocks>,
+    /// Readiness checks of the database and external dependencies.
+    pub health: Arc<HealthChecks>,
+}
+
+#[cfg(test)]
+impl AppState {
+    /// Build state backed by an in-memory database with default policies.
+    pub async fn for_tests() -> Self {
+        let repository = ShieldRepository::connect("sqlite::memory:")
+            .await
+            .expect("Failed to create test database");
+        repository
+            .init_schema()
+            .await
+            .expect("Failed to init schema");
+        let health = HealthChecks::new(Box::new(DatabaseCheck(repository.clone())));
+
+        let coordinator = EvaluationCoordinator::new(
+            Box::new(KeywordFirewall::new(vec!["bypass".into()], vec![])),
+            Box::new(HeuristicAlignmentChecker::new(false)),
+            Box::new(ConfigPolicyEngine::new(config::SafetyConfig::default())),
+        );
+
+        Self {
+            coordinator: Arc::new(coordinator),
+            repository,
+            jwt_manager: JwtManager::new("test-secret", "shield-core".to_string(), 24),
+            user_store: UserStore::new(vec![]),
+            config: Arc::new(Config {
+                server: config::ServerConfig {
+                    host: "127.0.0.1".to_string(),
+                    port: 0,
+                    decision_headers: false,
+                    max_list_response_bytes: 1024 * 1024,
+                    return_matched_patterns: true,
+                    guard_bypass_cidrs: Vec::new(),
+                    client_ip_header: None,
+                    trusted_proxies: Vec::new(),
+                    console_url: None,
+                    guard_company_deletion: true,
+                    selftest_enabled: false,
+                    idempotency_ttl_hours: 24,
+                    metrics_enabled: false,
+                    webhook_blocked_hosts: vec!["localhost".to_string(), "*.internal".to_string()],
+                },
+                database: config::DatabaseConfig {
+                    url: "sqlite::memory:".to_string(),
+                },
+                safety: config::SafetyConfig::default(),
+                auth: config::AuthConfig::default(),
+                llm: config::LlmConfig::default(),
+                plans: Default::default(),
+                events: Default::default(),
+                audit: Default::default(),
+                overrides: Default::default(),
+                retention: Default::default(),
+            }),
+            notifier: Arc::new(NotificationDispatcher::new(Arc::new(WebhookSender::new()))),
+            events: Arc::new(EventBus::disabled()),
+            hitl_feed: Arc::new(HitlTaskFeed::default()),
+            audit: Arc::new(AuditLog::disabled()),
+            accounts: Arc::new(NoopAccountRegistry),
+            rate_limiter: Arc::new(AppRateLimiter::new()),
+            login_throttle: Arc::new(LoginThrottle::new()),
+            idempotency_locks: Arc::new(IdempotencyLocks::new()),
+            health: Arc::new(health),
+        }
+    }
 }
 
 #[tokio::main]
@@ -69,15 +173,14 @@ async fn main() -> anyhow::Result<()> {
     );
 
     // Connect to database
-    let pool = SqlitePool::connect(&config.database.url)
+    let repository = ShieldRepository::connect(&config.database.url)
         .await
         .map_err(|e| {
             tracing::error!(error = %e, "Failed to connect to database");
             anyhow::anyhow!("Database connection error: {}", e)
         })?;
 
-    // Initialize repository and schema
-    let repository = ShieldRepository::new(pool);
+    // Initialize schema
     repository.init_schema().await.map_err(|e| {
         tracing::error!(error = %e, "Failed to initialize database schema");
         anyhow::anyhow!("Schema initialization error: {}", e)
@@ -86,52 +189,171 @@ async fn main() -> anyhow::Result<()> {
     tracing::info!("Database connected and schema initialized");
 
     // Build the evaluation coordinator
-    let mut firewalls: Vec<Box<dyn engine::InputFirewall>> = vec![Box::new(KeywordFirewall::new(
+    let keyword_firewall = KeywordFirewall::new(
         config.safety.suspicious_keywords.clone(),
-    ))];
+        config.safety.block_keywords.clone(),
+    )
+    .with_block_score(config.safety.suspicious_block_score)
+    .with_languages(&config.safety.block_keyword_languages)
+    .map_err(|e| {
+        tracing::error!(error = %e, "Failed to load firewall block keywords");
+        anyhow::anyhow!("{}", e)
+    })?;
+    let regex_firewall = RegexFirewall::new(config.safety.regex_rules.clone()).map_err(|e| {
+        tracing::error!(error = %e, "Failed to compile firewall regex rules");
+        anyhow::anyhow!("{}", e)
+    })?;
+    let firewalls: Vec<Box<dyn engine::InputFirewall>> = vec![
+        Box::new(keyword_firewall),
+        Box::new(regex_firewall),
+        Box::new(ToolCallFirewall::new(
+            config.safety.tool_call_injection.clone(),
+        )),
+        Box::new(ObfuscationFirewall::new(config.safety.obfuscation.clone())),
+        Box::new(CotDivergenceFirewall::new(
+            config.safety.cot_divergence.clone(),
+        )),
+        Box::new(AutomationLoopFirewall::new(
+            config.safety.automation_loop.clone(),
+            Box::new(InMemoryRecentActions::new()),
+        )),
+    ];
 
-    // Add Llama Guard if enabled
-    if config.llm.enabled && !config.llm.openrouter_api_key.is_empty() {
-        tracing::info!(
-            model = %config.llm.guard_model,
-            "Llama Guard neural firewall enabled"
-        );
+    let firewall = CompositeFirewall::new(firewalls);
+    let alignment_checker: Box<dyn engine::AsyncAlignmentChecker> =
+        match &config.llm.alignment_model {
+            Some(model) if !config.llm.openrouter_api_key.is_empty() => {
+                tracing::info!(model = %model, "LLM alignment checker enabled");
+                Box::new(engine::LlmAlignmentChecker::new(engine::OpenRouterConfig {
+                    api_key: config.llm.openrouter_api_key.clone(),
+                    model: model.clone(),
+                    timeout_secs: config.llm.timeout_secs,
+                    enabled: true,
+                    ..Default::default()
+                }))
+            }
+            _ => Box::new(HeuristicAlignmentChecker::new(false)),
+        };
+    let policy_engine = ConfigPolicyEngine::new(config.safety.clone());
+
+    let mut coordinator = EvaluationCoordinator::new(
+        Box::new(firewall),
+        alignment_checker,
+        Box::new(policy_engine),
+    )
+    .with_risk_tiers(RiskTierRules::new(config.safety.risk_tiers.clone()));
+
+    // Attach Llama Guard whenever an API key is configured, so trusted apps
+    // can force it on per request even when it is disabled by default
+    if !config.llm.openrouter_api_key.is_empty() {
+        if config.llm.enabled {
+            tracing::info!(
+                model = %config.llm.guard_model,
+                "Llama Guard neural firewall enabled"
+            );
+        } else {
+            tracing::info!("Llama Guard neural firewall disabled by default");
+        }
         let llm_config = engine::OpenRouterConfig {
             api_key: config.llm.openrouter_api_key.clone(),
             model: config.llm.guard_model.clone(),
             timeout_secs: config.llm.timeout_secs,
             enabled: true,
+            output_format: config.llm.guard_output_format,
+            cache_capacity: config.llm.guard_cache_capacity,
+            cache_ttl_secs: config.llm.guard_cache_ttl_secs,
+            fail_closed: config.llm.guard_fail_closed,
+            fail_closed_outcome: config.llm.guard_fail_closed_outcome,
+            ..Default::default()
         };
-        firewalls.push(Box::new(engine::SyncLlamaGuardFirewall::new(llm_config)));
+        coordinator = coordinator.with_guard(
+            Box::new(engine::LlamaGuardFirewall::new(llm_config)),
+            config.llm.enabled,
+        );
     } else {
         tracing::info!("Llama Guard neural firewall disabled");
     }
 
-    let firewall = CompositeFirewall::new(firewalls);
-    let alignment_checker = HeuristicAlignmentChecker::new(false);
-    let policy_engine = ConfigPolicyEngine::new(config.safety.clone());
+    let coordinator = Arc::new(coordinator);
 
-    let coordinator = Arc::new(EvaluationCoordinator::new(
-        Box::new(firewall),
-        Box::new(alignment_checker),
-        Box::new(policy_engine),
-    ));
+    // Readiness: the database, plus the Llama Guard API when it is on
+    let mut health = HealthChecks::new(Box::new(DatabaseCheck(repository.clone())));
+    if config.llm.enabled && !config.llm.openrouter_api_key.is_empty() {
+        health = health.with_dependency(
+            "llama_guard",
+            Box::new(OpenRouterCheck::new(
+                engine::OpenRouterConfig::default().base_url,
+                config.llm.openrouter_api_key.clone(),
+                std::time::Duration::from_secs(config.llm.timeout_secs),
+            )),
+            DEPENDENCY_CHECK_TTL,
+        );
+    }
 
     // Build authentication components
-    let api_key_validator = ApiKeyValidator::new(config.auth.api_keys.clone());
-    let jwt_manager = JwtManager::new(
-        &config.auth.jwt_secret,
-        config.auth.jwt_issuer.clone(),
-        config.auth.token_duration_hours,
+    let api_key_validator =
+        ApiKeyValidator::new(config.auth.api_keys.clone()).with_app_keys(repository.clone());
+    let jwt_manager = config.auth.jwt_verification_keys.iter().fold(
+        JwtManager::new(
+            &config.auth.jwt_secret,
+            config.auth.jwt_issuer.clone(),
+            config.auth.token_duration_hours,
+        )
+        .with_key_id(config.auth.jwt_key_id.clone()),
+        |manager, key| manager.with_verification_key(key),
     );
     let user_store = UserStore::new(config.auth.users.clone());
 
+    // Flush due HITL notification digests in the background
+    let notifier = Arc::new(NotificationDispatcher::new(Arc::new(
+        WebhookSender::new()
+            .with_blocked_hosts(config.server.webhook_blocked_hosts.clone())
+            .with_log(repository.clone()),
+    )));
+    {
+        let notifier = notifier.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(std::time::Duration::from_secs(30));
+            loop {
+                ticker.tick().await;
+                notifier.flush_due(chrono::Utc::now());
+            }
+        });
+    }
+
+    // Purge actions past their retention period in the background
+    retention::spawn(repository.clone(), config.retention.clone());
+
+    let events = Arc::new(start_event_bus(&config.events).await);
+
+    let audit = match &config.audit.path {
+        Some(path) => {
+            let audit = AuditLog::open(path).await.map_err(|e| {
+                tracing::error!(error = %e, "Failed to open audit log");
+                e
+            })?;
+            tracing::info!(path = %path.display(), "Recording decisions to audit log");
+            audit
+        }
+        None => AuditLog::disabled(),
+    };
+
     // Build application state
     let state = AppState {
         coordinator,
         repository,
         jwt_manager: jwt_manager.clone(),
         user_store: user_store.clone(),
+        config: Arc::new(config.clone()),
+        notifier,
+        events,
+        hitl_feed: Arc::new(HitlTaskFeed::default()),
+        audit: Arc::new(audit),
+        accounts: Arc::new(NoopAccountRegistry),
+        rate_limiter: Arc::new(AppRateLimiter::new()),
+        login_throttle: Arc::new(LoginThrottle::new()),
+        idempotency_locks: Arc::new(IdempotencyLocks::new()),
+        health: Arc::new(health),
     };
 
     if config.auth.enabled {
@@ -144,6 +366,11 @@ async fn main() -> anyhow::Result<()> {
         tracing::warn!("Authentication is DISABLED - enable for production");
     }
 
+    if config.server.metrics_enabled {
+        telemetry::install();
+        tracing::info!("Operational metrics available at /metrics");
+    }
+
     // Build router
     let app = build_router(state, config.auth.enabled, api_key_validator, jwt_manager);
 
@@ -154,7 +381,41 @@ async fn main() -> anyhow::Result<()> {
     tracing::info!(address = %addr, "Server listening");
     tracing::info!("Swagger UI available at http://{}/swagger-ui/", addr);
 
-    axum::serve(listener, app).await?;
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await?;
 
     Ok(())
 }
+
+/// Start decision event publishing if a destination is configured.
+async fn start_event_bus(config: &config::EventsConfig) -> EventBus {
+    let Some(url) = &config.redis_url else {
+        return EventBus::disabled();
+    };
+
+    #[cfg(feature = "redis-events")]
+    {
+        match events::RedisStreamPublisher::connect(url, config.stream.clone()).await {
+            Ok(publisher) => {
+                tracing::info!(stream = %config.stream, "Publishing decision events to Redis");
+                EventBus::start(Arc::new(publisher), config.buffer_size)
+            }
+            Err(e) => {
+                tracing::error!(error = %e, "Failed to connect to Redis; decision events disabled");
+                EventBus::disabled()
+            }
+        }
+    }
+
+    #[cfg(not(feature = "redis-events"))]
+    {
+        tracing::warn!(
+            redis_url = %config::redact_url_credentials(url),
+            "events.redis_url is set but this build lacks the redis-events feature"
+        );
+        EventBus::disabled()
+    }
+}
@@ -0,0 +1,1039 @@
+//! HITL notification delivery.
+//!
+//! New review tasks are announced on the company's webhook. In digest mode
+//! tasks are collected per company and sent as a single summary once the
+//! configured interval has elapsed, optionally letting critical-risk tasks
+//! through immediately so an attack doesn't flood reviewers. Blocked and
+//! escalated actions are announced on the webhook as they happen.
+//! Webhook deliveries can be logged, and a logged delivery replayed.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use chrono::{DateTime, Duration, Utc};
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+use uuid::Uuid;
+
+use crate::domain::{
+    ActionType, App, CompanySettings, DecisionStatus, RiskTier, WebhookDeliveryRecord,
+};
+use crate::storage::ShieldRepository;
+
+/// A HITL task as announced to notification targets.
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskNotice {
+    pub task_id: Uuid,
+    pub agent_action_id: Uuid,
+    pub risk_tier: RiskTier,
+    pub reasons: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub review_url: Option<String>,
+}
+
+/// The action behind a flagged decision, as sent to webhooks.
+#[derive(Debug, Clone, Serialize)]
+pub struct ActionSummary {
+    pub id: Uuid,
+    pub action_type: ActionType,
+    pub user_id: String,
+    pub input: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A blocked or escalated evaluation as announced to webhooks.
+#[derive(Debug, Clone, Serialize)]
+pub struct ActionNotice {
+    pub trace_id: String,
+    pub action: ActionSummary,
+    pub decision: DecisionStatus,
+    pub risk_tier: RiskTier,
+    pub reasons: Vec<String>,
+    pub hitl_task_id: Option<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub review_url: Option<String>,
+}
+
+/// Where a company's notifications are delivered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotificationTarget {
+    pub company_id: Uuid,
+    pub webhook_url: Option<String>,
+    pub email: Option<String>,
+    /// Key for signing webhook payloads, once the company has one.
+    pub webhook_secret: Option<String>,
+    /// Rotated-out key still within its overlap window; payloads are
+    /// signed with it as well.
+    pub previous_webhook_secret: Option<String>,
+}
+
+impl NotificationTarget {
+    /// Target for a company, or `None` if it has nowhere to send to.
+    pub fn for_company(settings: &CompanySettings) -> Option<Self> {
+        if settings.webhook_url.is_none() && settings.notification_email.is_none() {
+            return None;
+        }
+        Some(Self {
+            company_id: settings.id,
+            webhook_url: settings.webhook_url.clone(),
+            email: settings.notification_email.clone(),
+            webhook_secret: settings.webhook_secret.clone(),
+            previous_webhook_secret: settings
+                .previous_webhook_secret
+                .as_ref()
+                .and_then(|previous| previous.active_at(Utc::now()))
+                .map(str::to_string),
+        })
+    }
+
+    /// Target for an app with its own webhook, or `None` if it uses the
+    /// company's.
+    pub fn for_app(app: &App) -> Option<Self> {
+        Some(Self {
+            company_id: app.company_id,
+            webhook_url: Some(app.webhook_url.clone()?),
+            email: None,
+            webhook_secret: app.webhook_secret.clone(),
+            previous_webhook_secret: app
+                .previous_webhook_secret
+                .as_ref()
+                .and_then(|previous| previous.active_at(Utc::now()))
+                .map(str::to_string),
+        })
+    }
+
+    /// Value of [`SIGNATURE_HEADER`] for a body, or `None` without a key.
+    ///
+    /// During a rotation's overlap the header lists a signature per key,
+    /// newest first, separated by commas.
+    pub fn signature(&self, body: &[u8]) -> Option<String> {
+        let current = sign_payload(self.webhook_secret.as_deref()?, body);
+        Some(match &self.previous_webhook_secret {
+            Some(previous) => format!("{}, {}", current, sign_payload(previous, body)),
+            None => current,
+        })
+    }
+}
+
+/// Notification payload.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Notification {
+    /// A single task needs review.
+    HitlTaskCreated { company_id: Uuid, task: TaskNotice },
+    /// Tasks collected over one digest interval.
+    HitlDigest {
+        company_id: Uuid,
+        window_start: DateTime<Utc>,
+        window_end: DateTime<Utc>,
+        tasks: Vec<TaskNotice>,
+    },
+    /// An evaluation was blocked or sent to review.
+    ActionFlagged {
+        company_id: Uuid,
+        #[serde(flatten)]
+        notice: ActionNotice,
+    },
+    /// Sample event sent when an admin tests the webhook.
+    WebhookTest {
+        company_id: Uuid,
+        sent_at: DateTime<Utc>,
+    },
+}
+
+impl Notification {
+    /// Event name, as in the payload's `type` field.
+    pub fn event_type(&self) -> &'static str {
+        match self {
+            Notification::HitlTaskCreated { .. } => "hitl_task_created",
+            Notification::HitlDigest { .. } => "hitl_digest",
+            Notification::ActionFlagged { .. } => "action_flagged",
+            Notification::WebhookTest { .. } => "webhook_test",
+        }
+    }
+}
+
+/// Delivers notifications to a target.
+pub trait NotificationSender: Send + Sync {
+    fn send(&self, target: &NotificationTarget, notification: &Notification);
+
+    /// Send a prepared delivery's payload to `target`, recorded under the
+    /// delivery's ID.
+    fn deliver(&self, target: &NotificationTarget, delivery: WebhookDeliveryRecord);
+}
+
+/// Header carrying the payload signature, `sha256=<hex HMAC of the body>`
+/// (several, comma-separated, while a rotated secret is still valid).
+pub const SIGNATURE_HEADER: &str = "x-shield-signature";
+
+/// How long a single webhook attempt may take.
+const WEBHOOK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Pause before retrying a failed delivery.
+const WEBHOOK_RETRY_DELAY: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Posts notifications as JSON to the company webhook.
+///
+/// Delivery is fire-and-forget on a spawned task; a 5xx or network error is
+/// retried once, then logged. Payloads are signed when the company has a
+/// webhook secret. Email has no transport yet, so email-only targets are
+/// just logged.
+///
+/// Each delivery resolves the webhook's host and connects only to an
+/// address checked to be public, and redirects are never followed, so a
+/// URL vetted when it was saved can't be turned on internal services.
+pub struct WebhookSender {
+    client: reqwest::Client,
+    /// Hosts deliveries are refused for, besides `localhost` and private
+    /// addresses. `None` skips the checks.
+    blocked_hosts: Option<Vec<String>>,
+    /// Where finished deliveries are recorded, if anywhere.
+    log: Option<ShieldRepository>,
+}
+
+impl Default for WebhookSender {
+    fn default() -> Self {
+        Self {
+            client: webhook_client().build().unwrap_or_default(),
+            blocked_hosts: Some(Vec::new()),
+            log: None,
+        }
+    }
+}
+
+impl WebhookSender {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Refuse deliveries to hosts matching `blocked_hosts`, as
+    /// `server.webhook_blocked_hosts`.
+    pub fn with_blocked_hosts(mut self, blocked_hosts: Vec<String>) -> Self {
+        self.blocked_hosts = Some(blocked_hosts);
+        self
+    }
+
+    /// Deliver to any address, for tests posting to a local receiver.
+    #[cfg(test)]
+    pub fn allow_private_targets(mut self) -> Self {
+        self.blocked_hosts = None;
+        self
+    }
+
+    /// Record every delivery, with its outcome, in `repository`.
+    pub fn with_log(mut self, repository: ShieldRepository) -> Self {
+        self.log = Some(repository);
+        self
+    }
+
+    /// Post `delivery`'s payload on a spawned task, retrying once on a 5xx
+    /// or network error, then log the outcome.
+    fn spawn_delivery(&self, target: &NotificationTarget, delivery: WebhookDeliveryRecord) {
+        let client = self.client.clone();
+        let blocked_hosts = self.blocked_hosts.clone();
+        let target = target.clone();
+        let log = self.log.clone();
+
+        tokio::spawn(async move {
+            let client = match &blocked_hosts {
+                Some(blocked_hosts) => pinned_client(&delivery.url, blocked_hosts).await,
+                None => Ok(client),
+            };
+            let delivery = match client {
+                Ok(client) => {
+                    let request = signed_request(
+                        &client,
+                        &delivery.url,
+                        &target,
+                        delivery.payload.clone().into_bytes(),
+                    );
+                    post_with_retry(request, delivery).await
+                }
+                Err(error) => {
+                    tracing::warn!(
+                        company_id = %delivery.company_id,
+                        error = %error,
+                        "Webhook delivery refused"
+                    );
+                    WebhookDeliveryRecord {
+                        last_error: Some(error),
+                        ..delivery
+                    }
+                }
+            };
+
+            if let Some(log) = log {
+                if let Err(e) = log.save_webhook_delivery(&delivery).await {
+                    tracing::error!(error = %e, "Failed to record webhook delivery");
+                }
+            }
+        });
+    }
+
+    /// Deliver a notification once and wait for the receiver's answer, for
+    /// checking that a webhook works. `pinned` fixes the address the
+    /// webhook's host connects to, so an address vetted by the caller can't
+    /// be swapped out by a second DNS lookup.
+    pub async fn deliver_now(
+        target: &NotificationTarget,
+        notification: &Notification,
+        pinned: Option<SocketAddr>,
+    ) -> WebhookDelivery {
+        let started = Instant::now();
+        let failed = |error: String| WebhookDelivery {
+            status_code: None,
+            latency: started.elapsed(),
+            error: Some(error),
+        };
+
+        let Some(url) = target.webhook_url.as_deref() else {
+            return failed("No webhook configured".to_string());
+        };
+        let mut client = webhook_client();
+        if let Some(addr) = pinned {
+            let Some(host) = reqwest::Url::parse(url)
+                .ok()
+                .and_then(|url| url.host_str().map(str::to_string))
+            else {
+                return failed(format!("Invalid webhook URL: {}", url));
+            };
+            client = client.resolve(&host, addr);
+        }
+        let client = match client.build() {
+            Ok(client) => client,
+            Err(e) => return failed(e.to_string()),
+        };
+        let body = match serde_json::to_vec(notification) {
+            Ok(body) => body,
+            Err(e) => return failed(e.to_string()),
+        };
+        let request = signed_request(&client, url, target, body);
+
+        match request.send().await {
+            Ok(response) => {
+                let status = response.status();
+                WebhookDelivery {
+                    status_code: Some(status.as_u16()),
+                    latency: started.elapsed(),
+                    error: (!status.is_success()).then(|| format!("status {}", status)),
+                }
+            }
+            Err(e) => failed(e.to_string()),
+        }
+    }
+}
+
+/// Outcome of a single webhook delivery.
+#[derive(Debug, Clone)]
+pub struct WebhookDelivery {
+    /// HTTP status the receiver answered with, if it answered.
+    pub status_code: Option<u16>,
+    /// Time until the answer or the failure.
+    pub latency: std::time::Duration,
+    /// Why the delivery failed, if it did.
+    pub error: Option<String>,
+}
+
+/// Send `request` for `delivery`, retrying once on a 5xx or network error,
+/// and return the delivery with its outcome.
+async fn post_with_retry(
+    request: reqwest::RequestBuilder,
+    mut delivery: WebhookDeliveryRecord,
+) -> WebhookDeliveryRecord {
+    for attempt in 1..=2 {
+        if attempt > 1 {
+            tokio::time::sleep(WEBHOOK_RETRY_DELAY).await;
+        }
+        let Some(this_attempt) = request.try_clone() else {
+            break;
+        };
+        delivery.attempts = attempt;
+        let (error, retry) = match this_attempt.send().await {
+            Ok(response) => {
+                let status = response.status();
+                delivery.status_code = Some(status.as_u16());
+                if status.is_success() {
+                    delivery.last_error = None;
+                    break;
+                }
+                (format!("status {}", status), status.is_server_error())
+            }
+            Err(e) => {
+                delivery.status_code = None;
+                (e.to_string(), true)
+            }
+        };
+        tracing::warn!(
+            company_id = %delivery.company_id,
+            attempt,
+            error = %error,
+            "Webhook delivery failed"
+        );
+        delivery.last_error = Some(error);
+        if !retry {
+            break;
+        }
+    }
+    delivery
+}
+
+/// Client settings shared by every webhook delivery. Redirects aren't
+/// followed, so a receiver can't bounce the request to an internal address.
+fn webhook_client() -> reqwest::ClientBuilder {
+    reqwest::Client::builder()
+        .timeout(WEBHOOK_TIMEOUT)
+        .redirect(reqwest::redirect::Policy::none())
+}
+
+/// Client for delivering to `url` that connects only to an address its
+/// host resolves to now, after checking every resolved address is public.
+/// `localhost` and hosts matching `blocked_hosts` are refused outright.
+async fn pinned_client(url: &str, blocked_hosts: &[String]) -> Result<reqwest::Client, String> {
+    let parsed = crate::domain::validate_webhook_url(url, blocked_hosts)?;
+    let host = parsed
+        .host_str()
+        .unwrap_or_default()
+        .trim_matches(['[', ']'])
+        .to_string();
+    let port = parsed.port_or_known_default().unwrap_or(443);
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), port))
+        .await
+        .map_err(|e| format!("Could not resolve {}: {}", host, e))?
+        .collect();
+    if addrs
+        .iter()
+        .any(|addr| !crate::domain::is_public_ip(addr.ip()))
+    {
+        return Err(format!(
+            "Webhook URL must not point at a local or private address: {}",
+            url
+        ));
+    }
+    let addr = addrs
+        .first()
+        .ok_or_else(|| format!("Could not resolve {}", host))?;
+
+    webhook_client()
+        .resolve(&host, *addr)
+        .build()
+        .map_err(|e| e.to_string())
+}
+
+/// JSON POST of a payload to `url`, signed when the target has a key.
+fn signed_request(
+    client: &reqwest::Client,
+    url: &str,
+    target: &NotificationTarget,
+    body: Vec<u8>,
+) -> reqwest::RequestBuilder {
+    let mut request = client
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json");
+    if let Some(signature) = target.signature(&body) {
+        request = request.header(SIGNATURE_HEADER, signature);
+    }
+    request.body(body)
+}
+
+/// Signature for a webhook body, as sent in [`SIGNATURE_HEADER`].
+pub fn sign_payload(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Whether a [`SIGNATURE_HEADER`] value carries a valid signature of `body`
+/// under `secret`, as a receiver would check it.
+#[cfg(test)]
+pub fn verify_signature(secret: &str, body: &[u8], header: &str) -> bool {
+    let expected = sign_payload(secret, body);
+    header
+        .split(',')
+        .any(|signature| signature.trim() == expected)
+}
+
+impl NotificationSender for WebhookSender {
+    fn send(&self, target: &NotificationTarget, notification: &Notification) {
+        let Some(url) = target.webhook_url.clone() else {
+            tracing::debug!(
+                company_id = %target.company_id,
+                "No webhook configured; email delivery is not supported"
+            );
+            return;
+        };
+
+        let payload = match serde_json::to_string(notification) {
+            Ok(payload) => payload,
+            Err(e) => {
+                tracing::error!(error = %e, "Failed to serialize notification");
+                return;
+            }
+        };
+
+        self.spawn_delivery(
+            target,
+            WebhookDeliveryRecord::new(target.company_id, notification.event_type(), url, payload),
+        );
+    }
+
+    fn deliver(&self, target: &NotificationTarget, delivery: WebhookDeliveryRecord) {
+        self.spawn_delivery(target, delivery);
+    }
+}
+
+/// Tasks awaiting a company's next digest.
+struct PendingDigest {
+    target: NotificationTarget,
+    window_start: DateTime<Utc>,
+    interval: Duration,
+    tasks: Vec<TaskNotice>,
+}
+
+/// Routes task notifications, batching them per company in digest mode.
+pub struct NotificationDispatcher {
+    sender: Arc<dyn NotificationSender>,
+    pending: Mutex<HashMap<Uuid, PendingDigest>>,
+}
+
+impl NotificationDispatcher {
+    pub fn new(sender: Arc<dyn NotificationSender>) -> Self {
+        Self {
+            sender,
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Announce a new HITL task according to the company's digest settings.
+    ///
+    /// Tasks from an app with its own webhook go there right away; digests
+    /// only batch the company webhook.
+    pub fn task_created(
+        &self,
+        settings: &CompanySettings,
+        app: Option<&App>,
+        task: TaskNotice,
+        now: DateTime<Utc>,
+    ) {
+        if let Some(target) = app.and_then(NotificationTarget::for_app) {
+            self.sender.send(
+                &target,
+                &Notification::HitlTaskCreated {
+                    company_id: settings.id,
+                    task,
+                },
+            );
+            return;
+        }
+        let Some(target) = NotificationTarget::for_company(settings) else {
+            return;
+        };
+
+        let digest = &settings.notification_digest;
+        if !digest.enabled || (digest.critical_immediate && task.risk_tier == RiskTier::Critical) {
+            self.sender.send(
+                &target,
+                &Notification::HitlTaskCreated {
+                    company_id: settings.id,
+                    task,
+                },
+            );
+            return;
+        }
+
+        let mut pending = self.pending.lock().expect("digest lock poisoned");
+        let entry = pending.entry(settings.id).or_insert_with(|| PendingDigest {
+            target: target.clone(),
+            window_start: now,
+            interval: Duration::minutes(i64::from(digest.interval_minutes)),
+            tasks: Vec::new(),
+        });
+        // Deliver to wherever the company points now, not when the window opened
+        entry.target = target;
+        entry.tasks.push(task);
+    }
+
+    /// Announce a blocked or escalated evaluation on the app's webhook, or
+    /// the company's if the app has none.
+    ///
+    /// Always sent right away; digest mode only batches HITL tasks.
+    pub fn action_flagged(
+        &self,
+        settings: &CompanySettings,
+        app: Option<&App>,
+        notice: ActionNotice,
+    ) {
+        let Some(target) = app
+            .and_then(NotificationTarget::for_app)
+            .or_else(|| NotificationTarget::for_company(settings))
+        else {
+            return;
+        };
+        if target.webhook_url.is_none() {
+            return;
+        }
+        self.sender.send(
+            &target,
+            &Notification::ActionFlagged {
+                company_id: settings.id,
+                notice,
+            },
+        );
+    }
+
+    /// Re-send a task's creation event right away, bypassing digest mode.
+    ///
+    /// Returns the ID the new delivery is logged under, or `None` if the
+    /// company has no webhook.
+    pub fn replay_task(&self, settings: &CompanySettings, task: TaskNotice) -> Option<Uuid> {
+        let target = NotificationTarget::for_company(settings)?;
+        let url = target.webhook_url.clone()?;
+        let notification = Notification::HitlTaskCreated {
+            company_id: settings.id,
+            task,
+        };
+        let payload = match serde_json::to_string(&notification) {
+            Ok(payload) => payload,
+            Err(e) => {
+                tracing::error!(error = %e, "Failed to serialize notification");
+                return None;
+            }
+        };
+
+        let delivery =
+            WebhookDeliveryRecord::new(settings.id, notification.event_type(), url, payload);
+        let delivery_id = delivery.id;
+        self.sender.deliver(&target, delivery);
+        Some(delivery_id)
+    }
+
+    /// Send a logged delivery again to the webhook it went to, signed with
+    /// that webhook's current secret.
+    ///
+    /// Returns `false` if neither the company nor any of `apps` still
+    /// points at the delivery's URL.
+    pub fn replay_delivery(
+        &self,
+        settings: &CompanySettings,
+        apps: &[App],
+        delivery: &WebhookDeliveryRecord,
+    ) -> bool {
+        let Some(target) = NotificationTarget::for_company(settings)
+            .into_iter()
+            .chain(apps.iter().filter_map(NotificationTarget::for_app))
+            .find(|target| target.webhook_url.as_deref() == Some(delivery.url.as_str()))
+        else {
+            return false;
+        };
+        let mut replay = WebhookDeliveryRecord::new(
+            delivery.company_id,
+            delivery.event_type.clone(),
+            delivery.url.clone(),
+            delivery.payload.clone(),
+        );
+        replay.replay_of = Some(delivery.id);
+        self.sender.deliver(&target, replay);
+        true
+    }
+
+    /// Send every digest whose interval has elapsed. Returns how many were sent.
+    pub fn flush_due(&self, now: DateTime<Utc>) -> usize {
+        let due: Vec<PendingDigest> = {
+            let mut pending = self.pending.lock().expect("digest lock poisoned");
+            let due_ids: Vec<Uuid> = pending
+                .iter()
+                .filter(|(_, digest)| now >= digest.window_start + digest.interval)
+                .map(|(id, _)| *id)
+                .collect();
+            due_ids
+                .into_iter()
+                .filter_map(|id| pending.remove(&id))
+                .collect()
+        };
+
+        for digest in &due {
+            tracing::info!(
+                company_id = %digest.target.company_id,
+                tasks = digest.tasks.len(),
+                "Sending HITL digest"
+            );
+            self.sender.send(
+                &digest.target,
+                &Notification::HitlDigest {
+                    company_id: digest.target.company_id,
+                    window_start: digest.window_start,
+                    window_end: now,
+                    tasks: digest.tasks.clone(),
+                },
+            );
+        }
+
+        due.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::NotificationDigest;
+
+    #[derive(Default)]
+    struct RecordingSender {
+        sent: Mutex<Vec<Notification>>,
+        targets: Mutex<Vec<NotificationTarget>>,
+    }
+
+    impl NotificationSender for RecordingSender {
+        fn send(&self, target: &NotificationTarget, notification: &Notification) {
+            self.sent.lock().unwrap().push(notification.clone());
+            self.targets.lock().unwrap().push(target.clone());
+        }
+
+        fn deliver(&self, target: &NotificationTarget, _delivery: WebhookDeliveryRecord) {
+            self.targets.lock().unwrap().push(target.clone());
+        }
+    }
+
+    fn digest_settings() -> CompanySettings {
+        let mut settings = CompanySettings::new(Uuid::new_v4(), "Acme".to_string());
+        settings.webhook_url = Some("https://hooks.example.com/shield".to_string());
+        settings.notification_digest = NotificationDigest {
+            enabled: true,
+            interval_minutes: 10,
+            critical_immediate: true,
+        };
+        settings
+    }
+
+    fn notice(risk_tier: RiskTier) -> TaskNotice {
+        TaskNotice {
+            task_id: Uuid::new_v4(),
+            agent_action_id: Uuid::new_v4(),
+            risk_tier,
+            reasons: vec!["Amount requires review".to_string()],
+            created_at: Utc::now(),
+            review_url: None,
+        }
+    }
+
+    #[test]
+    fn test_digest_batches_tasks_into_one_summary() {
+        let sender = Arc::new(RecordingSender::default());
+        let dispatcher = NotificationDispatcher::new(sender.clone());
+        let settings = digest_settings();
+        let start = Utc::now();
+
+        for minute in 0..3 {
+            dispatcher.task_created(
+                &settings,
+                None,
+                notice(RiskTier::High),
+                start + Duration::minutes(minute),
+            );
+        }
+        assert!(sender.sent.lock().unwrap().is_empty());
+
+        // Interval not over yet
+        assert_eq!(dispatcher.flush_due(start + Duration::minutes(9)), 0);
+
+        assert_eq!(dispatcher.flush_due(start + Duration::minutes(10)), 1);
+        let sent = sender.sent.lock().unwrap();
+        assert_eq!(sent.len(), 1);
+        match &sent[0] {
+            Notification::HitlDigest { tasks, .. } => assert_eq!(tasks.len(), 3),
+            other => panic!("expected digest, got {:?}", other),
+        }
+        drop(sent);
+
+        // Nothing left pending
+        assert_eq!(dispatcher.flush_due(start + Duration::minutes(30)), 0);
+    }
+
+    #[test]
+    fn test_critical_task_sends_immediately_in_digest_mode() {
+        let sender = Arc::new(RecordingSender::default());
+        let dispatcher = NotificationDispatcher::new(sender.clone());
+        let settings = digest_settings();
+        let now = Utc::now();
+
+        dispatcher.task_created(&settings, None, notice(RiskTier::High), now);
+        dispatcher.task_created(&settings, None, notice(RiskTier::Critical), now);
+
+        let sent = sender.sent.lock().unwrap();
+        assert_eq!(sent.len(), 1);
+        assert!(matches!(
+            &sent[0],
+            Notification::HitlTaskCreated { task, .. } if task.risk_tier == RiskTier::Critical
+        ));
+    }
+
+    #[test]
+    fn test_without_digest_every_task_sends_immediately() {
+        let sender = Arc::new(RecordingSender::default());
+        let dispatcher = NotificationDispatcher::new(sender.clone());
+        let mut settings = digest_settings();
+        settings.notification_digest.enabled = false;
+
+        dispatcher.task_created(&settings, None, notice(RiskTier::High), Utc::now());
+        dispatcher.task_created(&settings, None, notice(RiskTier::High), Utc::now());
+        assert_eq!(sender.sent.lock().unwrap().len(), 2);
+
+        // Companies without a webhook or email get nothing
+        settings.webhook_url = None;
+        dispatcher.task_created(&settings, None, notice(RiskTier::High), Utc::now());
+        assert_eq!(sender.sent.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_webhook_is_signed_and_retried_on_server_error() {
+        use axum::{body::Bytes, extract::State, http::HeaderMap, http::StatusCode, routing::post};
+        use tokio::sync::mpsc;
+
+        type Received = (StatusCode, HeaderMap, Bytes);
+        let (tx, mut rx) = mpsc::unbounded_channel::<Received>();
+        let attempts = Arc::new(Mutex::new(0));
+        let app = axum::Router::new()
+            .route(
+                "/hook",
+                post(
+                    |State((tx, attempts)): State<(
+                        mpsc::UnboundedSender<Received>,
+                        Arc<Mutex<u32>>,
+                    )>,
+                     headers: HeaderMap,
+                     body: Bytes| async move {
+                        let mut attempts = attempts.lock().unwrap();
+                        *attempts += 1;
+                        // Fail the first delivery to exercise the retry
+                        let status = if *attempts == 1 {
+                            StatusCode::SERVICE_UNAVAILABLE
+                        } else {
+                            StatusCode::OK
+                        };
+                        tx.send((status, headers, body)).unwrap();
+                        status
+                    },
+                ),
+            )
+            .with_state((tx, attempts));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let target = NotificationTarget {
+            company_id: Uuid::new_v4(),
+            webhook_url: Some(format!("http://{}/hook", addr)),
+            email: None,
+            webhook_secret: Some("whsec_test".to_string()),
+            previous_webhook_secret: None,
+        };
+        let notice = ActionNotice {
+            trace_id: "trace-1".to_string(),
+            action: ActionSummary {
+                id: Uuid::new_v4(),
+                action_type: ActionType::TransferFunds,
+                user_id: "user-1".to_string(),
+                input: "Send $5000 to account 999".to_string(),
+                created_at: Utc::now(),
+            },
+            decision: DecisionStatus::RequireHitl,
+            risk_tier: RiskTier::High,
+            reasons: vec!["Amount requires review".to_string()],
+            hitl_task_id: Some(Uuid::new_v4()),
+            review_url: None,
+        };
+        WebhookSender::new().allow_private_targets().send(
+            &target,
+            &Notification::ActionFlagged {
+                company_id: target.company_id,
+                notice,
+            },
+        );
+
+        let timeout = std::time::Duration::from_secs(10);
+        let (status, _, first_body) = tokio::time::timeout(timeout, rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        let (status, headers, body) = tokio::time::timeout(timeout, rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, first_body);
+
+        assert_eq!(
+            headers[SIGNATURE_HEADER].to_str().unwrap(),
+            sign_payload("whsec_test", &body)
+        );
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload["type"], "action_flagged");
+        assert_eq!(payload["company_id"], target.company_id.to_string());
+        assert_eq!(payload["trace_id"], "trace-1");
+        assert_eq!(payload["decision"], "require_hitl");
+        assert_eq!(payload["risk_tier"], "high");
+        assert_eq!(payload["action"]["action_type"], "transfer_funds");
+        assert_eq!(payload["reasons"][0], "Amount requires review");
+        assert!(payload["hitl_task_id"].is_string());
+    }
+
+    #[tokio::test]
+    async fn test_deliver_now_reports_receiver_answer() {
+        use axum::{body::Bytes, http::HeaderMap, http::StatusCode, routing::post};
+        use tokio::sync::mpsc;
+
+        let (tx, mut rx) = mpsc::unbounded_channel::<(HeaderMap, Bytes)>();
+        let app = axum::Router::new()
+            .route(
+                "/hook",
+                post(move |headers: HeaderMap, body: Bytes| async move {
+                    tx.send((headers, body)).unwrap();
+                    StatusCode::NO_CONTENT
+                }),
+            )
+            .route("/gone", post(|| async { StatusCode::GONE }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        // The pinned address wins over DNS for the webhook's host
+        let mut target = NotificationTarget {
+            company_id: Uuid::new_v4(),
+            webhook_url: Some(format!("http://hooks.invalid:{}/hook", addr.port())),
+            email: None,
+            webhook_secret: Some("whsec_test".to_string()),
+            previous_webhook_secret: None,
+        };
+        let notification = Notification::WebhookTest {
+            company_id: target.company_id,
+            sent_at: Utc::now(),
+        };
+        let delivery = WebhookSender::deliver_now(&target, &notification, Some(addr)).await;
+        assert_eq!(delivery.status_code, Some(204));
+        assert!(delivery.error.is_none());
+
+        let (headers, body) = rx.recv().await.unwrap();
+        assert!(verify_signature(
+            "whsec_test",
+            &body,
+            headers[SIGNATURE_HEADER].to_str().unwrap()
+        ));
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload["type"], "webhook_test");
+        assert_eq!(payload["company_id"], target.company_id.to_string());
+
+        target.webhook_url = Some(format!("http://{}/gone", addr));
+        let delivery = WebhookSender::deliver_now(&target, &notification, None).await;
+        assert_eq!(delivery.status_code, Some(410));
+        assert!(delivery.error.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_webhook_redirects_are_not_followed() {
+        use axum::{http::StatusCode, response::Redirect, routing::post};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let followed = Arc::new(AtomicUsize::new(0));
+        let app = axum::Router::new()
+            .route("/hook", post(|| async { Redirect::temporary("/internal") }))
+            .route(
+                "/internal",
+                post({
+                    let followed = followed.clone();
+                    move || async move {
+                        followed.fetch_add(1, Ordering::SeqCst);
+                        StatusCode::OK
+                    }
+                }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let target = NotificationTarget {
+            company_id: Uuid::new_v4(),
+            webhook_url: Some(format!("http://{}/hook", addr)),
+            email: None,
+            webhook_secret: None,
+            previous_webhook_secret: None,
+        };
+        let notification = Notification::WebhookTest {
+            company_id: target.company_id,
+            sent_at: Utc::now(),
+        };
+        let delivery = WebhookSender::deliver_now(&target, &notification, None).await;
+        assert_eq!(delivery.status_code, Some(307));
+        assert!(delivery.error.is_some());
+        assert_eq!(followed.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_pinned_client_refuses_local_hosts() {
+        for url in [
+            "https://localhost/hook",
+            "https://127.0.0.1/hook",
+            "https://[::1]/hook",
+            "http://hooks.example.com/hook",
+        ] {
+            assert!(pinned_client(url, &[]).await.is_err(), "{url}");
+        }
+        let blocked = vec!["hooks.example.com".to_string()];
+        assert!(pinned_client("https://hooks.example.com/hook", &blocked)
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn test_rotated_secret_verifies_until_overlap_ends() {
+        let body = br#"{"type":"hitl_task_created"}"#;
+        let mut settings = digest_settings();
+        settings.webhook_secret = Some("whsec_new".to_string());
+        settings.previous_webhook_secret = Some(crate::domain::ExpiringSecret {
+            secret: "whsec_old".to_string(),
+            expires_at: Utc::now() + Duration::hours(1),
+        });
+
+        // During the overlap receivers on either key accept the payload
+        let target = NotificationTarget::for_company(&settings).unwrap();
+        let header = target.signature(body).unwrap();
+        assert!(verify_signature("whsec_new", body, &header));
+        assert!(verify_signature("whsec_old", body, &header));
+        assert!(!verify_signature("whsec_other", body, &header));
+
+        // Afterwards only the new key does
+        settings.previous_webhook_secret = Some(crate::domain::ExpiringSecret {
+            secret: "whsec_old".to_string(),
+            expires_at: Utc::now() - Duration::seconds(1),
+        });
+        let target = NotificationTarget::for_company(&settings).unwrap();
+        let header = target.signature(body).unwrap();
+        assert_eq!(header, sign_payload("whsec_new", body));
+        assert!(verify_signature("whsec_new", body, &header));
+        assert!(!verify_signature("whsec_old", body, &header));
+    }
+
+    #[test]
+    fn test_app_webhook_receives_its_notices() {
+        let sender = Arc::new(RecordingSender::default());
+        let dispatcher = NotificationDispatcher::new(sender.clone());
+        let settings = digest_settings();
+        let mut app = App::new(settings.id, "Payments bot".to_string(), None, 100);
+        app.webhook_url = Some("https://payments.example.com/shield".to_string());
+        app.webhook_secret = Some("whsec_app".to_string());
+
+        // Sent straight to the app's receiver, outside the company digest
+        let now = Utc::now();
+        dispatcher.task_created(&settings, Some(&app), notice(RiskTier::High), now);
+        assert_eq!(dispatcher.flush_due(now + Duration::minutes(10)), 0);
+
+        let targets = sender.targets.lock().unwrap();
+        assert_eq!(targets.len(), 1);
+        assert_eq!(targets[0].webhook_url, app.webhook_url);
+        assert_eq!(targets[0].webhook_secret.as_deref(), Some("whsec_app"));
+    }
+}
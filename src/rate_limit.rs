@@ -0,0 +1,278 @@
+//! Request rate limiting.
+//!
+//! Each app gets a token bucket holding up to its `rate_limit` requests,
+//! refilled continuously over a minute. Failed console logins are counted
+//! per email and per client IP, locking them out with increasing backoff.
+//! Both live in memory, so limits apply per instance and reset on restart.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+
+use crate::config::LoginThrottleConfig;
+
+struct TokenBucket {
+    tokens: f64,
+    refilled_at: DateTime<Utc>,
+}
+
+/// Tracks request budgets for apps calling the evaluate endpoint.
+#[derive(Default)]
+pub struct AppRateLimiter {
+    buckets: Mutex<HashMap<Uuid, TokenBucket>>,
+}
+
+impl AppRateLimiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Spend one request from the app's budget.
+    ///
+    /// `per_minute` is read from the app on every call, so edits take effect
+    /// immediately; 0 means unlimited. When the budget is spent, returns the
+    /// number of seconds until the next request would be allowed.
+    pub fn check(&self, app_id: Uuid, per_minute: u32, now: DateTime<Utc>) -> Result<(), u64> {
+        self.check_n(app_id, per_minute, 1, now)
+    }
+
+    /// Spend `requests` requests from the app's budget at once, or none of
+    /// them if the budget can't cover them all.
+    ///
+    /// A cost above `per_minute` can never be covered; the wait returned
+    /// is then until the budget is full.
+    pub fn check_n(
+        &self,
+        app_id: Uuid,
+        per_minute: u32,
+        requests: u32,
+        now: DateTime<Utc>,
+    ) -> Result<(), u64> {
+        if per_minute == 0 {
+            return Ok(());
+        }
+
+        let capacity = f64::from(per_minute);
+        let per_second = capacity / 60.0;
+        let mut buckets = self.buckets.lock().unwrap();
+        let bucket = buckets.entry(app_id).or_insert(TokenBucket {
+            tokens: capacity,
+            refilled_at: now,
+        });
+
+        let elapsed = (now - bucket.refilled_at).num_milliseconds().max(0) as f64 / 1000.0;
+        bucket.tokens = (bucket.tokens + elapsed * per_second).min(capacity);
+        bucket.refilled_at = now;
+
+        let cost = f64::from(requests);
+        if bucket.tokens >= cost {
+            bucket.tokens -= cost;
+            Ok(())
+        } else {
+            let wait = (cost.min(capacity) - bucket.tokens) / per_second;
+            Err(wait.ceil().max(1.0) as u64)
+        }
+    }
+}
+
+/// Failed login attempts for one email or IP.
+struct FailedLogins {
+    failures: u32,
+    window_started: DateTime<Utc>,
+    last_failure: DateTime<Utc>,
+    locked_until: Option<DateTime<Utc>>,
+    /// Lockouts so far; each doubles the next one.
+    lockouts: u32,
+}
+
+/// Tracks failed logins and the lockouts they earn.
+///
+/// Keys are opaque strings, such as `email:ana@example.com` or
+/// `ip:203.0.113.7`. Entries are forgotten once a key has gone
+/// `window_secs + max_lockout_secs` without failing, which also resets its
+/// backoff.
+#[derive(Default)]
+pub struct LoginThrottle {
+    entries: Mutex<HashMap<String, FailedLogins>>,
+}
+
+impl LoginThrottle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Seconds until the longest lockout among `keys` ends, if any is
+    /// locked out.
+    pub fn locked_for(&self, keys: &[String], now: DateTime<Utc>) -> Option<u64> {
+        let entries = self.entries.lock().unwrap();
+        keys.iter()
+            .filter_map(|key| entries.get(key)?.locked_until)
+            .filter(|until| *until > now)
+            .map(|until| seconds_until(until, now))
+            .max()
+    }
+
+    /// Count a failed attempt against each key. Returns the lockout, in
+    /// seconds, when this failure started one.
+    pub fn record_failure(
+        &self,
+        keys: &[String],
+        limits: &LoginThrottleConfig,
+        now: DateTime<Utc>,
+    ) -> Option<u64> {
+        if limits.max_failures == 0 {
+            return None;
+        }
+
+        let window = Duration::seconds(limits.window_secs as i64);
+        let ttl = window + Duration::seconds(limits.max_lockout_secs as i64);
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, entry| entry.last_failure + ttl > now);
+
+        let mut lockout = None;
+        for key in keys {
+            let entry = entries.entry(key.clone()).or_insert(FailedLogins {
+                failures: 0,
+                window_started: now,
+                last_failure: now,
+                locked_until: None,
+                lockouts: 0,
+            });
+            if now - entry.window_started > window {
+                entry.failures = 0;
+                entry.window_started = now;
+            }
+            entry.failures += 1;
+            entry.last_failure = now;
+
+            if entry.failures >= limits.max_failures {
+                let secs = limits
+                    .lockout_secs
+                    .saturating_mul(2u64.saturating_pow(entry.lockouts))
+                    .min(limits.max_lockout_secs);
+                entry.locked_until = Some(now + Duration::seconds(secs as i64));
+                entry.lockouts += 1;
+                entry.failures = 0;
+                entry.window_started = now;
+                lockout = lockout.max(Some(secs));
+            }
+        }
+        lockout
+    }
+
+    /// Forget the failures counted against `key`, e.g. after it logs in.
+    pub fn reset(&self, key: &str) {
+        self.entries.lock().unwrap().remove(key);
+    }
+}
+
+fn seconds_until(until: DateTime<Utc>, now: DateTime<Utc>) -> u64 {
+    let millis = (until - now).num_milliseconds().max(0) as u64;
+    millis.div_ceil(1000).max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rejects_once_budget_is_spent() {
+        let limiter = AppRateLimiter::new();
+        let app_id = Uuid::new_v4();
+        let now = Utc::now();
+
+        for _ in 0..3 {
+            assert!(limiter.check(app_id, 3, now).is_ok());
+        }
+        assert_eq!(limiter.check(app_id, 3, now), Err(20));
+
+        // Other apps have their own budget
+        assert!(limiter.check(Uuid::new_v4(), 3, now).is_ok());
+    }
+
+    #[test]
+    fn test_refills_over_time_and_follows_limit_changes() {
+        let limiter = AppRateLimiter::new();
+        let app_id = Uuid::new_v4();
+        let now = Utc::now();
+
+        assert!(limiter.check(app_id, 1, now).is_ok());
+        assert_eq!(limiter.check(app_id, 1, now), Err(60));
+        assert!(limiter
+            .check(app_id, 1, now + Duration::seconds(60))
+            .is_ok());
+
+        // Raising the limit refills faster; 0 turns it off
+        let later = now + Duration::seconds(61);
+        assert!(limiter.check(app_id, 60, later).is_ok());
+        assert!(limiter.check(app_id, 0, later).is_ok());
+    }
+
+    #[test]
+    fn test_check_n_spends_all_or_nothing() {
+        let limiter = AppRateLimiter::new();
+        let app_id = Uuid::new_v4();
+        let now = Utc::now();
+
+        assert!(limiter.check_n(app_id, 10, 4, now).is_ok());
+        assert_eq!(limiter.check_n(app_id, 10, 7, now), Err(6));
+        // The refused request spent nothing
+        assert!(limiter.check_n(app_id, 10, 6, now).is_ok());
+        assert!(limiter.check(app_id, 10, now).is_err());
+
+        // More than the whole budget is never allowed
+        let other = Uuid::new_v4();
+        assert_eq!(limiter.check_n(other, 3, 5, now), Err(1));
+        assert!(limiter.check_n(other, 3, 3, now).is_ok());
+    }
+
+    #[test]
+    fn test_login_lockouts_back_off_and_expire() {
+        let throttle = LoginThrottle::new();
+        let limits = LoginThrottleConfig {
+            max_failures: 3,
+            window_secs: 60,
+            lockout_secs: 30,
+            max_lockout_secs: 100,
+        };
+        let keys = vec!["email:ana@example.com".to_string()];
+        let now = Utc::now();
+
+        assert_eq!(throttle.record_failure(&keys, &limits, now), None);
+        assert_eq!(throttle.record_failure(&keys, &limits, now), None);
+        assert_eq!(throttle.record_failure(&keys, &limits, now), Some(30));
+        assert_eq!(throttle.locked_for(&keys, now), Some(30));
+        assert_eq!(
+            throttle.locked_for(&keys, now + Duration::seconds(30)),
+            None
+        );
+
+        // The next lockout doubles, up to the cap
+        let later = now + Duration::seconds(30);
+        for _ in 0..2 {
+            throttle.record_failure(&keys, &limits, later);
+        }
+        assert_eq!(throttle.record_failure(&keys, &limits, later), Some(60));
+        let later = later + Duration::seconds(60);
+        for _ in 0..2 {
+            throttle.record_failure(&keys, &limits, later);
+        }
+        assert_eq!(throttle.record_failure(&keys, &limits, later), Some(100));
+
+        // Failures outside the window don't add up
+        let later = later + Duration::seconds(100);
+        throttle.record_failure(&keys, &limits, later);
+        throttle.record_failure(&keys, &limits, later);
+        let later = later + Duration::seconds(61);
+        assert_eq!(throttle.record_failure(&keys, &limits, later), None);
+
+        // A quiet period forgets the key and its backoff
+        let later = later + Duration::seconds(161);
+        for _ in 0..2 {
+            throttle.record_failure(&keys, &limits, later);
+        }
+        assert_eq!(throttle.record_failure(&keys, &limits, later), Some(30));
+    }
+}
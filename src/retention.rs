@@ -0,0 +1,67 @@
+//! Background purge of actions past their retention period.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+
+use crate::config::RetentionConfig;
+use crate::domain::PurgeSummary;
+use crate::error::ShieldResult;
+use crate::storage::ShieldRepository;
+
+/// Delete every company's actions older than its retention period (its own,
+/// or `retention.days`), and actions belonging to no company older than
+/// `retention.days`.
+pub async fn purge_expired(
+    repository: &ShieldRepository,
+    config: &RetentionConfig,
+    now: DateTime<Utc>,
+) -> ShieldResult<PurgeSummary> {
+    let cutoff = |days: u32| now - chrono::Duration::days(days.into());
+    let mut summary = PurgeSummary::default();
+
+    for (company_id, days) in repository.list_company_retention().await? {
+        if let Some(days) = days.or(config.days) {
+            summary.add(
+                repository
+                    .purge_actions_before(Some(company_id), cutoff(days), config.batch_size)
+                    .await?,
+            );
+        }
+    }
+    if let Some(days) = config.days {
+        summary.add(
+            repository
+                .purge_actions_before(None, cutoff(days), config.batch_size)
+                .await?,
+        );
+    }
+
+    Ok(summary)
+}
+
+/// Run [`purge_expired`] every `retention.interval_secs`.
+pub fn spawn(repository: ShieldRepository, config: RetentionConfig) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(Duration::from_secs(config.interval_secs.max(1)));
+        loop {
+            ticker.tick().await;
+            match purge_expired(&repository, &config, Utc::now()).await {
+                Ok(summary) if summary != PurgeSummary::default() => {
+                    tracing::info!(
+                        actions = summary.actions,
+                        evaluations = summary.evaluations,
+                        hitl_tasks = summary.hitl_tasks,
+                        attack_events = summary.attack_events,
+                        reviewer_decisions = summary.reviewer_decisions,
+                        idempotency_keys = summary.idempotency_keys,
+                        webhook_deliveries = summary.webhook_deliveries,
+                        "Purged actions past their retention period"
+                    );
+                }
+                Ok(_) => {}
+                Err(e) => tracing::warn!(error = %e, "Retention purge failed"),
+            }
+        }
+    });
+}
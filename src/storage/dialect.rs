@@ -0,0 +1,168 @@
+//! SQL dialect differences between the supported databases.
+//!
+//! Queries are written against SQLite and adapted here for Postgres:
+//! placeholders, JSON access into the TEXT payload columns, and date math.
+
+use crate::error::{ShieldError, ShieldResult};
+
+/// Database engine behind a repository.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    Sqlite,
+    Postgres,
+}
+
+impl Backend {
+    /// Detect the engine from a connection URL.
+    pub fn from_url(url: &str) -> ShieldResult<Self> {
+        if url.starts_with("sqlite:") {
+            Ok(Backend::Sqlite)
+        } else if url.starts_with("postgres:") || url.starts_with("postgresql:") {
+            if cfg!(feature = "postgres") {
+                Ok(Backend::Postgres)
+            } else {
+                Err(ShieldError::Config(
+                    "Postgres support requires building with the `postgres` feature".to_string(),
+                ))
+            }
+        } else {
+            Err(ShieldError::Config(format!(
+                "Unsupported database URL: {}",
+                url.split(':').next().unwrap_or_default()
+            )))
+        }
+    }
+
+    /// Rewrite `?` placeholders into the engine's bind syntax.
+    pub fn placeholders(self, sql: &str) -> String {
+        if self == Backend::Sqlite {
+            return sql.to_string();
+        }
+
+        let mut out = String::with_capacity(sql.len() + 8);
+        let mut index = 0;
+        let mut in_string = false;
+        for c in sql.chars() {
+            match c {
+                '\'' => {
+                    in_string = !in_string;
+                    out.push(c);
+                }
+                '?' if !in_string => {
+                    index += 1;
+                    out.push('$');
+                    out.push_str(&index.to_string());
+                }
+                _ => out.push(c),
+            }
+        }
+        out
+    }
+
+    /// Adapt `CREATE TABLE`/`ALTER TABLE` column types.
+    pub fn ddl(self, sql: &str) -> String {
+        match self {
+            Backend::Sqlite => sql.to_string(),
+            // Postgres REAL is single precision
+            Backend::Postgres => sql.replace(" REAL", " DOUBLE PRECISION"),
+        }
+    }
+
+    /// Numeric field of a JSON column, NULL when absent or not a number.
+    pub fn json_number(self, column: &str, key: &str) -> String {
+        match self {
+            Backend::Sqlite => format!("CAST(json_extract({column}, '$.{key}') AS REAL)"),
+            Backend::Postgres => format!(
+                "CASE WHEN jsonb_typeof({column}::jsonb -> '{key}') = 'number' \
+                 THEN ({column}::jsonb ->> '{key}')::float8 END"
+            ),
+        }
+    }
+
+    /// Text field of a JSON column.
+    pub fn json_text(self, column: &str, key: &str) -> String {
+        match self {
+            Backend::Sqlite => format!("json_extract({column}, '$.{key}')"),
+            Backend::Postgres => format!("({column}::jsonb ->> '{key}')"),
+        }
+    }
+
+    /// Condition that a JSON flag is absent or false.
+    pub fn json_flag_unset(self, column: &str, key: &str) -> String {
+        match self {
+            Backend::Sqlite => format!("COALESCE(json_extract({column}, '$.{key}'), 0) = 0"),
+            Backend::Postgres => format!(
+                "COALESCE({column}::jsonb -> '{key}', 'false'::jsonb) \
+                 IN ('false'::jsonb, '0'::jsonb)"
+            ),
+        }
+    }
+
+    /// UTC calendar day (`YYYY-MM-DD`) of an RFC 3339 timestamp column.
+    pub fn date(self, column: &str) -> String {
+        match self {
+            Backend::Sqlite => format!("DATE({column})"),
+            Backend::Postgres => {
+                format!("(({column})::timestamptz AT TIME ZONE 'UTC')::date::text")
+            }
+        }
+    }
+
+    /// Start of the UTC hour (`YYYY-MM-DDTHH:00:00Z`) of an RFC 3339
+    /// timestamp column.
+    pub fn hour(self, column: &str) -> String {
+        match self {
+            Backend::Sqlite => format!("strftime('%Y-%m-%dT%H:00:00Z', {column})"),
+            Backend::Postgres => format!(
+                "to_char(({column})::timestamptz AT TIME ZONE 'UTC', \
+                 'YYYY-MM-DD\"T\"HH24:00:00\"Z\"')"
+            ),
+        }
+    }
+
+    /// Seconds elapsed between two RFC 3339 timestamp columns.
+    pub fn seconds_between(self, start: &str, end: &str) -> String {
+        match self {
+            Backend::Sqlite => format!("(julianday({end}) - julianday({start})) * 86400.0"),
+            Backend::Postgres => format!(
+                "EXTRACT(EPOCH FROM (({end})::timestamptz - ({start})::timestamptz))::float8"
+            ),
+        }
+    }
+
+    /// Case-insensitive `LIKE` operator.
+    pub fn like(self) -> &'static str {
+        match self {
+            Backend::Sqlite => "LIKE",
+            Backend::Postgres => "ILIKE",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_postgres_placeholders_skip_string_literals() {
+        let sql = "SELECT json_extract(p, '$.a?') FROM t WHERE a = ? AND b = ?";
+        assert_eq!(Backend::Sqlite.placeholders(sql), sql);
+        assert_eq!(
+            Backend::Postgres.placeholders(sql),
+            "SELECT json_extract(p, '$.a?') FROM t WHERE a = $1 AND b = $2"
+        );
+    }
+
+    #[test]
+    fn test_backend_from_url() {
+        assert_eq!(
+            Backend::from_url("sqlite::memory:").unwrap(),
+            Backend::Sqlite
+        );
+        assert!(Backend::from_url("mysql://localhost/shield").is_err());
+        assert_eq!(
+            Backend::from_url("postgres://localhost/shield").is_ok(),
+            cfg!(feature = "postgres")
+        );
+    }
+}
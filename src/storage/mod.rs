@@ -1,7 +1,9 @@
 //! Storage layer for Shield Core.
 //!
-//! Provides database access via SQLx with SQLite (MVP) or Postgres.
+//! Provides database access via SQLx with SQLite (MVP) or Postgres
+//! (`postgres` feature).
 
+mod dialect;
 mod models;
 mod repository;
 
@@ -7,9 +7,11 @@ use sqlx::FromRow;
 use uuid::Uuid;
 
 use crate::domain::{
-    AgentAction, App, AppStatus, AttackEvent, AttackOutcome, AttackType, Company, CompanyMember,
-    CompanyRole, CompanySettings, EvaluationResult, HitlTask, HitlTaskSummary, OAuthAccount,
-    OAuthProvider, PolicyThresholds, RiskTier, User, UserRole,
+    AgentAction, App, AppStatus, AttackEvent, AttackOutcome, AttackType, AuditEntry, Beneficiary,
+    Company, CompanyMember, CompanyRole, CompanySettings, EvaluationResult, ExpiringSecret,
+    HitlTask, HitlTaskSummary, IdempotencyRecord, OAuthAccount, OAuthProvider, PolicyProfile,
+    PolicyThresholds, RefreshToken, ReviewerDecision, RiskTier, User, UserRole,
+    WebhookDeliveryRecord,
 };
 
 /// Database row for agent_actions table.
@@ -27,6 +29,7 @@ pub struct AgentActionRow {
     pub cot_trace: Option<String>,
     pub metadata: Option<String>,
     pub created_at: String,
+    pub conversation: String,
 }
 
 impl TryFrom<AgentActionRow> for AgentAction {
@@ -49,6 +52,7 @@ impl TryFrom<AgentActionRow> for AgentAction {
             action_type: serde_json::from_str(&format!("\"{}\"", row.action_type))?,
             payload: serde_json::from_str(&row.payload)?,
             cot_trace: row.cot_trace,
+            conversation: serde_json::from_str(&row.conversation)?,
             metadata: row.metadata.map(|m| serde_json::from_str(&m)).transpose()?,
             created_at: DateTime::parse_from_rfc3339(&row.created_at)
                 .map_err(|e| crate::error::ShieldError::Internal(e.to_string()))?
@@ -68,6 +72,11 @@ pub struct EvaluationRow {
     pub rule_hits: String,
     pub neural_signals: String,
     pub created_at: String,
+    pub matched_patterns: String,
+    pub guard_model: Option<String>,
+    pub reason_entries: String,
+    pub layers: Option<String>,
+    pub risk_score: Option<f64>,
 }
 
 impl TryFrom<EvaluationRow> for EvaluationResult {
@@ -81,9 +90,18 @@ impl TryFrom<EvaluationRow> for EvaluationResult {
                 .map_err(|e| crate::error::ShieldError::Internal(e.to_string()))?,
             decision: serde_json::from_str(&format!("\"{}\"", row.decision))?,
             risk_tier: serde_json::from_str(&format!("\"{}\"", row.risk_tier))?,
+            risk_score: row.risk_score,
             reasons: serde_json::from_str(&row.reasons)?,
+            reason_entries: serde_json::from_str(&row.reason_entries)?,
             rule_hits: serde_json::from_str(&row.rule_hits)?,
             neural_signals: serde_json::from_str(&row.neural_signals)?,
+            matched_patterns: serde_json::from_str(&row.matched_patterns)?,
+            guard_model: row.guard_model,
+            layers: row
+                .layers
+                .as_deref()
+                .map(serde_json::from_str)
+                .transpose()?,
             created_at: DateTime::parse_from_rfc3339(&row.created_at)
                 .map_err(|e| crate::error::ShieldError::Internal(e.to_string()))?
                 .with_timezone(&Utc),
@@ -102,6 +120,8 @@ pub struct HitlTaskRow {
     pub reviewed_at: Option<String>,
     pub review_notes: Option<String>,
     pub created_at: String,
+    pub first_approver_id: Option<String>,
+    pub first_approved_at: Option<String>,
 }
 
 impl TryFrom<HitlTaskRow> for HitlTask {
@@ -129,6 +149,15 @@ impl TryFrom<HitlTaskRow> for HitlTask {
                 })
                 .transpose()?,
             review_notes: row.review_notes,
+            first_approver_id: row.first_approver_id,
+            first_approved_at: row
+                .first_approved_at
+                .map(|s| {
+                    DateTime::parse_from_rfc3339(&s)
+                        .map(|dt| dt.with_timezone(&Utc))
+                        .map_err(|e| crate::error::ShieldError::Internal(e.to_string()))
+                })
+                .transpose()?,
             created_at: DateTime::parse_from_rfc3339(&row.created_at)
                 .map_err(|e| crate::error::ShieldError::Internal(e.to_string()))?
                 .with_timezone(&Utc),
@@ -170,6 +199,45 @@ impl TryFrom<HitlTaskSummaryRow> for HitlTaskSummary {
     }
 }
 
+/// Database row for a reviewer's decision history.
+#[derive(Debug, Clone, FromRow)]
+pub struct ReviewerDecisionRow {
+    pub id: String,
+    pub agent_action_id: String,
+    pub action_type: String,
+    pub amount: Option<f64>,
+    pub risk_tier: String,
+    pub status: String,
+    pub created_at: String,
+    pub reviewed_at: String,
+}
+
+impl TryFrom<ReviewerDecisionRow> for ReviewerDecision {
+    type Error = crate::error::ShieldError;
+
+    fn try_from(row: ReviewerDecisionRow) -> Result<Self, Self::Error> {
+        Ok(ReviewerDecision {
+            task_id: Uuid::parse_str(&row.id)
+                .map_err(|e| crate::error::ShieldError::Internal(e.to_string()))?,
+            agent_action_id: Uuid::parse_str(&row.agent_action_id)
+                .map_err(|e| crate::error::ShieldError::Internal(e.to_string()))?,
+            action_type: row.action_type,
+            amount: row.amount,
+            risk_tier: row.risk_tier,
+            status: row
+                .status
+                .parse()
+                .map_err(|e: String| crate::error::ShieldError::Internal(e))?,
+            created_at: DateTime::parse_from_rfc3339(&row.created_at)
+                .map_err(|e| crate::error::ShieldError::Internal(e.to_string()))?
+                .with_timezone(&Utc),
+            reviewed_at: DateTime::parse_from_rfc3339(&row.reviewed_at)
+                .map_err(|e| crate::error::ShieldError::Internal(e.to_string()))?
+                .with_timezone(&Utc),
+        })
+    }
+}
+
 // ==================== Company Models ====================
 
 /// Database row for companies table.
@@ -181,6 +249,7 @@ pub struct CompanyRow {
     pub description: Option<String>,
     pub created_at: String,
     pub updated_at: String,
+    pub plan: String,
 }
 
 impl TryFrom<CompanyRow> for Company {
@@ -193,6 +262,7 @@ impl TryFrom<CompanyRow> for Company {
             name: row.name,
             slug: row.slug,
             description: row.description,
+            plan: row.plan,
             created_at: DateTime::parse_from_rfc3339(&row.created_at)
                 .map_err(|e| crate::error::ShieldError::Internal(e.to_string()))?
                 .with_timezone(&Utc),
@@ -250,6 +320,29 @@ pub struct AppRow {
     pub created_at: String,
     pub updated_at: String,
     pub last_used_at: Option<String>,
+    pub trusted: i32,
+    pub policy_profile_id: Option<String>,
+    pub permitted_company_ids: String,
+    pub scopes: String,
+    pub sandbox: i32,
+    pub webhook_url: Option<String>,
+    pub webhook_secret: Option<String>,
+    pub webhook_previous_secret: Option<String>,
+    pub webhook_previous_secret_expires_at: Option<String>,
+}
+
+/// Rebuild a rotated-out webhook secret from its columns.
+fn previous_webhook_secret(
+    secret: Option<String>,
+    expires_at: Option<String>,
+) -> Result<Option<ExpiringSecret>, crate::error::ShieldError> {
+    let (Some(secret), Some(expires_at)) = (secret, expires_at) else {
+        return Ok(None);
+    };
+    let expires_at = DateTime::parse_from_rfc3339(&expires_at)
+        .map_err(|e| crate::error::ShieldError::Internal(e.to_string()))?
+        .with_timezone(&Utc);
+    Ok(Some(ExpiringSecret { secret, expires_at }))
 }
 
 impl TryFrom<AppRow> for App {
@@ -270,6 +363,23 @@ impl TryFrom<AppRow> for App {
                 .parse::<AppStatus>()
                 .map_err(crate::error::ShieldError::Internal)?,
             rate_limit: row.rate_limit as u32,
+            scopes: serde_json::from_str(&row.scopes)?,
+            trusted: row.trusted != 0,
+            sandbox: row.sandbox != 0,
+            policy_profile_id: row
+                .policy_profile_id
+                .map(|id| {
+                    Uuid::parse_str(&id)
+                        .map_err(|e| crate::error::ShieldError::Internal(e.to_string()))
+                })
+                .transpose()?,
+            permitted_company_ids: serde_json::from_str(&row.permitted_company_ids)?,
+            webhook_url: row.webhook_url,
+            webhook_secret: row.webhook_secret,
+            previous_webhook_secret: previous_webhook_secret(
+                row.webhook_previous_secret,
+                row.webhook_previous_secret_expires_at,
+            )?,
             created_at: DateTime::parse_from_rfc3339(&row.created_at)
                 .map_err(|e| crate::error::ShieldError::Internal(e.to_string()))?
                 .with_timezone(&Utc),
@@ -355,13 +465,29 @@ pub struct CompanySettingsRow {
     pub company_id: String,
     pub logo: Option<String>,
     pub webhook_url: Option<String>,
+    pub webhook_secret: Option<String>,
+    pub webhook_previous_secret: Option<String>,
+    pub webhook_previous_secret_expires_at: Option<String>,
     pub notification_email: Option<String>,
+    pub console_url: Option<String>,
+    pub retention_days: Option<i32>,
+    pub timezone: String,
     pub max_auto_approve_amount: f64,
     pub hitl_threshold_amount: f64,
     pub velocity_limit_per_hour: i32,
     pub velocity_limit_per_day: i32,
     pub block_high_risk_actions: i32,
     pub require_hitl_for_new_beneficiaries: i32,
+    pub daily_transfer_limit: Option<f64>,
+    pub alignment_allowlist: Option<String>,
+    pub off_hours_schedule: Option<String>,
+    pub notification_digest: Option<String>,
+    pub pipeline: Option<String>,
+    pub thresholds_updated_at: Option<String>,
+    pub drain_detection: Option<String>,
+    pub allowed_risk_floor: Option<String>,
+    pub absolute_block_amount: Option<f64>,
+    pub crypto_address_allowlist: Option<String>,
 }
 
 impl CompanySettingsRow {
@@ -376,7 +502,35 @@ impl CompanySettingsRow {
             name: company_name,
             logo: self.logo,
             webhook_url: self.webhook_url,
+            webhook_secret: self.webhook_secret,
+            previous_webhook_secret: previous_webhook_secret(
+                self.webhook_previous_secret,
+                self.webhook_previous_secret_expires_at,
+            )?,
             notification_email: self.notification_email,
+            console_url: self.console_url,
+            notification_digest: self
+                .notification_digest
+                .as_deref()
+                .map(serde_json::from_str)
+                .transpose()?
+                .unwrap_or_default(),
+            pipeline: self
+                .pipeline
+                .as_deref()
+                .map(serde_json::from_str)
+                .transpose()?
+                .unwrap_or_default(),
+            retention_days: self.retention_days.map(|days| days as u32),
+            timezone: self.timezone,
+            thresholds_updated_at: self
+                .thresholds_updated_at
+                .map(|s| {
+                    DateTime::parse_from_rfc3339(&s)
+                        .map(|dt| dt.with_timezone(&Utc))
+                        .map_err(|e| crate::error::ShieldError::Internal(e.to_string()))
+                })
+                .transpose()?,
             policy_thresholds: PolicyThresholds {
                 max_auto_approve_amount: self.max_auto_approve_amount,
                 hitl_threshold_amount: self.hitl_threshold_amount,
@@ -384,11 +538,190 @@ impl CompanySettingsRow {
                 velocity_limit_per_day: self.velocity_limit_per_day,
                 block_high_risk_actions: self.block_high_risk_actions != 0,
                 require_hitl_for_new_beneficiaries: self.require_hitl_for_new_beneficiaries != 0,
+                daily_transfer_limit: self.daily_transfer_limit,
+                alignment_allowlist: self
+                    .alignment_allowlist
+                    .as_deref()
+                    .map(serde_json::from_str)
+                    .transpose()?
+                    .unwrap_or_default(),
+                off_hours: self
+                    .off_hours_schedule
+                    .as_deref()
+                    .map(serde_json::from_str)
+                    .transpose()?,
+                drain_detection: self
+                    .drain_detection
+                    .as_deref()
+                    .map(serde_json::from_str)
+                    .transpose()?,
+                allowed_risk_floor: self
+                    .allowed_risk_floor
+                    .as_deref()
+                    .map(str::parse)
+                    .transpose()
+                    .map_err(crate::error::ShieldError::Internal)?,
+                absolute_block_amount: self.absolute_block_amount,
+                crypto_address_allowlist: self
+                    .crypto_address_allowlist
+                    .as_deref()
+                    .map(serde_json::from_str)
+                    .transpose()?
+                    .unwrap_or_default(),
             },
         })
     }
 }
 
+// ==================== Policy Profiles ====================
+
+/// Database row for policy_profiles table.
+#[derive(Debug, Clone, FromRow)]
+pub struct PolicyProfileRow {
+    pub id: String,
+    pub company_id: String,
+    pub name: String,
+    pub thresholds: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl TryFrom<PolicyProfileRow> for PolicyProfile {
+    type Error = crate::error::ShieldError;
+
+    fn try_from(row: PolicyProfileRow) -> Result<Self, Self::Error> {
+        Ok(PolicyProfile {
+            id: Uuid::parse_str(&row.id)
+                .map_err(|e| crate::error::ShieldError::Internal(e.to_string()))?,
+            company_id: Uuid::parse_str(&row.company_id)
+                .map_err(|e| crate::error::ShieldError::Internal(e.to_string()))?,
+            name: row.name,
+            thresholds: serde_json::from_str(&row.thresholds)?,
+            created_at: DateTime::parse_from_rfc3339(&row.created_at)
+                .map_err(|e| crate::error::ShieldError::Internal(e.to_string()))?
+                .with_timezone(&Utc),
+            updated_at: DateTime::parse_from_rfc3339(&row.updated_at)
+                .map_err(|e| crate::error::ShieldError::Internal(e.to_string()))?
+                .with_timezone(&Utc),
+        })
+    }
+}
+
+// ==================== Audit Trail ====================
+
+/// Database row for audit_log table.
+#[derive(Debug, Clone, FromRow)]
+pub struct AuditEntryRow {
+    pub id: String,
+    pub company_id: Option<String>,
+    pub actor_id: String,
+    pub action: String,
+    pub target_type: String,
+    pub target_id: String,
+    pub before_state: Option<String>,
+    pub after_state: Option<String>,
+    pub created_at: String,
+}
+
+impl TryFrom<AuditEntryRow> for AuditEntry {
+    type Error = crate::error::ShieldError;
+
+    fn try_from(row: AuditEntryRow) -> Result<Self, Self::Error> {
+        Ok(AuditEntry {
+            id: Uuid::parse_str(&row.id)
+                .map_err(|e| crate::error::ShieldError::Internal(e.to_string()))?,
+            company_id: row
+                .company_id
+                .map(|s| Uuid::parse_str(&s))
+                .transpose()
+                .map_err(|e| crate::error::ShieldError::Internal(e.to_string()))?,
+            actor_id: row.actor_id,
+            action: row.action,
+            target_type: row.target_type,
+            target_id: row.target_id,
+            before: row
+                .before_state
+                .map(|s| serde_json::from_str(&s))
+                .transpose()?,
+            after: row
+                .after_state
+                .map(|s| serde_json::from_str(&s))
+                .transpose()?,
+            created_at: DateTime::parse_from_rfc3339(&row.created_at)
+                .map_err(|e| crate::error::ShieldError::Internal(e.to_string()))?
+                .with_timezone(&Utc),
+        })
+    }
+}
+
+// ==================== Beneficiaries ====================
+
+/// Database row for beneficiaries table.
+#[derive(Debug, Clone, FromRow)]
+pub struct BeneficiaryRow {
+    pub id: String,
+    pub company_id: String,
+    pub user_id: String,
+    pub account_identifier: String,
+    pub approved_at: String,
+}
+
+impl TryFrom<BeneficiaryRow> for Beneficiary {
+    type Error = crate::error::ShieldError;
+
+    fn try_from(row: BeneficiaryRow) -> Result<Self, Self::Error> {
+        Ok(Beneficiary {
+            id: Uuid::parse_str(&row.id)
+                .map_err(|e| crate::error::ShieldError::Internal(e.to_string()))?,
+            company_id: Uuid::parse_str(&row.company_id)
+                .map_err(|e| crate::error::ShieldError::Internal(e.to_string()))?,
+            user_id: row.user_id,
+            account_identifier: row.account_identifier,
+            approved_at: DateTime::parse_from_rfc3339(&row.approved_at)
+                .map_err(|e| crate::error::ShieldError::Internal(e.to_string()))?
+                .with_timezone(&Utc),
+        })
+    }
+}
+
+// ==================== Idempotency Keys ====================
+
+/// Database row for idempotency_keys table.
+#[derive(Debug, Clone, FromRow)]
+pub struct IdempotencyKeyRow {
+    pub app_id: String,
+    pub idempotency_key: String,
+    pub request_hash: String,
+    pub evaluation_id: String,
+    pub response: String,
+    pub created_at: String,
+}
+
+impl TryFrom<IdempotencyKeyRow> for IdempotencyRecord {
+    type Error = crate::error::ShieldError;
+
+    fn try_from(row: IdempotencyKeyRow) -> Result<Self, Self::Error> {
+        Ok(IdempotencyRecord {
+            app_id: if row.app_id.is_empty() {
+                None
+            } else {
+                Some(
+                    Uuid::parse_str(&row.app_id)
+                        .map_err(|e| crate::error::ShieldError::Internal(e.to_string()))?,
+                )
+            },
+            key: row.idempotency_key,
+            request_hash: row.request_hash,
+            evaluation_id: Uuid::parse_str(&row.evaluation_id)
+                .map_err(|e| crate::error::ShieldError::Internal(e.to_string()))?,
+            response: serde_json::from_str(&row.response)?,
+            created_at: DateTime::parse_from_rfc3339(&row.created_at)
+                .map_err(|e| crate::error::ShieldError::Internal(e.to_string()))?
+                .with_timezone(&Utc),
+        })
+    }
+}
+
 // ==================== Action List View ====================
 
 /// Row for action list query with evaluation data.
@@ -480,3 +813,85 @@ impl TryFrom<OAuthAccountRow> for OAuthAccount {
         })
     }
 }
+
+// ==================== Refresh Tokens ====================
+
+/// Database row for refresh_tokens table.
+#[derive(Debug, Clone, FromRow)]
+pub struct RefreshTokenRow {
+    pub id: String,
+    pub token_hash: String,
+    pub user_id: String,
+    pub email: String,
+    pub role: String,
+    pub expires_at: String,
+    pub revoked_at: Option<String>,
+    pub created_at: String,
+}
+
+impl TryFrom<RefreshTokenRow> for RefreshToken {
+    type Error = crate::error::ShieldError;
+
+    fn try_from(row: RefreshTokenRow) -> Result<Self, Self::Error> {
+        let parse_time = |value: &str| {
+            DateTime::parse_from_rfc3339(value)
+                .map(|dt| dt.with_timezone(&Utc))
+                .map_err(|e| crate::error::ShieldError::Internal(e.to_string()))
+        };
+        Ok(RefreshToken {
+            id: Uuid::parse_str(&row.id)
+                .map_err(|e| crate::error::ShieldError::Internal(e.to_string()))?,
+            token_hash: row.token_hash,
+            user_id: row.user_id,
+            email: row.email,
+            role: row
+                .role
+                .parse::<crate::auth::UserRole>()
+                .map_err(crate::error::ShieldError::Internal)?,
+            expires_at: parse_time(&row.expires_at)?,
+            revoked_at: row.revoked_at.as_deref().map(parse_time).transpose()?,
+            created_at: parse_time(&row.created_at)?,
+        })
+    }
+}
+
+// ==================== Webhook Deliveries ====================
+
+/// Database row for webhook_deliveries table.
+#[derive(Debug, Clone, FromRow)]
+pub struct WebhookDeliveryRow {
+    pub id: String,
+    pub company_id: String,
+    pub event_type: String,
+    pub url: String,
+    pub status_code: Option<i32>,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub payload: String,
+    pub replay_of: Option<String>,
+    pub created_at: String,
+}
+
+impl TryFrom<WebhookDeliveryRow> for WebhookDeliveryRecord {
+    type Error = crate::error::ShieldError;
+
+    fn try_from(row: WebhookDeliveryRow) -> Result<Self, Self::Error> {
+        let parse_id = |value: &str| {
+            Uuid::parse_str(value).map_err(|e| crate::error::ShieldError::Internal(e.to_string()))
+        };
+        Ok(WebhookDeliveryRecord {
+            id: parse_id(&row.id)?,
+            company_id: parse_id(&row.company_id)?,
+            event_type: row.event_type,
+            url: row.url,
+            status_code: row.status_code.and_then(|code| u16::try_from(code).ok()),
+            attempts: row.attempts.max(0) as u32,
+            last_error: row.last_error,
+            payload: row.payload,
+            replay_of: row.replay_of.as_deref().map(parse_id).transpose()?,
+            created_at: DateTime::parse_from_rfc3339(&row.created_at)
+                .map_err(|e| crate::error::ShieldError::Internal(e.to_string()))?
+                .with_timezone(&Utc),
+        })
+    }
+}
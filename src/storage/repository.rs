@@ -1,44 +1,82 @@
 //! Repository layer for database operations.
 
 use chrono::{DateTime, Utc};
-use sqlx::sqlite::SqlitePool;
+use futures_util::StreamExt;
+use sqlx::any::AnyPoolOptions;
+use sqlx::{AnyConnection, AnyPool};
 use uuid::Uuid;
 
 use crate::domain::{
-    AgentAction, App, AppStatus, AttackEvent, AttackOutcome, AttackType, Company, CompanyMember,
-    CompanyRole, CompanySettings, DecisionStatus, EvaluationResult, Granularity, HitlStatus,
-    HitlTask, HitlTaskDetails, HitlTaskSummary, MetricsOverview, OAuthAccount, OAuthProvider,
-    PolicyThresholds, RiskDistribution, RiskDistributionPoint, RiskTier, TimeRange, TimeSeriesData,
-    TimeSeriesPoint, Trends, User, UserCompanyMembership,
+    percent_change, AgentAction, ApiScope, App, AppStatus, AttackEvent, AttackOutcome, AttackType,
+    AuditEntry, Beneficiary, Company, CompanyMember, CompanyRole, CompanySettings, DecisionStatus,
+    EvaluationResult, Granularity, HitlStatus, HitlTask, HitlTaskDetails, HitlTaskSummary,
+    IdempotencyRecord, MetricsOverview, ModelBreakdown, ModelMetrics, NotificationDigest,
+    OAuthAccount, OAuthProvider, PeriodStats, PipelineLayers, PolicyProfile, PolicyThresholds,
+    PurgeSummary, RefreshToken, ReviewerBreakdown, ReviewerDecision, ReviewerMetrics,
+    ReviewerStats, RiskDistribution, RiskDistributionPoint, RiskTier, TimeRange, TimeSeriesData,
+    TimeSeriesPoint, TraceHistory, Trends, User, UserCompanyMembership, WebhookDeliveryRecord,
 };
 use crate::error::{ShieldError, ShieldResult};
+use crate::storage::dialect::Backend;
 use crate::storage::models::{
-    ActionListRow, AgentActionRow, AppRow, AttackEventRow, CompanyMemberRow, CompanyRow,
-    CompanySettingsRow, EvaluationRow, HitlTaskRow, HitlTaskSummaryRow, OAuthAccountRow, UserRow,
+    ActionListRow, AgentActionRow, AppRow, AttackEventRow, AuditEntryRow, BeneficiaryRow,
+    CompanyMemberRow, CompanyRow, CompanySettingsRow, EvaluationRow, HitlTaskRow,
+    HitlTaskSummaryRow, IdempotencyKeyRow, OAuthAccountRow, PolicyProfileRow, RefreshTokenRow,
+    ReviewerDecisionRow, UserRow, WebhookDeliveryRow,
 };
 
+/// Matches owner and admin memberships, which plans cap with `max_admins`.
+const ADMIN_ROLES_FILTER: &str = "role IN ('owner', 'admin')";
+
 /// Repository for all Shield database operations.
 #[derive(Clone)]
 pub struct ShieldRepository {
-    pool: SqlitePool,
+    pool: AnyPool,
+    backend: Backend,
 }
 
 impl ShieldRepository {
     /// Get a reference to the underlying pool.
-    pub fn pool(&self) -> &SqlitePool {
+    pub fn pool(&self) -> &AnyPool {
         &self.pool
     }
+
+    /// Adapt a query written with `?` placeholders to the connected engine.
+    fn sql(&self, query: &str) -> String {
+        self.backend.placeholders(query)
+    }
 }
 
 impl ShieldRepository {
-    /// Create a new repository with the given connection pool.
-    pub fn new(pool: SqlitePool) -> Self {
-        Self { pool }
+    /// Connect to the database at `url` (`sqlite:` or `postgres://`).
+    ///
+    /// `sqlite::memory:` is given a uniquely named shared-cache database so
+    /// every pooled connection sees the same data; it lives as long as the pool.
+    pub async fn connect(url: &str) -> ShieldResult<Self> {
+        sqlx::any::install_default_drivers();
+        let backend = Backend::from_url(url)?;
+
+        let mut options = AnyPoolOptions::new();
+        let url = if backend == Backend::Sqlite && url.contains(":memory:") {
+            options = options
+                .min_connections(1)
+                .idle_timeout(None)
+                .max_lifetime(None);
+            format!(
+                "sqlite:file:shield-{}?mode=memory&cache=shared",
+                Uuid::new_v4().simple()
+            )
+        } else {
+            url.to_string()
+        };
+
+        let pool = options.connect(&url).await?;
+        Ok(Self { pool, backend })
     }
 
     /// Initialize the database schema.
     pub async fn init_schema(&self) -> ShieldResult<()> {
-        sqlx::query(
+        sqlx::raw_sql(&self.backend.ddl(
             r#"
             CREATE TABLE IF NOT EXISTS agent_actions (
                 id TEXT PRIMARY KEY,
@@ -53,7 +91,9 @@ impl ShieldRepository {
                 payload TEXT NOT NULL,
                 cot_trace TEXT,
                 metadata TEXT,
-                created_at TEXT NOT NULL
+                created_at TEXT NOT NULL,
+                conversation TEXT NOT NULL DEFAULT '[]',
+                sandbox INTEGER NOT NULL DEFAULT 0
             );
 
             CREATE INDEX IF NOT EXISTS idx_agent_actions_user_id ON agent_actions(user_id);
@@ -62,11 +102,11 @@ impl ShieldRepository {
             CREATE INDEX IF NOT EXISTS idx_agent_actions_company_id ON agent_actions(company_id);
             CREATE INDEX IF NOT EXISTS idx_agent_actions_created_at ON agent_actions(created_at);
             "#,
-        )
+        ))
         .execute(&self.pool)
         .await?;
 
-        sqlx::query(
+        sqlx::raw_sql(&self.backend.ddl(
             r#"
             CREATE TABLE IF NOT EXISTS evaluations (
                 id TEXT PRIMARY KEY,
@@ -77,16 +117,48 @@ impl ShieldRepository {
                 rule_hits TEXT NOT NULL,
                 neural_signals TEXT NOT NULL,
                 created_at TEXT NOT NULL,
+                matched_patterns TEXT NOT NULL DEFAULT '[]',
+                guard_model TEXT,
+                reason_entries TEXT NOT NULL DEFAULT '[]',
+                layers TEXT,
+                risk_score REAL,
                 FOREIGN KEY (agent_action_id) REFERENCES agent_actions(id)
             );
 
             CREATE INDEX IF NOT EXISTS idx_evaluations_action_id ON evaluations(agent_action_id);
             "#,
-        )
+        ))
         .execute(&self.pool)
         .await?;
 
-        sqlx::query(
+        // Persisted re-evaluations, kept apart from the evaluations that
+        // decided each action
+        sqlx::raw_sql(&self.backend.ddl(
+            r#"
+            CREATE TABLE IF NOT EXISTS reevaluations (
+                id TEXT PRIMARY KEY,
+                agent_action_id TEXT NOT NULL,
+                decision TEXT NOT NULL,
+                risk_tier TEXT NOT NULL,
+                reasons TEXT NOT NULL,
+                rule_hits TEXT NOT NULL,
+                neural_signals TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                matched_patterns TEXT NOT NULL DEFAULT '[]',
+                guard_model TEXT,
+                reason_entries TEXT NOT NULL DEFAULT '[]',
+                layers TEXT,
+                risk_score REAL,
+                FOREIGN KEY (agent_action_id) REFERENCES agent_actions(id)
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_reevaluations_action_id ON reevaluations(agent_action_id);
+            "#,
+        ))
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::raw_sql(&self.backend.ddl(
             r#"
             CREATE TABLE IF NOT EXISTS hitl_tasks (
                 id TEXT PRIMARY KEY,
@@ -97,18 +169,20 @@ impl ShieldRepository {
                 reviewed_at TEXT,
                 review_notes TEXT,
                 created_at TEXT NOT NULL,
+                first_approver_id TEXT,
+                first_approved_at TEXT,
                 FOREIGN KEY (agent_action_id) REFERENCES agent_actions(id),
                 FOREIGN KEY (evaluation_id) REFERENCES evaluations(id)
             );
 
             CREATE INDEX IF NOT EXISTS idx_hitl_tasks_status ON hitl_tasks(status);
             "#,
-        )
+        ))
         .execute(&self.pool)
         .await?;
 
         // Company tables
-        sqlx::query(
+        sqlx::raw_sql(&self.backend.ddl(
             r#"
             CREATE TABLE IF NOT EXISTS companies (
                 id TEXT PRIMARY KEY,
@@ -116,16 +190,19 @@ impl ShieldRepository {
                 slug TEXT NOT NULL UNIQUE,
                 description TEXT,
                 created_at TEXT NOT NULL,
-                updated_at TEXT NOT NULL
+                updated_at TEXT NOT NULL,
+                plan TEXT NOT NULL DEFAULT 'unlimited',
+                deleted_at TEXT,
+                deleted_slug TEXT
             );
 
             CREATE INDEX IF NOT EXISTS idx_companies_slug ON companies(slug);
             "#,
-        )
+        ))
         .execute(&self.pool)
         .await?;
 
-        sqlx::query(
+        sqlx::raw_sql(&self.backend.ddl(
             r#"
             CREATE TABLE IF NOT EXISTS company_members (
                 id TEXT PRIMARY KEY,
@@ -141,11 +218,11 @@ impl ShieldRepository {
             CREATE INDEX IF NOT EXISTS idx_company_members_company ON company_members(company_id);
             CREATE INDEX IF NOT EXISTS idx_company_members_user ON company_members(user_id);
             "#,
-        )
+        ))
         .execute(&self.pool)
         .await?;
 
-        sqlx::query(
+        sqlx::raw_sql(&self.backend.ddl(
             r#"
             CREATE TABLE IF NOT EXISTS apps (
                 id TEXT PRIMARY KEY,
@@ -159,18 +236,27 @@ impl ShieldRepository {
                 created_at TEXT NOT NULL,
                 updated_at TEXT NOT NULL,
                 last_used_at TEXT,
+                trusted INTEGER NOT NULL DEFAULT 0,
+                policy_profile_id TEXT,
+                permitted_company_ids TEXT NOT NULL DEFAULT '[]',
+                scopes TEXT NOT NULL DEFAULT '["actions:evaluate"]',
+                sandbox INTEGER NOT NULL DEFAULT 0,
+                webhook_url TEXT,
+                webhook_secret TEXT,
+                webhook_previous_secret TEXT,
+                webhook_previous_secret_expires_at TEXT,
                 FOREIGN KEY (company_id) REFERENCES companies(id) ON DELETE CASCADE
             );
 
             CREATE INDEX IF NOT EXISTS idx_apps_company ON apps(company_id);
             CREATE INDEX IF NOT EXISTS idx_apps_api_key_hash ON apps(api_key_hash);
             "#,
-        )
+        ))
         .execute(&self.pool)
         .await?;
 
         // Attack events table
-        sqlx::query(
+        sqlx::raw_sql(&self.backend.ddl(
             r#"
             CREATE TABLE IF NOT EXISTS attack_events (
                 id TEXT PRIMARY KEY,
@@ -193,12 +279,12 @@ impl ShieldRepository {
             CREATE INDEX IF NOT EXISTS idx_attack_events_app ON attack_events(app_id);
             CREATE INDEX IF NOT EXISTS idx_attack_events_created_at ON attack_events(created_at);
             "#,
-        )
+        ))
         .execute(&self.pool)
         .await?;
 
         // Company settings table
-        sqlx::query(
+        sqlx::raw_sql(&self.backend.ddl(
             r#"
             CREATE TABLE IF NOT EXISTS company_settings (
                 company_id TEXT PRIMARY KEY,
@@ -210,16 +296,237 @@ impl ShieldRepository {
                 velocity_limit_per_hour INTEGER NOT NULL DEFAULT 10,
                 velocity_limit_per_day INTEGER NOT NULL DEFAULT 50,
                 block_high_risk_actions INTEGER NOT NULL DEFAULT 1,
-                require_hitl_for_new_beneficiaries INTEGER NOT NULL DEFAULT 1,
+                require_hitl_for_new_beneficiaries INTEGER NOT NULL DEFAULT 0,
+                daily_transfer_limit REAL,
+                alignment_allowlist TEXT,
+                timezone TEXT NOT NULL DEFAULT 'UTC',
+                off_hours_schedule TEXT,
+                notification_digest TEXT,
+                drain_detection TEXT,
+                allowed_risk_floor TEXT,
+                absolute_block_amount REAL,
+                crypto_address_allowlist TEXT,
+                pipeline TEXT,
+                thresholds_updated_at TEXT,
+                webhook_secret TEXT,
+                console_url TEXT,
+                webhook_previous_secret TEXT,
+                webhook_previous_secret_expires_at TEXT,
+                retention_days INTEGER,
                 FOREIGN KEY (company_id) REFERENCES companies(id) ON DELETE CASCADE
             );
             "#,
-        )
+        ))
+        .execute(&self.pool)
+        .await?;
+
+        // Policy profiles table
+        sqlx::raw_sql(&self.backend.ddl(
+            r#"
+            CREATE TABLE IF NOT EXISTS policy_profiles (
+                id TEXT PRIMARY KEY,
+                company_id TEXT NOT NULL,
+                name TEXT NOT NULL,
+                thresholds TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                UNIQUE (company_id, name),
+                FOREIGN KEY (company_id) REFERENCES companies(id) ON DELETE CASCADE
+            );
+            "#,
+        ))
+        .execute(&self.pool)
+        .await?;
+
+        // Beneficiaries table
+        sqlx::raw_sql(&self.backend.ddl(
+            r#"
+            CREATE TABLE IF NOT EXISTS beneficiaries (
+                id TEXT PRIMARY KEY,
+                company_id TEXT NOT NULL,
+                user_id TEXT NOT NULL,
+                account_identifier TEXT NOT NULL,
+                approved_at TEXT NOT NULL,
+                UNIQUE (company_id, user_id, account_identifier),
+                FOREIGN KEY (company_id) REFERENCES companies(id) ON DELETE CASCADE
+            );
+            "#,
+        ))
+        .execute(&self.pool)
+        .await?;
+
+        // Idempotency keys table. Keys are scoped to the app that sent them
+        // (empty for service API keys). Tables from before the scoping hold
+        // only short-lived replays, so they are dropped rather than migrated.
+        if !self.has_column("idempotency_keys", "app_id").await? {
+            sqlx::raw_sql("DROP TABLE IF EXISTS idempotency_keys")
+                .execute(&self.pool)
+                .await?;
+        }
+        sqlx::raw_sql(&self.backend.ddl(
+            r#"
+            CREATE TABLE IF NOT EXISTS idempotency_keys (
+                app_id TEXT NOT NULL DEFAULT '',
+                idempotency_key TEXT NOT NULL,
+                request_hash TEXT NOT NULL,
+                evaluation_id TEXT NOT NULL,
+                response TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                PRIMARY KEY (app_id, idempotency_key)
+            );
+            CREATE INDEX IF NOT EXISTS idx_idempotency_keys_created ON idempotency_keys(created_at);
+            "#,
+        ))
+        .execute(&self.pool)
+        .await?;
+
+        // Audit trail table. No foreign key to companies: entries must
+        // outlive the company they describe.
+        sqlx::raw_sql(&self.backend.ddl(
+            r#"
+            CREATE TABLE IF NOT EXISTS audit_log (
+                id TEXT PRIMARY KEY,
+                company_id TEXT,
+                actor_id TEXT NOT NULL,
+                action TEXT NOT NULL,
+                target_type TEXT NOT NULL,
+                target_id TEXT NOT NULL,
+                before_state TEXT,
+                after_state TEXT,
+                created_at TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_audit_log_company ON audit_log(company_id, created_at);
+            "#,
+        ))
+        .execute(&self.pool)
+        .await?;
+
+        // Webhook delivery log
+        sqlx::raw_sql(&self.backend.ddl(
+            r#"
+            CREATE TABLE IF NOT EXISTS webhook_deliveries (
+                id TEXT PRIMARY KEY,
+                company_id TEXT NOT NULL,
+                event_type TEXT NOT NULL,
+                url TEXT NOT NULL,
+                status_code INTEGER,
+                attempts INTEGER NOT NULL,
+                last_error TEXT,
+                payload TEXT NOT NULL,
+                replay_of TEXT,
+                created_at TEXT NOT NULL,
+                FOREIGN KEY (company_id) REFERENCES companies(id) ON DELETE CASCADE
+            );
+            CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_company ON webhook_deliveries(company_id, created_at);
+            "#,
+        ))
         .execute(&self.pool)
         .await?;
 
+        // Columns added after the initial release
+        self.ensure_column("company_settings", "daily_transfer_limit", "REAL")
+            .await?;
+        self.ensure_column("company_settings", "alignment_allowlist", "TEXT")
+            .await?;
+        self.ensure_column(
+            "company_settings",
+            "timezone",
+            "TEXT NOT NULL DEFAULT 'UTC'",
+        )
+        .await?;
+        self.ensure_column("company_settings", "off_hours_schedule", "TEXT")
+            .await?;
+        self.ensure_column("company_settings", "notification_digest", "TEXT")
+            .await?;
+        self.ensure_column("company_settings", "drain_detection", "TEXT")
+            .await?;
+        self.ensure_column("company_settings", "allowed_risk_floor", "TEXT")
+            .await?;
+        self.ensure_column("company_settings", "absolute_block_amount", "REAL")
+            .await?;
+        self.ensure_column("company_settings", "crypto_address_allowlist", "TEXT")
+            .await?;
+        self.ensure_column("company_settings", "pipeline", "TEXT")
+            .await?;
+        self.ensure_column("company_settings", "thresholds_updated_at", "TEXT")
+            .await?;
+        self.ensure_column("company_settings", "webhook_secret", "TEXT")
+            .await?;
+        self.ensure_column("company_settings", "console_url", "TEXT")
+            .await?;
+        self.ensure_column("company_settings", "webhook_previous_secret", "TEXT")
+            .await?;
+        self.ensure_column(
+            "company_settings",
+            "webhook_previous_secret_expires_at",
+            "TEXT",
+        )
+        .await?;
+        self.ensure_column("company_settings", "retention_days", "INTEGER")
+            .await?;
+        self.ensure_column("apps", "trusted", "INTEGER NOT NULL DEFAULT 0")
+            .await?;
+        self.ensure_column("apps", "policy_profile_id", "TEXT")
+            .await?;
+        self.ensure_column(
+            "apps",
+            "permitted_company_ids",
+            "TEXT NOT NULL DEFAULT '[]'",
+        )
+        .await?;
+        self.ensure_column("apps", "sandbox", "INTEGER NOT NULL DEFAULT 0")
+            .await?;
+        self.ensure_column(
+            "apps",
+            "scopes",
+            r#"TEXT NOT NULL DEFAULT '["actions:evaluate"]'"#,
+        )
+        .await?;
+        for column in [
+            "webhook_url",
+            "webhook_secret",
+            "webhook_previous_secret",
+            "webhook_previous_secret_expires_at",
+        ] {
+            self.ensure_column("apps", column, "TEXT").await?;
+        }
+        self.ensure_column("agent_actions", "sandbox", "INTEGER NOT NULL DEFAULT 0")
+            .await?;
+        for column in ["first_approver_id", "first_approved_at"] {
+            self.ensure_column("hitl_tasks", column, "TEXT").await?;
+        }
+        self.ensure_column("companies", "plan", "TEXT NOT NULL DEFAULT 'unlimited'")
+            .await?;
+        for column in ["deleted_at", "deleted_slug"] {
+            self.ensure_column("companies", column, "TEXT").await?;
+        }
+        self.ensure_column(
+            "evaluations",
+            "matched_patterns",
+            "TEXT NOT NULL DEFAULT '[]'",
+        )
+        .await?;
+        self.ensure_column("evaluations", "guard_model", "TEXT")
+            .await?;
+        self.ensure_column(
+            "evaluations",
+            "reason_entries",
+            "TEXT NOT NULL DEFAULT '[]'",
+        )
+        .await?;
+        self.ensure_column("evaluations", "layers", "TEXT").await?;
+        for table in ["evaluations", "reevaluations"] {
+            self.ensure_column(table, "risk_score", "REAL").await?;
+        }
+        self.ensure_column(
+            "agent_actions",
+            "conversation",
+            "TEXT NOT NULL DEFAULT '[]'",
+        )
+        .await?;
+
         // Users table (for OAuth and password auth)
-        sqlx::query(
+        sqlx::raw_sql(&self.backend.ddl(
             r#"
             CREATE TABLE IF NOT EXISTS users (
                 id TEXT PRIMARY KEY,
@@ -235,12 +542,12 @@ impl ShieldRepository {
 
             CREATE INDEX IF NOT EXISTS idx_users_email ON users(email);
             "#,
-        )
+        ))
         .execute(&self.pool)
         .await?;
 
         // OAuth accounts table
-        sqlx::query(
+        sqlx::raw_sql(&self.backend.ddl(
             r#"
             CREATE TABLE IF NOT EXISTS oauth_accounts (
                 id TEXT PRIMARY KEY,
@@ -255,46 +562,99 @@ impl ShieldRepository {
             CREATE INDEX IF NOT EXISTS idx_oauth_accounts_user_id ON oauth_accounts(user_id);
             CREATE INDEX IF NOT EXISTS idx_oauth_accounts_provider ON oauth_accounts(provider, provider_account_id);
             "#,
-        )
+        ))
+        .execute(&self.pool)
+        .await?;
+
+        // Refresh tokens table. Users from the config file have no users
+        // row, so user_id is not a foreign key.
+        sqlx::raw_sql(&self.backend.ddl(
+            r#"
+            CREATE TABLE IF NOT EXISTS refresh_tokens (
+                id TEXT PRIMARY KEY,
+                token_hash TEXT NOT NULL UNIQUE,
+                user_id TEXT NOT NULL,
+                email TEXT NOT NULL,
+                role TEXT NOT NULL,
+                expires_at TEXT NOT NULL,
+                revoked_at TEXT,
+                created_at TEXT NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_refresh_tokens_user_id ON refresh_tokens(user_id);
+            "#,
+        ))
         .execute(&self.pool)
         .await?;
 
         Ok(())
     }
 
+    /// Add a column to an existing table if it is missing.
+    ///
+    /// `CREATE TABLE IF NOT EXISTS` leaves databases created by older
+    /// versions untouched, so new columns are added here.
+    async fn ensure_column(&self, table: &str, column: &str, definition: &str) -> ShieldResult<()> {
+        if !self.has_column(table, column).await? {
+            sqlx::query(&self.backend.ddl(&format!(
+                "ALTER TABLE {} ADD COLUMN {} {}",
+                table, column, definition
+            )))
+            .execute(&self.pool)
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Whether `table` exists and has `column`.
+    async fn has_column(&self, table: &str, column: &str) -> ShieldResult<bool> {
+        let lookup = match self.backend {
+            Backend::Sqlite => "SELECT name FROM pragma_table_info(?) WHERE name = ?",
+            Backend::Postgres => {
+                "SELECT column_name::text FROM information_schema.columns \
+                 WHERE table_name::text = ? AND column_name::text = ?"
+            }
+        };
+        let existing: Option<(String,)> = sqlx::query_as(&self.sql(lookup))
+            .bind(table)
+            .bind(column)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(existing.is_some())
+    }
+
     // ==================== Agent Actions ====================
 
     /// Save an agent action to the database.
     pub async fn save_action(&self, action: &AgentAction) -> ShieldResult<()> {
-        sqlx::query(
-            r#"
-            INSERT INTO agent_actions (
-                id, trace_id, app_id, user_id, channel, model_name,
-                original_intent, action_type, payload, cot_trace, metadata, created_at
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
-            "#,
-        )
-        .bind(action.id.to_string())
-        .bind(&action.trace_id)
-        .bind(action.app_id.map(|id| id.to_string()))
-        .bind(&action.user_id)
-        .bind(&action.channel)
-        .bind(&action.model_name)
-        .bind(&action.original_intent)
-        .bind(action.action_type.to_string())
-        .bind(serde_json::to_string(&action.payload)?)
-        .bind(&action.cot_trace)
-        .bind(
-            action
-                .metadata
-                .as_ref()
-                .map(serde_json::to_string)
-                .transpose()?,
-        )
-        .bind(action.created_at.to_rfc3339())
-        .execute(&self.pool)
-        .await?;
+        let mut conn = self.pool.acquire().await?;
+        self.insert_action(&mut conn, action, None, false).await
+    }
 
+    /// Save evaluated actions with their evaluations and HITL tasks in one
+    /// transaction, so a failure part way through saves none of them.
+    ///
+    /// Actions evaluated for a company are saved with it, like
+    /// [`Self::save_action_with_company`] (or [`Self::save_sandbox_action`]
+    /// when `sandbox` is set).
+    pub async fn save_evaluated_actions(
+        &self,
+        items: &[(&AgentAction, &EvaluationResult, Option<&HitlTask>)],
+        company_id: Option<Uuid>,
+        sandbox: bool,
+    ) -> ShieldResult<()> {
+        let mut tx = self.pool.begin().await?;
+        for (action, evaluation, task) in items {
+            self.insert_action(&mut tx, action, company_id, sandbox)
+                .await?;
+            self.insert_evaluation(&mut tx, evaluation).await?;
+            if let Some(task) = task {
+                self.insert_hitl_task(&mut tx, task).await?;
+            }
+        }
+        tx.commit().await?;
         Ok(())
     }
 
@@ -304,18 +664,52 @@ impl ShieldRepository {
         action: &AgentAction,
         company_id: Uuid,
     ) -> ShieldResult<()> {
-        sqlx::query(
+        self.insert_company_action(action, company_id, false).await
+    }
+
+    /// Save an agent action from a sandbox app.
+    ///
+    /// Sandbox actions are kept out of metrics, the activity log (unless
+    /// asked for) and the history used by later evaluations.
+    pub async fn save_sandbox_action(
+        &self,
+        action: &AgentAction,
+        company_id: Uuid,
+    ) -> ShieldResult<()> {
+        self.insert_company_action(action, company_id, true).await
+    }
+
+    async fn insert_company_action(
+        &self,
+        action: &AgentAction,
+        company_id: Uuid,
+        sandbox: bool,
+    ) -> ShieldResult<()> {
+        let mut conn = self.pool.acquire().await?;
+        self.insert_action(&mut conn, action, Some(company_id), sandbox)
+            .await
+    }
+
+    async fn insert_action(
+        &self,
+        conn: &mut AnyConnection,
+        action: &AgentAction,
+        company_id: Option<Uuid>,
+        sandbox: bool,
+    ) -> ShieldResult<()> {
+        sqlx::query(&self.sql(
             r#"
             INSERT INTO agent_actions (
                 id, trace_id, app_id, company_id, user_id, channel, model_name,
-                original_intent, action_type, payload, cot_trace, metadata, created_at
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                original_intent, action_type, payload, cot_trace, metadata, created_at,
+                conversation, sandbox
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
-        )
+        ))
         .bind(action.id.to_string())
         .bind(&action.trace_id)
         .bind(action.app_id.map(|id| id.to_string()))
-        .bind(company_id.to_string())
+        .bind(company_id.map(|id| id.to_string()))
         .bind(&action.user_id)
         .bind(&action.channel)
         .bind(&action.model_name)
@@ -331,56 +725,660 @@ impl ShieldRepository {
                 .transpose()?,
         )
         .bind(action.created_at.to_rfc3339())
-        .execute(&self.pool)
+        .bind(serde_json::to_string(&action.conversation)?)
+        .bind(sandbox as i32)
+        .execute(conn)
         .await?;
 
         Ok(())
     }
 
-    /// Get an agent action by ID.
-    pub async fn get_action(&self, id: Uuid) -> ShieldResult<AgentAction> {
-        let row: AgentActionRow = sqlx::query_as("SELECT * FROM agent_actions WHERE id = ?")
-            .bind(id.to_string())
-            .fetch_optional(&self.pool)
-            .await?
-            .ok_or_else(|| ShieldError::NotFound(format!("Action {} not found", id)))?;
+    /// Sum a user's transfer and payment amounts since the given time, per
+    /// payload currency (`None` for payloads without one).
+    ///
+    /// Blocked actions and rejected HITL tasks are excluded since no funds moved,
+    /// as is `exclude_action_id` (the action being evaluated, which is already
+    /// stored when re-evaluating).
+    pub async fn sum_user_transfers_since(
+        &self,
+        company_id: Uuid,
+        user_id: &str,
+        since: DateTime<Utc>,
+        exclude_action_id: Uuid,
+    ) -> ShieldResult<Vec<(Option<String>, f64)>> {
+        let totals: Vec<(Option<String>, Option<f64>)> = sqlx::query_as(&self.sql(&format!(
+            r#"
+            SELECT {currency}, SUM({amount})
+            FROM agent_actions a
+            JOIN evaluations e ON e.agent_action_id = a.id
+            LEFT JOIN hitl_tasks h ON h.agent_action_id = a.id
+            WHERE a.company_id = ?
+                AND a.sandbox = 0
+                AND a.user_id = ?
+                AND a.action_type IN ('transfer_funds', 'pay_bill')
+                AND a.created_at >= ?
+                AND a.id != ?
+                AND e.decision != 'block'
+                AND (h.status IS NULL OR h.status != 'rejected')
+            GROUP BY {currency}
+            "#,
+            amount = self.backend.json_number("a.payload", "amount"),
+            currency = self.backend.json_text("a.payload", "currency")
+        )))
+        .bind(company_id.to_string())
+        .bind(user_id)
+        .bind(since.to_rfc3339())
+        .bind(exclude_action_id.to_string())
+        .fetch_all(&self.pool)
+        .await?;
 
-        row.try_into()
+        Ok(totals
+            .into_iter()
+            .filter_map(|(currency, total)| total.map(|total| (currency, total)))
+            .collect())
     }
 
-    // ==================== Evaluations ====================
-
-    /// Save an evaluation result to the database.
-    pub async fn save_evaluation(&self, eval: &EvaluationResult) -> ShieldResult<()> {
-        sqlx::query(
+    /// Count a user's evaluated transfers and bill payments since `since`,
+    /// excluding `exclude_action_id`.
+    pub async fn count_user_transfers_since(
+        &self,
+        company_id: Uuid,
+        user_id: &str,
+        since: DateTime<Utc>,
+        exclude_action_id: Uuid,
+    ) -> ShieldResult<u32> {
+        let (count,): (i64,) = sqlx::query_as(&self.sql(
             r#"
-            INSERT INTO evaluations (
-                id, agent_action_id, decision, risk_tier,
-                reasons, rule_hits, neural_signals, created_at
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            SELECT COUNT(*)
+            FROM agent_actions a
+            JOIN evaluations e ON e.agent_action_id = a.id
+            WHERE a.company_id = ?
+                AND a.sandbox = 0
+                AND a.user_id = ?
+                AND a.action_type IN ('transfer_funds', 'pay_bill')
+                AND a.created_at >= ?
+                AND a.id != ?
             "#,
-        )
-        .bind(eval.id.to_string())
-        .bind(eval.agent_action_id.to_string())
-        .bind(eval.decision.to_string())
-        .bind(eval.risk_tier.to_string())
+        ))
+        .bind(company_id.to_string())
+        .bind(user_id)
+        .bind(since.to_rfc3339())
+        .bind(exclude_action_id.to_string())
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count as u32)
+    }
+
+    /// Count a user's evaluated actions since `since`, excluding `exclude_action_id`.
+    pub async fn count_user_evaluations_since(
+        &self,
+        company_id: Uuid,
+        user_id: &str,
+        since: DateTime<Utc>,
+        exclude_action_id: Uuid,
+    ) -> ShieldResult<u32> {
+        let (count,): (i64,) = sqlx::query_as(&self.sql(
+            r#"
+            SELECT COUNT(*)
+            FROM agent_actions a
+            JOIN evaluations e ON e.agent_action_id = a.id
+            WHERE a.company_id = ?
+                AND a.sandbox = 0
+                AND a.user_id = ?
+                AND a.created_at >= ?
+                AND a.id != ?
+            "#,
+        ))
+        .bind(company_id.to_string())
+        .bind(user_id)
+        .bind(since.to_rfc3339())
+        .bind(exclude_action_id.to_string())
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count as u32)
+    }
+
+    /// Summarize the company's earlier actions with `trace_id`, excluding
+    /// `exclude_action_id`. `None` if the trace ID is new to the company.
+    pub async fn get_trace_history(
+        &self,
+        company_id: Uuid,
+        trace_id: &str,
+        user_id: &str,
+        exclude_action_id: Uuid,
+    ) -> ShieldResult<Option<TraceHistory>> {
+        let (first_seen, blocked, other_user): (Option<String>, Option<i64>, Option<i64>) =
+            sqlx::query_as(&self.sql(
+                r#"
+                SELECT
+                    MIN(a.created_at),
+                    MAX(CASE WHEN e.decision = 'block' THEN 1 ELSE 0 END),
+                    MAX(CASE WHEN a.user_id != ? THEN 1 ELSE 0 END)
+                FROM agent_actions a
+                LEFT JOIN evaluations e ON e.agent_action_id = a.id
+                WHERE a.trace_id = ?
+                    AND a.id != ?
+                    AND a.company_id = ?
+                    AND a.sandbox = 0
+                "#,
+            ))
+            .bind(user_id)
+            .bind(trace_id)
+            .bind(exclude_action_id.to_string())
+            .bind(company_id.to_string())
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(first_seen
+            .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
+            .map(|dt| TraceHistory {
+                first_seen: dt.with_timezone(&Utc),
+                blocked: blocked.unwrap_or(0) == 1,
+                other_user: other_user.unwrap_or(0) == 1,
+            }))
+    }
+
+    /// Get when the user's most recent blocked action since `since` was created.
+    pub async fn get_user_last_block_since(
+        &self,
+        company_id: Uuid,
+        user_id: &str,
+        since: DateTime<Utc>,
+        exclude_action_id: Uuid,
+    ) -> ShieldResult<Option<DateTime<Utc>>> {
+        let (last,): (Option<String>,) = sqlx::query_as(&self.sql(
+            r#"
+            SELECT MAX(a.created_at)
+            FROM agent_actions a
+            JOIN evaluations e ON e.agent_action_id = a.id
+            WHERE a.company_id = ?
+                AND a.sandbox = 0
+                AND a.user_id = ?
+                AND a.created_at >= ?
+                AND a.id != ?
+                AND e.decision = 'block'
+            "#,
+        ))
+        .bind(company_id.to_string())
+        .bind(user_id)
+        .bind(since.to_rfc3339())
+        .bind(exclude_action_id.to_string())
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(last
+            .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
+            .map(|dt| dt.with_timezone(&Utc)))
+    }
+
+    /// Count a user's outgoing transfers and payments since `since`, and sum
+    /// them per payload currency like [`Self::sum_user_transfers_since`].
+    ///
+    /// Transfers flagged internal, blocked actions, rejected HITL tasks and
+    /// `exclude_action_id` are not counted.
+    pub async fn summarize_user_outflows_since(
+        &self,
+        company_id: Uuid,
+        user_id: &str,
+        since: DateTime<Utc>,
+        exclude_action_id: Uuid,
+    ) -> ShieldResult<(u32, Vec<(Option<String>, f64)>)> {
+        let groups: Vec<(Option<String>, i64, Option<f64>)> = sqlx::query_as(&self.sql(&format!(
+            r#"
+            SELECT {currency}, COUNT(*), SUM({amount})
+            FROM agent_actions a
+            JOIN evaluations e ON e.agent_action_id = a.id
+            LEFT JOIN hitl_tasks h ON h.agent_action_id = a.id
+            WHERE a.company_id = ?
+                AND a.sandbox = 0
+                AND a.user_id = ?
+                AND a.action_type IN ('transfer_funds', 'pay_bill')
+                AND {not_internal}
+                AND a.created_at >= ?
+                AND a.id != ?
+                AND e.decision != 'block'
+                AND (h.status IS NULL OR h.status != 'rejected')
+            GROUP BY {currency}
+            "#,
+            amount = self.backend.json_number("a.payload", "amount"),
+            currency = self.backend.json_text("a.payload", "currency"),
+            not_internal = self.backend.json_flag_unset("a.payload", "internal")
+        )))
+        .bind(company_id.to_string())
+        .bind(user_id)
+        .bind(since.to_rfc3339())
+        .bind(exclude_action_id.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+
+        let count = groups.iter().map(|(_, count, _)| *count).sum::<i64>();
+        let totals = groups
+            .into_iter()
+            .filter_map(|(currency, _, total)| total.map(|total| (currency, total)))
+            .collect();
+        Ok((count as u32, totals))
+    }
+
+    /// Get the amount still refundable on an original transfer or payment,
+    /// with the original's payload currency (`None` when it has none).
+    ///
+    /// Returns `None` if the original is unknown, belongs to another user,
+    /// older than `since`, or wasn't allowed or approved by a reviewer.
+    /// Other refunds referencing the same original (besides
+    /// `exclude_action_id`) are deducted.
+    pub async fn get_refundable_amount(
+        &self,
+        company_id: Uuid,
+        user_id: &str,
+        original_action_id: Uuid,
+        since: DateTime<Utc>,
+        exclude_action_id: Uuid,
+    ) -> ShieldResult<Option<(f64, Option<String>)>> {
+        let original: Option<(Option<f64>, Option<String>)> = sqlx::query_as(&self.sql(&format!(
+            r#"
+            SELECT {amount}, {currency}
+            FROM agent_actions a
+            JOIN evaluations e ON e.agent_action_id = a.id
+            LEFT JOIN hitl_tasks h ON h.agent_action_id = a.id
+            WHERE a.id = ?
+                AND a.company_id = ?
+                AND a.user_id = ?
+                AND a.sandbox = 0
+                AND a.action_type IN ('transfer_funds', 'pay_bill')
+                AND a.created_at >= ?
+                AND (e.decision = 'allow' OR (e.decision = 'require_hitl' AND h.status = 'approved'))
+            "#,
+            amount = self.backend.json_number("a.payload", "amount"),
+            currency = self.backend.json_text("a.payload", "currency")
+        )))
+        .bind(original_action_id.to_string())
+        .bind(company_id.to_string())
+        .bind(user_id)
+        .bind(since.to_rfc3339())
+        .fetch_optional(&self.pool)
+        .await?;
+
+        let Some((Some(original_amount), currency)) = original else {
+            return Ok(None);
+        };
+
+        let (refunded,): (Option<f64>,) = sqlx::query_as(&self.sql(&format!(
+            r#"
+            SELECT SUM({amount})
+            FROM agent_actions a
+            JOIN evaluations e ON e.agent_action_id = a.id
+            LEFT JOIN hitl_tasks h ON h.agent_action_id = a.id
+            WHERE a.company_id = ?
+                AND a.sandbox = 0
+                AND a.action_type = 'refund_transaction'
+                AND {original_id} = ?
+                AND a.id != ?
+                AND e.decision != 'block'
+                AND (h.status IS NULL OR h.status != 'rejected')
+            "#,
+            amount = self.backend.json_number("a.payload", "amount"),
+            original_id = self.backend.json_text("a.payload", "original_transaction_id")
+        )))
+        .bind(company_id.to_string())
+        .bind(original_action_id.to_string())
+        .bind(exclude_action_id.to_string())
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(Some((
+            (original_amount - refunded.unwrap_or(0.0)).max(0.0),
+            currency,
+        )))
+    }
+
+    /// Get an agent action by ID.
+    pub async fn get_action(&self, id: Uuid) -> ShieldResult<AgentAction> {
+        let row: AgentActionRow =
+            sqlx::query_as(&self.sql("SELECT * FROM agent_actions WHERE id = ?"))
+                .bind(id.to_string())
+                .fetch_optional(&self.pool)
+                .await?
+                .ok_or_else(|| ShieldError::NotFound(format!("Action {} not found", id)))?;
+
+        row.try_into()
+    }
+
+    /// Get an agent action by ID, scoped to a company.
+    pub async fn get_company_action(
+        &self,
+        company_id: Uuid,
+        id: Uuid,
+    ) -> ShieldResult<AgentAction> {
+        let row: AgentActionRow = sqlx::query_as(
+            &self.sql("SELECT * FROM agent_actions WHERE id = ? AND company_id = ?"),
+        )
+        .bind(id.to_string())
+        .bind(company_id.to_string())
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| ShieldError::NotFound(format!("Action {} not found", id)))?;
+
+        row.try_into()
+    }
+
+    /// Delete a company's actions created before `cutoff` (`None`: actions
+    /// that belong to no company), along with their evaluations, HITL tasks
+    /// and attack events.
+    ///
+    /// Works through `batch_size` actions per transaction so other writers
+    /// are never locked out for long.
+    pub async fn purge_actions_before(
+        &self,
+        company_id: Option<Uuid>,
+        cutoff: DateTime<Utc>,
+        batch_size: u32,
+    ) -> ShieldResult<PurgeSummary> {
+        let select = self.sql(&format!(
+            "SELECT id FROM agent_actions WHERE {} AND created_at < ? LIMIT ?",
+            if company_id.is_some() {
+                "company_id = ?"
+            } else {
+                "company_id IS NULL"
+            }
+        ));
+        let mut summary = PurgeSummary::default();
+
+        loop {
+            let mut tx = self.pool.begin().await?;
+
+            let mut query = sqlx::query_as::<_, (String,)>(&select);
+            if let Some(company_id) = company_id {
+                query = query.bind(company_id.to_string());
+            }
+            let ids: Vec<String> = query
+                .bind(cutoff.to_rfc3339())
+                .bind(batch_size.max(1) as i64)
+                .fetch_all(&mut *tx)
+                .await?
+                .into_iter()
+                .map(|(id,)| id)
+                .collect();
+            if ids.is_empty() {
+                break;
+            }
+
+            // Children first, so foreign keys hold at every step. Reviewer
+            // decisions and cached responses point at the tasks and
+            // evaluations, so they go before those.
+            let reviewer_decisions = self
+                .delete_by_parent(
+                    &mut tx,
+                    "audit_log",
+                    "target_type = 'hitl_task' AND target_id",
+                    "hitl_tasks",
+                    &ids,
+                )
+                .await?;
+            let idempotency_keys = self
+                .delete_by_parent(
+                    &mut tx,
+                    "idempotency_keys",
+                    "evaluation_id",
+                    "evaluations",
+                    &ids,
+                )
+                .await?;
+            let hitl_tasks = self
+                .delete_by_action(&mut tx, "hitl_tasks", "agent_action_id", &ids)
+                .await?;
+            let attack_events = self
+                .delete_by_action(&mut tx, "attack_events", "agent_action_id", &ids)
+                .await?;
+            let evaluations = self
+                .delete_by_action(&mut tx, "evaluations", "agent_action_id", &ids)
+                .await?
+                + self
+                    .delete_by_action(&mut tx, "reevaluations", "agent_action_id", &ids)
+                    .await?;
+            let actions = self
+                .delete_by_action(&mut tx, "agent_actions", "id", &ids)
+                .await?;
+            tx.commit().await?;
+
+            summary.add(PurgeSummary {
+                actions,
+                evaluations,
+                hitl_tasks,
+                attack_events,
+                reviewer_decisions,
+                idempotency_keys,
+                ..Default::default()
+            });
+        }
+
+        // Webhook deliveries aren't tied to actions; they age out with them
+        if let Some(company_id) = company_id {
+            let delete = self.sql(
+                "DELETE FROM webhook_deliveries WHERE id IN \
+                 (SELECT id FROM webhook_deliveries WHERE company_id = ? AND created_at < ? LIMIT ?)",
+            );
+            loop {
+                let deleted = sqlx::query(&delete)
+                    .bind(company_id.to_string())
+                    .bind(cutoff.to_rfc3339())
+                    .bind(batch_size.max(1) as i64)
+                    .execute(&self.pool)
+                    .await?
+                    .rows_affected();
+                if deleted == 0 {
+                    break;
+                }
+                summary.webhook_deliveries += deleted;
+            }
+        }
+
+        Ok(summary)
+    }
+
+    /// Delete the rows of `table` whose `column` is one of `action_ids`.
+    async fn delete_by_action(
+        &self,
+        conn: &mut AnyConnection,
+        table: &str,
+        column: &str,
+        action_ids: &[String],
+    ) -> ShieldResult<u64> {
+        let sql = self.sql(&format!(
+            "DELETE FROM {} WHERE {} IN ({})",
+            table,
+            column,
+            vec!["?"; action_ids.len()].join(", ")
+        ));
+        let mut query = sqlx::query(&sql);
+        for id in action_ids {
+            query = query.bind(id);
+        }
+        Ok(query.execute(conn).await?.rows_affected())
+    }
+
+    /// Delete the rows of `table` matching `filter` (ending in the column
+    /// to match) against the IDs of `parent` rows of `action_ids`.
+    async fn delete_by_parent(
+        &self,
+        conn: &mut AnyConnection,
+        table: &str,
+        filter: &str,
+        parent: &str,
+        action_ids: &[String],
+    ) -> ShieldResult<u64> {
+        let sql = self.sql(&format!(
+            "DELETE FROM {} WHERE {} IN (SELECT id FROM {} WHERE agent_action_id IN ({}))",
+            table,
+            filter,
+            parent,
+            vec!["?"; action_ids.len()].join(", ")
+        ));
+        let mut query = sqlx::query(&sql);
+        for id in action_ids {
+            query = query.bind(id);
+        }
+        Ok(query.execute(conn).await?.rows_affected())
+    }
+
+    // ==================== Evaluations ====================
+
+    /// Save an evaluation result to the database.
+    pub async fn save_evaluation(&self, eval: &EvaluationResult) -> ShieldResult<()> {
+        let mut conn = self.pool.acquire().await?;
+        self.insert_evaluation(&mut conn, eval).await
+    }
+
+    /// Save a re-evaluation of a stored action, appending `audit` to the
+    /// audit trail in the same transaction. It is kept apart from the
+    /// action's evaluations, so it never changes the action's decision.
+    pub async fn save_reevaluation(
+        &self,
+        eval: &EvaluationResult,
+        audit: &AuditEntry,
+    ) -> ShieldResult<()> {
+        let mut tx = self.pool.begin().await?;
+        self.insert_evaluation_into(&mut tx, "reevaluations", eval)
+            .await?;
+        self.insert_audit_entry(&mut tx, audit).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Get the most recent persisted re-evaluation of an action.
+    pub async fn get_latest_reevaluation_for_action(
+        &self,
+        agent_action_id: Uuid,
+    ) -> ShieldResult<EvaluationResult> {
+        let row: EvaluationRow = sqlx::query_as(&self.sql(
+            "SELECT * FROM reevaluations WHERE agent_action_id = ? ORDER BY created_at DESC LIMIT 1",
+        ))
+        .bind(agent_action_id.to_string())
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| {
+            ShieldError::NotFound(format!("No re-evaluation for action {}", agent_action_id))
+        })?;
+
+        row.try_into()
+    }
+
+    async fn insert_evaluation(
+        &self,
+        conn: &mut AnyConnection,
+        eval: &EvaluationResult,
+    ) -> ShieldResult<()> {
+        self.insert_evaluation_into(conn, "evaluations", eval).await
+    }
+
+    async fn insert_evaluation_into(
+        &self,
+        conn: &mut AnyConnection,
+        table: &str,
+        eval: &EvaluationResult,
+    ) -> ShieldResult<()> {
+        sqlx::query(&self.sql(&format!(
+            r#"
+            INSERT INTO {} (
+                id, agent_action_id, decision, risk_tier,
+                reasons, rule_hits, neural_signals, created_at, matched_patterns,
+                guard_model, reason_entries, layers, risk_score
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+            table
+        )))
+        .bind(eval.id.to_string())
+        .bind(eval.agent_action_id.to_string())
+        .bind(eval.decision.to_string())
+        .bind(eval.risk_tier.to_string())
         .bind(serde_json::to_string(&eval.reasons)?)
         .bind(serde_json::to_string(&eval.rule_hits)?)
         .bind(serde_json::to_string(&eval.neural_signals)?)
         .bind(eval.created_at.to_rfc3339())
-        .execute(&self.pool)
+        .bind(serde_json::to_string(&eval.matched_patterns)?)
+        .bind(&eval.guard_model)
+        .bind(serde_json::to_string(&eval.reason_entries)?)
+        .bind(
+            eval.layers
+                .as_ref()
+                .map(serde_json::to_string)
+                .transpose()?,
+        )
+        .bind(eval.risk_score)
+        .execute(conn)
+        .await?;
+
+        Ok(())
+    }
+
+    /// List one page of the evaluations of a company's actions, in a
+    /// stable order for walking them all.
+    pub async fn list_company_evaluations(
+        &self,
+        company_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> ShieldResult<Vec<EvaluationResult>> {
+        let rows: Vec<EvaluationRow> = sqlx::query_as(&self.sql(
+            r#"
+            SELECT e.* FROM evaluations e
+            JOIN agent_actions a ON e.agent_action_id = a.id
+            WHERE a.company_id = ?
+            ORDER BY e.id
+            LIMIT ? OFFSET ?
+            "#,
+        ))
+        .bind(company_id.to_string())
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
         .await?;
 
+        rows.into_iter().map(|r| r.try_into()).collect()
+    }
+
+    /// Store recomputed risk scores, all in one transaction.
+    pub async fn update_evaluation_risk_scores(&self, scores: &[(Uuid, f64)]) -> ShieldResult<()> {
+        let mut tx = self.pool.begin().await?;
+        for (id, score) in scores {
+            sqlx::query(&self.sql("UPDATE evaluations SET risk_score = ? WHERE id = ?"))
+                .bind(*score)
+                .bind(id.to_string())
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+
         Ok(())
     }
 
     /// Get an evaluation by ID.
     pub async fn get_evaluation(&self, id: Uuid) -> ShieldResult<EvaluationResult> {
-        let row: EvaluationRow = sqlx::query_as("SELECT * FROM evaluations WHERE id = ?")
-            .bind(id.to_string())
-            .fetch_optional(&self.pool)
-            .await?
-            .ok_or_else(|| ShieldError::NotFound(format!("Evaluation {} not found", id)))?;
+        let row: EvaluationRow =
+            sqlx::query_as(&self.sql("SELECT * FROM evaluations WHERE id = ?"))
+                .bind(id.to_string())
+                .fetch_optional(&self.pool)
+                .await?
+                .ok_or_else(|| ShieldError::NotFound(format!("Evaluation {} not found", id)))?;
+
+        row.try_into()
+    }
+
+    /// Get the most recent evaluation of an action.
+    pub async fn get_latest_evaluation_for_action(
+        &self,
+        agent_action_id: Uuid,
+    ) -> ShieldResult<EvaluationResult> {
+        let row: EvaluationRow = sqlx::query_as(&self.sql(
+            "SELECT * FROM evaluations WHERE agent_action_id = ? ORDER BY created_at DESC LIMIT 1",
+        ))
+        .bind(agent_action_id.to_string())
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| {
+            ShieldError::NotFound(format!("No evaluation for action {}", agent_action_id))
+        })?;
 
         row.try_into()
     }
@@ -389,14 +1387,23 @@ impl ShieldRepository {
 
     /// Save a HITL task to the database.
     pub async fn save_hitl_task(&self, task: &HitlTask) -> ShieldResult<()> {
-        sqlx::query(
+        let mut conn = self.pool.acquire().await?;
+        self.insert_hitl_task(&mut conn, task).await
+    }
+
+    async fn insert_hitl_task(
+        &self,
+        conn: &mut AnyConnection,
+        task: &HitlTask,
+    ) -> ShieldResult<()> {
+        sqlx::query(&self.sql(
             r#"
             INSERT INTO hitl_tasks (
                 id, agent_action_id, evaluation_id, status,
                 reviewer_id, reviewed_at, review_notes, created_at
             ) VALUES (?, ?, ?, ?, ?, ?, ?, ?)
             "#,
-        )
+        ))
         .bind(task.id.to_string())
         .bind(task.agent_action_id.to_string())
         .bind(task.evaluation_id.to_string())
@@ -405,7 +1412,7 @@ impl ShieldRepository {
         .bind(task.reviewed_at.map(|dt| dt.to_rfc3339()))
         .bind(&task.review_notes)
         .bind(task.created_at.to_rfc3339())
-        .execute(&self.pool)
+        .execute(conn)
         .await?;
 
         Ok(())
@@ -413,7 +1420,7 @@ impl ShieldRepository {
 
     /// Get a HITL task by ID.
     pub async fn get_hitl_task(&self, id: Uuid) -> ShieldResult<HitlTask> {
-        let row: HitlTaskRow = sqlx::query_as("SELECT * FROM hitl_tasks WHERE id = ?")
+        let row: HitlTaskRow = sqlx::query_as(&self.sql("SELECT * FROM hitl_tasks WHERE id = ?"))
             .bind(id.to_string())
             .fetch_optional(&self.pool)
             .await?
@@ -422,6 +1429,23 @@ impl ShieldRepository {
         row.try_into()
     }
 
+    /// Get the HITL task an evaluation created.
+    pub async fn get_hitl_task_for_evaluation(
+        &self,
+        evaluation_id: Uuid,
+    ) -> ShieldResult<HitlTask> {
+        let row: HitlTaskRow =
+            sqlx::query_as(&self.sql("SELECT * FROM hitl_tasks WHERE evaluation_id = ?"))
+                .bind(evaluation_id.to_string())
+                .fetch_optional(&self.pool)
+                .await?
+                .ok_or_else(|| {
+                    ShieldError::NotFound(format!("No HITL task for evaluation {}", evaluation_id))
+                })?;
+
+        row.try_into()
+    }
+
     /// Get full HITL task details including action and evaluation.
     pub async fn get_hitl_task_details(&self, id: Uuid) -> ShieldResult<HitlTaskDetails> {
         let task = self.get_hitl_task(id).await?;
@@ -429,36 +1453,111 @@ impl ShieldRepository {
         let evaluation = self.get_evaluation(task.evaluation_id).await?;
 
         Ok(HitlTaskDetails {
+            resolved_decision: task.resolved_decision(),
             task,
             agent_action,
             evaluation,
         })
     }
 
-    /// Update a HITL task's status and review info.
+    /// Update a HITL task's status and review info, appending `audit` to
+    /// the audit trail in the same transaction.
     pub async fn update_hitl_task(
         &self,
         id: Uuid,
         status: HitlStatus,
         reviewer_id: &str,
         notes: Option<&str>,
+        audit: Option<&AuditEntry>,
     ) -> ShieldResult<HitlTask> {
         let reviewed_at = chrono::Utc::now().to_rfc3339();
 
-        sqlx::query(
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(&self.sql(
             r#"
             UPDATE hitl_tasks
             SET status = ?, reviewer_id = ?, reviewed_at = ?, review_notes = ?
             WHERE id = ?
             "#,
-        )
+        ))
         .bind(status.to_string())
         .bind(reviewer_id)
         .bind(&reviewed_at)
         .bind(notes)
         .bind(id.to_string())
-        .execute(&self.pool)
+        .execute(&mut *tx)
+        .await?;
+        if let Some(entry) = audit {
+            self.insert_audit_entry(&mut tx, entry).await?;
+        }
+        tx.commit().await?;
+
+        self.get_hitl_task(id).await
+    }
+
+    /// Decide several pending tasks in one transaction, each with its own
+    /// notes and audit entry. Returns the IDs actually updated; tasks no
+    /// longer pending are left alone and get no audit entry.
+    pub async fn update_pending_hitl_tasks(
+        &self,
+        updates: &[(Uuid, Option<&str>, AuditEntry)],
+        status: HitlStatus,
+        reviewer_id: &str,
+    ) -> ShieldResult<Vec<Uuid>> {
+        let reviewed_at = chrono::Utc::now().to_rfc3339();
+        let mut updated = Vec::with_capacity(updates.len());
+
+        let mut tx = self.pool.begin().await?;
+        for (id, notes, audit) in updates {
+            let result = sqlx::query(&self.sql(
+                r#"
+                UPDATE hitl_tasks
+                SET status = ?, reviewer_id = ?, reviewed_at = ?, review_notes = ?
+                WHERE id = ? AND status = 'pending'
+                "#,
+            ))
+            .bind(status.to_string())
+            .bind(reviewer_id)
+            .bind(&reviewed_at)
+            .bind(*notes)
+            .bind(id.to_string())
+            .execute(&mut *tx)
+            .await?;
+            if result.rows_affected() > 0 {
+                self.insert_audit_entry(&mut tx, audit).await?;
+                updated.push(*id);
+            }
+        }
+        tx.commit().await?;
+
+        Ok(updated)
+    }
+
+    /// Record the first of two approvals a task needs; it stays pending
+    /// until [`Self::update_hitl_task`] records the confirmation.
+    pub async fn record_first_approval(
+        &self,
+        id: Uuid,
+        reviewer_id: &str,
+        notes: Option<&str>,
+        audit: &AuditEntry,
+    ) -> ShieldResult<HitlTask> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(&self.sql(
+            r#"
+            UPDATE hitl_tasks
+            SET first_approver_id = ?, first_approved_at = ?, review_notes = ?
+            WHERE id = ? AND status = 'pending'
+            "#,
+        ))
+        .bind(reviewer_id)
+        .bind(chrono::Utc::now().to_rfc3339())
+        .bind(notes)
+        .bind(id.to_string())
+        .execute(&mut *tx)
         .await?;
+        self.insert_audit_entry(&mut tx, audit).await?;
+        tx.commit().await?;
 
         self.get_hitl_task(id).await
     }
@@ -469,57 +1568,148 @@ impl ShieldRepository {
         status: Option<HitlStatus>,
         limit: i64,
         offset: i64,
-    ) -> ShieldResult<Vec<HitlTaskSummary>> {
-        let query = if status.is_some() {
-            r#"
-            SELECT
-                t.id,
-                a.user_id,
-                a.action_type,
-                json_extract(a.payload, '$.amount') as amount,
-                e.risk_tier,
-                t.status,
-                t.created_at
-            FROM hitl_tasks t
-            JOIN agent_actions a ON t.agent_action_id = a.id
-            JOIN evaluations e ON t.evaluation_id = e.id
-            WHERE t.status = ?
-            ORDER BY t.created_at DESC
-            LIMIT ? OFFSET ?
-            "#
+    ) -> ShieldResult<(Vec<HitlTaskSummary>, i64)> {
+        let filter = if status.is_some() {
+            "WHERE t.status = ?"
         } else {
+            ""
+        };
+        let query = self.sql(&format!(
             r#"
             SELECT
                 t.id,
                 a.user_id,
                 a.action_type,
-                json_extract(a.payload, '$.amount') as amount,
+                {amount} as amount,
                 e.risk_tier,
                 t.status,
                 t.created_at
             FROM hitl_tasks t
             JOIN agent_actions a ON t.agent_action_id = a.id
             JOIN evaluations e ON t.evaluation_id = e.id
+            {filter}
             ORDER BY t.created_at DESC
             LIMIT ? OFFSET ?
-            "#
-        };
+            "#,
+            amount = self.backend.json_number("a.payload", "amount"),
+        ));
 
         let rows: Vec<HitlTaskSummaryRow> = if let Some(s) = status {
-            sqlx::query_as(query)
+            sqlx::query_as(&query)
                 .bind(s.to_string())
                 .bind(limit)
                 .bind(offset)
                 .fetch_all(&self.pool)
                 .await?
         } else {
-            sqlx::query_as(query)
+            sqlx::query_as(&query)
                 .bind(limit)
                 .bind(offset)
                 .fetch_all(&self.pool)
                 .await?
         };
 
+        let (total,): (i64,) = match status {
+            Some(s) => {
+                sqlx::query_as(&self.sql("SELECT COUNT(*) FROM hitl_tasks WHERE status = ?"))
+                    .bind(s.to_string())
+                    .fetch_one(&self.pool)
+                    .await?
+            }
+            None => {
+                sqlx::query_as(&self.sql("SELECT COUNT(*) FROM hitl_tasks"))
+                    .fetch_one(&self.pool)
+                    .await?
+            }
+        };
+
+        let tasks = rows
+            .into_iter()
+            .map(|r| r.try_into())
+            .collect::<ShieldResult<Vec<_>>>()?;
+        Ok((tasks, total))
+    }
+
+    /// Aggregate a reviewer's decisions on a company's HITL tasks.
+    pub async fn get_reviewer_stats(
+        &self,
+        company_id: Uuid,
+        reviewer_id: &str,
+    ) -> ShieldResult<ReviewerStats> {
+        let (total, approved, rejected, avg_secs, overrides): (i64, i64, i64, Option<f64>, i64) =
+            sqlx::query_as(&self.sql(&format!(
+                r#"
+                SELECT
+                    COUNT(*),
+                    COALESCE(SUM(CASE WHEN t.status = 'approved' THEN 1 ELSE 0 END), 0),
+                    COALESCE(SUM(CASE WHEN t.status = 'rejected' THEN 1 ELSE 0 END), 0),
+                    AVG({review_secs}),
+                    COALESCE(SUM(CASE WHEN t.status = 'approved'
+                        AND e.risk_tier IN ('high', 'critical') THEN 1 ELSE 0 END), 0)
+                FROM hitl_tasks t
+                JOIN agent_actions a ON t.agent_action_id = a.id
+                JOIN evaluations e ON t.evaluation_id = e.id
+                WHERE a.company_id = ?
+                    AND t.reviewer_id = ?
+                    AND t.status != 'pending'
+                "#,
+                review_secs = self.backend.seconds_between("t.created_at", "t.reviewed_at")
+            )))
+            .bind(company_id.to_string())
+            .bind(reviewer_id)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(ReviewerStats {
+            reviewer_id: reviewer_id.to_string(),
+            total_reviewed: total,
+            approved,
+            rejected,
+            avg_time_to_decision_secs: avg_secs,
+            overrides,
+            override_rate: if total > 0 {
+                overrides as f64 / total as f64
+            } else {
+                0.0
+            },
+        })
+    }
+
+    /// List a reviewer's most recent decisions on a company's HITL tasks.
+    pub async fn list_reviewer_decisions(
+        &self,
+        company_id: Uuid,
+        reviewer_id: &str,
+        limit: i64,
+    ) -> ShieldResult<Vec<ReviewerDecision>> {
+        let rows: Vec<ReviewerDecisionRow> = sqlx::query_as(&self.sql(&format!(
+            r#"
+            SELECT
+                t.id,
+                t.agent_action_id,
+                a.action_type,
+                {amount} as amount,
+                e.risk_tier,
+                t.status,
+                t.created_at,
+                t.reviewed_at
+            FROM hitl_tasks t
+            JOIN agent_actions a ON t.agent_action_id = a.id
+            JOIN evaluations e ON t.evaluation_id = e.id
+            WHERE a.company_id = ?
+                AND t.reviewer_id = ?
+                AND t.status != 'pending'
+            ORDER BY t.reviewed_at DESC
+            LIMIT ?
+            "#,
+            amount = self.backend.json_number("a.payload", "amount")
+        )))
+        .bind(company_id.to_string())
+        .bind(reviewer_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
         rows.into_iter().map(|r| r.try_into()).collect()
     }
 
@@ -527,16 +1717,17 @@ impl ShieldRepository {
 
     /// Create a new company.
     pub async fn create_company(&self, company: &Company) -> ShieldResult<()> {
-        sqlx::query(
+        sqlx::query(&self.sql(
             r#"
-            INSERT INTO companies (id, name, slug, description, created_at, updated_at)
-            VALUES (?, ?, ?, ?, ?, ?)
+            INSERT INTO companies (id, name, slug, description, plan, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
             "#,
-        )
+        ))
         .bind(company.id.to_string())
         .bind(&company.name)
         .bind(&company.slug)
         .bind(&company.description)
+        .bind(&company.plan)
         .bind(company.created_at.to_rfc3339())
         .bind(company.updated_at.to_rfc3339())
         .execute(&self.pool)
@@ -545,24 +1736,28 @@ impl ShieldRepository {
         Ok(())
     }
 
-    /// Get a company by ID.
+    /// Get a company by ID (soft-deleted companies aren't found).
     pub async fn get_company(&self, id: Uuid) -> ShieldResult<Company> {
-        let row: CompanyRow = sqlx::query_as("SELECT * FROM companies WHERE id = ?")
-            .bind(id.to_string())
-            .fetch_optional(&self.pool)
-            .await?
-            .ok_or_else(|| ShieldError::NotFound(format!("Company {} not found", id)))?;
+        let row: CompanyRow = sqlx::query_as(
+            &self.sql("SELECT * FROM companies WHERE id = ? AND deleted_at IS NULL"),
+        )
+        .bind(id.to_string())
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| ShieldError::NotFound(format!("Company {} not found", id)))?;
 
         row.try_into()
     }
 
-    /// Get a company by slug.
+    /// Get a company by slug (soft-deleted companies aren't found).
     pub async fn get_company_by_slug(&self, slug: &str) -> ShieldResult<Company> {
-        let row: CompanyRow = sqlx::query_as("SELECT * FROM companies WHERE slug = ?")
-            .bind(slug)
-            .fetch_optional(&self.pool)
-            .await?
-            .ok_or_else(|| ShieldError::NotFound(format!("Company '{}' not found", slug)))?;
+        let row: CompanyRow = sqlx::query_as(
+            &self.sql("SELECT * FROM companies WHERE slug = ? AND deleted_at IS NULL"),
+        )
+        .bind(slug)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| ShieldError::NotFound(format!("Company '{}' not found", slug)))?;
 
         row.try_into()
     }
@@ -578,30 +1773,119 @@ impl ShieldRepository {
 
         if let Some(name) = name {
             let slug = Company::slugify(name);
-            sqlx::query("UPDATE companies SET name = ?, slug = ?, updated_at = ? WHERE id = ?")
-                .bind(name)
-                .bind(&slug)
-                .bind(&updated_at)
-                .bind(id.to_string())
-                .execute(&self.pool)
-                .await?;
+            sqlx::query(
+                &self.sql("UPDATE companies SET name = ?, slug = ?, updated_at = ? WHERE id = ?"),
+            )
+            .bind(name)
+            .bind(&slug)
+            .bind(&updated_at)
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await?;
         }
 
         if let Some(desc) = description {
-            sqlx::query("UPDATE companies SET description = ?, updated_at = ? WHERE id = ?")
-                .bind(desc)
-                .bind(&updated_at)
-                .bind(id.to_string())
-                .execute(&self.pool)
-                .await?;
+            sqlx::query(
+                &self.sql("UPDATE companies SET description = ?, updated_at = ? WHERE id = ?"),
+            )
+            .bind(desc)
+            .bind(&updated_at)
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await?;
         }
 
         self.get_company(id).await
     }
 
-    /// Delete a company.
+    /// Move a company to a different plan.
+    pub async fn update_company_plan(&self, id: Uuid, plan: &str) -> ShieldResult<Company> {
+        sqlx::query(&self.sql("UPDATE companies SET plan = ?, updated_at = ? WHERE id = ?"))
+            .bind(plan)
+            .bind(chrono::Utc::now().to_rfc3339())
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await?;
+
+        self.get_company(id).await
+    }
+
+    /// Soft-delete a company.
+    ///
+    /// The company and everything under it stay in the database but are
+    /// hidden, its members lose access and its apps' keys stop working.
+    /// Its slug is released right away (kept aside for
+    /// [`Self::restore_company`]) so a new company can take it.
     pub async fn delete_company(&self, id: Uuid) -> ShieldResult<()> {
-        let result = sqlx::query("DELETE FROM companies WHERE id = ?")
+        let now = chrono::Utc::now().to_rfc3339();
+        let result = sqlx::query(&self.sql(
+            r#"
+            UPDATE companies
+            SET deleted_at = ?, deleted_slug = slug, slug = ?, updated_at = ?
+            WHERE id = ? AND deleted_at IS NULL
+            "#,
+        ))
+        .bind(&now)
+        .bind(Self::released_slug(id))
+        .bind(&now)
+        .bind(id.to_string())
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(ShieldError::NotFound(format!("Company {} not found", id)));
+        }
+
+        Ok(())
+    }
+
+    /// Placeholder slug of a soft-deleted company. The `:` never appears in
+    /// slugs from [`Company::slugify`], so it can't collide with a live one.
+    fn released_slug(id: Uuid) -> String {
+        format!("deleted:{}", id)
+    }
+
+    /// Undo a soft delete, giving the company back its original slug.
+    ///
+    /// Fails if another company has taken the slug in the meantime; that
+    /// company keeps it.
+    pub async fn restore_company(&self, id: Uuid) -> ShieldResult<Company> {
+        let (slug,): (Option<String>,) = sqlx::query_as(
+            &self.sql("SELECT deleted_slug FROM companies WHERE id = ? AND deleted_at IS NOT NULL"),
+        )
+        .bind(id.to_string())
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| ShieldError::NotFound(format!("No deleted company {}", id)))?;
+        let slug = slug.unwrap_or_else(|| Self::released_slug(id));
+
+        if self.get_company_by_slug(&slug).await.is_ok() {
+            return Err(ShieldError::BadRequest(format!(
+                "Slug '{}' now belongs to another company",
+                slug
+            )));
+        }
+
+        sqlx::query(&self.sql(
+            r#"
+            UPDATE companies
+            SET slug = ?, deleted_at = NULL, deleted_slug = NULL, updated_at = ?
+            WHERE id = ?
+            "#,
+        ))
+        .bind(&slug)
+        .bind(chrono::Utc::now().to_rfc3339())
+        .bind(id.to_string())
+        .execute(&self.pool)
+        .await?;
+
+        self.get_company(id).await
+    }
+
+    /// Permanently delete a company, live or soft-deleted, cascading to
+    /// its members, apps, settings and attack events.
+    pub async fn hard_delete_company(&self, id: Uuid) -> ShieldResult<()> {
+        let result = sqlx::query(&self.sql("DELETE FROM companies WHERE id = ?"))
             .bind(id.to_string())
             .execute(&self.pool)
             .await?;
@@ -615,14 +1899,14 @@ impl ShieldRepository {
 
     /// List companies for a user.
     pub async fn list_user_companies(&self, user_id: &str) -> ShieldResult<Vec<Company>> {
-        let rows: Vec<CompanyRow> = sqlx::query_as(
+        let rows: Vec<CompanyRow> = sqlx::query_as(&self.sql(
             r#"
             SELECT c.* FROM companies c
             JOIN company_members m ON c.id = m.company_id
-            WHERE m.user_id = ?
+            WHERE m.user_id = ? AND c.deleted_at IS NULL
             ORDER BY c.name ASC
             "#,
-        )
+        ))
         .bind(user_id)
         .fetch_all(&self.pool)
         .await?;
@@ -634,12 +1918,12 @@ impl ShieldRepository {
 
     /// Add a member to a company.
     pub async fn add_company_member(&self, member: &CompanyMember) -> ShieldResult<()> {
-        sqlx::query(
+        sqlx::query(&self.sql(
             r#"
             INSERT INTO company_members (id, company_id, user_id, email, role, created_at)
             VALUES (?, ?, ?, ?, ?, ?)
             "#,
-        )
+        ))
         .bind(member.id.to_string())
         .bind(member.company_id.to_string())
         .bind(&member.user_id)
@@ -652,10 +1936,59 @@ impl ShieldRepository {
         Ok(())
     }
 
+    /// Add a member to a company unless, as an owner or admin, they would
+    /// take it past `max_admins` owners and admins. The count and insert
+    /// are one statement, so concurrent additions can't both slip under
+    /// the limit. Returns whether the member was added.
+    pub async fn add_company_member_within_admin_limit(
+        &self,
+        member: &CompanyMember,
+        max_admins: Option<usize>,
+    ) -> ShieldResult<bool> {
+        let Some(max_admins) = max_admins.filter(|_| member.role.is_owner_or_admin()) else {
+            self.add_company_member(member).await?;
+            return Ok(true);
+        };
+
+        let result = sqlx::query(&self.sql(&format!(
+            r#"
+            INSERT INTO company_members (id, company_id, user_id, email, role, created_at)
+            SELECT ?, ?, ?, ?, ?, ?
+            WHERE (
+                SELECT COUNT(*) FROM company_members WHERE company_id = ? AND {admin}
+            ) < ?
+            "#,
+            admin = ADMIN_ROLES_FILTER,
+        )))
+        .bind(member.id.to_string())
+        .bind(member.company_id.to_string())
+        .bind(&member.user_id)
+        .bind(&member.email)
+        .bind(member.role.to_string())
+        .bind(member.created_at.to_rfc3339())
+        .bind(member.company_id.to_string())
+        .bind(max_admins as i64)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Count members of a company.
+    pub async fn count_company_members(&self, company_id: Uuid) -> ShieldResult<usize> {
+        let (count,): (i64,) =
+            sqlx::query_as(&self.sql("SELECT COUNT(*) FROM company_members WHERE company_id = ?"))
+                .bind(company_id.to_string())
+                .fetch_one(&self.pool)
+                .await?;
+
+        Ok(count as usize)
+    }
+
     /// Get company members.
     pub async fn list_company_members(&self, company_id: Uuid) -> ShieldResult<Vec<CompanyMember>> {
         let rows: Vec<CompanyMemberRow> = sqlx::query_as(
-            "SELECT * FROM company_members WHERE company_id = ? ORDER BY created_at ASC",
+            &self.sql("SELECT * FROM company_members WHERE company_id = ? ORDER BY created_at ASC"),
         )
         .bind(company_id.to_string())
         .fetch_all(&self.pool)
@@ -664,53 +1997,147 @@ impl ShieldRepository {
         rows.into_iter().map(|r| r.try_into()).collect()
     }
 
+    /// List one page of a company's members, oldest first, optionally only
+    /// those with `role`. Also returns the total matching.
+    pub async fn list_company_members_page(
+        &self,
+        company_id: Uuid,
+        role: Option<CompanyRole>,
+        limit: i64,
+        offset: i64,
+    ) -> ShieldResult<(Vec<CompanyMember>, i64)> {
+        let mut conditions = vec!["company_id = ?".to_string()];
+        let mut binds = vec![company_id.to_string()];
+
+        if let Some(role) = role {
+            conditions.push("role = ?".to_string());
+            binds.push(role.to_string());
+        }
+
+        let where_clause = conditions.join(" AND ");
+
+        let query = self.sql(&format!(
+            "SELECT * FROM company_members WHERE {} ORDER BY created_at ASC, id ASC LIMIT ? OFFSET ?",
+            where_clause
+        ));
+        let count_query = self.sql(&format!(
+            "SELECT COUNT(*) FROM company_members WHERE {}",
+            where_clause
+        ));
+
+        let mut query_builder = sqlx::query_as::<_, CompanyMemberRow>(&query);
+        let mut count_builder = sqlx::query_as::<_, (i64,)>(&count_query);
+        for bind in &binds {
+            query_builder = query_builder.bind(bind.clone());
+            count_builder = count_builder.bind(bind.clone());
+        }
+
+        let rows = query_builder
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(&self.pool)
+            .await?;
+        let (total,) = count_builder.fetch_one(&self.pool).await?;
+
+        let members = rows
+            .into_iter()
+            .map(|r| r.try_into())
+            .collect::<ShieldResult<Vec<_>>>()?;
+
+        Ok((members, total))
+    }
+
     /// Get a user's membership in a company.
     pub async fn get_company_member(
         &self,
         company_id: Uuid,
         user_id: &str,
     ) -> ShieldResult<CompanyMember> {
-        let row: CompanyMemberRow =
-            sqlx::query_as("SELECT * FROM company_members WHERE company_id = ? AND user_id = ?")
-                .bind(company_id.to_string())
-                .bind(user_id)
-                .fetch_optional(&self.pool)
-                .await?
-                .ok_or_else(|| ShieldError::NotFound("Member not found".to_string()))?;
+        // Members of a soft-deleted company lose access along with it
+        let row: CompanyMemberRow = sqlx::query_as(&self.sql(
+            r#"
+            SELECT m.* FROM company_members m
+            JOIN companies c ON c.id = m.company_id
+            WHERE m.company_id = ? AND m.user_id = ? AND c.deleted_at IS NULL
+            "#,
+        ))
+        .bind(company_id.to_string())
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| ShieldError::NotFound("Member not found".to_string()))?;
 
         row.try_into()
     }
 
-    /// Update a member's role.
+    /// Update a member's role, appending `audit` to the audit trail in the
+    /// same transaction.
+    ///
+    /// Promoting someone to owner or admin is refused, returning `false`,
+    /// when the company already has `max_admins` owners and admins; the
+    /// count is part of the update so concurrent promotions can't both
+    /// pass it.
     pub async fn update_member_role(
         &self,
         company_id: Uuid,
         user_id: &str,
         role: CompanyRole,
-    ) -> ShieldResult<()> {
-        let result =
-            sqlx::query("UPDATE company_members SET role = ? WHERE company_id = ? AND user_id = ?")
-                .bind(role.to_string())
-                .bind(company_id.to_string())
-                .bind(user_id)
-                .execute(&self.pool)
-                .await?;
+        max_admins: Option<usize>,
+        audit: &AuditEntry,
+    ) -> ShieldResult<bool> {
+        let max_admins = max_admins.filter(|_| role.is_owner_or_admin());
+        let limit = if max_admins.is_some() {
+            format!(
+                " AND ({admin} OR (SELECT COUNT(*) FROM company_members \
+                 WHERE company_id = ? AND {admin}) < ?)",
+                admin = ADMIN_ROLES_FILTER,
+            )
+        } else {
+            String::new()
+        };
+
+        let mut tx = self.pool.begin().await?;
+        let query = self.sql(&format!(
+            "UPDATE company_members SET role = ? WHERE company_id = ? AND user_id = ?{}",
+            limit
+        ));
+        let mut update = sqlx::query(&query)
+            .bind(role.to_string())
+            .bind(company_id.to_string())
+            .bind(user_id);
+        if let Some(max_admins) = max_admins {
+            update = update.bind(company_id.to_string()).bind(max_admins as i64);
+        }
+        let result = update.execute(&mut *tx).await?;
 
         if result.rows_affected() == 0 {
-            return Err(ShieldError::NotFound("Member not found".to_string()));
+            let exists: Option<(String,)> = sqlx::query_as(
+                &self.sql("SELECT id FROM company_members WHERE company_id = ? AND user_id = ?"),
+            )
+            .bind(company_id.to_string())
+            .bind(user_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+            if exists.is_none() {
+                return Err(ShieldError::NotFound("Member not found".to_string()));
+            }
+            return Ok(false);
         }
+        self.insert_audit_entry(&mut tx, audit).await?;
+        tx.commit().await?;
 
-        Ok(())
+        Ok(true)
     }
 
     /// Remove a member from a company.
     pub async fn remove_company_member(&self, company_id: Uuid, user_id: &str) -> ShieldResult<()> {
-        let result =
-            sqlx::query("DELETE FROM company_members WHERE company_id = ? AND user_id = ?")
-                .bind(company_id.to_string())
-                .bind(user_id)
-                .execute(&self.pool)
-                .await?;
+        let result = sqlx::query(
+            &self.sql("DELETE FROM company_members WHERE company_id = ? AND user_id = ?"),
+        )
+        .bind(company_id.to_string())
+        .bind(user_id)
+        .execute(&self.pool)
+        .await?;
 
         if result.rows_affected() == 0 {
             return Err(ShieldError::NotFound("Member not found".to_string()));
@@ -723,14 +2150,14 @@ impl ShieldRepository {
 
     /// Create a new app.
     pub async fn create_app(&self, app: &App, api_key_hash: &str) -> ShieldResult<()> {
-        sqlx::query(
+        sqlx::query(&self.sql(
             r#"
             INSERT INTO apps (
                 id, company_id, name, description, api_key_hash, api_key_prefix,
-                status, rate_limit, created_at, updated_at, last_used_at
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                status, rate_limit, created_at, updated_at, last_used_at, trusted, scopes
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
-        )
+        ))
         .bind(app.id.to_string())
         .bind(app.company_id.to_string())
         .bind(&app.name)
@@ -742,99 +2169,365 @@ impl ShieldRepository {
         .bind(app.created_at.to_rfc3339())
         .bind(app.updated_at.to_rfc3339())
         .bind(app.last_used_at.map(|dt| dt.to_rfc3339()))
+        .bind(app.trusted as i32)
+        .bind(serde_json::to_string(&app.scopes)?)
         .execute(&self.pool)
         .await?;
 
         Ok(())
     }
 
-    /// Get an app by ID.
-    pub async fn get_app(&self, id: Uuid) -> ShieldResult<App> {
-        let row: AppRow = sqlx::query_as("SELECT * FROM apps WHERE id = ?")
-            .bind(id.to_string())
-            .fetch_optional(&self.pool)
-            .await?
-            .ok_or_else(|| ShieldError::NotFound(format!("App {} not found", id)))?;
+    /// Get an app by ID, scoped to its company.
+    ///
+    /// Apps in other companies are reported as not found.
+    pub async fn get_app(&self, company_id: Uuid, id: Uuid) -> ShieldResult<App> {
+        let row: AppRow =
+            sqlx::query_as(&self.sql("SELECT * FROM apps WHERE id = ? AND company_id = ?"))
+                .bind(id.to_string())
+                .bind(company_id.to_string())
+                .fetch_optional(&self.pool)
+                .await?
+                .ok_or_else(|| ShieldError::NotFound(format!("App {} not found", id)))?;
 
         row.try_into()
     }
 
     /// Get an app by API key hash.
     pub async fn get_app_by_api_key_hash(&self, api_key_hash: &str) -> ShieldResult<App> {
-        let row: AppRow = sqlx::query_as("SELECT * FROM apps WHERE api_key_hash = ?")
-            .bind(api_key_hash)
-            .fetch_optional(&self.pool)
-            .await?
-            .ok_or_else(|| ShieldError::NotFound("Invalid API key".to_string()))?;
+        let row: AppRow = sqlx::query_as(&self.sql(
+            r#"
+            SELECT a.* FROM apps a
+            JOIN companies c ON c.id = a.company_id
+            WHERE a.api_key_hash = ? AND c.deleted_at IS NULL
+            "#,
+        ))
+        .bind(api_key_hash)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| ShieldError::NotFound("Invalid API key".to_string()))?;
 
         row.try_into()
     }
 
     /// List apps for a company.
     pub async fn list_company_apps(&self, company_id: Uuid) -> ShieldResult<Vec<App>> {
-        let rows: Vec<AppRow> =
-            sqlx::query_as("SELECT * FROM apps WHERE company_id = ? ORDER BY created_at DESC")
+        let rows: Vec<AppRow> = sqlx::query_as(
+            &self.sql("SELECT * FROM apps WHERE company_id = ? ORDER BY created_at DESC"),
+        )
+        .bind(company_id.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(|r| r.try_into()).collect()
+    }
+
+    /// Count apps belonging to a company.
+    pub async fn count_company_apps(&self, company_id: Uuid) -> ShieldResult<usize> {
+        let (count,): (i64,) =
+            sqlx::query_as(&self.sql("SELECT COUNT(*) FROM apps WHERE company_id = ?"))
                 .bind(company_id.to_string())
-                .fetch_all(&self.pool)
+                .fetch_one(&self.pool)
                 .await?;
 
-        rows.into_iter().map(|r| r.try_into()).collect()
+        Ok(count as usize)
     }
 
-    /// Update an app.
-    pub async fn update_app(
-        &self,
-        id: Uuid,
-        name: Option<&str>,
-        description: Option<&str>,
-        status: Option<AppStatus>,
-        rate_limit: Option<u32>,
-    ) -> ShieldResult<App> {
-        let updated_at = chrono::Utc::now().to_rfc3339();
+    /// Count a company's active apps.
+    pub async fn count_active_company_apps(&self, company_id: Uuid) -> ShieldResult<usize> {
+        let (count,): (i64,) = sqlx::query_as(
+            &self.sql("SELECT COUNT(*) FROM apps WHERE company_id = ? AND status = 'active'"),
+        )
+        .bind(company_id.to_string())
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count as usize)
+    }
+
+    /// Count a company's HITL tasks still awaiting review.
+    pub async fn count_pending_company_hitl_tasks(&self, company_id: Uuid) -> ShieldResult<usize> {
+        let (count,): (i64,) = sqlx::query_as(&self.sql(
+            r#"
+            SELECT COUNT(*)
+            FROM hitl_tasks t
+            JOIN agent_actions a ON t.agent_action_id = a.id
+            WHERE a.company_id = ? AND t.status = 'pending'
+            "#,
+        ))
+        .bind(company_id.to_string())
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count as usize)
+    }
+
+    /// Count first approvals awaiting confirmation among the tasks of the
+    /// company that `agent_action_id` belongs to (or of company-less actions).
+    pub async fn count_unconfirmed_overrides(&self, agent_action_id: Uuid) -> ShieldResult<usize> {
+        let (count,): (i64,) = sqlx::query_as(&self.sql(
+            r#"
+            SELECT COUNT(*)
+            FROM hitl_tasks t
+            JOIN agent_actions a ON t.agent_action_id = a.id
+            JOIN agent_actions o ON o.id = ?
+            WHERE t.status = 'pending' AND t.first_approver_id IS NOT NULL
+              AND (a.company_id = o.company_id
+                   OR (a.company_id IS NULL AND o.company_id IS NULL))
+            "#,
+        ))
+        .bind(agent_action_id.to_string())
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count as usize)
+    }
+
+    /// Update an app, scoped to its company.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn update_app(
+        &self,
+        company_id: Uuid,
+        id: Uuid,
+        name: Option<&str>,
+        description: Option<&str>,
+        status: Option<AppStatus>,
+        rate_limit: Option<u32>,
+        trusted: Option<bool>,
+    ) -> ShieldResult<App> {
+        // Fail before touching anything if the app isn't in this company
+        self.get_app(company_id, id).await?;
+        let updated_at = chrono::Utc::now().to_rfc3339();
 
         if let Some(name) = name {
-            sqlx::query("UPDATE apps SET name = ?, updated_at = ? WHERE id = ?")
-                .bind(name)
-                .bind(&updated_at)
-                .bind(id.to_string())
-                .execute(&self.pool)
-                .await?;
+            sqlx::query(
+                &self.sql(
+                    "UPDATE apps SET name = ?, updated_at = ? WHERE id = ? AND company_id = ?",
+                ),
+            )
+            .bind(name)
+            .bind(&updated_at)
+            .bind(id.to_string())
+            .bind(company_id.to_string())
+            .execute(&self.pool)
+            .await?;
         }
 
         if let Some(desc) = description {
-            sqlx::query("UPDATE apps SET description = ?, updated_at = ? WHERE id = ?")
-                .bind(desc)
-                .bind(&updated_at)
-                .bind(id.to_string())
-                .execute(&self.pool)
-                .await?;
+            sqlx::query(&self.sql(
+                "UPDATE apps SET description = ?, updated_at = ? WHERE id = ? AND company_id = ?",
+            ))
+            .bind(desc)
+            .bind(&updated_at)
+            .bind(id.to_string())
+            .bind(company_id.to_string())
+            .execute(&self.pool)
+            .await?;
         }
 
         if let Some(status) = status {
-            sqlx::query("UPDATE apps SET status = ?, updated_at = ? WHERE id = ?")
-                .bind(status.to_string())
-                .bind(&updated_at)
-                .bind(id.to_string())
-                .execute(&self.pool)
-                .await?;
+            sqlx::query(
+                &self.sql(
+                    "UPDATE apps SET status = ?, updated_at = ? WHERE id = ? AND company_id = ?",
+                ),
+            )
+            .bind(status.to_string())
+            .bind(&updated_at)
+            .bind(id.to_string())
+            .bind(company_id.to_string())
+            .execute(&self.pool)
+            .await?;
         }
 
         if let Some(rate_limit) = rate_limit {
-            sqlx::query("UPDATE apps SET rate_limit = ?, updated_at = ? WHERE id = ?")
-                .bind(rate_limit as i64)
-                .bind(&updated_at)
-                .bind(id.to_string())
-                .execute(&self.pool)
-                .await?;
+            sqlx::query(&self.sql(
+                "UPDATE apps SET rate_limit = ?, updated_at = ? WHERE id = ? AND company_id = ?",
+            ))
+            .bind(rate_limit as i64)
+            .bind(&updated_at)
+            .bind(id.to_string())
+            .bind(company_id.to_string())
+            .execute(&self.pool)
+            .await?;
+        }
+
+        if let Some(trusted) = trusted {
+            sqlx::query(&self.sql(
+                "UPDATE apps SET trusted = ?, updated_at = ? WHERE id = ? AND company_id = ?",
+            ))
+            .bind(trusted as i32)
+            .bind(&updated_at)
+            .bind(id.to_string())
+            .bind(company_id.to_string())
+            .execute(&self.pool)
+            .await?;
+        }
+
+        self.get_app(company_id, id).await
+    }
+
+    /// Assign an app to a policy profile, or back to company thresholds with `None`.
+    pub async fn set_app_policy_profile(
+        &self,
+        company_id: Uuid,
+        app_id: Uuid,
+        profile_id: Option<Uuid>,
+    ) -> ShieldResult<App> {
+        let result = sqlx::query(&self.sql(
+            "UPDATE apps SET policy_profile_id = ?, updated_at = ? WHERE id = ? AND company_id = ?",
+        ))
+        .bind(profile_id.map(|id| id.to_string()))
+        .bind(chrono::Utc::now().to_rfc3339())
+        .bind(app_id.to_string())
+        .bind(company_id.to_string())
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(ShieldError::NotFound(format!("App {} not found", app_id)));
+        }
+
+        self.get_app(company_id, app_id).await
+    }
+
+    /// Mark an app as a sandbox (non-production) app or clear the flag.
+    pub async fn set_app_sandbox(
+        &self,
+        company_id: Uuid,
+        app_id: Uuid,
+        sandbox: bool,
+    ) -> ShieldResult<App> {
+        let result = sqlx::query(
+            &self
+                .sql("UPDATE apps SET sandbox = ?, updated_at = ? WHERE id = ? AND company_id = ?"),
+        )
+        .bind(sandbox as i32)
+        .bind(chrono::Utc::now().to_rfc3339())
+        .bind(app_id.to_string())
+        .bind(company_id.to_string())
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(ShieldError::NotFound(format!("App {} not found", app_id)));
+        }
+
+        self.get_app(company_id, app_id).await
+    }
+
+    /// Point an app's notifications at its own webhook, or back at the
+    /// company's with `None`.
+    pub async fn set_app_webhook_url(
+        &self,
+        company_id: Uuid,
+        app_id: Uuid,
+        webhook_url: Option<&str>,
+    ) -> ShieldResult<App> {
+        let result = sqlx::query(&self.sql(
+            "UPDATE apps SET webhook_url = ?, updated_at = ? WHERE id = ? AND company_id = ?",
+        ))
+        .bind(webhook_url)
+        .bind(chrono::Utc::now().to_rfc3339())
+        .bind(app_id.to_string())
+        .bind(company_id.to_string())
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(ShieldError::NotFound(format!("App {} not found", app_id)));
+        }
+
+        self.get_app(company_id, app_id).await
+    }
+
+    /// Replace the key an app's webhook payloads are signed with. The old
+    /// key keeps signing alongside it until `previous_expires_at`. `audit`
+    /// is appended in the same transaction.
+    pub async fn rotate_app_webhook_secret(
+        &self,
+        company_id: Uuid,
+        app_id: Uuid,
+        secret: &str,
+        previous_expires_at: DateTime<Utc>,
+        audit: &AuditEntry,
+    ) -> ShieldResult<App> {
+        let mut tx = self.pool.begin().await?;
+        let result = sqlx::query(&self.sql(
+            r#"
+            UPDATE apps
+            SET webhook_previous_secret = webhook_secret,
+                webhook_previous_secret_expires_at = ?,
+                webhook_secret = ?,
+                updated_at = ?
+            WHERE id = ? AND company_id = ?
+            "#,
+        ))
+        .bind(previous_expires_at.to_rfc3339())
+        .bind(secret)
+        .bind(chrono::Utc::now().to_rfc3339())
+        .bind(app_id.to_string())
+        .bind(company_id.to_string())
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(ShieldError::NotFound(format!("App {} not found", app_id)));
+        }
+        self.insert_audit_entry(&mut tx, audit).await?;
+        tx.commit().await?;
+
+        self.get_app(company_id, app_id).await
+    }
+
+    /// Set what an app's API key may do.
+    pub async fn set_app_scopes(
+        &self,
+        company_id: Uuid,
+        app_id: Uuid,
+        scopes: &[ApiScope],
+    ) -> ShieldResult<App> {
+        let result = sqlx::query(
+            &self.sql("UPDATE apps SET scopes = ?, updated_at = ? WHERE id = ? AND company_id = ?"),
+        )
+        .bind(serde_json::to_string(scopes)?)
+        .bind(chrono::Utc::now().to_rfc3339())
+        .bind(app_id.to_string())
+        .bind(company_id.to_string())
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(ShieldError::NotFound(format!("App {} not found", app_id)));
+        }
+
+        self.get_app(company_id, app_id).await
+    }
+
+    /// Set the other companies an app may evaluate for.
+    pub async fn set_app_permitted_companies(
+        &self,
+        company_id: Uuid,
+        app_id: Uuid,
+        permitted_company_ids: &[Uuid],
+    ) -> ShieldResult<App> {
+        let result = sqlx::query(&self.sql("UPDATE apps SET permitted_company_ids = ?, updated_at = ? WHERE id = ? AND company_id = ?"))
+        .bind(serde_json::to_string(permitted_company_ids)?)
+        .bind(chrono::Utc::now().to_rfc3339())
+        .bind(app_id.to_string())
+        .bind(company_id.to_string())
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(ShieldError::NotFound(format!("App {} not found", app_id)));
         }
 
-        self.get_app(id).await
+        self.get_app(company_id, app_id).await
     }
 
     /// Update app's last used timestamp.
     pub async fn update_app_last_used(&self, id: Uuid) -> ShieldResult<()> {
         let now = chrono::Utc::now().to_rfc3339();
-        sqlx::query("UPDATE apps SET last_used_at = ? WHERE id = ?")
+        sqlx::query(&self.sql("UPDATE apps SET last_used_at = ? WHERE id = ?"))
             .bind(&now)
             .bind(id.to_string())
             .execute(&self.pool)
@@ -843,10 +2536,11 @@ impl ShieldRepository {
         Ok(())
     }
 
-    /// Delete an app.
-    pub async fn delete_app(&self, id: Uuid) -> ShieldResult<()> {
-        let result = sqlx::query("DELETE FROM apps WHERE id = ?")
+    /// Delete an app, scoped to its company.
+    pub async fn delete_app(&self, company_id: Uuid, id: Uuid) -> ShieldResult<()> {
+        let result = sqlx::query(&self.sql("DELETE FROM apps WHERE id = ? AND company_id = ?"))
             .bind(id.to_string())
+            .bind(company_id.to_string())
             .execute(&self.pool)
             .await?;
 
@@ -857,6 +2551,75 @@ impl ShieldRepository {
         Ok(())
     }
 
+    /// Set the status of several apps in a company within one transaction.
+    ///
+    /// When `app_ids` is `None`, every app in the company is updated.
+    /// Returns each requested app ID paired with its previous status, or
+    /// `None` if the app does not exist in this company.
+    pub async fn bulk_update_app_status(
+        &self,
+        company_id: Uuid,
+        app_ids: Option<&[Uuid]>,
+        status: AppStatus,
+    ) -> ShieldResult<Vec<(Uuid, Option<AppStatus>)>> {
+        let updated_at = chrono::Utc::now().to_rfc3339();
+        let mut tx = self.pool.begin().await?;
+
+        let targets: Vec<(Uuid, Option<AppStatus>)> = match app_ids {
+            Some(ids) => {
+                let mut targets = Vec::with_capacity(ids.len());
+                for id in ids {
+                    let current: Option<(String,)> = sqlx::query_as(
+                        &self.sql("SELECT status FROM apps WHERE id = ? AND company_id = ?"),
+                    )
+                    .bind(id.to_string())
+                    .bind(company_id.to_string())
+                    .fetch_optional(&mut *tx)
+                    .await?;
+                    let previous = current
+                        .map(|(s,)| s.parse::<AppStatus>().map_err(ShieldError::Internal))
+                        .transpose()?;
+                    targets.push((*id, previous));
+                }
+                targets
+            }
+            None => {
+                let rows: Vec<(String, String)> = sqlx::query_as(&self.sql(
+                    "SELECT id, status FROM apps WHERE company_id = ? ORDER BY created_at DESC",
+                ))
+                .bind(company_id.to_string())
+                .fetch_all(&mut *tx)
+                .await?;
+                rows.into_iter()
+                    .map(|(id, s)| {
+                        let id = Uuid::parse_str(&id)
+                            .map_err(|e| ShieldError::Internal(e.to_string()))?;
+                        let previous = s.parse::<AppStatus>().map_err(ShieldError::Internal)?;
+                        Ok((id, Some(previous)))
+                    })
+                    .collect::<ShieldResult<Vec<_>>>()?
+            }
+        };
+
+        for (id, previous) in &targets {
+            if previous.is_some_and(|p| p != status) {
+                sqlx::query(&self.sql(
+                    "UPDATE apps SET status = ?, updated_at = ? WHERE id = ? AND company_id = ?",
+                ))
+                .bind(status.to_string())
+                .bind(&updated_at)
+                .bind(id.to_string())
+                .bind(company_id.to_string())
+                .execute(&mut *tx)
+                .await?;
+            }
+        }
+
+        tx.commit().await?;
+
+        Ok(targets)
+    }
+
     // ==================== Metrics ====================
 
     /// Get metrics overview for a company.
@@ -872,34 +2635,34 @@ impl ShieldRepository {
 
         // Current period counts
         let (total, blocked, escalated): (i64, i64, i64) = if let Some(app_id) = app_id {
-            sqlx::query_as(
+            sqlx::query_as(&self.sql(
                 r#"
                 SELECT
                     COUNT(*) as total,
-                    SUM(CASE WHEN e.decision = 'block' THEN 1 ELSE 0 END) as blocked,
-                    SUM(CASE WHEN e.decision = 'require_hitl' THEN 1 ELSE 0 END) as escalated
+                    COALESCE(SUM(CASE WHEN e.decision = 'block' THEN 1 ELSE 0 END), 0) as blocked,
+                    COALESCE(SUM(CASE WHEN e.decision = 'require_hitl' THEN 1 ELSE 0 END), 0) as escalated
                 FROM agent_actions a
                 JOIN evaluations e ON a.id = e.agent_action_id
-                WHERE a.company_id = ? AND a.app_id = ? AND a.created_at >= ?
+                WHERE a.company_id = ? AND a.sandbox = 0 AND a.app_id = ? AND a.created_at >= ?
                 "#,
-            )
+            ))
             .bind(company_id.to_string())
             .bind(app_id.to_string())
             .bind(&start_time)
             .fetch_one(&self.pool)
             .await?
         } else {
-            sqlx::query_as(
+            sqlx::query_as(&self.sql(
                 r#"
                 SELECT
                     COUNT(*) as total,
-                    SUM(CASE WHEN e.decision = 'block' THEN 1 ELSE 0 END) as blocked,
-                    SUM(CASE WHEN e.decision = 'require_hitl' THEN 1 ELSE 0 END) as escalated
+                    COALESCE(SUM(CASE WHEN e.decision = 'block' THEN 1 ELSE 0 END), 0) as blocked,
+                    COALESCE(SUM(CASE WHEN e.decision = 'require_hitl' THEN 1 ELSE 0 END), 0) as escalated
                 FROM agent_actions a
                 JOIN evaluations e ON a.id = e.agent_action_id
-                WHERE a.company_id = ? AND a.created_at >= ?
+                WHERE a.company_id = ? AND a.sandbox = 0 AND a.created_at >= ?
                 "#,
-            )
+            ))
             .bind(company_id.to_string())
             .bind(&start_time)
             .fetch_one(&self.pool)
@@ -908,15 +2671,15 @@ impl ShieldRepository {
 
         // Attack counts
         let attack_stats: (i64, i64) = if let Some(app_id) = app_id {
-            sqlx::query_as(
+            sqlx::query_as(&self.sql(
                 r#"
                 SELECT
                     COUNT(*) as total,
-                    SUM(CASE WHEN outcome = 'allowed' THEN 1 ELSE 0 END) as successful
+                    COALESCE(SUM(CASE WHEN outcome = 'allowed' THEN 1 ELSE 0 END), 0) as successful
                 FROM attack_events
                 WHERE company_id = ? AND app_id = ? AND created_at >= ?
                 "#,
-            )
+            ))
             .bind(company_id.to_string())
             .bind(app_id.to_string())
             .bind(&start_time)
@@ -924,15 +2687,15 @@ impl ShieldRepository {
             .await
             .unwrap_or((0, 0))
         } else {
-            sqlx::query_as(
+            sqlx::query_as(&self.sql(
                 r#"
                 SELECT
                     COUNT(*) as total,
-                    SUM(CASE WHEN outcome = 'allowed' THEN 1 ELSE 0 END) as successful
+                    COALESCE(SUM(CASE WHEN outcome = 'allowed' THEN 1 ELSE 0 END), 0) as successful
                 FROM attack_events
                 WHERE company_id = ? AND created_at >= ?
                 "#,
-            )
+            ))
             .bind(company_id.to_string())
             .bind(&start_time)
             .fetch_one(&self.pool)
@@ -942,26 +2705,26 @@ impl ShieldRepository {
 
         // Unique users impacted
         let (users_impacted,): (i64,) = if let Some(app_id) = app_id {
-            sqlx::query_as(
+            sqlx::query_as(&self.sql(
                 r#"
                 SELECT COUNT(DISTINCT user_id)
                 FROM agent_actions
-                WHERE company_id = ? AND app_id = ? AND created_at >= ?
+                WHERE company_id = ? AND sandbox = 0 AND app_id = ? AND created_at >= ?
                 "#,
-            )
+            ))
             .bind(company_id.to_string())
             .bind(app_id.to_string())
             .bind(&start_time)
             .fetch_one(&self.pool)
             .await?
         } else {
-            sqlx::query_as(
+            sqlx::query_as(&self.sql(
                 r#"
                 SELECT COUNT(DISTINCT user_id)
                 FROM agent_actions
-                WHERE company_id = ? AND created_at >= ?
+                WHERE company_id = ? AND sandbox = 0 AND created_at >= ?
                 "#,
-            )
+            ))
             .bind(company_id.to_string())
             .bind(&start_time)
             .fetch_one(&self.pool)
@@ -969,31 +2732,32 @@ impl ShieldRepository {
         };
 
         // Previous period for trends
-        let (prev_total, prev_blocked, prev_escalated): (i64, i64, i64) = sqlx::query_as(
-            r#"
+        let (prev_total, prev_blocked, prev_escalated): (i64, i64, i64) =
+            sqlx::query_as(&self.sql(
+                r#"
             SELECT
                 COUNT(*) as total,
-                SUM(CASE WHEN e.decision = 'block' THEN 1 ELSE 0 END) as blocked,
-                SUM(CASE WHEN e.decision = 'require_hitl' THEN 1 ELSE 0 END) as escalated
+                COALESCE(SUM(CASE WHEN e.decision = 'block' THEN 1 ELSE 0 END), 0) as blocked,
+                COALESCE(SUM(CASE WHEN e.decision = 'require_hitl' THEN 1 ELSE 0 END), 0) as escalated
             FROM agent_actions a
             JOIN evaluations e ON a.id = e.agent_action_id
-            WHERE a.company_id = ? AND a.created_at >= ? AND a.created_at < ?
+            WHERE a.company_id = ? AND a.sandbox = 0 AND a.created_at >= ? AND a.created_at < ?
             "#,
-        )
-        .bind(company_id.to_string())
-        .bind(&prev_start)
-        .bind(&start_time)
-        .fetch_one(&self.pool)
-        .await
-        .unwrap_or((0, 0, 0));
+            ))
+            .bind(company_id.to_string())
+            .bind(&prev_start)
+            .bind(&start_time)
+            .fetch_one(&self.pool)
+            .await
+            .unwrap_or((0, 0, 0));
 
-        let (prev_attacks,): (i64,) = sqlx::query_as(
+        let (prev_attacks,): (i64,) = sqlx::query_as(&self.sql(
             r#"
             SELECT COUNT(*)
             FROM attack_events
             WHERE company_id = ? AND created_at >= ? AND created_at < ?
             "#,
-        )
+        ))
         .bind(company_id.to_string())
         .bind(&prev_start)
         .bind(&start_time)
@@ -1001,18 +2765,6 @@ impl ShieldRepository {
         .await
         .unwrap_or((0,));
 
-        fn calc_trend(current: i64, previous: i64) -> f64 {
-            if previous == 0 {
-                if current > 0 {
-                    100.0
-                } else {
-                    0.0
-                }
-            } else {
-                ((current - previous) as f64 / previous as f64) * 100.0
-            }
-        }
-
         let attack_success_rate = if attack_stats.0 > 0 {
             (attack_stats.1 as f64 / attack_stats.0 as f64) * 100.0
         } else {
@@ -1027,80 +2779,161 @@ impl ShieldRepository {
             attack_success_rate,
             users_impacted,
             trends: Trends {
-                total_actions: calc_trend(total, prev_total),
-                blocked_actions: calc_trend(blocked, prev_blocked),
-                escalated_actions: calc_trend(escalated, prev_escalated),
-                attack_attempts: calc_trend(attack_stats.0, prev_attacks),
+                total_actions: percent_change(total, prev_total),
+                blocked_actions: percent_change(blocked, prev_blocked),
+                escalated_actions: percent_change(escalated, prev_escalated),
+                attack_attempts: percent_change(attack_stats.0, prev_attacks),
             },
         })
     }
 
+    /// Get evaluation statistics for a bounded period `[start, end)`.
+    pub async fn get_period_stats(
+        &self,
+        company_id: Uuid,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        app_id: Option<Uuid>,
+    ) -> ShieldResult<PeriodStats> {
+        let mut conditions = vec!["company_id = ?", "created_at >= ?", "created_at < ?"];
+        if app_id.is_some() {
+            conditions.push("app_id = ?");
+        }
+        let where_clause = conditions.join(" AND ");
+
+        let action_sql = self.sql(&format!(
+            r#"
+            SELECT
+                COUNT(*) as total,
+                COALESCE(SUM(CASE WHEN e.decision = 'allow' THEN 1 ELSE 0 END), 0) as allowed,
+                COALESCE(SUM(CASE WHEN e.decision = 'block' THEN 1 ELSE 0 END), 0) as blocked,
+                COALESCE(SUM(CASE WHEN e.decision = 'require_hitl' THEN 1 ELSE 0 END), 0) as escalated,
+                COUNT(DISTINCT a.user_id) as users
+            FROM (SELECT * FROM agent_actions WHERE {} AND sandbox = 0) a
+            JOIN evaluations e ON a.id = e.agent_action_id
+            "#,
+            where_clause
+        ));
+        let mut action_query = sqlx::query_as::<_, (i64, i64, i64, i64, i64)>(&action_sql)
+            .bind(company_id.to_string())
+            .bind(start.to_rfc3339())
+            .bind(end.to_rfc3339());
+        if let Some(app_id) = app_id {
+            action_query = action_query.bind(app_id.to_string());
+        }
+        let (total, allowed, blocked, escalated, users_impacted) =
+            action_query.fetch_one(&self.pool).await?;
+
+        let attack_sql = self.sql(&format!(
+            "SELECT COUNT(*) FROM attack_events WHERE {}",
+            where_clause
+        ));
+        let mut attack_query = sqlx::query_as::<_, (i64,)>(&attack_sql)
+            .bind(company_id.to_string())
+            .bind(start.to_rfc3339())
+            .bind(end.to_rfc3339());
+        if let Some(app_id) = app_id {
+            attack_query = attack_query.bind(app_id.to_string());
+        }
+        let (attack_attempts,) = attack_query.fetch_one(&self.pool).await?;
+
+        Ok(PeriodStats {
+            start,
+            end,
+            total_actions: total,
+            allowed_actions: allowed,
+            blocked_actions: blocked,
+            escalated_actions: escalated,
+            attack_attempts,
+            users_impacted,
+        })
+    }
+
     /// Get time series data for a company.
     pub async fn get_time_series(
         &self,
         company_id: Uuid,
         time_range: TimeRange,
-        _granularity: Granularity,
+        granularity: Granularity,
         app_id: Option<Uuid>,
     ) -> ShieldResult<TimeSeriesData> {
-        let start_time = time_range.start_time().to_rfc3339();
+        let now = Utc::now();
+        let start = now - chrono::Duration::hours(time_range.hours());
+        let start_time = start.to_rfc3339();
+        let bucket = match granularity {
+            Granularity::Hour => self.backend.hour("a.created_at"),
+            Granularity::Day => self.backend.date("a.created_at"),
+        };
 
-        // For simplicity, group by date
         let rows: Vec<(String, i64, i64, i64)> = if let Some(app_id) = app_id {
-            sqlx::query_as(
+            sqlx::query_as(&self.sql(&format!(
                 r#"
                 SELECT
-                    DATE(a.created_at) as date,
+                    {bucket} as bucket,
                     SUM(CASE WHEN e.decision = 'allow' THEN 1 ELSE 0 END) as allowed,
                     SUM(CASE WHEN e.decision = 'require_hitl' THEN 1 ELSE 0 END) as hitl,
                     SUM(CASE WHEN e.decision = 'block' THEN 1 ELSE 0 END) as blocked
                 FROM agent_actions a
                 JOIN evaluations e ON a.id = e.agent_action_id
-                WHERE a.company_id = ? AND a.app_id = ? AND a.created_at >= ?
-                GROUP BY DATE(a.created_at)
-                ORDER BY date ASC
-                "#,
-            )
+                WHERE a.company_id = ? AND a.sandbox = 0 AND a.app_id = ? AND a.created_at >= ?
+                GROUP BY {bucket}
+                ORDER BY bucket ASC
+                "#
+            )))
             .bind(company_id.to_string())
             .bind(app_id.to_string())
             .bind(&start_time)
             .fetch_all(&self.pool)
             .await?
         } else {
-            sqlx::query_as(
+            sqlx::query_as(&self.sql(&format!(
                 r#"
                 SELECT
-                    DATE(a.created_at) as date,
+                    {bucket} as bucket,
                     SUM(CASE WHEN e.decision = 'allow' THEN 1 ELSE 0 END) as allowed,
                     SUM(CASE WHEN e.decision = 'require_hitl' THEN 1 ELSE 0 END) as hitl,
                     SUM(CASE WHEN e.decision = 'block' THEN 1 ELSE 0 END) as blocked
                 FROM agent_actions a
                 JOIN evaluations e ON a.id = e.agent_action_id
-                WHERE a.company_id = ? AND a.created_at >= ?
-                GROUP BY DATE(a.created_at)
-                ORDER BY date ASC
-                "#,
-            )
+                WHERE a.company_id = ? AND a.sandbox = 0 AND a.created_at >= ?
+                GROUP BY {bucket}
+                ORDER BY bucket ASC
+                "#
+            )))
             .bind(company_id.to_string())
             .bind(&start_time)
             .fetch_all(&self.pool)
             .await?
         };
 
-        let data = rows
+        let mut counts: std::collections::HashMap<DateTime<Utc>, (i64, i64, i64)> = rows
             .into_iter()
-            .filter_map(|(date, allowed, hitl, blocked)| {
-                DateTime::parse_from_rfc3339(&format!("{}T00:00:00Z", date))
+            .filter_map(|(bucket, allowed, hitl, blocked)| {
+                // Daily buckets come back as a bare date
+                let bucket = match granularity {
+                    Granularity::Hour => bucket,
+                    Granularity::Day => format!("{}T00:00:00Z", bucket),
+                };
+                DateTime::parse_from_rfc3339(&bucket)
                     .ok()
-                    .map(|ts| TimeSeriesPoint {
-                        timestamp: ts.with_timezone(&Utc),
-                        allowed,
-                        hitl,
-                        blocked,
-                    })
+                    .map(|ts| (ts.with_timezone(&Utc), (allowed, hitl, blocked)))
             })
             .collect();
 
+        // Zero-fill every bucket from the start of the range up to now
+        let mut data = Vec::new();
+        let mut timestamp = granularity.bucket_start(start);
+        while timestamp <= now {
+            let (allowed, hitl, blocked) = counts.remove(&timestamp).unwrap_or_default();
+            data.push(TimeSeriesPoint {
+                timestamp,
+                allowed,
+                hitl,
+                blocked,
+            });
+            timestamp += granularity.step();
+        }
+
         Ok(TimeSeriesData { data })
     }
 
@@ -1114,30 +2947,30 @@ impl ShieldRepository {
         let start_time = time_range.start_time().to_rfc3339();
 
         let rows: Vec<(String, i64)> = if let Some(app_id) = app_id {
-            sqlx::query_as(
+            sqlx::query_as(&self.sql(
                 r#"
                 SELECT e.risk_tier, COUNT(*) as count
                 FROM evaluations e
                 JOIN agent_actions a ON e.agent_action_id = a.id
-                WHERE a.company_id = ? AND a.app_id = ? AND a.created_at >= ?
+                WHERE a.company_id = ? AND a.sandbox = 0 AND a.app_id = ? AND a.created_at >= ?
                 GROUP BY e.risk_tier
                 "#,
-            )
+            ))
             .bind(company_id.to_string())
             .bind(app_id.to_string())
             .bind(&start_time)
             .fetch_all(&self.pool)
             .await?
         } else {
-            sqlx::query_as(
+            sqlx::query_as(&self.sql(
                 r#"
                 SELECT e.risk_tier, COUNT(*) as count
                 FROM evaluations e
                 JOIN agent_actions a ON e.agent_action_id = a.id
-                WHERE a.company_id = ? AND a.created_at >= ?
+                WHERE a.company_id = ? AND a.sandbox = 0 AND a.created_at >= ?
                 GROUP BY e.risk_tier
                 "#,
-            )
+            ))
             .bind(company_id.to_string())
             .bind(&start_time)
             .fetch_all(&self.pool)
@@ -1161,49 +2994,262 @@ impl ShieldRepository {
         Ok(RiskDistribution { data })
     }
 
-    // ==================== Actions List ====================
-
-    /// List actions for a company with filtering.
-    #[allow(clippy::too_many_arguments)]
-    pub async fn list_company_actions(
+    /// Get block and escalation rates per action `model_name`.
+    pub async fn get_metrics_by_model(
         &self,
         company_id: Uuid,
+        time_range: TimeRange,
         app_id: Option<Uuid>,
-        decision: Option<DecisionStatus>,
-        risk_tier: Option<RiskTier>,
-        user_id: Option<&str>,
+    ) -> ShieldResult<ModelBreakdown> {
+        let app_filter = if app_id.is_some() {
+            "AND a.app_id = ?"
+        } else {
+            ""
+        };
+        let query = self.sql(&format!(
+            r#"
+            SELECT
+                a.model_name,
+                COUNT(*) as total,
+                SUM(CASE WHEN e.decision = 'block' THEN 1 ELSE 0 END) as blocked,
+                SUM(CASE WHEN e.decision = 'require_hitl' THEN 1 ELSE 0 END) as escalated
+            FROM evaluations e
+            JOIN agent_actions a ON e.agent_action_id = a.id
+            WHERE a.company_id = ? AND a.sandbox = 0 AND a.created_at >= ? {}
+            GROUP BY a.model_name
+            ORDER BY total DESC, a.model_name
+            "#,
+            app_filter
+        ));
+
+        let mut query_builder = sqlx::query_as::<_, (String, i64, i64, i64)>(&query)
+            .bind(company_id.to_string())
+            .bind(time_range.start_time().to_rfc3339());
+        if let Some(app_id) = app_id {
+            query_builder = query_builder.bind(app_id.to_string());
+        }
+        let rows = query_builder.fetch_all(&self.pool).await?;
+
+        let rate = |count: i64, total: i64| {
+            if total > 0 {
+                (count as f64 / total as f64) * 100.0
+            } else {
+                0.0
+            }
+        };
+        let data = rows
+            .into_iter()
+            .map(|(model_name, total, blocked, escalated)| ModelMetrics {
+                model_name,
+                total_actions: total,
+                blocked_actions: blocked,
+                escalated_actions: escalated,
+                block_rate: rate(blocked, total),
+                escalation_rate: rate(escalated, total),
+            })
+            .collect();
+
+        Ok(ModelBreakdown { data })
+    }
+
+    /// Get approve/reject counts and median decision time per reviewer for
+    /// tasks decided within the time range. Pending tasks are left out.
+    pub async fn get_metrics_by_reviewer(
+        &self,
+        company_id: Uuid,
+        time_range: TimeRange,
+        app_id: Option<Uuid>,
+    ) -> ShieldResult<ReviewerBreakdown> {
+        let app_filter = if app_id.is_some() {
+            "AND a.app_id = ?"
+        } else {
+            ""
+        };
+        let query = self.sql(&format!(
+            r#"
+            SELECT t.reviewer_id, t.status, {review_secs}
+            FROM hitl_tasks t
+            JOIN agent_actions a ON t.agent_action_id = a.id
+            WHERE a.company_id = ?
+                AND t.reviewer_id IS NOT NULL
+                AND t.status IN ('approved', 'rejected')
+                AND t.reviewed_at >= ? {app_filter}
+            "#,
+            review_secs = self
+                .backend
+                .seconds_between("t.created_at", "t.reviewed_at")
+        ));
+
+        let mut query_builder = sqlx::query_as::<_, (String, String, Option<f64>)>(&query)
+            .bind(company_id.to_string())
+            .bind(time_range.start_time().to_rfc3339());
+        if let Some(app_id) = app_id {
+            query_builder = query_builder.bind(app_id.to_string());
+        }
+        let rows = query_builder.fetch_all(&self.pool).await?;
+
+        let mut by_reviewer: std::collections::BTreeMap<String, (i64, i64, Vec<f64>)> =
+            std::collections::BTreeMap::new();
+        for (reviewer_id, status, secs) in rows {
+            let entry = by_reviewer.entry(reviewer_id).or_default();
+            if status == "approved" {
+                entry.0 += 1;
+            } else {
+                entry.1 += 1;
+            }
+            entry.2.extend(secs);
+        }
+
+        let mut data: Vec<ReviewerMetrics> = by_reviewer
+            .into_iter()
+            .map(|(reviewer_id, (approved, rejected, mut secs))| {
+                secs.sort_by(f64::total_cmp);
+                let mid = secs.len() / 2;
+                let median = match secs.len() {
+                    0 => None,
+                    n if n % 2 == 1 => Some(secs[mid]),
+                    _ => Some((secs[mid - 1] + secs[mid]) / 2.0),
+                };
+                ReviewerMetrics {
+                    reviewer_id,
+                    approved,
+                    rejected,
+                    median_time_to_decision_secs: median,
+                }
+            })
+            .collect();
+        // Stable, so ties stay in reviewer order
+        data.sort_by_key(|r| std::cmp::Reverse(r.approved + r.rejected));
+
+        Ok(ReviewerBreakdown { data })
+    }
+
+    // ==================== Actions List ====================
+
+    /// List actions for a company with filtering.
+    ///
+    /// With `after` (the `created_at` and id of the last row already seen)
+    /// the page starts right after that row and `offset` is ignored, so
+    /// actions arriving meanwhile don't shift it. Returns the rows, the
+    /// total matching the filters, and how many of those precede the page.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn list_company_actions(
+        &self,
+        company_id: Uuid,
+        app_id: Option<Uuid>,
+        decision: Option<DecisionStatus>,
+        risk_tier: Option<RiskTier>,
+        user_id: Option<&str>,
         search: Option<&str>,
         time_range: Option<TimeRange>,
+        sandbox: bool,
+        after: Option<(&str, &str)>,
         limit: i64,
         offset: i64,
-    ) -> ShieldResult<(Vec<ActionListRow>, i64)> {
-        let mut conditions = vec!["a.company_id = ?".to_string()];
-        let start_time = time_range.map(|tr| tr.start_time().to_rfc3339());
+    ) -> ShieldResult<(Vec<ActionListRow>, i64, i64)> {
+        let (where_clause, binds) = self.company_actions_filter(
+            company_id, app_id, decision, risk_tier, user_id, search, time_range, sandbox,
+        );
 
-        if app_id.is_some() {
-            conditions.push("a.app_id = ?".to_string());
-        }
-        if decision.is_some() {
-            conditions.push("e.decision = ?".to_string());
-        }
-        if risk_tier.is_some() {
-            conditions.push("e.risk_tier = ?".to_string());
-        }
-        if user_id.is_some() {
-            conditions.push("a.user_id = ?".to_string());
-        }
-        if search.is_some() {
-            conditions.push(
-                "(a.user_id LIKE ? OR a.trace_id LIKE ? OR a.action_type LIKE ?)".to_string(),
-            );
+        let (query, count_query) = match after {
+            Some(_) => (
+                format!(
+                    "{} LIMIT ?",
+                    self.company_actions_select(&format!(
+                        "{} AND (a.created_at, a.id) < (?, ?)",
+                        where_clause
+                    ))
+                ),
+                "COUNT(*), COUNT(CASE WHEN (a.created_at, a.id) >= (?, ?) THEN 1 END)",
+            ),
+            None => (
+                format!(
+                    "{} LIMIT ? OFFSET ?",
+                    self.company_actions_select(&where_clause)
+                ),
+                // Same shape; the preceding count is just the offset
+                "COUNT(*), COUNT(*)",
+            ),
+        };
+        let query = self.sql(&query);
+        let count_query = self.sql(&format!(
+            r#"
+            SELECT {}
+            FROM agent_actions a
+            JOIN evaluations e ON a.id = e.agent_action_id
+            WHERE {}
+            "#,
+            count_query, where_clause
+        ));
+
+        // Build the query dynamically
+        let mut query_builder = sqlx::query_as::<_, ActionListRow>(&query);
+        let mut count_builder = sqlx::query_as::<_, (i64, i64)>(&count_query);
+        if let Some((created_at, id)) = after {
+            count_builder = count_builder.bind(created_at).bind(id);
         }
-        if start_time.is_some() {
-            conditions.push("a.created_at >= ?".to_string());
+        for value in &binds {
+            query_builder = query_builder.bind(value);
+            count_builder = count_builder.bind(value);
         }
 
-        let where_clause = conditions.join(" AND ");
+        query_builder = match after {
+            Some((created_at, id)) => query_builder.bind(created_at).bind(id).bind(limit),
+            None => query_builder.bind(limit).bind(offset),
+        };
+
+        let rows = query_builder.fetch_all(&self.pool).await?;
+        let (total, preceding) = count_builder.fetch_one(&self.pool).await?;
+        let preceding = if after.is_some() { preceding } else { offset };
+
+        Ok((rows, total, preceding))
+    }
+
+    /// Stream every action matching the [`Self::list_company_actions`]
+    /// filters, newest first, without loading them all at once.
+    ///
+    /// Rows are read on a background task and handed over as the receiver
+    /// is drained; dropping the receiver stops the query.
+    #[allow(clippy::too_many_arguments)]
+    pub fn stream_company_actions(
+        &self,
+        company_id: Uuid,
+        app_id: Option<Uuid>,
+        decision: Option<DecisionStatus>,
+        risk_tier: Option<RiskTier>,
+        user_id: Option<&str>,
+        search: Option<&str>,
+        time_range: Option<TimeRange>,
+        sandbox: bool,
+    ) -> tokio::sync::mpsc::Receiver<ShieldResult<ActionListRow>> {
+        let (where_clause, binds) = self.company_actions_filter(
+            company_id, app_id, decision, risk_tier, user_id, search, time_range, sandbox,
+        );
+        let query = self.sql(&self.company_actions_select(&where_clause));
+        let pool = self.pool.clone();
+        let (tx, rx) = tokio::sync::mpsc::channel(256);
+
+        tokio::spawn(async move {
+            let mut query_builder = sqlx::query_as::<_, ActionListRow>(&query);
+            for value in &binds {
+                query_builder = query_builder.bind(value);
+            }
+            let mut rows = query_builder.fetch(&pool);
+            while let Some(row) = rows.next().await {
+                let failed = row.is_err();
+                if tx.send(row.map_err(Into::into)).await.is_err() || failed {
+                    break;
+                }
+            }
+        });
 
-        let query = format!(
+        rx
+    }
+
+    /// `SELECT` of company action rows, newest first, for a `WHERE` clause
+    /// from [`Self::company_actions_filter`].
+    fn company_actions_select(&self, where_clause: &str) -> String {
+        format!(
             r#"
             SELECT
                 a.id,
@@ -1212,8 +3258,8 @@ impl ShieldRepository {
                 a.user_id,
                 a.action_type,
                 a.original_intent,
-                json_extract(a.payload, '$.amount') as amount,
-                json_extract(a.payload, '$.currency') as currency,
+                {} as amount,
+                {} as currency,
                 e.decision,
                 e.risk_tier,
                 e.reasons,
@@ -1221,81 +3267,78 @@ impl ShieldRepository {
             FROM agent_actions a
             JOIN evaluations e ON a.id = e.agent_action_id
             WHERE {}
-            ORDER BY a.created_at DESC
-            LIMIT ? OFFSET ?
+            ORDER BY a.created_at DESC, a.id DESC
             "#,
+            self.backend.json_number("a.payload", "amount"),
+            self.backend.json_text("a.payload", "currency"),
             where_clause
-        );
-
-        let count_query = format!(
-            r#"
-            SELECT COUNT(*)
-            FROM agent_actions a
-            JOIN evaluations e ON a.id = e.agent_action_id
-            WHERE {}
-            "#,
-            where_clause
-        );
-
-        // Build the query dynamically
-        let mut query_builder = sqlx::query_as::<_, ActionListRow>(&query);
-        let mut count_builder = sqlx::query_as::<_, (i64,)>(&count_query);
+        )
+    }
 
-        query_builder = query_builder.bind(company_id.to_string());
-        count_builder = count_builder.bind(company_id.to_string());
+    /// `WHERE` clause for the company action list filters, and the values
+    /// to bind to it in order.
+    #[allow(clippy::too_many_arguments)]
+    fn company_actions_filter(
+        &self,
+        company_id: Uuid,
+        app_id: Option<Uuid>,
+        decision: Option<DecisionStatus>,
+        risk_tier: Option<RiskTier>,
+        user_id: Option<&str>,
+        search: Option<&str>,
+        time_range: Option<TimeRange>,
+        sandbox: bool,
+    ) -> (String, Vec<String>) {
+        let mut conditions = vec![
+            "a.company_id = ?".to_string(),
+            format!("a.sandbox = {}", sandbox as i32),
+        ];
+        let mut binds = vec![company_id.to_string()];
 
         if let Some(app) = app_id {
-            query_builder = query_builder.bind(app.to_string());
-            count_builder = count_builder.bind(app.to_string());
+            conditions.push("a.app_id = ?".to_string());
+            binds.push(app.to_string());
         }
         if let Some(d) = decision {
-            query_builder = query_builder.bind(d.to_string());
-            count_builder = count_builder.bind(d.to_string());
+            conditions.push("e.decision = ?".to_string());
+            binds.push(d.to_string());
         }
         if let Some(r) = risk_tier {
-            query_builder = query_builder.bind(r.to_string());
-            count_builder = count_builder.bind(r.to_string());
+            conditions.push("e.risk_tier = ?".to_string());
+            binds.push(r.to_string());
         }
         if let Some(u) = user_id {
-            query_builder = query_builder.bind(u);
-            count_builder = count_builder.bind(u);
+            conditions.push("a.user_id = ?".to_string());
+            binds.push(u.to_string());
         }
         if let Some(s) = search {
+            let like = self.backend.like();
+            conditions.push(format!(
+                "(a.user_id {like} ? OR a.trace_id {like} ? OR a.action_type {like} ?)"
+            ));
             let pattern = format!("%{}%", s);
-            query_builder = query_builder
-                .bind(pattern.clone())
-                .bind(pattern.clone())
-                .bind(pattern.clone());
-            count_builder = count_builder
-                .bind(pattern.clone())
-                .bind(pattern.clone())
-                .bind(pattern);
+            binds.extend([pattern.clone(), pattern.clone(), pattern]);
         }
-        if let Some(ref st) = start_time {
-            query_builder = query_builder.bind(st);
-            count_builder = count_builder.bind(st);
+        if let Some(tr) = time_range {
+            conditions.push("a.created_at >= ?".to_string());
+            binds.push(tr.start_time().to_rfc3339());
         }
 
-        query_builder = query_builder.bind(limit).bind(offset);
-
-        let rows = query_builder.fetch_all(&self.pool).await?;
-        let (total,) = count_builder.fetch_one(&self.pool).await?;
-
-        Ok((rows, total))
+        (conditions.join(" AND "), binds)
     }
 
     // ==================== Attack Events ====================
 
     /// Save an attack event.
     pub async fn save_attack_event(&self, event: &AttackEvent) -> ShieldResult<()> {
-        sqlx::query(
+        sqlx::query(&self.sql(
             r#"
             INSERT INTO attack_events (
                 id, company_id, app_id, agent_action_id, attack_type, severity,
                 blocked, outcome, user_id, description, details, created_at
             ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
-        )
+        ))
         .bind(event.id.to_string())
         .bind(event.company_id.to_string())
         .bind(event.app_id.map(|id| id.to_string()))
@@ -1342,7 +3385,7 @@ impl ShieldRepository {
 
         let where_clause = conditions.join(" AND ");
 
-        let query = format!(
+        let query = self.sql(&format!(
             r#"
             SELECT ae.*
             FROM attack_events ae
@@ -1351,12 +3394,12 @@ impl ShieldRepository {
             LIMIT ? OFFSET ?
             "#,
             where_clause
-        );
+        ));
 
-        let count_query = format!(
+        let count_query = self.sql(&format!(
             r#"SELECT COUNT(*) FROM attack_events ae WHERE {}"#,
             where_clause
-        );
+        ));
 
         let mut query_builder = sqlx::query_as::<_, AttackEventRow>(&query);
         let mut count_builder = sqlx::query_as::<_, (i64,)>(&count_query);
@@ -1401,7 +3444,7 @@ impl ShieldRepository {
         let company = self.get_company(company_id).await?;
 
         let row: Option<CompanySettingsRow> =
-            sqlx::query_as("SELECT * FROM company_settings WHERE company_id = ?")
+            sqlx::query_as(&self.sql("SELECT * FROM company_settings WHERE company_id = ?"))
                 .bind(company_id.to_string())
                 .fetch_optional(&self.pool)
                 .await?;
@@ -1421,87 +3464,729 @@ impl ShieldRepository {
         logo: Option<&str>,
         webhook_url: Option<&str>,
         notification_email: Option<&str>,
+        timezone: Option<&str>,
         thresholds: Option<&PolicyThresholds>,
     ) -> ShieldResult<CompanySettings> {
-        // Ensure settings row exists
-        let existing: Option<(String,)> =
-            sqlx::query_as("SELECT company_id FROM company_settings WHERE company_id = ?")
+        self.ensure_settings_row(company_id).await?;
+
+        if let Some(logo) = logo {
+            sqlx::query(&self.sql("UPDATE company_settings SET logo = ? WHERE company_id = ?"))
+                .bind(logo)
                 .bind(company_id.to_string())
-                .fetch_optional(&self.pool)
+                .execute(&self.pool)
+                .await?;
+        }
+
+        if let Some(url) = webhook_url {
+            sqlx::query(
+                &self.sql("UPDATE company_settings SET webhook_url = ? WHERE company_id = ?"),
+            )
+            .bind(url)
+            .bind(company_id.to_string())
+            .execute(&self.pool)
+            .await?;
+        }
+
+        if let Some(email) = notification_email {
+            sqlx::query(
+                &self
+                    .sql("UPDATE company_settings SET notification_email = ? WHERE company_id = ?"),
+            )
+            .bind(email)
+            .bind(company_id.to_string())
+            .execute(&self.pool)
+            .await?;
+        }
+
+        if let Some(tz) = timezone {
+            sqlx::query(&self.sql("UPDATE company_settings SET timezone = ? WHERE company_id = ?"))
+                .bind(tz)
+                .bind(company_id.to_string())
+                .execute(&self.pool)
                 .await?;
+        }
+
+        if let Some(t) = thresholds {
+            sqlx::query(&self.sql(
+                r#"
+                UPDATE company_settings SET
+                    max_auto_approve_amount = ?,
+                    hitl_threshold_amount = ?,
+                    velocity_limit_per_hour = ?,
+                    velocity_limit_per_day = ?,
+                    block_high_risk_actions = ?,
+                    require_hitl_for_new_beneficiaries = ?,
+                    daily_transfer_limit = ?,
+                    alignment_allowlist = ?,
+                    off_hours_schedule = ?,
+                    drain_detection = ?,
+                    allowed_risk_floor = ?,
+                    absolute_block_amount = ?,
+                    crypto_address_allowlist = ?,
+                    thresholds_updated_at = ?
+                WHERE company_id = ?
+                "#,
+            ))
+            .bind(t.max_auto_approve_amount)
+            .bind(t.hitl_threshold_amount)
+            .bind(t.velocity_limit_per_hour)
+            .bind(t.velocity_limit_per_day)
+            .bind(if t.block_high_risk_actions { 1 } else { 0 })
+            .bind(if t.require_hitl_for_new_beneficiaries {
+                1
+            } else {
+                0
+            })
+            .bind(t.daily_transfer_limit)
+            .bind(serde_json::to_string(&t.alignment_allowlist)?)
+            .bind(
+                t.off_hours
+                    .as_ref()
+                    .map(serde_json::to_string)
+                    .transpose()?,
+            )
+            .bind(
+                t.drain_detection
+                    .as_ref()
+                    .map(serde_json::to_string)
+                    .transpose()?,
+            )
+            .bind(t.allowed_risk_floor.map(|tier| tier.to_string()))
+            .bind(t.absolute_block_amount)
+            .bind(serde_json::to_string(&t.crypto_address_allowlist)?)
+            .bind(chrono::Utc::now().to_rfc3339())
+            .bind(company_id.to_string())
+            .execute(&self.pool)
+            .await?;
+        }
+
+        self.get_company_settings(company_id).await
+    }
+
+    /// Update a company's HITL notification digest mode.
+    pub async fn update_notification_digest(
+        &self,
+        company_id: Uuid,
+        digest: &NotificationDigest,
+    ) -> ShieldResult<CompanySettings> {
+        self.ensure_settings_row(company_id).await?;
+
+        sqlx::query(
+            &self.sql("UPDATE company_settings SET notification_digest = ? WHERE company_id = ?"),
+        )
+        .bind(serde_json::to_string(digest)?)
+        .bind(company_id.to_string())
+        .execute(&self.pool)
+        .await?;
+
+        self.get_company_settings(company_id).await
+    }
+
+    /// Update which evaluation pipeline layers a company runs.
+    pub async fn update_pipeline_layers(
+        &self,
+        company_id: Uuid,
+        layers: &PipelineLayers,
+    ) -> ShieldResult<CompanySettings> {
+        self.ensure_settings_row(company_id).await?;
+
+        sqlx::query(&self.sql("UPDATE company_settings SET pipeline = ? WHERE company_id = ?"))
+            .bind(serde_json::to_string(layers)?)
+            .bind(company_id.to_string())
+            .execute(&self.pool)
+            .await?;
+
+        self.get_company_settings(company_id).await
+    }
+
+    /// Set or clear the console base URL used for a company's review links.
+    pub async fn update_console_url(
+        &self,
+        company_id: Uuid,
+        console_url: Option<&str>,
+    ) -> ShieldResult<CompanySettings> {
+        self.ensure_settings_row(company_id).await?;
+
+        sqlx::query(&self.sql("UPDATE company_settings SET console_url = ? WHERE company_id = ?"))
+            .bind(console_url)
+            .bind(company_id.to_string())
+            .execute(&self.pool)
+            .await?;
+
+        self.get_company_settings(company_id).await
+    }
+
+    /// Set or clear how many days a company's actions are kept.
+    pub async fn update_retention_days(
+        &self,
+        company_id: Uuid,
+        retention_days: Option<u32>,
+    ) -> ShieldResult<CompanySettings> {
+        self.ensure_settings_row(company_id).await?;
+
+        sqlx::query(
+            &self.sql("UPDATE company_settings SET retention_days = ? WHERE company_id = ?"),
+        )
+        .bind(retention_days.map(|days| days as i32))
+        .bind(company_id.to_string())
+        .execute(&self.pool)
+        .await?;
+
+        self.get_company_settings(company_id).await
+    }
+
+    /// All companies, soft-deleted ones included, with their own retention
+    /// period if they set one.
+    pub async fn list_company_retention(&self) -> ShieldResult<Vec<(Uuid, Option<u32>)>> {
+        let rows: Vec<(String, Option<i32>)> = sqlx::query_as(
+            r#"
+            SELECT c.id, s.retention_days
+            FROM companies c
+            LEFT JOIN company_settings s ON s.company_id = c.id
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|(id, days)| {
+                let id = Uuid::parse_str(&id).map_err(|e| ShieldError::Internal(e.to_string()))?;
+                Ok((id, days.map(|days| days as u32)))
+            })
+            .collect()
+    }
+
+    /// Replace the key a company's webhook payloads are signed with. The
+    /// old key keeps signing alongside it until `previous_expires_at`.
+    /// `audit` is appended in the same transaction.
+    pub async fn rotate_webhook_secret(
+        &self,
+        company_id: Uuid,
+        secret: &str,
+        previous_expires_at: DateTime<Utc>,
+        audit: &AuditEntry,
+    ) -> ShieldResult<()> {
+        self.ensure_settings_row(company_id).await?;
+
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(&self.sql(
+            r#"
+            UPDATE company_settings
+            SET webhook_previous_secret = webhook_secret,
+                webhook_previous_secret_expires_at = ?,
+                webhook_secret = ?
+            WHERE company_id = ?
+            "#,
+        ))
+        .bind(previous_expires_at.to_rfc3339())
+        .bind(secret)
+        .bind(company_id.to_string())
+        .execute(&mut *tx)
+        .await?;
+        self.insert_audit_entry(&mut tx, audit).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Insert a default settings row for a company if it has none yet.
+    async fn ensure_settings_row(&self, company_id: Uuid) -> ShieldResult<()> {
+        let existing: Option<(String,)> = sqlx::query_as(
+            &self.sql("SELECT company_id FROM company_settings WHERE company_id = ?"),
+        )
+        .bind(company_id.to_string())
+        .fetch_optional(&self.pool)
+        .await?;
 
         if existing.is_none() {
-            sqlx::query("INSERT INTO company_settings (company_id) VALUES (?)")
+            sqlx::query(&self.sql("INSERT INTO company_settings (company_id) VALUES (?)"))
                 .bind(company_id.to_string())
                 .execute(&self.pool)
                 .await?;
         }
 
-        if let Some(logo) = logo {
-            sqlx::query("UPDATE company_settings SET logo = ? WHERE company_id = ?")
-                .bind(logo)
+        Ok(())
+    }
+
+    // ==================== Policy Profiles ====================
+
+    /// Create a policy profile.
+    pub async fn create_policy_profile(&self, profile: &PolicyProfile) -> ShieldResult<()> {
+        sqlx::query(&self.sql(
+            r#"
+            INSERT INTO policy_profiles (id, company_id, name, thresholds, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#,
+        ))
+        .bind(profile.id.to_string())
+        .bind(profile.company_id.to_string())
+        .bind(&profile.name)
+        .bind(serde_json::to_string(&profile.thresholds)?)
+        .bind(profile.created_at.to_rfc3339())
+        .bind(profile.updated_at.to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Get a policy profile by ID, scoped to its company.
+    pub async fn get_policy_profile(
+        &self,
+        company_id: Uuid,
+        id: Uuid,
+    ) -> ShieldResult<PolicyProfile> {
+        let row: PolicyProfileRow = sqlx::query_as(
+            &self.sql("SELECT * FROM policy_profiles WHERE id = ? AND company_id = ?"),
+        )
+        .bind(id.to_string())
+        .bind(company_id.to_string())
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| ShieldError::NotFound(format!("Policy profile {} not found", id)))?;
+
+        row.try_into()
+    }
+
+    /// Get a policy profile by name, scoped to its company.
+    pub async fn get_policy_profile_by_name(
+        &self,
+        company_id: Uuid,
+        name: &str,
+    ) -> ShieldResult<Option<PolicyProfile>> {
+        let row: Option<PolicyProfileRow> = sqlx::query_as(
+            &self.sql("SELECT * FROM policy_profiles WHERE company_id = ? AND name = ?"),
+        )
+        .bind(company_id.to_string())
+        .bind(name)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|r| r.try_into()).transpose()
+    }
+
+    /// List a company's policy profiles.
+    pub async fn list_policy_profiles(&self, company_id: Uuid) -> ShieldResult<Vec<PolicyProfile>> {
+        let rows: Vec<PolicyProfileRow> = sqlx::query_as(
+            &self.sql("SELECT * FROM policy_profiles WHERE company_id = ? ORDER BY name ASC"),
+        )
+        .bind(company_id.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(|r| r.try_into()).collect()
+    }
+
+    /// Update a policy profile.
+    pub async fn update_policy_profile(
+        &self,
+        company_id: Uuid,
+        id: Uuid,
+        name: Option<&str>,
+        thresholds: Option<&PolicyThresholds>,
+    ) -> ShieldResult<PolicyProfile> {
+        self.get_policy_profile(company_id, id).await?;
+        let updated_at = chrono::Utc::now().to_rfc3339();
+
+        if let Some(name) = name {
+            sqlx::query(&self.sql("UPDATE policy_profiles SET name = ?, updated_at = ? WHERE id = ? AND company_id = ?"))
+            .bind(name)
+            .bind(&updated_at)
+            .bind(id.to_string())
+            .bind(company_id.to_string())
+            .execute(&self.pool)
+            .await?;
+        }
+
+        if let Some(thresholds) = thresholds {
+            sqlx::query(&self.sql("UPDATE policy_profiles SET thresholds = ?, updated_at = ? WHERE id = ? AND company_id = ?"))
+            .bind(serde_json::to_string(thresholds)?)
+            .bind(&updated_at)
+            .bind(id.to_string())
+            .bind(company_id.to_string())
+            .execute(&self.pool)
+            .await?;
+        }
+
+        self.get_policy_profile(company_id, id).await
+    }
+
+    /// Delete a policy profile; apps using it fall back to company thresholds.
+    pub async fn delete_policy_profile(&self, company_id: Uuid, id: Uuid) -> ShieldResult<()> {
+        let mut tx = self.pool.begin().await?;
+
+        let result =
+            sqlx::query(&self.sql("DELETE FROM policy_profiles WHERE id = ? AND company_id = ?"))
+                .bind(id.to_string())
+                .bind(company_id.to_string())
+                .execute(&mut *tx)
+                .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(ShieldError::NotFound(format!(
+                "Policy profile {} not found",
+                id
+            )));
+        }
+
+        sqlx::query(&self.sql("UPDATE apps SET policy_profile_id = NULL WHERE policy_profile_id = ? AND company_id = ?"))
+        .bind(id.to_string())
+        .bind(company_id.to_string())
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    // ==================== Beneficiaries ====================
+
+    /// Approve a beneficiary. Approving an account the user already has
+    /// is a no-op.
+    pub async fn add_beneficiary(&self, beneficiary: &Beneficiary) -> ShieldResult<()> {
+        sqlx::query(&self.sql(
+            r#"
+            INSERT INTO beneficiaries (id, company_id, user_id, account_identifier, approved_at)
+            VALUES (?, ?, ?, ?, ?)
+            ON CONFLICT (company_id, user_id, account_identifier) DO NOTHING
+            "#,
+        ))
+        .bind(beneficiary.id.to_string())
+        .bind(beneficiary.company_id.to_string())
+        .bind(&beneficiary.user_id)
+        .bind(&beneficiary.account_identifier)
+        .bind(beneficiary.approved_at.to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// List a company's beneficiaries, optionally for one user, oldest first.
+    pub async fn list_beneficiaries(
+        &self,
+        company_id: Uuid,
+        user_id: Option<&str>,
+    ) -> ShieldResult<Vec<Beneficiary>> {
+        let rows: Vec<BeneficiaryRow> = match user_id {
+            Some(user_id) => {
+                sqlx::query_as(&self.sql(
+                    "SELECT * FROM beneficiaries WHERE company_id = ? AND user_id = ? ORDER BY approved_at ASC",
+                ))
+                .bind(company_id.to_string())
+                .bind(user_id)
+                .fetch_all(&self.pool)
+                .await?
+            }
+            None => {
+                sqlx::query_as(&self.sql(
+                    "SELECT * FROM beneficiaries WHERE company_id = ? ORDER BY approved_at ASC",
+                ))
+                .bind(company_id.to_string())
+                .fetch_all(&self.pool)
+                .await?
+            }
+        };
+
+        rows.into_iter().map(|r| r.try_into()).collect()
+    }
+
+    /// Remove a beneficiary from a company's allowlist.
+    pub async fn delete_beneficiary(&self, company_id: Uuid, id: Uuid) -> ShieldResult<()> {
+        let result =
+            sqlx::query(&self.sql("DELETE FROM beneficiaries WHERE id = ? AND company_id = ?"))
+                .bind(id.to_string())
                 .bind(company_id.to_string())
                 .execute(&self.pool)
                 .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(ShieldError::NotFound(format!(
+                "Beneficiary {} not found",
+                id
+            )));
         }
 
-        if let Some(url) = webhook_url {
-            sqlx::query("UPDATE company_settings SET webhook_url = ? WHERE company_id = ?")
-                .bind(url)
-                .bind(company_id.to_string())
-                .execute(&self.pool)
-                .await?;
-        }
+        Ok(())
+    }
+
+    /// Whether `account_identifier` is an approved beneficiary of the user.
+    pub async fn is_approved_beneficiary(
+        &self,
+        company_id: Uuid,
+        user_id: &str,
+        account_identifier: &str,
+    ) -> ShieldResult<bool> {
+        let row: Option<(String,)> = sqlx::query_as(&self.sql(
+            "SELECT id FROM beneficiaries WHERE company_id = ? AND user_id = ? AND account_identifier = ?",
+        ))
+        .bind(company_id.to_string())
+        .bind(user_id)
+        .bind(account_identifier)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.is_some())
+    }
+
+    // ==================== Idempotency Keys ====================
+
+    /// Get the response stored under an app's idempotency key (`None` for
+    /// service API keys), if it was stored at or after `since`.
+    pub async fn get_idempotency_record(
+        &self,
+        app_id: Option<Uuid>,
+        key: &str,
+        since: DateTime<Utc>,
+    ) -> ShieldResult<Option<IdempotencyRecord>> {
+        let row: Option<IdempotencyKeyRow> = sqlx::query_as(&self.sql(
+            "SELECT * FROM idempotency_keys \
+             WHERE app_id = ? AND idempotency_key = ? AND created_at >= ?",
+        ))
+        .bind(app_id.map(|id| id.to_string()).unwrap_or_default())
+        .bind(key)
+        .bind(since.to_rfc3339())
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|r| r.try_into()).transpose()
+    }
+
+    /// Store the response for an idempotency key, replacing an expired one.
+    pub async fn save_idempotency_record(&self, record: &IdempotencyRecord) -> ShieldResult<()> {
+        sqlx::query(&self.sql(
+            r#"
+            INSERT INTO idempotency_keys (app_id, idempotency_key, request_hash, evaluation_id, response, created_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            ON CONFLICT (app_id, idempotency_key) DO UPDATE SET
+                request_hash = excluded.request_hash,
+                evaluation_id = excluded.evaluation_id,
+                response = excluded.response,
+                created_at = excluded.created_at
+            "#,
+        ))
+        .bind(record.app_id.map(|id| id.to_string()).unwrap_or_default())
+        .bind(&record.key)
+        .bind(&record.request_hash)
+        .bind(record.evaluation_id.to_string())
+        .bind(record.response.to_string())
+        .bind(record.created_at.to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Delete idempotency records stored before `cutoff`. Returns how many
+    /// were deleted.
+    pub async fn delete_idempotency_records_before(
+        &self,
+        cutoff: DateTime<Utc>,
+    ) -> ShieldResult<u64> {
+        let result = sqlx::query(&self.sql("DELETE FROM idempotency_keys WHERE created_at < ?"))
+            .bind(cutoff.to_rfc3339())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    // ==================== Audit Trail ====================
+
+    /// Append an entry to the audit trail. Entries are never updated or
+    /// deleted.
+    pub async fn record_audit(&self, entry: &AuditEntry) -> ShieldResult<()> {
+        let mut conn = self.pool.acquire().await?;
+        self.insert_audit_entry(&mut conn, entry).await
+    }
+
+    /// Append an audit entry on `conn`, so it can share a transaction with
+    /// the change it records.
+    async fn insert_audit_entry(
+        &self,
+        conn: &mut AnyConnection,
+        entry: &AuditEntry,
+    ) -> ShieldResult<()> {
+        sqlx::query(&self.sql(
+            r#"
+            INSERT INTO audit_log (id, company_id, actor_id, action, target_type, target_id, before_state, after_state, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        ))
+        .bind(entry.id.to_string())
+        .bind(entry.company_id.map(|id| id.to_string()))
+        .bind(&entry.actor_id)
+        .bind(&entry.action)
+        .bind(&entry.target_type)
+        .bind(&entry.target_id)
+        .bind(entry.before.as_ref().map(|v| v.to_string()))
+        .bind(entry.after.as_ref().map(|v| v.to_string()))
+        .bind(entry.created_at.to_rfc3339())
+        .execute(conn)
+        .await?;
+
+        Ok(())
+    }
+
+    /// List a company's audit entries, newest first, with total count.
+    pub async fn list_audit_entries(
+        &self,
+        company_id: Uuid,
+        actor_id: Option<&str>,
+        time_range: Option<TimeRange>,
+        limit: i64,
+        offset: i64,
+    ) -> ShieldResult<(Vec<AuditEntry>, i64)> {
+        let mut conditions = vec!["company_id = ?".to_string()];
+        let mut binds = vec![company_id.to_string()];
+
+        if let Some(actor) = actor_id {
+            conditions.push("actor_id = ?".to_string());
+            binds.push(actor.to_string());
+        }
+        if let Some(tr) = time_range {
+            conditions.push("created_at >= ?".to_string());
+            binds.push(tr.start_time().to_rfc3339());
+        }
+
+        let where_clause = conditions.join(" AND ");
+
+        let query = self.sql(&format!(
+            "SELECT * FROM audit_log WHERE {} ORDER BY created_at DESC, id DESC LIMIT ? OFFSET ?",
+            where_clause
+        ));
+        let count_query = self.sql(&format!(
+            "SELECT COUNT(*) FROM audit_log WHERE {}",
+            where_clause
+        ));
+
+        let mut query_builder = sqlx::query_as::<_, AuditEntryRow>(&query);
+        let mut count_builder = sqlx::query_as::<_, (i64,)>(&count_query);
+        for bind in &binds {
+            query_builder = query_builder.bind(bind.clone());
+            count_builder = count_builder.bind(bind.clone());
+        }
+
+        let rows = query_builder
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(&self.pool)
+            .await?;
+        let (total,) = count_builder.fetch_one(&self.pool).await?;
+
+        let entries = rows
+            .into_iter()
+            .map(|r| r.try_into())
+            .collect::<ShieldResult<Vec<_>>>()?;
+
+        Ok((entries, total))
+    }
+
+    /// Company an agent action was evaluated for, if any.
+    pub async fn get_action_company_id(&self, action_id: Uuid) -> ShieldResult<Option<Uuid>> {
+        let row: Option<(Option<String>,)> =
+            sqlx::query_as(&self.sql("SELECT company_id FROM agent_actions WHERE id = ?"))
+                .bind(action_id.to_string())
+                .fetch_optional(&self.pool)
+                .await?;
+
+        row.and_then(|(company_id,)| company_id)
+            .map(|s| Uuid::parse_str(&s).map_err(|e| ShieldError::Internal(e.to_string())))
+            .transpose()
+    }
+
+    // ==================== Webhook Deliveries ====================
+
+    /// Record a finished webhook delivery.
+    pub async fn save_webhook_delivery(
+        &self,
+        delivery: &WebhookDeliveryRecord,
+    ) -> ShieldResult<()> {
+        sqlx::query(&self.sql(
+            r#"
+            INSERT INTO webhook_deliveries (
+                id, company_id, event_type, url, status_code, attempts,
+                last_error, payload, replay_of, created_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        ))
+        .bind(delivery.id.to_string())
+        .bind(delivery.company_id.to_string())
+        .bind(&delivery.event_type)
+        .bind(&delivery.url)
+        .bind(delivery.status_code.map(i32::from))
+        .bind(delivery.attempts as i32)
+        .bind(&delivery.last_error)
+        .bind(&delivery.payload)
+        .bind(delivery.replay_of.map(|id| id.to_string()))
+        .bind(delivery.created_at.to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Get one of a company's webhook deliveries.
+    pub async fn get_webhook_delivery(
+        &self,
+        company_id: Uuid,
+        id: Uuid,
+    ) -> ShieldResult<WebhookDeliveryRecord> {
+        let row: WebhookDeliveryRow = sqlx::query_as(
+            &self.sql("SELECT * FROM webhook_deliveries WHERE id = ? AND company_id = ?"),
+        )
+        .bind(id.to_string())
+        .bind(company_id.to_string())
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| ShieldError::NotFound(format!("Webhook delivery {} not found", id)))?;
+
+        row.try_into()
+    }
 
-        if let Some(email) = notification_email {
-            sqlx::query("UPDATE company_settings SET notification_email = ? WHERE company_id = ?")
-                .bind(email)
-                .bind(company_id.to_string())
-                .execute(&self.pool)
-                .await?;
-        }
+    /// List a company's webhook deliveries, newest first, with total count.
+    pub async fn list_webhook_deliveries(
+        &self,
+        company_id: Uuid,
+        limit: i64,
+        offset: i64,
+    ) -> ShieldResult<(Vec<WebhookDeliveryRecord>, i64)> {
+        let rows: Vec<WebhookDeliveryRow> = sqlx::query_as(&self.sql(
+            r#"
+            SELECT * FROM webhook_deliveries WHERE company_id = ?
+            ORDER BY created_at DESC, id DESC LIMIT ? OFFSET ?
+            "#,
+        ))
+        .bind(company_id.to_string())
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+        let (total,): (i64,) = sqlx::query_as(
+            &self.sql("SELECT COUNT(*) FROM webhook_deliveries WHERE company_id = ?"),
+        )
+        .bind(company_id.to_string())
+        .fetch_one(&self.pool)
+        .await?;
 
-        if let Some(t) = thresholds {
-            sqlx::query(
-                r#"
-                UPDATE company_settings SET
-                    max_auto_approve_amount = ?,
-                    hitl_threshold_amount = ?,
-                    velocity_limit_per_hour = ?,
-                    velocity_limit_per_day = ?,
-                    block_high_risk_actions = ?,
-                    require_hitl_for_new_beneficiaries = ?
-                WHERE company_id = ?
-                "#,
-            )
-            .bind(t.max_auto_approve_amount)
-            .bind(t.hitl_threshold_amount)
-            .bind(t.velocity_limit_per_hour)
-            .bind(t.velocity_limit_per_day)
-            .bind(if t.block_high_risk_actions { 1 } else { 0 })
-            .bind(if t.require_hitl_for_new_beneficiaries {
-                1
-            } else {
-                0
-            })
-            .bind(company_id.to_string())
-            .execute(&self.pool)
-            .await?;
-        }
+        let deliveries = rows
+            .into_iter()
+            .map(|r| r.try_into())
+            .collect::<ShieldResult<Vec<_>>>()?;
 
-        self.get_company_settings(company_id).await
+        Ok((deliveries, total))
     }
 
     // ==================== Users ====================
 
     /// Create a new user.
     pub async fn create_user(&self, user: &User) -> ShieldResult<()> {
-        sqlx::query(
+        sqlx::query(&self.sql(
             r#"
             INSERT INTO users (id, email, name, image, role, email_verified, password_hash, created_at, updated_at)
             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
-        )
+        ))
         .bind(user.id.to_string())
         .bind(&user.email)
         .bind(&user.name)
@@ -1519,7 +4204,7 @@ impl ShieldRepository {
 
     /// Get a user by ID.
     pub async fn get_user(&self, id: Uuid) -> ShieldResult<User> {
-        let row: UserRow = sqlx::query_as("SELECT * FROM users WHERE id = ?")
+        let row: UserRow = sqlx::query_as(&self.sql("SELECT * FROM users WHERE id = ?"))
             .bind(id.to_string())
             .fetch_optional(&self.pool)
             .await?
@@ -1530,7 +4215,7 @@ impl ShieldRepository {
 
     /// Get a user by email.
     pub async fn get_user_by_email(&self, email: &str) -> ShieldResult<Option<User>> {
-        let row: Option<UserRow> = sqlx::query_as("SELECT * FROM users WHERE email = ?")
+        let row: Option<UserRow> = sqlx::query_as(&self.sql("SELECT * FROM users WHERE email = ?"))
             .bind(email)
             .fetch_optional(&self.pool)
             .await?;
@@ -1548,7 +4233,7 @@ impl ShieldRepository {
         let updated_at = chrono::Utc::now().to_rfc3339();
 
         if let Some(name) = name {
-            sqlx::query("UPDATE users SET name = ?, updated_at = ? WHERE id = ?")
+            sqlx::query(&self.sql("UPDATE users SET name = ?, updated_at = ? WHERE id = ?"))
                 .bind(name)
                 .bind(&updated_at)
                 .bind(id.to_string())
@@ -1557,7 +4242,7 @@ impl ShieldRepository {
         }
 
         if let Some(image) = image {
-            sqlx::query("UPDATE users SET image = ?, updated_at = ? WHERE id = ?")
+            sqlx::query(&self.sql("UPDATE users SET image = ?, updated_at = ? WHERE id = ?"))
                 .bind(image)
                 .bind(&updated_at)
                 .bind(id.to_string())
@@ -1568,16 +4253,27 @@ impl ShieldRepository {
         self.get_user(id).await
     }
 
+    /// Replace a user's password hash.
+    pub async fn update_user_password(&self, id: Uuid, password_hash: &str) -> ShieldResult<()> {
+        sqlx::query(&self.sql("UPDATE users SET password_hash = ?, updated_at = ? WHERE id = ?"))
+            .bind(password_hash)
+            .bind(chrono::Utc::now().to_rfc3339())
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
     // ==================== OAuth Accounts ====================
 
     /// Create an OAuth account link.
     pub async fn create_oauth_account(&self, account: &OAuthAccount) -> ShieldResult<()> {
-        sqlx::query(
+        sqlx::query(&self.sql(
             r#"
             INSERT INTO oauth_accounts (id, user_id, provider, provider_account_id, created_at)
             VALUES (?, ?, ?, ?, ?)
             "#,
-        )
+        ))
         .bind(account.id.to_string())
         .bind(account.user_id.to_string())
         .bind(account.provider.to_string())
@@ -1595,13 +4291,13 @@ impl ShieldRepository {
         provider: OAuthProvider,
         provider_account_id: &str,
     ) -> ShieldResult<Option<User>> {
-        let row: Option<UserRow> = sqlx::query_as(
+        let row: Option<UserRow> = sqlx::query_as(&self.sql(
             r#"
             SELECT u.* FROM users u
             JOIN oauth_accounts oa ON u.id = oa.user_id
             WHERE oa.provider = ? AND oa.provider_account_id = ?
             "#,
-        )
+        ))
         .bind(provider.to_string())
         .bind(provider_account_id)
         .fetch_optional(&self.pool)
@@ -1613,7 +4309,7 @@ impl ShieldRepository {
     /// Get all OAuth accounts for a user.
     pub async fn get_user_oauth_accounts(&self, user_id: Uuid) -> ShieldResult<Vec<OAuthAccount>> {
         let rows: Vec<OAuthAccountRow> =
-            sqlx::query_as("SELECT * FROM oauth_accounts WHERE user_id = ?")
+            sqlx::query_as(&self.sql("SELECT * FROM oauth_accounts WHERE user_id = ?"))
                 .bind(user_id.to_string())
                 .fetch_all(&self.pool)
                 .await?;
@@ -1626,15 +4322,15 @@ impl ShieldRepository {
         &self,
         user_id: &str,
     ) -> ShieldResult<Vec<UserCompanyMembership>> {
-        let rows: Vec<(String, String, String, String)> = sqlx::query_as(
+        let rows: Vec<(String, String, String, String)> = sqlx::query_as(&self.sql(
             r#"
             SELECT c.id, c.name, c.slug, m.role
             FROM companies c
             JOIN company_members m ON c.id = m.company_id
-            WHERE m.user_id = ?
+            WHERE m.user_id = ? AND c.deleted_at IS NULL
             ORDER BY c.name ASC
             "#,
-        )
+        ))
         .bind(user_id)
         .fetch_all(&self.pool)
         .await?;
@@ -1651,6 +4347,69 @@ impl ShieldRepository {
             })
             .collect())
     }
+
+    // ==================== Refresh Tokens ====================
+
+    /// Store a newly issued refresh token.
+    pub async fn save_refresh_token(&self, token: &RefreshToken) -> ShieldResult<()> {
+        sqlx::query(&self.sql(
+            r#"
+            INSERT INTO refresh_tokens (id, token_hash, user_id, email, role, expires_at, revoked_at, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        ))
+        .bind(token.id.to_string())
+        .bind(&token.token_hash)
+        .bind(&token.user_id)
+        .bind(&token.email)
+        .bind(token.role.to_string())
+        .bind(token.expires_at.to_rfc3339())
+        .bind(token.revoked_at.map(|dt| dt.to_rfc3339()))
+        .bind(token.created_at.to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Look a refresh token up by its hash, whether or not it is still
+    /// active.
+    pub async fn get_refresh_token(&self, token_hash: &str) -> ShieldResult<Option<RefreshToken>> {
+        let row: Option<RefreshTokenRow> =
+            sqlx::query_as(&self.sql("SELECT * FROM refresh_tokens WHERE token_hash = ?"))
+                .bind(token_hash)
+                .fetch_optional(&self.pool)
+                .await?;
+
+        row.map(|r| r.try_into()).transpose()
+    }
+
+    /// Revoke a refresh token. Returns whether an unrevoked token was found.
+    pub async fn revoke_refresh_token(&self, token_hash: &str) -> ShieldResult<bool> {
+        let result = sqlx::query(&self.sql(
+            "UPDATE refresh_tokens SET revoked_at = ? WHERE token_hash = ? AND revoked_at IS NULL",
+        ))
+        .bind(Utc::now().to_rfc3339())
+        .bind(token_hash)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Revoke every active refresh token issued to a user. Returns how many
+    /// were revoked.
+    pub async fn revoke_user_refresh_tokens(&self, user_id: &str) -> ShieldResult<u64> {
+        let result = sqlx::query(&self.sql(
+            "UPDATE refresh_tokens SET revoked_at = ? WHERE user_id = ? AND revoked_at IS NULL",
+        ))
+        .bind(Utc::now().to_rfc3339())
+        .bind(user_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
 }
 
 #[cfg(test)]
@@ -1659,10 +4418,9 @@ mod tests {
     use crate::domain::{ActionType, DecisionStatus, RiskTier};
 
     async fn setup_test_db() -> ShieldRepository {
-        let pool = SqlitePool::connect("sqlite::memory:")
+        let repo = ShieldRepository::connect("sqlite::memory:")
             .await
             .expect("Failed to create test database");
-        let repo = ShieldRepository::new(pool);
         repo.init_schema().await.expect("Failed to init schema");
         repo
     }
@@ -1745,11 +4503,12 @@ mod tests {
         repo.save_hitl_task(&task).await.unwrap();
 
         // List pending tasks
-        let tasks = repo
+        let (tasks, total) = repo
             .list_hitl_tasks(Some(HitlStatus::Pending), 10, 0)
             .await
             .unwrap();
         assert_eq!(tasks.len(), 1);
+        assert_eq!(total, 1);
         assert_eq!(tasks[0].status, HitlStatus::Pending);
 
         // Approve task
@@ -1759,16 +4518,544 @@ mod tests {
                 HitlStatus::Approved,
                 "admin@example.com",
                 Some("Looks good"),
+                None,
             )
             .await
             .unwrap();
         assert_eq!(updated.status, HitlStatus::Approved);
 
         // Verify no more pending
-        let pending = repo
+        let (pending, total) = repo
             .list_hitl_tasks(Some(HitlStatus::Pending), 10, 0)
             .await
             .unwrap();
         assert!(pending.is_empty());
+        assert_eq!(total, 0);
+    }
+
+    #[tokio::test]
+    async fn test_sum_user_transfers_since_skips_blocked() {
+        let repo = setup_test_db().await;
+        let company = Company::new("Acme".to_string(), "acme".to_string(), None);
+        repo.create_company(&company).await.unwrap();
+
+        let transfer = |user_id: &str, amount: f64| {
+            AgentAction::new(
+                user_id,
+                "chatbot",
+                "gpt-4",
+                "Transfer funds",
+                ActionType::TransferFunds,
+                serde_json::json!({ "to_account_id": "bob", "amount": amount }),
+            )
+        };
+
+        let allowed = transfer("user123", 60.0);
+        repo.save_action_with_company(&allowed, company.id)
+            .await
+            .unwrap();
+        repo.save_evaluation(&EvaluationResult::allow(allowed.id))
+            .await
+            .unwrap();
+
+        let blocked = transfer("user123", 500.0);
+        repo.save_action_with_company(&blocked, company.id)
+            .await
+            .unwrap();
+        repo.save_evaluation(&EvaluationResult::block(blocked.id, vec![], vec![]))
+            .await
+            .unwrap();
+
+        let other_user = transfer("user456", 40.0);
+        repo.save_action_with_company(&other_user, company.id)
+            .await
+            .unwrap();
+        repo.save_evaluation(&EvaluationResult::allow(other_user.id))
+            .await
+            .unwrap();
+
+        let since = Utc::now() - chrono::Duration::hours(1);
+        let total = repo
+            .sum_user_transfers_since(company.id, "user123", since, Uuid::nil())
+            .await
+            .unwrap();
+        assert_eq!(total, vec![(None, 60.0)]);
+
+        let euros = AgentAction::new(
+            "user123",
+            "chatbot",
+            "gpt-4",
+            "Transfer funds",
+            ActionType::TransferFunds,
+            serde_json::json!({ "to_account_id": "bob", "amount": 25.0, "currency": "EUR" }),
+        );
+        repo.save_action_with_company(&euros, company.id)
+            .await
+            .unwrap();
+        repo.save_evaluation(&EvaluationResult::allow(euros.id))
+            .await
+            .unwrap();
+        let mut totals = repo
+            .sum_user_transfers_since(company.id, "user123", since, Uuid::nil())
+            .await
+            .unwrap();
+        totals.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(totals, vec![(None, 60.0), (Some("EUR".to_string()), 25.0)]);
+
+        let total = repo
+            .sum_user_transfers_since(
+                company.id,
+                "user123",
+                Utc::now() + chrono::Duration::hours(1),
+                Uuid::nil(),
+            )
+            .await
+            .unwrap();
+        assert!(total.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_admin_limit_holds_under_concurrent_writes() {
+        let repo = setup_test_db().await;
+        let company = Company::new("Acme".to_string(), "acme".to_string(), None);
+        repo.create_company(&company).await.unwrap();
+
+        let admins: Vec<_> = (0..5)
+            .map(|i| {
+                CompanyMember::new(
+                    company.id,
+                    format!("admin-{}", i),
+                    format!("admin-{}@example.com", i),
+                    CompanyRole::Admin,
+                )
+            })
+            .collect();
+        let added = futures_util::future::join_all(
+            admins
+                .iter()
+                .map(|m| repo.add_company_member_within_admin_limit(m, Some(2))),
+        )
+        .await;
+        let added = added.into_iter().filter(|r| *r.as_ref().unwrap()).count();
+        assert_eq!(added, 2);
+
+        let members: Vec<_> = (0..3)
+            .map(|i| {
+                CompanyMember::new(
+                    company.id,
+                    format!("member-{}", i),
+                    format!("member-{}@example.com", i),
+                    CompanyRole::Member,
+                )
+            })
+            .collect();
+        for member in &members {
+            assert!(repo
+                .add_company_member_within_admin_limit(member, Some(2))
+                .await
+                .unwrap());
+        }
+
+        let audit = |user_id: &str| {
+            AuditEntry::new(
+                Some(company.id),
+                "owner-1",
+                "member.role_changed",
+                "member",
+                user_id,
+                None,
+                None,
+            )
+        };
+        let audits: Vec<_> = members.iter().map(|m| audit(&m.user_id)).collect();
+        let promoted =
+            futures_util::future::join_all(members.iter().zip(&audits).map(|(m, audit)| {
+                repo.update_member_role(company.id, &m.user_id, CompanyRole::Admin, Some(2), audit)
+            }))
+            .await;
+        assert!(promoted.into_iter().all(|r| !r.unwrap()));
+
+        let admin_count = repo
+            .list_company_members(company.id)
+            .await
+            .unwrap()
+            .iter()
+            .filter(|m| m.role.is_owner_or_admin())
+            .count();
+        assert_eq!(admin_count, 2);
+
+        let result = repo
+            .update_member_role(
+                company.id,
+                "nobody",
+                CompanyRole::Admin,
+                Some(2),
+                &audit("nobody"),
+            )
+            .await;
+        assert!(matches!(result, Err(ShieldError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_company_settings_alignment_allowlist_roundtrip() {
+        let repo = setup_test_db().await;
+        let company = Company::new("Acme".to_string(), "acme".to_string(), None);
+        repo.create_company(&company).await.unwrap();
+
+        let thresholds = PolicyThresholds {
+            alignment_allowlist: vec![crate::domain::IntentAllowlistEntry {
+                action_type: ActionType::TransferFunds,
+                phrases: vec!["top up my savings".to_string()],
+            }],
+            ..Default::default()
+        };
+        repo.update_company_settings(company.id, None, None, None, None, Some(&thresholds))
+            .await
+            .unwrap();
+
+        let settings = repo.get_company_settings(company.id).await.unwrap();
+        let allowlist = settings.policy_thresholds.alignment_allowlist;
+        assert_eq!(allowlist.len(), 1);
+        assert_eq!(allowlist[0].action_type, ActionType::TransferFunds);
+        assert_eq!(allowlist[0].phrases, vec!["top up my savings".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_hourly_time_series_zero_fills_empty_hours() {
+        let repo = setup_test_db().await;
+        let company = Company::new("Acme".to_string(), "acme".to_string(), None);
+        repo.create_company(&company).await.unwrap();
+
+        let now = Utc::now();
+        for hours_ago in [1, 3, 3, 5] {
+            let mut action = AgentAction::new(
+                "user123",
+                "chatbot",
+                "gpt-4",
+                "Check balance",
+                ActionType::GetBalance,
+                serde_json::json!({}),
+            );
+            action.created_at = now - chrono::Duration::hours(hours_ago);
+            repo.save_action_with_company(&action, company.id)
+                .await
+                .unwrap();
+            repo.save_evaluation(&EvaluationResult::allow(action.id))
+                .await
+                .unwrap();
+        }
+
+        let series = repo
+            .get_time_series(company.id, TimeRange::Last24h, Granularity::Hour, None)
+            .await
+            .unwrap();
+        // 24 full hours plus the current, partial one
+        assert_eq!(series.data.len(), 25);
+        assert!(series
+            .data
+            .windows(2)
+            .all(|w| w[1].timestamp - w[0].timestamp == chrono::Duration::hours(1)));
+
+        let counts: Vec<i64> = series.data.iter().rev().map(|p| p.allowed).collect();
+        assert_eq!(&counts[..7], &[0, 1, 0, 2, 0, 1, 0]);
+        assert_eq!(counts.iter().filter(|&&c| c == 0).count(), 22);
+
+        let daily = repo
+            .get_time_series(company.id, TimeRange::Last7d, Granularity::Day, None)
+            .await
+            .unwrap();
+        assert_eq!(daily.data.len(), 8);
+        assert_eq!(daily.data.iter().map(|p| p.allowed).sum::<i64>(), 4);
+    }
+
+    /// Exercise the queries whose SQL differs between backends.
+    async fn check_dialect_queries(repo: &ShieldRepository) {
+        let slug = format!("acme-{}", Uuid::new_v4().simple());
+        let company = Company::new("Acme".to_string(), slug, None);
+        repo.create_company(&company).await.unwrap();
+
+        let transfer = |amount: serde_json::Value| {
+            AgentAction::new(
+                "user123",
+                "chatbot",
+                "gpt-4",
+                "Transfer funds",
+                ActionType::TransferFunds,
+                serde_json::json!({ "to_account_id": "bob", "amount": amount, "currency": "USD" }),
+            )
+        };
+
+        let allowed = transfer(serde_json::json!(60));
+        repo.save_action_with_company(&allowed, company.id)
+            .await
+            .unwrap();
+        repo.save_evaluation(&EvaluationResult::allow(allowed.id))
+            .await
+            .unwrap();
+
+        let escalated = transfer(serde_json::json!(500.5));
+        repo.save_action_with_company(&escalated, company.id)
+            .await
+            .unwrap();
+        let eval = EvaluationResult::require_hitl(escalated.id, vec![], vec![]);
+        repo.save_evaluation(&eval).await.unwrap();
+        let task = HitlTask::new(escalated.id, eval.id);
+        repo.save_hitl_task(&task).await.unwrap();
+
+        let (tasks, _) = repo
+            .list_hitl_tasks(Some(HitlStatus::Pending), 100, 0)
+            .await
+            .unwrap();
+        let listed = tasks.iter().find(|t| t.id == task.id).unwrap();
+        assert_eq!(listed.amount, Some(500.5));
+
+        let since = Utc::now() - chrono::Duration::hours(1);
+        let (count, total) = repo
+            .summarize_user_outflows_since(company.id, "user123", since, Uuid::nil())
+            .await
+            .unwrap();
+        assert_eq!((count, total), (2, vec![(Some("USD".to_string()), 560.5)]));
+        let refundable = |original: Uuid, user_id: &'static str| {
+            repo.get_refundable_amount(company.id, user_id, original, since, Uuid::nil())
+        };
+        assert_eq!(
+            refundable(allowed.id, "user123").await.unwrap(),
+            Some((60.0, Some("USD".to_string())))
+        );
+        assert_eq!(refundable(allowed.id, "someone-else").await.unwrap(), None);
+        // An escalated original only counts once a reviewer approves it
+        assert_eq!(refundable(escalated.id, "user123").await.unwrap(), None);
+
+        repo.update_hitl_task(task.id, HitlStatus::Approved, "reviewer-1", None, None)
+            .await
+            .unwrap();
+        assert_eq!(
+            refundable(escalated.id, "user123").await.unwrap(),
+            Some((500.5, Some("USD".to_string())))
+        );
+        let stats = repo
+            .get_reviewer_stats(company.id, "reviewer-1")
+            .await
+            .unwrap();
+        assert_eq!(stats.total_reviewed, 1);
+        assert!(stats.avg_time_to_decision_secs.unwrap() >= 0.0);
+
+        let (actions, total, _) = repo
+            .list_company_actions(
+                company.id,
+                None,
+                None,
+                None,
+                None,
+                Some("TRANSFER"),
+                Some(TimeRange::Last24h),
+                false,
+                None,
+                10,
+                0,
+            )
+            .await
+            .unwrap();
+        assert_eq!(total, 2);
+        assert!(actions.iter().any(|a| a.amount == Some(60.0)));
+        assert_eq!(actions[0].currency.as_deref(), Some("USD"));
+
+        let series = repo
+            .get_time_series(company.id, TimeRange::Last24h, Granularity::Day, None)
+            .await
+            .unwrap();
+        let allowed_count: i64 = series.data.iter().map(|p| p.allowed).sum();
+        let hitl_count: i64 = series.data.iter().map(|p| p.hitl).sum();
+        assert_eq!((allowed_count, hitl_count), (1, 1));
+        let hourly = repo
+            .get_time_series(company.id, TimeRange::Last24h, Granularity::Hour, None)
+            .await
+            .unwrap();
+        assert_eq!(hourly.data.len(), 25);
+        assert_eq!(hourly.data.last().unwrap().allowed, 1);
+
+        let overview = repo
+            .get_metrics_overview(company.id, TimeRange::Last24h, None)
+            .await
+            .unwrap();
+        assert_eq!(overview.total_actions, 2);
+        assert_eq!(overview.escalated_actions, 1);
+
+        let thresholds = PolicyThresholds {
+            daily_transfer_limit: Some(2500.0),
+            ..Default::default()
+        };
+        repo.update_company_settings(company.id, None, None, None, None, Some(&thresholds))
+            .await
+            .unwrap();
+        let settings = repo.get_company_settings(company.id).await.unwrap();
+        assert_eq!(
+            settings.policy_thresholds.daily_transfer_limit,
+            Some(2500.0)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dialect_queries_sqlite() {
+        let repo = setup_test_db().await;
+        check_dialect_queries(&repo).await;
+    }
+
+    /// Runs against a live database when `SHIELD_TEST_POSTGRES_URL` is set.
+    #[tokio::test]
+    async fn test_dialect_queries_postgres() {
+        let Ok(url) = std::env::var("SHIELD_TEST_POSTGRES_URL") else {
+            return;
+        };
+        let repo = ShieldRepository::connect(&url).await.unwrap();
+        repo.init_schema().await.unwrap();
+        // Schema setup must be repeatable on an existing database
+        repo.init_schema().await.unwrap();
+        check_dialect_queries(&repo).await;
+    }
+
+    #[tokio::test]
+    async fn test_purge_removes_actions_older_than_cutoff() {
+        let repo = setup_test_db().await;
+        let company = Company::new("Acme".to_string(), "acme".to_string(), None);
+        repo.create_company(&company).await.unwrap();
+        let now = Utc::now();
+
+        // Three old actions (so purging takes several batches) and a new one
+        let mut old_ids = Vec::new();
+        let mut new_id = None;
+        for days in [100, 95, 91, 10] {
+            let mut action = AgentAction::new(
+                "user123",
+                "chatbot",
+                "gpt-4",
+                "Transfer $500",
+                ActionType::TransferFunds,
+                serde_json::json!({"amount": 500.0}),
+            );
+            action.created_at = now - chrono::Duration::days(days);
+            repo.save_action_with_company(&action, company.id)
+                .await
+                .unwrap();
+            let eval = EvaluationResult::require_hitl(action.id, vec![], vec![]);
+            repo.save_evaluation(&eval).await.unwrap();
+            let task = HitlTask::new(action.id, eval.id);
+            repo.save_hitl_task(&task).await.unwrap();
+            repo.record_audit(&AuditEntry::new(
+                Some(company.id),
+                "reviewer-1",
+                "hitl_task.approved",
+                "hitl_task",
+                task.id.to_string(),
+                None,
+                None,
+            ))
+            .await
+            .unwrap();
+            repo.save_idempotency_record(&IdempotencyRecord::new(
+                None,
+                format!("key-{}", days),
+                "hash",
+                eval.id,
+                serde_json::json!({}),
+            ))
+            .await
+            .unwrap();
+            repo.save_attack_event(&AttackEvent::new(
+                company.id,
+                None,
+                action.id,
+                AttackType::PromptInjection,
+                RiskTier::High,
+                AttackOutcome::Blocked,
+                "user123".to_string(),
+                "Injected instructions".to_string(),
+            ))
+            .await
+            .unwrap();
+            if days > 90 {
+                old_ids.push(action.id);
+            } else {
+                new_id = Some(action.id);
+            }
+        }
+
+        // Actions of no company are left to their own purge
+        let mut orphan = AgentAction::new(
+            "user123",
+            "chatbot",
+            "gpt-4",
+            "Check my balance",
+            ActionType::GetBalance,
+            serde_json::json!({}),
+        );
+        orphan.created_at = now - chrono::Duration::days(100);
+        repo.save_action(&orphan).await.unwrap();
+
+        let mut delivery_ids = Vec::new();
+        for days in [120, 100, 95, 10] {
+            let mut delivery = WebhookDeliveryRecord::new(
+                company.id,
+                "hitl_task_created",
+                "https://hooks.example.com/shield",
+                "{}".to_string(),
+            );
+            delivery.created_at = now - chrono::Duration::days(days);
+            repo.save_webhook_delivery(&delivery).await.unwrap();
+            delivery_ids.push(delivery.id);
+        }
+
+        let summary = repo
+            .purge_actions_before(Some(company.id), now - chrono::Duration::days(90), 2)
+            .await
+            .unwrap();
+        assert_eq!(
+            summary,
+            PurgeSummary {
+                actions: 3,
+                evaluations: 3,
+                hitl_tasks: 3,
+                attack_events: 3,
+                reviewer_decisions: 3,
+                idempotency_keys: 3,
+                webhook_deliveries: 3,
+            }
+        );
+        let (deliveries, _) = repo
+            .list_webhook_deliveries(company.id, 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!(deliveries[0].id, delivery_ids[3]);
+        for id in old_ids {
+            assert!(matches!(
+                repo.get_action(id).await,
+                Err(ShieldError::NotFound(_))
+            ));
+        }
+        let new_id = new_id.unwrap();
+        assert!(repo.get_action(new_id).await.is_ok());
+        assert!(repo.get_latest_evaluation_for_action(new_id).await.is_ok());
+        let (tasks, _) = repo.list_hitl_tasks(None, 10, 0).await.unwrap();
+        assert_eq!(tasks.len(), 1);
+        let (entries, _) = repo
+            .list_audit_entries(company.id, None, None, 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].target_id, tasks[0].id.to_string());
+        assert!(repo
+            .get_idempotency_record(None, "key-10", now - chrono::Duration::days(1))
+            .await
+            .unwrap()
+            .is_some());
+        assert!(repo.get_action(orphan.id).await.is_ok());
+
+        let summary = repo
+            .purge_actions_before(None, now - chrono::Duration::days(90), 2)
+            .await
+            .unwrap();
+        assert_eq!(summary.actions, 1);
+        assert!(repo.get_action(new_id).await.is_ok());
     }
 }
@@ -0,0 +1,87 @@
+//! Operational metrics for Prometheus scrapers.
+//!
+//! Unlike the per-company business metrics under `/v1/companies/{id}/metrics`,
+//! these describe the service itself: evaluation throughput, firewall
+//! activity, guard latency and HTTP traffic. They are kept in a process-wide
+//! recorder and exported at `GET /metrics`.
+
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use axum::body::Body;
+use axum::extract::MatchedPath;
+use axum::http::Request;
+use axum::middleware::Next;
+use axum::response::Response;
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+
+use crate::domain::EvaluationResult;
+
+const EVALUATIONS_TOTAL: &str = "shield_evaluations_total";
+const FIREWALL_BLOCKS_TOTAL: &str = "shield_firewall_blocks_total";
+const LLAMAGUARD_LATENCY_SECONDS: &str = "shield_llamaguard_latency_seconds";
+const HTTP_REQUESTS_TOTAL: &str = "shield_http_requests_total";
+
+/// Histogram buckets for guard API calls, in seconds.
+const LATENCY_BUCKETS: &[f64] = &[0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0];
+
+static HANDLE: OnceLock<PrometheusHandle> = OnceLock::new();
+
+/// Install the global metrics recorder. Later calls return the recorder
+/// installed by the first.
+pub fn install() -> &'static PrometheusHandle {
+    HANDLE.get_or_init(|| {
+        PrometheusBuilder::new()
+            .set_buckets_for_metric(
+                Matcher::Full(LLAMAGUARD_LATENCY_SECONDS.to_string()),
+                LATENCY_BUCKETS,
+            )
+            .expect("latency buckets are not empty")
+            .install_recorder()
+            .expect("no other metrics recorder is installed")
+    })
+}
+
+/// Render every metric in the Prometheus text exposition format.
+pub fn render() -> String {
+    install().render()
+}
+
+/// Count a finished evaluation by decision, and the firewall block behind
+/// it if there was one.
+pub fn record_evaluation(evaluation: &EvaluationResult) {
+    metrics::counter!(EVALUATIONS_TOTAL, "decision" => evaluation.decision.to_string())
+        .increment(1);
+    if evaluation
+        .rule_hits
+        .iter()
+        .any(|hit| hit == "FIREWALL_BLOCK")
+    {
+        metrics::counter!(FIREWALL_BLOCKS_TOTAL).increment(1);
+    }
+}
+
+/// Record how long a Llama Guard API call took.
+pub fn record_guard_latency(elapsed: Duration) {
+    metrics::histogram!(LLAMAGUARD_LATENCY_SECONDS).record(elapsed.as_secs_f64());
+}
+
+/// Middleware counting HTTP requests by route and status.
+///
+/// Requests are labelled with the route template (`/v1/hitl/tasks/:id`)
+/// rather than the raw path, so IDs don't explode label cardinality.
+pub async fn track_http_requests(request: Request<Body>, next: Next) -> Response {
+    let path = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+    let response = next.run(request).await;
+    metrics::counter!(
+        HTTP_REQUESTS_TOTAL,
+        "path" => path,
+        "status" => response.status().as_u16().to_string()
+    )
+    .increment(1);
+    response
+}